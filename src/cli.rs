@@ -4,8 +4,12 @@
 //!   json-osi gen -i data.json --rust out/models.rs                  # write Rust
 //!   json-osi gen -i data.json --schema out/schema.json --rust -     # both; Rust to stdout
 //!   json-osi gen -i '-' --ndjson --rust out.rs                      # read NDJSON from stdin
+//!   cat a.json b.json | jq -c --seq . | json-osi gen -i - --separator rs --schema -
+//!   json-osi gen -i 'data/*.json' --schema out/schema.json --machine     # NDJSON progress events on stdout
+//!   json-osi explain -i data.ndjson --ndjson --path '$.price'       # values behind each kind at a path
 
-use std::io::{self, Read, Write};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, IsTerminal, Read, Write};
 use std::path::{Path, PathBuf};
 use colored::Colorize;
 
@@ -13,8 +17,16 @@ use clap::{Args, Parser, Subcommand, ValueEnum};
 use rayon::prelude::*;
 use serde_json::Value;
 
-use crate::inference::{observe_value, U};
+use crate::events::Event;
+use crate::inference::U;
+use crate::ingest::{Extra, Trackers};
+use crate::inference::str::Grex;
 use crate::norm_ir::NTy;
+use crate::jpath::JPath;
+use crate::manifest::{Checkpoint, DoneFile, EvidenceFile, GenOptions, Header, IngestSettings};
+use crate::size::SizeStats;
+use crate::timeline::Timeline;
+use crate::report::{Diagnostic, ParseFailures, Report};
 
 /// Top-level CLI
 #[derive(Parser, Debug)]
@@ -22,12 +34,88 @@ use crate::norm_ir::NTy;
 pub struct CommandLineInterface {
     #[command(subcommand)]
     cmd: Command,
+
+    /// Color the log on stderr: `auto` colors only a terminal, and never when
+    /// NO_COLOR is set (CLICOLOR_FORCE forces it)
+    #[arg(long, global = true, value_enum, default_value_t = Color::Auto)]
+    color: Color,
+
+    /// Refuse anything beyond reading inputs and writing outputs: starting
+    /// processes (`--plugin`) and fetching URLs fail instead
+    #[arg(long, global = true)]
+    offline: bool,
+
+    /// Log every process start or URL fetch, and sum them up at the end
+    #[arg(long, global = true)]
+    sandbox_audit: bool,
+
+    /// Line breaks in generated files: `native` is CRLF on Windows, LF elsewhere
+    #[arg(long, global = true, value_enum, default_value_t = Newline::Lf)]
+    newline: Newline,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum, Eq, PartialEq)]
+enum Newline {
+    Lf,
+    Crlf,
+    Native,
+}
+
+impl Newline {
+    fn newline(self) -> crate::portable::Newline {
+        match self {
+            Self::Lf => crate::portable::Newline::Lf,
+            Self::Crlf => crate::portable::Newline::Crlf,
+            Self::Native => crate::portable::Newline::Native,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum, Eq, PartialEq)]
+enum Color {
+    Auto,
+    Always,
+    Never,
+}
+
+impl Color {
+    fn enabled(self) -> bool {
+        // NO_COLOR disables color whatever its value; CLICOLOR_FORCE=0 doesn't force it
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        let force = std::env::var_os("CLICOLOR_FORCE").is_some_and(|v| !v.is_empty() && v != "0");
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::Auto => !no_color && (force || io::stderr().is_terminal()),
+        }
+    }
 }
 
 #[derive(Subcommand, Debug)]
 enum Command {
     /// Generate one or more outputs in a single pass
-    Gen(Gen),
+    Gen(Box<Gen>),
+    /// Hoist component types shared by several endpoint IRs (from `gen --ir-json`)
+    /// into a `common` module, with one module per endpoint referencing it
+    Unify(Unify),
+    /// Write a runnable cargo project around the models of an IR (from `gen --ir-json`):
+    /// a `main.rs` parsing a sample payload with path-annotated errors, and a test
+    Scaffold(Scaffold),
+    /// Check a generated `.rs` file's header against saved evidence (from `gen --evidence`)
+    VerifyGenerated(VerifyGenerated),
+    /// Decide one array as a tuple or a list from saved evidence (`gen --evidence`) and
+    /// regenerate the outputs, without reading the inputs again
+    Redecide(Redecide),
+    /// Show the concrete values behind the type inferred at one path, per JSON kind
+    Explain(Explain),
+    /// Compare the schema inferred from the inputs against a reference JSON Schema
+    /// (path precision / recall, kind agreement, constraint tightness)
+    Score(Score),
+    /// Check new inputs against a committed schema
+    Validate(Validate),
+    /// Write a synthetic NDJSON corpus with controllable traits (tuple arity drift, null
+    /// pads, enum cardinality, nesting, size) for benchmarking inference
+    GenCorpus(GenCorpus),
 }
 
 #[derive(Args, Debug, Clone)]
@@ -36,360 +124,2674 @@ struct InputSettings {
     #[arg(long, default_value_t = false)]
     ndjson: bool,
 
-    /// JQ pre-process filter for each document (via `jaq`)
+    /// Split each input into several documents at a separator, e.g. to stream
+    /// documents through '-' from a shell pipeline
+    #[arg(long, value_enum, conflicts_with = "ndjson")]
+    separator: Option<Separator>,
+
+    /// JQ pre-process filter for each document (via `jaq`); each output is a document,
+    /// and a document the filter yields nothing for contributes nothing (counted in the
+    /// report)
     #[arg(long)]
     jq_expr: Option<String>,
 
+    /// A document --jq-expr yields nothing for is an error (handled per --on-error)
+    /// instead of skipped
+    #[arg(long, default_value_t = false, requires = "jq_expr")]
+    require_match: bool,
+
+    /// What a document that can't be read (malformed JSON, a failing --jq-expr, jq output
+    /// that isn't JSON) does to the run
+    #[arg(long, value_enum, default_value_t = OnError::Skip)]
+    on_error: OnError,
+
+    /// Infer the documents inside strings that always hold a JSON object or array:
+    /// `contentSchema` in the schema, a `JsonString<T>` field decoded from the string
+    /// in the Rust models
+    #[arg(long, default_value_t = false)]
+    json_strings: bool,
+
+    /// Type slots whose strings all spell JSON numbers ("12", "4.5") as integers / numbers
+    /// that may be written either way; the Rust models accept both forms
+    #[arg(long, default_value_t = false)]
+    coerce_numeric_strings: bool,
+
+    /// Read empty strings ("") as null: fields holding them aren't required, and the
+    /// Rust models read them as `None`. Slots where "" looks like null are reported
+    /// either way
+    #[arg(long, default_value_t = false)]
+    empty_strings_as_null: bool,
+
+    /// Compare keys and string values in Unicode NFC, without zero-width characters,
+    /// soft hyphens or byte order marks: keys spelled differently are one field, the
+    /// Rust models accepting every spelling seen (`#[serde(alias)]`), and the merged
+    /// keys are reported
+    #[arg(long, default_value_t = false)]
+    normalize_unicode: bool,
+
+    /// Observe at most N documents over all inputs (after --max-samples-per-input),
+    /// picked per --sampling; the inputs are counted through once first
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    max_samples: Option<u64>,
+
+    /// Observe at most N documents of each input, picked per --sampling
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    max_samples_per_input: Option<u64>,
+
+    /// How --max-samples / --max-samples-per-input pick documents
+    #[arg(long, value_enum, default_value_t = SampleMethod::Reservoir)]
+    sampling: SampleMethod,
+
+    /// Seed for --sampling: the same seed, inputs and limits pick the same documents
+    #[arg(long, value_name = "SEED", default_value_t = 0)]
+    seed: u64,
+
+    /// Look into arrays and objects nested at most N deep (counting from each document,
+    /// and from each --json-strings document); deeper ones are typed as any JSON
+    /// (`serde_json::Value` in Rust, an unconstrained schema), with a warning
+    #[arg(long, value_name = "N", default_value_t = crate::inference::MAX_DEPTH, value_parser = clap::value_parser!(u32).range(1..))]
+    max_depth: u32,
+
     /// One or more inputs:
     /// - literal paths
     /// - quoted glob patterns
     /// - '-' for stdin
     #[arg(long, short, num_args = 1.., required = true, value_name = "PATH|GLOB|-")]
-    input: Vec<String>,
+    input: Vec<PathBuf>,
+
+    /// Print the resolved input files (after glob expansion and de-duplication) and exit
+    #[arg(long, default_value_t = false)]
+    list_inputs: bool,
+
+    /// Cache glob listings in FILE, reused while the walked directories' mtimes are unchanged
+    #[arg(long, value_name = "FILE")]
+    input_cache: Option<PathBuf>,
+
+    /// Follow symlinked files and directories found while expanding globs (skipped otherwise;
+    /// literal paths are always read)
+    #[arg(long, default_value_t = false)]
+    follow_symlinks: bool,
+
+    /// Don't skip entries excluded by `.json-osi-ignore` files (gitignore syntax) while
+    /// expanding globs
+    #[arg(long, default_value_t = false)]
+    no_ignore: bool,
+
+    /// Continue a run stopped by Ctrl-C / SIGTERM from its checkpoint; the files it finished
+    /// are not read again (they must be unchanged)
+    #[arg(long, value_name = "FILE")]
+    resume: Option<PathBuf>,
+
+    /// Where a run stopped by Ctrl-C / SIGTERM saves the evidence of the files it finished
+    #[arg(long, value_name = "FILE", default_value = "json-osi.checkpoint.evd")]
+    checkpoint: PathBuf,
 }
 
 #[derive(Args, Debug, Clone)]
 struct CommonSettings {
-    
+    /// Keep integers apart from floats in a slot holding both: `oneOf [integer, number]`
+    /// in the schema and an untagged enum in Rust, instead of one `number` (`f64`)
+    #[arg(long, default_value_t = false)]
+    exact_ints: bool,
+
+    /// Fail on ambiguous inference decisions (list vs tuple without proof, mixed kinds,
+    /// too few samples) instead of guessing; each error names the path to resolve
+    #[arg(long, default_value_t = false)]
+    strict_inference: bool,
+
+    /// JSON object of per-path decisions, e.g. {"$.results": "list", "$.value": "union"}
+    /// (`list`, `tuple`, `union`, `accept`, `passthrough`); `list` / `tuple` also apply
+    /// without strict mode, and `passthrough` types the path as any JSON, kept unparsed in Rust
+    #[arg(long, value_name = "FILE")]
+    overrides: Option<PathBuf>,
+
+    /// Stop at each ambiguous decision, show the evidence, and save the answer into the
+    /// --overrides file (created if missing); needs a terminal on stdin
+    #[arg(long, default_value_t = false, requires = "overrides")]
+    interactive: bool,
+
+    /// Drop union arms holding less than SHARE (0..1) of their slot's non-null values,
+    /// e.g. 0.001 for a malformed string among a thousand integers; the report lists them
+    #[arg(long, value_name = "SHARE", value_parser = parse_share)]
+    min_arm_share: Option<f64>,
+
+    /// Require fields non-null in at least SHARE (0..=1) of their objects instead of in
+    /// all of them, e.g. 0.995; the report lists the fields this decides and the ones
+    /// that barely missed it
+    #[arg(long, value_name = "SHARE", value_parser = parse_threshold)]
+    required_threshold: Option<f64>,
+
+    /// Read integer slots holding at most MAX distinct values (status codes, protocol
+    /// enums) as enums: `enum` in the schema, a `#[repr(i64)]` enum in Rust; a slot past
+    /// its numbers literal cap (see --policy) keeps no values to enumerate
+    #[arg(long, value_name = "MAX", value_parser = clap::value_parser!(u32).range(1..))]
+    int_enums: Option<u32>,
+
+    /// JSON policy file; its `literal_caps` set how many distinct strings / numbers a
+    /// slot keeps (for enums and patterns) by depth and path, e.g.
+    /// {"literal_caps": [{"min_depth": 4, "strings": 8}, {"path": "$.kind", "strings": 256}]}
+    #[arg(long, value_name = "FILE")]
+    policy: Option<PathBuf>,
+
+    /// Give up on synthesizing a string slot's pattern after MS milliseconds of grex
+    /// (with --grex); the slot stays a plain string and the report lists it, as it does
+    /// slots with too many literals to try
+    #[arg(long, value_name = "MS", default_value_t = crate::inference::str::GREX_TIME_BUDGET_MS)]
+    grex_time_budget: u64,
+
+    /// Synthesize a regex `pattern` for string slots that are neither enums, URIs nor a
+    /// known format, from the literals they kept (with grex); checked by the Rust models
+    #[arg(long, default_value_t = false)]
+    grex: bool,
+
+    /// Distinct literals a string slot needs before grex is tried (with --grex)
+    #[arg(long, value_name = "N", default_value_t = crate::inference::str::GREX_MIN_SAMPLES)]
+    grex_min_samples: usize,
+
+    /// Drop synthesized patterns longer than N characters (with --grex); the slot stays a
+    /// plain string
+    #[arg(long, value_name = "N", default_value_t = crate::inference::str::GREX_MAX_PATTERN_LEN)]
+    grex_max_len: usize,
+
+    /// Drop synthesized patterns with more than N `|` alternations, whitelists of the
+    /// literals rather than patterns (with --grex); the slot stays a plain string
+    #[arg(long, value_name = "N", default_value_t = crate::inference::str::GREX_MAX_ALTS)]
+    grex_max_alts: usize,
+}
+
+impl CommonSettings {
+    /// Regex synthesis as configured.
+    fn grex(&self) -> Grex {
+        Grex {
+            enabled: self.grex || crate::inference::ENABLE_GREX,
+            min_samples: self.grex_min_samples,
+            max_len: self.grex_max_len,
+            max_alts: self.grex_max_alts,
+        }
+    }
+}
+
+fn parse_share(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(x) if (0.0..1.0).contains(&x) => Ok(x),
+        Ok(_) => Err("must be at least 0 and below 1".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_threshold(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(x) if x > 0.0 && x <= 1.0 => Ok(x),
+        Ok(_) => Err("must be above 0 and at most 1".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
 }
 
 /// Unified generator: choose any combination of outputs.
 /// For any output flag, pass `-` to write to stdout.
-#[derive(Args, Debug)]
+#[derive(Args, Debug, Clone)]
 struct Gen {
     #[command(flatten)]
     input: InputSettings,
 
+    /// Write the standard bundle into DIR (created if missing): schema.json, models.rs,
+    /// report.md, ir.json and summary.json; an output given by its own flag goes there instead
+    #[arg(long, short, value_name = "DIR")]
+    out: Option<PathBuf>,
+
     /// Top-level Rust type name (when emitting Rust)
     #[arg(long, default_value = "Root")]
     root_type: String,
 
+    /// Inputs are endpoint captures holding both bodies: `--pair request=EXPR response=EXPR`,
+    /// each EXPR a jq filter picking that body out of a document (e.g. `.req.body`). The root
+    /// is then `{"request": …, "response": …}`, its two halves the `Request` and `Response`
+    /// Rust types (unless --names pins others)
+    #[arg(long, num_args = 2, value_name = "ROLE=EXPR", conflicts_with = "jq_expr")]
+    pair: Vec<String>,
+
+    /// What to do when two different types derive the same Rust name
+    #[arg(long, value_enum, default_value_t = NameCollisions::Suffix)]
+    name_collisions: NameCollisions,
+
+    /// Naming manifest shared by every emitter (JSON: path → type name). Names already in
+    /// FILE are kept, the names of this run are written back, and the schema carries them as
+    /// `title` so generators for other languages name their types like the Rust models
+    #[arg(long, value_name = "FILE")]
+    names: Option<PathBuf>,
+
+    /// Literal dictionary kept across runs (JSON: enum path → value → first / last seen).
+    /// Values seen before but missing from this run stay in the enums until deleted from
+    /// FILE; the values of this run are written back, and the report lists what changed
+    #[arg(long, value_name = "FILE")]
+    literals: Option<PathBuf>,
+
+    /// Data dictionary (CSV: path, description, …; a header row is skipped): each
+    /// description goes into the schema as `description` and onto the Rust field or
+    /// tuple position as its doc comment
+    #[arg(long, value_name = "FILE")]
+    dictionary: Option<PathBuf>,
+
+    /// Names for tuple positions (JSON, or TOML for a `.toml` file: path → name, e.g.
+    /// {"$.points[*][0]": "latitude"}): a tuple with a named position becomes a Rust struct
+    /// with named fields, and the schema carries the names as `title` (and as the property
+    /// keys with --schema-tuples objects)
+    #[arg(long, value_name = "FILE")]
+    tuple_names: Option<PathBuf>,
+
+    /// Primary key of the root record (e.g. '$.id' or '$[0]'), or 'auto' to pick the
+    /// string slot that is unique across all documents. Emits `key()` + `index_by_key`.
+    #[arg(long, value_name = "PATH|auto")]
+    key: Option<String>,
+
     /// Emit JSON Schema to file (or '-' for stdout)
     #[arg(long, value_name = "FILE|-")]
     schema: Option<PathBuf>,
 
+    /// Emit an OpenAPI 3.1 operation skeleton to file (or '-' for stdout): the --pair
+    /// request and response schemas as its body and `200` response, the path left to fill in
+    #[arg(long, value_name = "FILE|-", requires = "pair")]
+    openapi: Option<PathBuf>,
+
+    /// Detect a discriminator field of the root object (a string with few values that
+    /// decides which other fields appear) and emit one `if`/`then` per value in the schema
+    #[arg(long, default_value_t = false)]
+    schema_conditionals: bool,
+
+    /// How the schema spells tuples: `positional` (`prefixItems`), or `objects` keyed
+    /// "0", "1", … (or the --tuple-names names) and marked `x-encoding: positional-array`, for human readers (such a
+    /// schema no longer validates the data; the Rust models still decode arrays)
+    #[arg(long, value_enum, default_value_t = SchemaTuples::Positional)]
+    schema_tuples: SchemaTuples,
+
+    /// Each document is one record of a stream: the schema defines the record type (under
+    /// `$defs`) plus `x-stream` metadata (how records are delimited), and the Rust models
+    /// get `read_records` / `parse_records` over NDJSON
+    #[arg(long, default_value_t = false)]
+    records_schema: bool,
+
+    /// Records, tuples and tagged unions found at several paths are defined once: under
+    /// `$defs` (each occurrence a `$ref`) in the schema, one struct in the Rust models.
+    /// Identical means down to the constraints; a lower --tightness shares more
+    #[arg(long, default_value_t = false)]
+    share_types: bool,
+
     /// Emit strict Rust models to file (or '-' for stdout)
     #[arg(long, value_name = "FILE|-")]
     rust: Option<PathBuf>,
 
+    /// Strictness vs. size / compile time of the Rust models: `small` uses derive-only
+    /// serde (plain scalars, untagged unions, shared identical types) and drops range,
+    /// pattern and URI checks
+    #[arg(long, value_enum, default_value_t = Profile::Strict)]
+    codegen_profile: Profile,
+
+    /// How many observed constraints every output carries: 0 = shapes only, 1 = + string
+    /// formats, 2 = + enums and patterns, 3 = + numeric bounds and list lengths (all)
+    #[arg(long, value_name = "0..3", default_value_t = crate::norm_ir::MAX_TIGHTNESS,
+          value_parser = clap::value_parser!(u8).range(0..=crate::norm_ir::MAX_TIGHTNESS as i64))]
+    tightness: u8,
+
+    /// Show how much evidence backs each field and tuple position (objects / arrays seen,
+    /// present, non-null) and each type (values seen, distinct ones): `x-evidence` and
+    /// `x-stats` in the schema, doc comments on the Rust fields, counts in --ir-json.
+    /// Outputs then change whenever the corpus grows
+    #[arg(long, default_value_t = false)]
+    evidence_counts: bool,
+
+    /// Show a few values seen at each string and number slot (up to 3; strings of at most
+    /// 80 characters), kept even where the literal caps drop the rest: `examples` in the
+    /// schema, doc comments on the Rust fields and tuple positions, and in --ir-json.
+    /// They are the corpus's own data, personal data included
+    #[arg(long, default_value_t = false)]
+    examples: bool,
+
+    /// Emit `generated_config.rs` (constants describing the checks the Rust models enforce)
+    /// to file (or '-' for stdout); requires --rust
+    #[arg(long, value_name = "FILE|-", requires = "rust")]
+    rust_config: Option<PathBuf>,
+
+    /// Write a standalone cargo crate (Cargo.toml, lib.rs, models, generated_config.rs,
+    /// and a test over captured sample documents) into DIR
+    #[arg(long, value_name = "DIR")]
+    rust_crate: Option<PathBuf>,
+
+    /// Also write `contract_tests.rs`, unit tests pinning each generated type's fields, tuple
+    /// arity and enum values: beside --rust FILE (declare it `#[cfg(test)] mod contract_tests;`
+    /// next to the models module) and into --rust-crate
+    #[arg(long, default_value_t = false)]
+    contract_tests: bool,
+
+    /// jq program run on the normalized IR (the `--ir-json` form) before anything is emitted,
+    /// for bespoke renames, merges or drops; it must output exactly one IR
+    #[arg(long, value_name = "FILE.jq")]
+    ir_transform: Option<PathBuf>,
+
     /// Emit a pretty-printed debug view of the lowered IR (not JSON; uses Debug)
     #[arg(long = "ir-debug", value_name = "FILE|-")]
     ir_debug: Option<PathBuf>,
 
+    /// Emit the normalized IR as JSON (input for `unify`) to file (or '-' for stdout)
+    #[arg(long = "ir-json", value_name = "FILE|-")]
+    ir_json: Option<PathBuf>,
+
+    /// Save the joined evidence (plus input manifest) for `verify-generated` to file (or '-' for stdout)
+    #[arg(long, value_name = "FILE.evd|-")]
+    evidence: Option<PathBuf>,
+
+    /// Export the joined evidence as a documented JSON tree (counts, ranges, literal samples,
+    /// and --column-stats / --histograms per slot) for notebooks and visualization tools,
+    /// to file (or '-' for stdout)
+    #[arg(long, value_name = "FILE|-")]
+    evidence_json: Option<PathBuf>,
+
+    /// Emit a Markdown run report (per-file contributions, …) to file (or '-' for stdout)
+    #[arg(long, value_name = "FILE|-")]
+    report: Option<PathBuf>,
+
+    /// Emit a JSON run summary (documents read, evidence id, generated code, files written)
+    /// to file (or '-' for stdout)
+    #[arg(long, value_name = "FILE|-")]
+    summary: Option<PathBuf>,
+
+    /// Track the first document (file and line) to bring each kind of value to each path,
+    /// and write them as JSON to file (or '-' for stdout); --ir-debug then ends with the
+    /// paths holding several kinds
+    #[arg(long, value_name = "FILE|-")]
+    provenance: Option<PathBuf>,
+
+    /// Add approximate uniqueness and functional dependencies of the root record's
+    /// columns (top-level fields / tuple positions) to the report
+    #[arg(long, default_value_t = false)]
+    column_stats: bool,
+
+    /// Add median / p95 and a coarse histogram of every numeric slot to the report, and
+    /// flag sentinel-looking values (`-1`, `999999`, …) that sit apart from the rest
+    #[arg(long, default_value_t = false)]
+    histograms: bool,
+
+    /// Detect marker values standing in for "missing" (`-1` in a non-negative slot,
+    /// `"N/A"` next to real data) and list them in the report; `null` reads them as
+    /// `None` in the Rust models, `enum` gives each its own variant
+    #[arg(long, value_enum, value_name = "POLICY")]
+    sentinels: Option<Sentinels>,
+
+    /// Timestamp of each document (e.g. '$.meta.ts'; RFC 3339 or epoch); adds a timeline
+    /// of when paths first appeared and stopped appearing to the report
+    #[arg(long, value_name = "PATH")]
+    timestamp: Option<String>,
+
+    /// Bucket size for --timestamp
+    #[arg(long, value_enum, default_value_t = TimeWindow::Day, requires = "timestamp")]
+    timestamp_window: TimeWindow,
+
+    /// Estimate typical and worst-case bytes per record, serialized and in the Rust
+    /// models, from observed lengths and presence rates; adds them to the report
+    #[arg(long, default_value_t = false)]
+    size_estimate: bool,
+
+    /// Tag fields that look like personal data (emails, phone numbers, names, GPS
+    /// coordinates) with a sensitivity level: `x-pii` in the schema, `pii` in the
+    /// Rust `SCHEMA` table, and a section in the report
+    #[arg(long, default_value_t = false)]
+    pii: bool,
+
+    /// Named string formats, one per line: `format "place_id" = regex "^ChIJ…$"`, optionally
+    /// followed by `check "crate::path::to_fn"`; string slots whose every value matches get
+    /// `format` in the schema and a checked newtype (with `is_<format>()`) in the Rust models
+    #[arg(long, value_name = "FILE")]
+    formats: Option<PathBuf>,
+
+    /// Generate `redact(&mut self)` on models holding `--pii` fields: `blank` empties
+    /// strings, zeroes numbers and clears optional fields; `hash` replaces strings with
+    /// a stable digest (numbers are zeroed either way)
+    #[arg(long, value_enum, value_name = "MODE", requires = "pii")]
+    redact: Option<Redact>,
+
+    /// Type strings detected as UUIDs as `uuid::Uuid` in the Rust models (the consuming
+    /// crate needs `uuid` with its `serde` feature); otherwise they get a checked newtype
+    #[arg(long, default_value_t = false)]
+    rust_uuid: bool,
+
+    /// Decode strings detected as hex or base64 into `Vec<u8>` newtypes in the Rust models;
+    /// otherwise they get a checked string newtype
+    #[arg(long, default_value_t = false)]
+    rust_bytes: bool,
+
+    /// Type strings detected as decimal amounts (`19.99`, `1,299.00`) as newtypes over
+    /// `rust_decimal::Decimal` in the Rust models (the consuming crate needs `rust_decimal`);
+    /// otherwise they get a checked string newtype
+    #[arg(long, default_value_t = false)]
+    rust_decimal: bool,
+
+    /// Read integer slots whose values all look like Unix timestamps (2000 to 2100, in seconds
+    /// or milliseconds) as `chrono::DateTime<Utc>` in the Rust models, through `serde_with`
+    /// (the consuming crate needs `chrono`, and `serde_with` with its `chrono_0_4` feature),
+    /// and give them `format: unix-time` / `unix-time-ms` in the schema; the report lists
+    /// such slots either way
+    #[arg(long, default_value_t = false)]
+    epoch_timestamps: bool,
+
+    /// Collapse root columns that are always null together into one `Option<…Group>`
+    /// struct in the Rust output (the groups are also listed in the report)
+    #[arg(long, default_value_t = false)]
+    group_nullables: bool,
+
+    /// Check the first documents (up to 10000) against the generated types and, for any
+    /// that fail, suggest the smallest relaxation (e.g. "drop maxItems at $.results")
+    #[arg(long, default_value_t = false)]
+    revalidate: bool,
+
+    /// WASI plugin module (repeatable), run after inference: gets the normalized IR and
+    /// sample documents as JSON on stdin and answers with lint warnings and extra output
+    /// files; it can't change the inferred types
+    #[arg(long, value_name = "MODULE.wasm")]
+    plugin: Vec<PathBuf>,
+
+    /// WASI runtime that runs plugins, as `<PROGRAM> run <MODULE.wasm>`
+    #[arg(long, default_value = "wasmtime", value_name = "PROGRAM")]
+    plugin_runtime: String,
+
+    /// Directory for files emitted by plugins
+    #[arg(long, default_value = ".", value_name = "DIR")]
+    plugin_out: PathBuf,
+
+    /// How counts and sizes read in the report and log: `short` (1.2B docs, 3.4 GB),
+    /// `grouped` (1,234,567,890), or `raw`
+    #[arg(long, value_enum, default_value_t = NumberFormat::Short)]
+    number_format: NumberFormat,
+
     /// Optional: choose one or more streams to also print to stdout (redundant with '-' paths)
     #[arg(long = "stdout", value_enum)]
     stdout_streams: Vec<StdoutStream>,
 
+    /// How outputs bound for stdout are printed: one after another, or as a single JSON
+    /// object keyed by output (`schema`, `rust`, `report`, …) once all are done
+    #[arg(long, value_enum, default_value_t = StdoutFormat::Plain)]
+    stdout_format: StdoutFormat,
+
+    /// Print progress and results as newline-delimited JSON events on stdout (phases,
+    /// files done, warnings, artifacts) instead of the human log on stderr
+    #[arg(long, default_value_t = false, conflicts_with = "stdout_format")]
+    machine: bool,
+
     #[command(flatten)]
     common: CommonSettings,
 }
 
-#[derive(Copy, Clone, Debug, ValueEnum, Eq, PartialEq)]
-enum StdoutStream {
-    Schema,
-    Rust,
-    IrDebug,
-}
+#[derive(Args, Debug)]
+struct Unify {
+    /// Normalized IR files written by `gen --ir-json`; the file stem names the endpoint module
+    #[arg(required = true, num_args = 2.., value_name = "IR_JSON")]
+    inputs: Vec<PathBuf>,
 
-// --------------------------- Legacy (hidden) ---------------------------
+    /// Output directory for `mod.rs`, `common.rs`, and one `<endpoint>.rs` per input
+    #[arg(long, short, value_name = "DIR")]
+    out: PathBuf,
 
-#[derive(Args, Debug)]
-struct LegacyJsonSchemaOut {
-    #[command(flatten)]
-    input_settings: InputSettings,
-    /// output .json file (stdout if omitted)
-    #[arg(short, long)]
-    out: Option<PathBuf>,
-    #[command(flatten)]
-    common_settings: CommonSettings,
+    /// Root Rust type name inside each endpoint module
+    #[arg(long, default_value = "Root")]
+    root_type: String,
+
+    /// What to do when two different types derive the same Rust name
+    #[arg(long, value_enum, default_value_t = NameCollisions::Suffix)]
+    name_collisions: NameCollisions,
 }
 
 #[derive(Args, Debug)]
-struct LegacyRustOut {
-    #[command(flatten)]
-    input_settings: InputSettings,
-    /// top-level Rust type name
+struct Scaffold {
+    /// Normalized IR written by `gen --ir-json`
+    #[arg(long, value_name = "IR_JSON")]
+    ir: PathBuf,
+
+    /// Project directory (created if missing)
+    #[arg(long, short, value_name = "DIR")]
+    out: PathBuf,
+
+    /// A captured payload: copied to `sample.json`, parsed by `cargo run` and `cargo test`
+    #[arg(long, value_name = "FILE")]
+    sample: PathBuf,
+
+    /// Top-level Rust type name
     #[arg(long, default_value = "Root")]
     root_type: String,
-    /// output .rs file (stdout if omitted)
-    #[arg(short, long)]
-    out: Option<PathBuf>,
-    #[command(flatten)]
-    common_settings: CommonSettings,
+
+    /// What to do when two different types derive the same Rust name
+    #[arg(long, value_enum, default_value_t = NameCollisions::Suffix)]
+    name_collisions: NameCollisions,
+
+    /// Strictness vs. size / compile time of the Rust models (as for `gen`)
+    #[arg(long, value_enum, default_value_t = Profile::Strict)]
+    codegen_profile: Profile,
 }
 
-// --------------------------- Impl ---------------------------
+#[derive(Args, Debug)]
+struct VerifyGenerated {
+    /// Generated file (`--rust` or `--rust-config` output)
+    artifact: PathBuf,
 
-impl CommandLineInterface {
-    pub fn load() -> Self {
-        Self::parse()
-    }
+    /// Evidence saved by the same `gen` run (`--evidence`)
+    #[arg(long, value_name = "FILE.evd")]
+    against: PathBuf,
 
-    pub fn run(&self) {
-        match &self.cmd {
-            Command::Gen(cfg) => run_gen(cfg),
-            // Command::Schema(old) => run_legacy_schema(old),
-            // Command::Rust(old) => run_legacy_rust(old),
-        }
-    }
+    /// The `--policy` file the artifact was generated with
+    #[arg(long, value_name = "FILE")]
+    policy: Option<PathBuf>,
 }
 
-// --------------------------- gen ---------------------------
+#[derive(Args, Debug)]
+struct Redecide {
+    /// Evidence saved by `gen --evidence`
+    #[arg(long, value_name = "FILE.evd")]
+    evidence: PathBuf,
 
-fn run_gen(cfg: &Gen) {
-    eprintln!("{}", format!("▶︎ began: {}", get_current_pretty_time().bright_magenta()).cyan());
-    
-    let start = std::time::Instant::now();
-    
-    // At least one target?
-    if cfg.schema.is_none() && cfg.rust.is_none() && cfg.ir_debug.is_none()
-        && cfg.stdout_streams.is_empty()
-    {
-        eprintln!("error: no outputs requested. Use one or more of --schema, --rust, --ir-debug, or --stdout …");
-        std::process::exit(2);
-    }
+    /// Array to decide, as written in the IR (e.g. '$.bbox', '$.rows[*].span')
+    #[arg(long, value_name = "PATH")]
+    path: String,
 
-    // Build merged & normalized summary
-    let normalized = compute_and_normalize(&cfg.input, &cfg.common);
-    let ir_root = crate::norm_ir::lower_from_norm(&normalized);
+    /// Shape the array gets
+    #[arg(long = "as", value_enum, value_name = "SHAPE")]
+    shape: Shape,
 
-    // Lower IR once; reuse for multiple emits
-    // let ir_root = crate::lower::lower_to_ir(&u);
+    /// Models generated from the same evidence (`gen --rust`), regenerated in place with
+    /// the options recorded in their header
+    #[arg(long, value_name = "FILE.rs")]
+    rust: Option<PathBuf>,
 
-    // 1) Schema
-    if cfg.schema.is_some() || cfg.stdout_streams.contains(&StdoutStream::Schema) {
-        let schema = crate::norm_ir::schema_from_norm(&normalized);
-        let schema_src = serde_json::to_string_pretty(&schema).unwrap();
+    /// Also regenerate `generated_config.rs` to file (or '-' for stdout); requires --rust
+    #[arg(long, value_name = "FILE|-", requires = "rust")]
+    rust_config: Option<PathBuf>,
 
-        // file target
-        if let Some(path) = cfg.schema.as_ref() {
-            write_sink(path, &schema_src).unwrap();
-        }
+    /// Emit JSON Schema to file (or '-' for stdout)
+    #[arg(long, value_name = "FILE|-")]
+    schema: Option<PathBuf>,
 
-        // stdout stream (if requested, even if also wrote file)
-        if cfg.stdout_streams.contains(&StdoutStream::Schema) && cfg.schema.as_deref() != Some(Path::new("-")) {
-            println!("{schema_src}");
-        }
-    }
+    /// Emit the normalized IR as JSON to file (or '-' for stdout)
+    #[arg(long = "ir-json", value_name = "FILE|-")]
+    ir_json: Option<PathBuf>,
 
-    // 2) Rust
-    if cfg.rust.is_some() || cfg.stdout_streams.contains(&StdoutStream::Rust) {
-        let mut cg = crate::codegen::Codegen::new();
-        cg.emit(&ir_root, &cfg.root_type);
-        let rust_src = cg.into_string();
-        if let Some(path) = cfg.rust.as_ref() {
-            write_sink(path, &rust_src).unwrap();
-        }
-        if cfg.stdout_streams.contains(&StdoutStream::Rust) && cfg.rust.as_deref() != Some(Path::new("-")) {
-            println!("{rust_src}");
-        }
-    }
+    /// Save the decision into this overrides file (created if missing) so later
+    /// `gen --overrides` runs keep it; its other decisions apply too
+    #[arg(long, value_name = "FILE")]
+    overrides: Option<PathBuf>,
 
-    // 3) IR debug (human pretty; not JSON)
-    if cfg.ir_debug.is_some() || cfg.stdout_streams.contains(&StdoutStream::IrDebug) {
-        let ir_txt = format!("{:#?}", ir_root);
-        if let Some(path) = cfg.ir_debug.as_ref() {
-            write_sink(path, &ir_txt).unwrap();
-        }
-        if cfg.stdout_streams.contains(&StdoutStream::IrDebug) && cfg.ir_debug.as_deref() != Some(Path::new("-")) {
-            println!("{ir_txt}");
-        }
-    }
+    /// The `--policy` file the evidence was gathered with
+    #[arg(long, value_name = "FILE")]
+    policy: Option<PathBuf>,
+}
 
-    {
-        let elapsed = start.elapsed();
-        eprintln!("{}", format!(
-            "{} » inference took {}",
-            "[INFO]".bright_magenta(),
-            format_duration(elapsed)
-        ).cyan());
+#[derive(Copy, Clone, Debug, ValueEnum, Eq, PartialEq)]
+enum Shape {
+    /// One item type for every position
+    List,
+    /// A type per position
+    Tuple,
+}
+
+impl Shape {
+    fn decision(self) -> crate::overrides::Decision {
+        use crate::overrides::Decision;
+        match self {
+            Self::List => Decision::List,
+            Self::Tuple => Decision::Tuple,
+        }
     }
 }
 
-// --------------------------- Core pipeline ---------------------------
+#[derive(Args, Debug)]
+struct Score {
+    #[command(flatten)]
+    input: InputSettings,
 
-fn compute_and_normalize(
-    input_settings: &InputSettings,
-    common_settings: &CommonSettings
-) -> NTy {
-    let _ = common_settings;
-    let source_paths = resolve_file_path_patterns(&input_settings.input).expect("failed to resolve input file paths");
+    /// Reference JSON Schema (local `$ref`s are followed)
+    #[arg(long, value_name = "SCHEMA.json")]
+    truth: PathBuf,
 
-    eprintln!("{}", format!(
-        "▶︎ total source files: {}",
-        source_paths.len().to_string().green(),
-    ).cyan());
+    /// Print the scores as JSON
+    #[arg(long, default_value_t = false)]
+    json: bool,
 
-    let ndjson = input_settings.ndjson;
-    let jq_expr = input_settings.jq_expr.clone();
+    #[command(flatten)]
+    common: CommonSettings,
+}
 
-    eprintln!("{}", format!(
-        "{} ▶︎ file(s) pipeline: {}",
-        format!("[{}]", get_current_pretty_time()).bright_magenta(),
-        "began".blue()
-    ).cyan());
+#[derive(Args, Debug)]
+struct Validate {
+    #[command(flatten)]
+    input: InputSettings,
 
-    let combined = source_paths
-        .par_iter()
-        .map(|path| {
-            if let Some(jq_filter) = input_settings.jq_expr.as_ref() {
-                eprintln!("{}", format!(
-                    "  ❍ processing: {} » '{}'",
-                    path.to_str().unwrap().green(),
-                    jq_filter.blue()
-                ).cyan());
-            } else {
-                eprintln!("{}", format!(
-                    "  ❍ processing: {}",
-                    path.to_str().unwrap().green(),
-                ).cyan());
-            }
+    /// The committed JSON Schema (as written by `gen --schema`)
+    #[arg(long, value_name = "SCHEMA.json")]
+    schema: PathBuf,
 
-            let path_str = path.to_string_lossy().to_string();
+    /// What to check
+    #[arg(long, value_enum, default_value_t = ValidateMode::Enums)]
+    mode: ValidateMode,
 
-            // Read source (supports '-' stdin)
-            let src = if path_str == "-" {
-                let mut buf = String::new();
-                io::stdin().read_to_string(&mut buf).expect("failed to read stdin");
-                buf
-            } else {
-                std::fs::read_to_string(path)
-                    .unwrap_or_else(|e| panic!("read failed ({path_str}): {e}"))
+    /// Print the results as JSON
+    #[arg(long, default_value_t = false)]
+    json: bool,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum, Eq, PartialEq)]
+enum ValidateMode {
+    /// Which variants of each enum the inputs use, and which of their values fall outside
+    /// it; exits with 1 if any enum misses a value
+    Enums,
+    /// Each input document against the whole schema (the subset `gen` writes, see
+    /// `schema_check`); exits with 1 if any fails
+    Documents,
+}
+
+#[derive(Args, Debug)]
+struct GenCorpus {
+    /// NDJSON output file (or '-' for stdout)
+    #[arg(long, short, value_name = "FILE|-")]
+    out: PathBuf,
+
+    /// Documents to write
+    #[arg(long, default_value_t = 1000)]
+    docs: u64,
+
+    /// Stop once this many bytes are written, even before --docs
+    #[arg(long, value_name = "BYTES")]
+    max_bytes: Option<u64>,
+
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Positions every `record` tuple has
+    #[arg(long, default_value_t = 6)]
+    tuple_arity: usize,
+
+    /// Extra trailing `record` positions a document may have (0..=N)
+    #[arg(long, default_value_t = 0)]
+    arity_drift: usize,
+
+    /// Chance that a `record` position after the first is null
+    #[arg(long, default_value_t = 0.1)]
+    null_pad: f64,
+
+    /// Distinct values of the `kind` string
+    #[arg(long, default_value_t = 4)]
+    enum_cardinality: usize,
+
+    /// Nesting levels of `node.children`
+    #[arg(long, default_value_t = 2)]
+    depth: usize,
+
+    /// Characters in each document's `blob` string
+    #[arg(long, default_value_t = 0)]
+    blob_len: usize,
+
+    /// Also write the JSON Schema the corpus conforms to (for `score --truth`)
+    #[arg(long, value_name = "FILE")]
+    truth: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct Explain {
+    #[command(flatten)]
+    input: InputSettings,
+
+    /// Path to explain (e.g. '$.results[*].price'; `[*]` visits every list item)
+    #[arg(long, value_name = "PATH")]
+    path: String,
+
+    /// Values to print per kind
+    #[arg(long, default_value_t = 5, value_name = "N")]
+    witnesses: usize,
+
+    /// Print where each value came from (`file:line` for NDJSON, `file#N` for jq outputs)
+    #[arg(long, default_value_t = false)]
+    provenance: bool,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum, Eq, PartialEq)]
+enum NameCollisions {
+    /// Append numeric suffixes (`Name1`, `Name2`, …)
+    Suffix,
+    /// Qualify the later type with its JSON path, then suffix if still taken
+    Path,
+    /// Fail with a description of both shapes
+    Error,
+}
+
+impl NameCollisions {
+    fn policy(self) -> crate::codegen::NamingPolicy {
+        use crate::codegen::CollisionPolicy;
+        crate::codegen::NamingPolicy {
+            collisions: match self {
+                Self::Suffix => CollisionPolicy::Suffix,
+                Self::Path => CollisionPolicy::Path,
+                Self::Error => CollisionPolicy::Error,
+            },
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum, Eq, PartialEq)]
+enum SchemaTuples {
+    /// Arrays with `prefixItems`, as the data is written
+    Positional,
+    /// Objects keyed by position, marked `x-encoding: positional-array`
+    Objects,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum, Eq, PartialEq)]
+enum Profile {
+    /// Bespoke deserializers enforcing everything observed
+    Strict,
+    /// Derive-based serde; smaller code, faster builds, fewer checks
+    Small,
+}
+
+impl Profile {
+    fn profile(self) -> crate::codegen::CodegenProfile {
+        use crate::codegen::CodegenProfile;
+        match self {
+            Self::Strict => CodegenProfile::Strict,
+            Self::Small => CodegenProfile::Small,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum, Eq, PartialEq)]
+enum Redact {
+    /// Empty strings, zeroed numbers, `None` for optional fields
+    Blank,
+    /// Stable digests in place of strings
+    Hash,
+}
+
+impl Redact {
+    fn mode(self) -> crate::codegen::RedactMode {
+        use crate::codegen::RedactMode;
+        match self {
+            Self::Blank => RedactMode::Blank,
+            Self::Hash => RedactMode::Hash,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum, Eq, PartialEq)]
+enum Separator {
+    /// RFC 7464 JSON text sequences (each document starts with an RS, 0x1E)
+    Rs,
+    /// YAML-style `---` lines between documents
+    Dashes,
+}
+
+impl Separator {
+    fn separator(self) -> crate::inputs::Separator {
+        match self {
+            Self::Rs => crate::inputs::Separator::Rs,
+            Self::Dashes => crate::inputs::Separator::Dashes,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum, Eq, PartialEq)]
+enum SampleMethod {
+    /// A uniform random sample (bottom-k over hashed priorities)
+    Reservoir,
+    /// Every n-th document, from an offset picked by --seed
+    Stride,
+}
+
+impl SampleMethod {
+    fn method(self) -> crate::sampling::Method {
+        match self {
+            Self::Reservoir => crate::sampling::Method::Reservoir,
+            Self::Stride => crate::sampling::Method::Stride,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum, Eq, PartialEq)]
+enum OnError {
+    /// Skip it; the report counts and lists the skipped documents
+    Skip,
+    /// Stop with an error naming it
+    Fail,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum, Eq, PartialEq)]
+enum Sentinels {
+    /// Report only
+    Keep,
+    /// Markers (and null) deserialize as `None`
+    Null,
+    /// One enum variant per marker, next to `Value(…)`
+    Enum,
+}
+
+impl Sentinels {
+    fn policy(self) -> crate::sentinel::SentinelPolicy {
+        use crate::sentinel::SentinelPolicy;
+        match self {
+            Self::Keep => SentinelPolicy::Keep,
+            Self::Null => SentinelPolicy::Null,
+            Self::Enum => SentinelPolicy::Enum,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum, Eq, PartialEq)]
+enum TimeWindow {
+    Hour,
+    Day,
+    Week,
+    Month,
+}
+
+impl TimeWindow {
+    fn window(self) -> crate::timeline::Window {
+        use crate::timeline::Window;
+        match self {
+            Self::Hour => Window::Hour,
+            Self::Day => Window::Day,
+            Self::Week => Window::Week,
+            Self::Month => Window::Month,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum, Eq, PartialEq)]
+enum NumberFormat {
+    Short,
+    Grouped,
+    Raw,
+}
+
+impl NumberFormat {
+    fn format(self) -> crate::human::NumberFormat {
+        use crate::human::NumberFormat as F;
+        match self {
+            Self::Short => F::Short,
+            Self::Grouped => F::Grouped,
+            Self::Raw => F::Raw,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum, Eq, PartialEq)]
+enum StdoutStream {
+    Schema,
+    Rust,
+    IrDebug,
+    IrJson,
+    Report,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum, Eq, PartialEq)]
+enum StdoutFormat {
+    /// Each output as-is, in the order they are produced
+    Plain,
+    /// One JSON object: JSON outputs embedded as values, text outputs as strings
+    Envelope,
+}
+
+// --------------------------- Legacy (hidden) ---------------------------
+
+#[derive(Args, Debug)]
+struct LegacyJsonSchemaOut {
+    #[command(flatten)]
+    input_settings: InputSettings,
+    /// output .json file (stdout if omitted)
+    #[arg(short, long)]
+    out: Option<PathBuf>,
+    #[command(flatten)]
+    common_settings: CommonSettings,
+}
+
+#[derive(Args, Debug)]
+struct LegacyRustOut {
+    #[command(flatten)]
+    input_settings: InputSettings,
+    /// top-level Rust type name
+    #[arg(long, default_value = "Root")]
+    root_type: String,
+    /// output .rs file (stdout if omitted)
+    #[arg(short, long)]
+    out: Option<PathBuf>,
+    #[command(flatten)]
+    common_settings: CommonSettings,
+}
+
+// --------------------------- Impl ---------------------------
+
+impl CommandLineInterface {
+    pub fn load() -> Self {
+        Self::parse()
+    }
+
+    pub fn run(&self) {
+        // decided once here: `colored` would otherwise look at stdout, not the stderr log
+        colored::control::set_override(self.color.enabled());
+        crate::sandbox::configure(self.offline, self.sandbox_audit);
+        crate::portable::configure(self.newline.newline());
+        match &self.cmd {
+            Command::Gen(cfg) => run_gen(cfg),
+            Command::Unify(cfg) => run_unify(cfg),
+            Command::VerifyGenerated(cfg) => run_verify_generated(cfg),
+            Command::Redecide(cfg) => run_redecide(cfg),
+            Command::Explain(cfg) => run_explain(cfg),
+            Command::Score(cfg) => run_score(cfg),
+            Command::Validate(cfg) => run_validate(cfg),
+            Command::Scaffold(cfg) => run_scaffold(cfg),
+            Command::GenCorpus(cfg) => run_gen_corpus(cfg),
+            // Command::Schema(old) => run_legacy_schema(old),
+            // Command::Rust(old) => run_legacy_rust(old),
+        }
+        if let Some(summary) = crate::sandbox::summary() {
+            log_info(summary);
+        }
+    }
+}
+
+// --------------------------- gen ---------------------------
+
+impl Gen {
+    /// `--out DIR`: the bundle outputs not given explicitly, at their standard names in DIR.
+    fn bundled(&self, dir: &Path) -> Gen {
+        std::fs::create_dir_all(dir).unwrap_or_else(|e| {
+            log_error(format!("--out {}: {e}", dir.to_string_lossy()));
+            std::process::exit(2);
+        });
+        let or = |given: &Option<PathBuf>, name: &str| given.clone().or_else(|| Some(dir.join(name)));
+        Gen {
+            schema: or(&self.schema, "schema.json"),
+            rust: or(&self.rust, "models.rs"),
+            report: or(&self.report, "report.md"),
+            ir_json: or(&self.ir_json, "ir.json"),
+            summary: or(&self.summary, "summary.json"),
+            ..self.clone()
+        }
+    }
+
+    /// This run reading `--pair` through a jq filter building the pair object.
+    fn paired(&self) -> Gen {
+        let mut exprs: BTreeMap<&str, &str> = BTreeMap::new();
+        for raw in &self.pair {
+            let Some((role, expr)) = raw.split_once('=').filter(|(r, _)| crate::openapi::ROLES.contains(r)) else {
+                log_error(format!("--pair {raw}: expected request=EXPR or response=EXPR"));
+                std::process::exit(2);
+            };
+            if exprs.insert(role, expr).is_some() {
+                log_error(format!("--pair: {role} given twice"));
+                std::process::exit(2);
+            }
+        }
+        let fields: Vec<String> = crate::openapi::ROLES
+            .iter()
+            .map(|role| format!("{role:?}: ({})", exprs[role]))
+            .collect();
+        let mut input = self.input.clone();
+        input.jq_expr = Some(format!("{{{}}}", fields.join(", ")));
+        Gen { input, ..self.clone() }
+    }
+}
+
+fn run_gen(cfg: &Gen) {
+    let bundled;
+    let cfg = match cfg.out.as_deref() {
+        Some(dir) => {
+            bundled = cfg.bundled(dir);
+            &bundled
+        }
+        None => cfg,
+    };
+    let paired;
+    let cfg = match cfg.pair.is_empty() {
+        true => cfg,
+        false => {
+            paired = cfg.paired();
+            &paired
+        }
+    };
+    if cfg.input.list_inputs {
+        resolve_inputs(&cfg.input);
+    }
+    if cfg.machine {
+        crate::events::enable();
+    }
+    progress(format!("▶︎ began: {}", get_current_pretty_time().bright_magenta()));
+    
+    let start = std::time::Instant::now();
+    
+    // At least one target?
+    if cfg.schema.is_none() && cfg.openapi.is_none() && cfg.rust.is_none() && cfg.ir_debug.is_none()
+        && cfg.ir_json.is_none() && cfg.report.is_none() && cfg.summary.is_none() && cfg.evidence.is_none() && cfg.evidence_json.is_none() && cfg.rust_crate.is_none()
+        && cfg.provenance.is_none() && cfg.stdout_streams.is_empty() && cfg.plugin.is_empty()
+    {
+        log_error("no outputs requested. Use --out DIR, or one or more of --schema, --openapi, --rust, --rust-crate, --ir-debug, --ir-json, --report, --summary, --evidence, --evidence-json, --provenance, --plugin, or --stdout …");
+        std::process::exit(2);
+    }
+
+    // fail before inference rather than after it
+    if crate::sandbox::offline() && !cfg.plugin.is_empty() {
+        log_error(format!("--plugin starts `{}`, which --offline refuses", cfg.plugin_runtime));
+        std::process::exit(2);
+    }
+
+    if cfg.contract_tests && cfg.rust_crate.is_none() && cfg.rust.as_deref().is_none_or(|p| p == Path::new("-")) {
+        log_error("--contract-tests are written beside the models: add --rust FILE or --rust-crate");
+        std::process::exit(2);
+    }
+
+    if cfg.input.empty_strings_as_null && cfg.sentinels == Some(Sentinels::Enum) {
+        log_error("--empty-strings-as-null reads \"\" as `None`, which --sentinels enum can't; use --sentinels null");
+        std::process::exit(2);
+    }
+
+    let wants_report = cfg.report.is_some() || cfg.stdout_streams.contains(&StdoutStream::Report);
+    let mut stdout = StdoutSink::new(cfg.stdout_format);
+    // column stats and histograms are also exported with --evidence-json
+    let exported = cfg.evidence_json.is_some();
+    for (on, flag) in [(cfg.column_stats && !exported, "--column-stats"), (cfg.histograms && !exported, "--histograms"), (cfg.sentinels == Some(Sentinels::Keep), "--sentinels keep"), (cfg.timestamp.is_some(), "--timestamp"), (cfg.size_estimate, "--size-estimate")] {
+        if on && !wants_report {
+            log_warn(format!("{flag} only appears in the report; add --report"));
+        }
+    }
+
+    let timestamp = cfg.timestamp.as_ref().map(|raw| {
+        let path = raw.parse::<JPath>().unwrap_or_else(|e| {
+            log_error(format!("--timestamp: {e}"));
+            std::process::exit(2);
+        });
+        if path.segs().contains(&crate::jpath::Seg::Items) {
+            log_error(format!("--timestamp: {path} must name one value per document (no `[*]`)"));
+            std::process::exit(2);
+        }
+        (path, cfg.timestamp_window.window())
+    });
+
+    // Build merged & normalized summary
+    let sniff_keys = cfg.key.as_deref() == Some("auto");
+    let trackers = Trackers {
+        keys: sniff_keys,
+        columns: cfg.column_stats,
+        numbers: cfg.histograms || cfg.sentinels.is_some(),
+        histograms: cfg.histograms,
+        markers: cfg.sentinels.is_some(),
+        null_groups: cfg.column_stats || cfg.group_nullables,
+        discriminator: cfg.schema_conditionals,
+        timestamp,
+        sizes: cfg.size_estimate,
+        pii: cfg.pii,
+        formats: cfg.formats.as_ref().map(|path| {
+            crate::formats::Formats::load(path).unwrap_or_else(|e| {
+                log_error(format!("--formats: {e}"));
+                std::process::exit(2);
+            })
+        }),
+        evidence: cfg.evidence.is_some() || cfg.evidence_json.is_some(),
+        provenance: cfg.provenance.is_some(),
+        samples: if cfg.revalidate {
+            crate::validate::MAX_SAMPLES
+        } else if !cfg.plugin.is_empty() {
+            crate::plugin::MAX_SAMPLES
+        } else if cfg.rust_crate.is_some() {
+            crate::rust_crate::MAX_SAMPLES
+        } else {
+            0
+        },
+    };
+    let key = match cfg.key.as_deref() {
+        None | Some("auto") => None,
+        Some(raw) => Some(raw.parse::<JPath>().unwrap_or_else(|e| {
+            log_error(format!("--key: {e}"));
+            std::process::exit(2);
+        })),
+    };
+
+    let ir_transform = cfg.ir_transform.as_ref().map(|path| {
+        std::fs::read_to_string(path).unwrap_or_else(|e| {
+            log_error(format!("--ir-transform {}: {e}", path.to_string_lossy()));
+            std::process::exit(2);
+        })
+    });
+    let (mut normalized, mut report, evidence) = compute_and_normalize(&cfg.input, &cfg.common, &trackers);
+    crate::norm_ir::loosen(&mut normalized, cfg.tightness);
+    if !cfg.evidence_counts {
+        crate::norm_ir::strip_support(&mut normalized);
+    }
+    if !cfg.examples {
+        crate::norm_ir::strip_examples(&mut normalized);
+    }
+    let mut normalized = apply_ir_transform(ir_transform.as_deref(), normalized);
+    for (pass, replaced) in crate::rewrite::run_registered(&mut normalized) {
+        log_info(format!("rewrite pass {pass}: {replaced} node(s) replaced"));
+    }
+    let literals = cfg.literals.as_ref().map(|path| {
+        let mut dict = crate::literals::load(path).unwrap_or_else(|e| {
+            log_error(format!("--literals {}: {e}", path.to_string_lossy()));
+            std::process::exit(2);
+        });
+        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        let (retained, changes) = crate::literals::merge(&normalized, &mut dict, &now);
+        for c in &changes {
+            match &c.last_seen {
+                None => log_info(format!("new enum value at {}: {}", c.path, format!("{:?}", c.value).green())),
+                Some(last) => log_info(format!("enum value at {} not seen since {last}: {:?} kept", c.path, c.value)),
+            }
+        }
+        crate::literals::retain(&mut normalized, &retained);
+        report.literals = Some(changes);
+        (path, dict, retained)
+    });
+    report.number_format = cfg.number_format.format();
+    let descriptions = cfg.dictionary.as_ref().map(|path| {
+        let descriptions = crate::descriptions::load(path).unwrap_or_else(|e| {
+            log_error(format!("--dictionary {}: {e}", path.to_string_lossy()));
+            std::process::exit(2);
+        });
+        for at in crate::norm_ir::unmatched(&normalized, &descriptions) {
+            log_warn(format!("--dictionary: no slot at {at}"));
+        }
+        descriptions
+    });
+    let tuple_names = cfg.tuple_names.as_ref().map(|path| {
+        let names = crate::tuple_names::load(path).unwrap_or_else(|e| {
+            log_error(format!("--tuple-names {}: {e}", path.to_string_lossy()));
+            std::process::exit(2);
+        });
+        for at in crate::norm_ir::unmatched(&normalized, &names) {
+            log_warn(format!("--tuple-names: no tuple position at {at}"));
+        }
+        names
+    });
+    let key = key.or_else(|| {
+        if !sniff_keys {
+            return None;
+        }
+        let picked = report.key_candidates.first().map(|p| p.parse::<JPath>().unwrap());
+        match &picked {
+            Some(p) => log_info(format!("record key: {}", p.to_string().green())),
+            None => log_warn("--key auto: no string slot is unique across all documents"),
+        }
+        picked
+    });
+    let ir_root = crate::norm_ir::lower_from_norm(&normalized);
+    report.epochs = crate::epoch::detect(&ir_root);
+    report.epochs_typed = cfg.epoch_timestamps;
+    for x in &report.epochs {
+        let hint = if cfg.epoch_timestamps { "" } else { "; --epoch-timestamps reads it as `DateTime<Utc>`" };
+        log_info(format!("timestamp at {}: {}{hint}", x.path, x.unit.format().green()));
+    }
+    let sentinels = match (cfg.sentinels, report.sentinels.as_ref()) {
+        (Some(policy), Some(found)) => {
+            report.sentinel_policy = policy.policy();
+            for x in found {
+                log_info(format!(
+                    "sentinel at {}: {} in {} of {} value(s)",
+                    x.path, x.mark.to_string().green(), x.count, x.total,
+                ));
+            }
+            crate::sentinel::SentinelModel::new(policy.policy(), found)
+        }
+        _ => crate::sentinel::SentinelModel::default(),
+    };
+    for (x, _) in report.empty_strings.iter().filter(|(_, likely)| *likely) {
+        let hint = if report.ingest.empty_as_null { "" } else { "; --empty-strings-as-null reads it as `None`" };
+        log_info(format!("\"\" looks like null at {}: {} of {} value(s){hint}", x.path, x.count, x.total));
+    }
+    let sentinels = if report.ingest.empty_as_null {
+        let found: Vec<_> = report.empty_strings.iter().map(|(x, _)| x.clone()).collect();
+        sentinels.with_empty_strings(&found)
+    } else {
+        sentinels
+    };
+    for x in report.pii.iter().flatten() {
+        log_info(format!("personal data at {}: {} ({})", x.path, x.tag.kind.as_str().green(), x.tag.sensitivity.as_str()));
+    }
+    for x in report.formats.iter().flatten() {
+        log_info(format!("format at {}: {} ({} value(s))", x.path, x.format.name.green(), x.values));
+    }
+    let formats = report.formats.as_deref().map(crate::formats::rules).unwrap_or_default();
+
+    if crate::events::machine() {
+        crate::events::emit(&Event::Phase { phase: "emit" });
+    }
+
+    // Lower IR once; reuse for multiple emits
+    // let ir_root = crate::lower::lower_to_ir(&u);
+
+    // Names are decided once, by the Rust emitter, and shared with every other output.
+    let mut pinned = cfg.names.as_deref().map(load_names).unwrap_or_default();
+    if !cfg.pair.is_empty() {
+        for role in crate::openapi::ROLES {
+            let at = JPath::root().child(crate::jpath::Seg::Field(role.to_string()));
+            pinned.entry(at.to_string()).or_insert_with(|| crate::codegen::to_type_name(role));
+        }
+    }
+    let want_rust = cfg.rust.is_some() || cfg.rust_crate.is_some() || cfg.stdout_streams.contains(&StdoutStream::Rust);
+    let options = GenOptions {
+        root_type: cfg.root_type.clone(),
+        name_collisions: cfg.name_collisions.to_possible_value().unwrap().get_name().to_string(),
+        key: key.as_ref().map(|k| k.to_string()),
+        group_nullables: cfg.group_nullables,
+        codegen_profile: cfg.codegen_profile.to_possible_value().unwrap().get_name().to_string(),
+        overrides: report.overrides.clone(),
+        sentinels,
+        ir_transform: ir_transform.clone(),
+        rewrites: crate::rewrite::registered(),
+        pii: report.pii.as_deref().map(crate::pii::tags).unwrap_or_default(),
+        formats: formats.clone(),
+        epochs: if cfg.epoch_timestamps { crate::epoch::rules(&report.epochs) } else { Default::default() },
+        redact: cfg.redact.map(Redact::mode),
+        uuid: cfg.rust_uuid,
+        bytes: cfg.rust_bytes,
+        decimal: cfg.rust_decimal,
+        descriptions: descriptions.unwrap_or_default(),
+        tuple_names: tuple_names.unwrap_or_default(),
+        aliases: crate::key_forms::aliases(&report.merged_keys),
+        records: cfg.records_schema,
+        share_types: cfg.share_types,
+        names: pinned,
+        retained: literals.as_ref().map(|(_, _, retained)| retained.clone()).unwrap_or_default(),
+        dropped_arms: crate::outliers::rules(&report.outliers),
+        required_threshold: cfg.common.required_threshold.unwrap_or(crate::required::ALL),
+        int_enums: cfg.common.int_enums.map_or(0, |n| n as usize),
+        grex: cfg.common.grex(),
+        exact_ints: !crate::inference::collapse_ints(),
+        tightness: cfg.tightness,
+        evidence_counts: cfg.evidence_counts,
+        examples: cfg.examples,
+    };
+    let rendered = (want_rust || cfg.names.is_some()).then(|| {
+        let groups: Vec<Vec<JPath>> = report.nullable_groups
+            .iter()
+            .flatten()
+            .map(|g| g.iter().map(|p| p.parse::<JPath>().unwrap()).collect())
+            .collect();
+        render_rust(&normalized, &options, &groups)
+    });
+    if let (Some(path), Some(rendered)) = (cfg.names.as_ref(), rendered.as_ref()) {
+        stdout.write("names", path, &serde_json::to_string_pretty(&rendered.names).unwrap());
+    }
+    if let Some((path, dict, _)) = &literals {
+        stdout.write("literals", path, &serde_json::to_string_pretty(dict).unwrap());
+    }
+
+    // 1) Schema
+    let mut schema_conformance = None;
+    if cfg.schema.is_some() || cfg.openapi.is_some() || cfg.stdout_streams.contains(&StdoutStream::Schema) {
+        let mut schema = crate::norm_ir::schema_from_norm(&normalized);
+        if let Some(disc) = report.discriminator.as_ref() {
+            disc.apply(&mut schema);
+        }
+        if let Some(found) = report.pii.as_ref() {
+            crate::pii::annotate(&mut schema, found);
+        }
+        crate::formats::annotate(&mut schema, &formats);
+        crate::epoch::annotate(&mut schema, &options.epochs);
+        crate::descriptions::annotate(&mut schema, &options.descriptions);
+        if let Some(rendered) = rendered.as_ref().filter(|_| cfg.names.is_some()) {
+            crate::norm_ir::annotate_titles(&mut schema, &rendered.names);
+        }
+        crate::tuple_names::annotate(&mut schema, &options.tuple_names);
+        crate::key_forms::annotate(&mut schema, &options.aliases);
+        if options.share_types {
+            let shared = crate::share::share(&normalized, &options.root_type);
+            log_info(format!("{} shared type(s) across {} path(s)", shared.defs.len(), shared.occurrences()));
+            crate::share::share_schema(&mut schema, &shared);
+        }
+        if cfg.schema_tuples == SchemaTuples::Objects {
+            crate::norm_ir::tuples_as_objects(&mut schema, &options.tuple_names);
+        }
+        if let Some(path) = cfg.openapi.as_ref() {
+            match crate::openapi::skeleton(&schema, &options.root_type, report.docs()) {
+                Some(doc) => stdout.write("openapi", path, &serde_json::to_string_pretty(&doc).unwrap()),
+                None => log_warn("--openapi: the root is no longer the --pair object (see --ir-transform); not written"),
+            }
+        }
+        if options.records {
+            schema = crate::norm_ir::records_schema(schema, &options.root_type, report.ingest.encoding());
+        }
+        // the samples went into the schema, so it has to take them
+        if cfg.revalidate {
+            match crate::schema_check::Checker::new(&schema) {
+                Ok(mut checker) => {
+                    report.samples.iter().for_each(|doc| checker.observe(doc));
+                    schema_conformance = Some(checker.finish());
+                }
+                Err(e) => log_warn(format!("schema not checked: {e}")),
+            }
+        }
+        let schema_src = serde_json::to_string_pretty(&schema).unwrap();
+
+        // file target
+        if let Some(path) = cfg.schema.as_ref() {
+            stdout.write("schema", path, &schema_src);
+        }
+
+        // stdout stream (if requested, even if also wrote file)
+        if cfg.stdout_streams.contains(&StdoutStream::Schema) && cfg.schema.as_deref() != Some(Path::new("-")) {
+            stdout.print("schema", &schema_src);
+        }
+    }
+
+    // 2) Rust
+    if want_rust && let Some(Rendered { models, config, record, metrics, contract_tests, .. }) = rendered {
+        warn_code_outliers(&metrics);
+        report.code.push(metrics);
+        let header = |artifact: &str| Header {
+            artifact: artifact.to_string(),
+            tool: crate::manifest::tool(),
+            policy: crate::manifest::policy_hash(),
+            inputs: report.manifest.clone(),
+            evidence: report.evidence_id.clone(),
+            options: options.clone(),
+            body: String::new(),
+        };
+        let config_src = header("rust-config").stamp(&config);
+        if let Some(path) = cfg.rust_config.as_ref() {
+            stdout.write("rust_config", path, &config_src);
+        }
+        let rust_src = header("rust").stamp(&models);
+        if let Some(dir) = cfg.rust_crate.as_ref() {
+            let sources = crate::rust_crate::CrateSources {
+                models: &rust_src,
+                config: &config_src,
+                root_type: &cfg.root_type,
+                record: &record,
+                samples: &report.samples[..report.samples.len().min(crate::rust_crate::MAX_SAMPLES)],
+                contract_tests: cfg.contract_tests.then_some(contract_tests.as_str()),
+            };
+            crate::rust_crate::write_crate(dir, &sources)
+                .unwrap_or_else(|e| panic!("failed to write crate ({}): {e}", dir.to_string_lossy()));
+            stdout.wrote("rust_crate", dir);
+            if !crate::events::machine() {
+                log_info(format!("crate written: {}", dir.to_string_lossy().green()));
+            }
+        }
+        if let Some(path) = cfg.rust.as_ref() {
+            stdout.write("rust", path, &rust_src);
+            if cfg.contract_tests && path != Path::new("-") {
+                stdout.write("contract_tests", &path.with_file_name("contract_tests.rs"), &contract_tests);
+            }
+        }
+        if cfg.stdout_streams.contains(&StdoutStream::Rust) && cfg.rust.as_deref() != Some(Path::new("-")) {
+            stdout.print("rust", &rust_src);
+        }
+    }
+
+    // 3) IR debug (human pretty; not JSON)
+    if cfg.ir_debug.is_some() || cfg.stdout_streams.contains(&StdoutStream::IrDebug) {
+        let mut ir_txt = format!("{:#?}", ir_root);
+        if let Some(provenance) = &report.provenance {
+            ir_txt.push_str(&provenance.render());
+        }
+        if let Some(path) = cfg.ir_debug.as_ref() {
+            stdout.write("ir_debug", path, &ir_txt);
+        }
+        if cfg.stdout_streams.contains(&StdoutStream::IrDebug) && cfg.ir_debug.as_deref() != Some(Path::new("-")) {
+            stdout.print("ir_debug", &ir_txt);
+        }
+    }
+
+    // 4) Normalized IR (JSON; machine-readable, consumed by `unify`)
+    if cfg.ir_json.is_some() || cfg.stdout_streams.contains(&StdoutStream::IrJson) {
+        let ir_json = serde_json::to_string_pretty(&normalized).unwrap();
+        if let Some(path) = cfg.ir_json.as_ref() {
+            stdout.write("ir_json", path, &ir_json);
+        }
+        if cfg.stdout_streams.contains(&StdoutStream::IrJson) && cfg.ir_json.as_deref() != Some(Path::new("-")) {
+            stdout.print("ir_json", &ir_json);
+        }
+    }
+
+    // 5) Evidence snapshot (for `verify-generated`) and its documented JSON export
+    if let (Some(path), Some(evidence)) = (cfg.evidence_json.as_ref(), evidence.as_ref()) {
+        let tree = crate::evidence_json::export(evidence, &report);
+        stdout.write("evidence_json", path, &serde_json::to_string_pretty(&tree).unwrap());
+    }
+    if let (Some(path), Some(evidence)) = (cfg.evidence.as_ref(), evidence) {
+        let evd = EvidenceFile {
+            format: crate::manifest::EVIDENCE_FORMAT.to_string(),
+            tool: crate::manifest::tool(),
+            policy: crate::manifest::policy_hash(),
+            ingest: report.ingest.clone(),
+            inputs: report.inputs(),
+            manifest: report.manifest.clone(),
+            id: report.evidence_id.clone(),
+            evidence,
+        };
+        stdout.write("evidence", path, &serde_json::to_string(&evd).unwrap());
+    }
+
+    // 6) Re-validate samples against the generated types
+    if cfg.revalidate {
+        let rv = crate::validate::revalidate(&ir_root, &report.samples);
+        if rv.failed == 0 {
+            log_info(format!("revalidated {} sample(s): all pass", rv.checked.to_string().green()));
+        } else {
+            log_warn(format!(
+                "revalidated {} sample(s): {} fail; suggested relaxations:",
+                rv.checked,
+                rv.failed.to_string().red(),
+            ));
+            for sug in &rv.suggestions {
+                let line = format!("{} at {} ({} doc(s), first: sample {})", sug.relax, sug.path, sug.docs, sug.first + 1);
+                if crate::events::machine() {
+                    crate::events::emit(&Event::Warning { message: format!("suggested relaxation: {line}") });
+                } else {
+                    eprintln!("  - {line}");
+                }
+            }
+        }
+        if let Some(c) = &schema_conformance {
+            if c.failed == 0 {
+                log_info(format!("checked {} sample(s) against the schema: all pass", c.docs.to_string().green()));
+            } else {
+                log_warn(format!("checked {} sample(s) against the schema: {} fail", c.docs, c.failed.to_string().red()));
+                for p in &c.problems {
+                    let line = format!("{} at {}: {} ({} doc(s), first: sample {})", p.keyword, p.path, p.message, p.docs, p.first + 1);
+                    if crate::events::machine() {
+                        crate::events::emit(&Event::Warning { message: format!("schema check: {line}") });
+                    } else {
+                        eprintln!("  - {line}");
+                    }
+                }
+            }
+        }
+        report.revalidation = Some(crate::validate::Revalidation { schema: schema_conformance, ..rv });
+    }
+
+    // 7) Plugins
+    for module in &cfg.plugin {
+        let name = module.file_name().unwrap_or(module.as_os_str()).to_string_lossy().to_string();
+        let request = crate::plugin::Request {
+            abi: crate::plugin::ABI,
+            ir: &normalized,
+            docs: report.docs(),
+            sources: report.sources.iter().map(|s| s.path.as_str()).collect(),
+            samples: &report.samples[..report.samples.len().min(crate::plugin::MAX_SAMPLES)],
+        };
+        let response = crate::plugin::run(&cfg.plugin_runtime, module, &request).unwrap_or_else(|e| {
+            log_error(format!("--plugin {}: {e}", module.to_string_lossy()));
+            std::process::exit(2);
+        });
+        for w in response.warnings {
+            log_warn(format!("{name}: {}{}", w.path.as_ref().map(|p| format!("{p}: ")).unwrap_or_default(), w.message));
+            report.plugin_warnings.push((name.clone(), w));
+        }
+        for out in &response.outputs {
+            let path = crate::plugin::output_path(&cfg.plugin_out, &out.name).unwrap_or_else(|e| {
+                log_error(format!("--plugin {}: {e}", module.to_string_lossy()));
+                std::process::exit(2);
+            });
+            stdout.write("plugin", &path, &out.contents);
+        }
+    }
+
+    // 8) Report (Markdown)
+    if wants_report {
+        let report_md = report.render_markdown();
+        if let Some(path) = cfg.report.as_ref() {
+            stdout.write("report", path, &report_md);
+        }
+        if cfg.stdout_streams.contains(&StdoutStream::Report) && cfg.report.as_deref() != Some(Path::new("-")) {
+            stdout.print("report", &report_md);
+        }
+    }
+
+    if let Some(path) = cfg.provenance.as_ref() {
+        let provenance = report.provenance.clone().unwrap_or_default();
+        stdout.write("provenance", path, &serde_json::to_string_pretty(&provenance.to_json(report.docs())).unwrap());
+    }
+
+    // 9) Summary (JSON; lists everything written above)
+    if let Some(path) = cfg.summary.as_ref() {
+        let summary = report.summary(&stdout.written);
+        stdout.write("summary", path, &serde_json::to_string_pretty(&summary).unwrap());
+    }
+    stdout.finish();
+
+    {
+        let elapsed = start.elapsed();
+        if crate::events::machine() {
+            crate::events::emit(&Event::Finished { elapsed_ms: elapsed.as_millis() });
+        } else {
+            let f = report.number_format;
+            log_info(format!(
+                "inference took {}: {} docs, {} read ({} docs/s)",
+                format_duration(elapsed),
+                f.count(report.docs()),
+                f.bytes(report.bytes()),
+                f.rate(report.docs(), elapsed),
+            ));
+        }
+    }
+}
+
+/// `ir` rewritten by an `--ir-transform` program, if any.
+fn apply_ir_transform(program: Option<&str>, ir: NTy) -> NTy {
+    let Some(program) = program else { return ir };
+    crate::jq_exec::transform_ir(program, &ir).unwrap_or_else(|e| {
+        log_error(format!("--ir-transform: {e}"));
+        std::process::exit(2);
+    })
+}
+
+/// Output of [`render_rust`]; sources carry no header yet.
+struct Rendered {
+    models: String,
+    config: String,
+    /// Rust type the root resolved to.
+    record: String,
+    metrics: crate::metrics::CodeMetrics,
+    /// The naming manifest (see [`crate::codegen::Codegen::names`]).
+    names: BTreeMap<String, String>,
+    /// `contract_tests.rs` (see [`crate::codegen::Codegen::contract_tests`]).
+    contract_tests: String,
+}
+
+/// The naming manifest at `path` (`--names`); empty if it doesn't exist yet.
+fn load_names(path: &Path) -> BTreeMap<String, String> {
+    if !path.exists() {
+        return BTreeMap::new();
+    }
+    let src = std::fs::read_to_string(path).map_err(|e| e.to_string());
+    src.and_then(|src| serde_json::from_str(&src).map_err(|e| e.to_string())).unwrap_or_else(|e| {
+        log_error(format!("--names {}: {e}", path.to_string_lossy()));
+        std::process::exit(2);
+    })
+}
+
+/// Rust models and `generated_config.rs` for a lowered IR.
+/// `groups` is only used with `options.group_nullables`.
+fn render_rust(normalized: &NTy, options: &GenOptions, groups: &[Vec<JPath>]) -> Rendered {
+    let naming = NameCollisions::from_str(&options.name_collisions, true).unwrap_or_else(|e| {
+        log_error(format!("name collision policy: {e}"));
+        std::process::exit(2);
+    });
+    let key = options.key.as_ref().map(|k| k.parse::<JPath>().unwrap_or_else(|e| {
+        log_error(format!("--key: {e}"));
+        std::process::exit(2);
+    }));
+    let profile = Profile::from_str(&options.codegen_profile, true).unwrap_or_else(|e| {
+        log_error(format!("codegen profile: {e}"));
+        std::process::exit(2);
+    });
+    let mut cg = crate::codegen::Codegen::new()
+        .with_naming(naming.policy())
+        .with_profile(profile.profile())
+        .with_pii(&options.pii)
+        .with_formats(&options.formats)
+        .with_epochs(&options.epochs)
+        .with_descriptions(&options.descriptions)
+        .with_tuple_names(&options.tuple_names)
+        .with_aliases(&options.aliases)
+        .with_records(options.records)
+        .with_redact(options.redact)
+        .with_uuid(options.uuid)
+        .with_bytes(options.bytes)
+        .with_decimal(options.decimal)
+        .with_patterns(options.grex.enabled)
+        .with_tightness(options.tightness)
+        .with_names(&options.names);
+    if options.examples {
+        cg = cg.with_examples(&crate::norm_ir::examples(normalized));
+    }
+    if let Some(key) = key.as_ref() {
+        cg = cg.with_key(key);
+    }
+    if options.group_nullables {
+        cg = cg.with_nullable_groups(groups);
+    }
+    if options.share_types {
+        cg = cg.with_shared(&crate::share::share(normalized, &options.root_type).names());
+    }
+    let modeled = crate::sentinel::model(&crate::norm_ir::lower_from_norm(normalized), &options.sentinels);
+    let record = cg.emit(&modeled, &options.root_type);
+    exit_on_name_collisions(cg.collisions());
+    if let Some(key) = key.as_ref() && let Err(e) = cg.emit_record_key(&modeled, &record, key) {
+        log_error(e.to_string());
+        std::process::exit(2);
+    }
+    let config = cg.generated_config(key.as_ref());
+    let metrics = cg.metrics("models");
+    let names = cg.names().clone();
+    let contract_tests = cg.contract_tests("super::models");
+    self_check(&cg, &config, &contract_tests);
+    Rendered { models: cg.into_string(), config, record, metrics, names, contract_tests }
+}
+
+/// Stop before writing generated Rust rustc would reject, naming the items at
+/// fault and the JSON paths they were generated for (see [`crate::rust_check`]).
+fn self_check(cg: &crate::codegen::Codegen, config: &str, contract_tests: &str) {
+    const SHOWN: usize = 10;
+    let mut problems: Vec<String> = crate::rust_check::check(cg.source())
+        .into_iter()
+        .map(|p| {
+            let at = p.item.as_deref().map(|item| cg.origin(item, p.member.as_deref())).unwrap_or_default();
+            match at.is_empty() {
+                true => format!("models, {p}"),
+                false => format!("models, {p} (generated for {})", at.join(", ")),
+            }
+        })
+        .collect();
+    problems.extend(crate::rust_check::check(config).into_iter().map(|p| format!("generated_config.rs, {p}")));
+    problems.extend(crate::rust_check::check(contract_tests).into_iter().map(|p| format!("contract_tests.rs, {p}")));
+    if problems.is_empty() {
+        return;
+    }
+    for p in problems.iter().take(SHOWN) {
+        log_error(format!("generated Rust: {p}"));
+    }
+    if problems.len() > SHOWN {
+        log_error(format!("… and {} more", problems.len() - SHOWN));
+    }
+    log_error("the generated Rust would not compile; nothing was written (please report this with the paths above)");
+    std::process::exit(2);
+}
+
+// --------------------------- verify-generated ---------------------------
+
+fn run_verify_generated(cfg: &VerifyGenerated) {
+    let artifact_str = cfg.artifact.to_string_lossy();
+    let src = std::fs::read_to_string(&cfg.artifact)
+        .unwrap_or_else(|e| panic!("read failed ({artifact_str}): {e}"));
+    let src = crate::portable::lf(&src);
+    let (header, body) = Header::parse(&src).unwrap_or_else(|e| {
+        log_error(format!("{artifact_str}: {e}"));
+        std::process::exit(2);
+    });
+    let evd_str = cfg.against.to_string_lossy();
+    let evd: EvidenceFile = serde_json::from_str(
+        &std::fs::read_to_string(&cfg.against).unwrap_or_else(|e| panic!("read failed ({evd_str}): {e}")),
+    )
+    .unwrap_or_else(|e| panic!("evidence parse error ({evd_str}): {e}"));
+
+    if let Some(path) = cfg.policy.as_ref() {
+        install_policy(path);
+    }
+    let mut checks = crate::manifest::verify(&header, body, &evd);
+
+    // Regenerate from the evidence when this binary would produce the same output.
+    let regen = "regenerated";
+    if header.tool != crate::manifest::tool() {
+        checks.push(crate::manifest::Check::skipped(regen, format!("artifact made by {}, this is {}", header.tool, crate::manifest::tool())));
+    } else if header.policy != crate::manifest::policy_hash() {
+        checks.push(crate::manifest::Check::skipped(regen, "inference policy differs from this build (or from --policy)"));
+    } else if header.options.rewrites != crate::rewrite::registered() {
+        checks.push(crate::manifest::Check::skipped(regen, "rewrite passes differ from this build"));
+    } else if header.options.group_nullables {
+        checks.push(crate::manifest::Check::skipped(regen, "--group-nullables depends on data not kept in the evidence"));
+    } else {
+        crate::inference::set_collapse_ints(!header.options.exact_ints);
+        let mut evidence = evd.evidence.clone();
+        crate::outliers::drop(&mut evidence, &header.options.dropped_arms);
+        let mut normalized = crate::norm_ir::normalize_with(
+            evidence,
+            &header.options.overrides,
+            header.options.required_threshold,
+            header.options.int_enums,
+            header.options.grex,
+        );
+        crate::norm_ir::loosen(&mut normalized, header.options.tightness);
+        if !header.options.evidence_counts {
+            crate::norm_ir::strip_support(&mut normalized);
+        }
+        if !header.options.examples {
+            crate::norm_ir::strip_examples(&mut normalized);
+        }
+        let mut normalized = apply_ir_transform(header.options.ir_transform.as_deref(), normalized);
+        crate::rewrite::run_registered(&mut normalized);
+        crate::literals::retain(&mut normalized, &header.options.retained);
+        let rendered = render_rust(&normalized, &header.options, &[]);
+        let expected = if header.artifact == "rust-config" { rendered.config } else { rendered.models };
+        let hash = crate::manifest::sha256(expected.as_bytes());
+        checks.push(crate::manifest::Check {
+            what: regen,
+            ok: Some(hash == header.body),
+            detail: format!("{} from evidence: {hash}", header.artifact),
+        });
+    }
+
+    let mut failed = false;
+    for c in &checks {
+        let tag = match c.ok {
+            Some(true) => "[OK]".green(),
+            Some(false) => "[FAIL]".red(),
+            None => "[SKIP]".yellow(),
+        };
+        failed |= c.ok == Some(false);
+        println!("{tag} {}: {}", c.what, c.detail);
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+// --------------------------- redecide ---------------------------
+
+fn run_redecide(cfg: &Redecide) {
+    if cfg.rust.is_none() && cfg.schema.is_none() && cfg.ir_json.is_none() && cfg.overrides.is_none() {
+        log_error("nothing to regenerate. Use one or more of --rust, --schema, --ir-json, --overrides");
+        std::process::exit(2);
+    }
+    let path = cfg.path.parse::<JPath>().unwrap_or_else(|e| {
+        log_error(format!("--path: {e}"));
+        std::process::exit(2);
+    });
+    let evd_str = cfg.evidence.to_string_lossy();
+    let evd: EvidenceFile = serde_json::from_str(
+        &std::fs::read_to_string(&cfg.evidence).unwrap_or_else(|e| panic!("read failed ({evd_str}): {e}")),
+    )
+    .unwrap_or_else(|e| panic!("evidence parse error ({evd_str}): {e}"));
+    if evd.format != crate::manifest::EVIDENCE_FORMAT {
+        log_error(format!("{evd_str}: expected {}, found {}", crate::manifest::EVIDENCE_FORMAT, evd.format));
+        std::process::exit(2);
+    }
+    if let Some(path) = cfg.policy.as_ref() {
+        install_policy(path);
+    }
+    if evd.policy != crate::manifest::policy_hash() {
+        log_error(format!("{evd_str}: made by {} with a different inference policy (or --policy); rerun gen", evd.tool));
+        std::process::exit(2);
+    }
+    // Both hypotheses are kept in the evidence; only the decision between them changes.
+    let Some(arr) = crate::overrides::evidence_at(&evd.evidence, &path).and_then(|u| u.arr.as_ref()) else {
+        log_error(format!("--path: no array observed at {path} in {evd_str}"));
+        std::process::exit(2);
+    };
+    log_info(format!(
+        "{path} as {}: {} array(s), {}..={} item(s)",
+        cfg.shape.to_possible_value().unwrap().get_name().green(),
+        arr.samples,
+        arr.len_min,
+        arr.len_max,
+    ));
+
+    let models = cfg.rust.as_ref().map(|rust| {
+        let rust_str = rust.to_string_lossy();
+        let src = std::fs::read_to_string(rust).unwrap_or_else(|e| panic!("read failed ({rust_str}): {e}"));
+        let src = crate::portable::lf(&src);
+        let (header, _) = Header::parse(&src).unwrap_or_else(|e| {
+            log_error(format!("{rust_str}: {e}"));
+            std::process::exit(2);
+        });
+        if header.artifact != "rust" {
+            log_error(format!("{rust_str}: expected `gen --rust` output, found {}", header.artifact));
+            std::process::exit(2);
+        }
+        if header.evidence != evd.id {
+            log_error(format!("{rust_str} was generated from other evidence ({}, not {})", header.evidence, evd.id));
+            std::process::exit(2);
+        }
+        if header.options.group_nullables {
+            log_error(format!("{rust_str}: --group-nullables depends on data not kept in the evidence; rerun gen"));
+            std::process::exit(2);
+        }
+        header
+    });
+
+    let mut overrides = models.as_ref().map(|h| h.options.overrides.clone()).unwrap_or_default();
+    if let Some(file) = cfg.overrides.as_ref() {
+        let mut saved = crate::overrides::Overrides::load_or_default(file).unwrap_or_else(|e| {
+            log_error(format!("--overrides: {e}"));
+            std::process::exit(2);
+        });
+        saved.0.insert(path.to_string(), cfg.shape.decision());
+        saved
+            .save(file)
+            .unwrap_or_else(|e| panic!("failed to write overrides ({}): {e}", file.to_string_lossy()));
+        overrides.0.extend(saved.0);
+    }
+    overrides.0.insert(path.to_string(), cfg.shape.decision());
+
+    let ir_transform = models.as_ref().and_then(|h| h.options.ir_transform.clone());
+    let tightness = models.as_ref().map_or(crate::norm_ir::MAX_TIGHTNESS, |h| h.options.tightness);
+    let evidence_counts = models.as_ref().is_some_and(|h| h.options.evidence_counts);
+    let mut evidence = evd.evidence.clone();
+    if let Some(header) = models.as_ref() {
+        crate::inference::set_collapse_ints(!header.options.exact_ints);
+        crate::outliers::drop(&mut evidence, &header.options.dropped_arms);
+    }
+    let required_threshold = models.as_ref().map_or(crate::required::ALL, |h| h.options.required_threshold);
+    let int_enums = models.as_ref().map_or(0, |h| h.options.int_enums);
+    let grex = models.as_ref().map_or(Grex::DEFAULT, |h| h.options.grex);
+    let mut normalized = crate::norm_ir::normalize_with(evidence, &overrides, required_threshold, int_enums, grex);
+    crate::norm_ir::loosen(&mut normalized, tightness);
+    if !evidence_counts {
+        crate::norm_ir::strip_support(&mut normalized);
+    }
+    if !models.as_ref().is_some_and(|h| h.options.examples) {
+        crate::norm_ir::strip_examples(&mut normalized);
+    }
+    let mut normalized = apply_ir_transform(ir_transform.as_deref(), normalized);
+    crate::rewrite::run_registered(&mut normalized);
+    if let Some(header) = models.as_ref() {
+        crate::literals::retain(&mut normalized, &header.options.retained);
+    }
+    let mut stdout = StdoutSink::new(StdoutFormat::Plain);
+
+    let rendered = models.map(|header| {
+        let options = GenOptions { overrides, ..header.options };
+        let rendered = render_rust(&normalized, &options, &[]);
+        (options, rendered)
+    });
+    if let Some(path) = cfg.schema.as_ref() {
+        let mut schema = crate::norm_ir::schema_from_norm(&normalized);
+        if let Some((options, rendered)) = rendered.as_ref() {
+            crate::pii::annotate_tags(&mut schema, &options.pii);
+            crate::formats::annotate(&mut schema, &options.formats);
+            crate::epoch::annotate(&mut schema, &options.epochs);
+            crate::descriptions::annotate(&mut schema, &options.descriptions);
+            if !options.names.is_empty() {
+                crate::norm_ir::annotate_titles(&mut schema, &rendered.names);
+            }
+            crate::tuple_names::annotate(&mut schema, &options.tuple_names);
+            crate::key_forms::annotate(&mut schema, &options.aliases);
+            if options.share_types {
+                crate::share::share_schema(&mut schema, &crate::share::share(&normalized, &options.root_type));
+            }
+            if options.records {
+                schema = crate::norm_ir::records_schema(schema, &options.root_type, evd.ingest.encoding());
+            }
+        }
+        stdout.write("schema", path, &serde_json::to_string_pretty(&schema).unwrap());
+    }
+    if let (Some(path), Some((options, rendered))) = (cfg.rust.as_ref(), rendered) {
+        let Rendered { models, config, metrics, .. } = rendered;
+        warn_code_outliers(&metrics);
+        let header = |artifact: &str| Header {
+            artifact: artifact.to_string(),
+            tool: crate::manifest::tool(),
+            policy: crate::manifest::policy_hash(),
+            inputs: evd.manifest.clone(),
+            evidence: evd.id.clone(),
+            options: options.clone(),
+            body: String::new(),
+        };
+        if let Some(config_path) = cfg.rust_config.as_ref() {
+            stdout.write("rust_config", config_path, &header("rust-config").stamp(&config));
+        }
+        stdout.write("rust", path, &header("rust").stamp(&models));
+    }
+    if let Some(path) = cfg.ir_json.as_ref() {
+        stdout.write("ir_json", path, &serde_json::to_string_pretty(&normalized).unwrap());
+    }
+    stdout.finish();
+}
+
+// --------------------------- explain ---------------------------
+
+fn run_explain(cfg: &Explain) {
+    let path = cfg.path.parse::<JPath>().unwrap_or_else(|e| {
+        log_error(format!("--path: {e}"));
+        std::process::exit(2);
+    });
+    let mut w = crate::explain::Witnesses::new(path, cfg.witnesses);
+    for_each_document(&cfg.input, |doc, source| {
+        w.observe(doc, cfg.provenance.then_some(source));
+    });
+    print!("{}", w.render());
+}
+
+/// Serial walk over every input document (after `--jq-expr`), with its source location.
+/// The `gen` pipeline reads in parallel; this is for commands that need input order.
+/// Documents that can't be read are skipped and counted, or stop the run (`--on-error`).
+fn for_each_document(input: &InputSettings, mut f: impl FnMut(&Value, &str)) {
+    let source_paths = resolve_inputs(input);
+    let mut docs = 0u64;
+    let mut failed: Option<ParseFailures> = None;
+    for path in &source_paths {
+        let path_str = path.to_string_lossy().to_string();
+        let src = if path.as_os_str() == "-" {
+            let mut buf = String::new();
+            io::stdin().read_to_string(&mut buf).expect("failed to read stdin");
+            buf
+        } else {
+            std::fs::read_to_string(path)
+                .unwrap_or_else(|e| panic!("read failed ({path_str}): {e}"))
+        };
+        let mut emit = |text: &str, line: Option<usize>| {
+            let loc = match line {
+                Some(line) => format!("{path_str}:{line}"),
+                None => path_str.clone(),
             };
-            fn apply_sources(
-                jq_expr: Option<&String>,
-                input: &Value,
-                path_str: &str,
-            ) -> U {
-                let sources = match jq_expr.as_ref() {
-                    None => {
-                        vec![input.clone()]
-                    },
-                    Some(expr) => {
-                        crate::jq_exec::run_jaq(expr, input)
-                            .unwrap_or_else(|e| panic!("jq failed ({path_str}): {e}"))
-                            .into_iter()
-                            .map(|t| {
-                                serde_json::from_str::<Value>(&t).unwrap_or_else(|e| {
-                                    panic!("jq output not JSON ({path_str}): {e}\n{t}")
-                                })
-                            })
-                            .collect::<Vec<_>>()
+            let read = serde_json::from_str::<Value>(text)
+                .map_err(|e| e.to_string())
+                .and_then(|v| match input.jq_expr.as_ref() {
+                    None => Ok(vec![(v, loc.clone())]),
+                    Some(expr) => crate::ingest::jq_documents(expr, &v, input.require_match).map(|outs| {
+                        outs.into_iter().enumerate().map(|(i, pv)| (pv, format!("{loc}#{}", i + 1))).collect()
+                    }),
+                });
+            match read {
+                Ok(read) => {
+                    for (v, loc) in &read {
+                        docs += 1;
+                        f(v, loc);
+                    }
+                }
+                Err(message) => {
+                    let d = Diagnostic { file: path_str.clone(), line: line.map(|l| l as u64), path: None, message };
+                    if input.on_error == OnError::Fail {
+                        log_error(&d);
+                        log_error("--on-error fail: a document could not be read");
+                        std::process::exit(2);
                     }
-                };
-                sources
-                    .into_par_iter()
-                    .map(|pv| {
-                        observe_value(&pv)
-                    })
-                    .reduce(
-                        || U::empty(),
-                        |a, b| U::join(&a, &b)
-                    )
+                    let one = ParseFailures::one(d);
+                    failed = Some(match failed.take() {
+                        Some(failed) => ParseFailures::join(failed, one),
+                        None => one,
+                    });
+                }
+            }
+        };
+        if let Some(sep) = input.separator {
+            for (line, text) in crate::inputs::records(&src, sep.separator()) {
+                emit(text, Some(line));
+            }
+        } else if input.ndjson {
+            for (i, line) in src.lines().enumerate() {
+                let line = line.trim();
+                if !line.is_empty() {
+                    emit(line, Some(i + 1));
+                }
             }
-            if ndjson {
-                src .lines()
-                    .enumerate()
-                    .filter_map(|(i, line)| {
-                        let line = line.trim();
-                        if line.is_empty() {
-                            return None
-                        }
-                        let v: Value = serde_json::from_str(line).unwrap_or_else(|e| {
-                            panic!("NDJSON parse error {path_str}:{}: {e}\n{line}", i + 1)
-                        });
-                        Some(apply_sources(jq_expr.as_ref(), &v, &path_str))
-                    })
-                    .fold(
-                        U::empty(),
-                        |a, b| U::join(&a, &b)
-                    )
+        } else {
+            emit(&src, None);
+        }
+    }
+    if let Some(failed) = failed {
+        warn_skipped(&failed, docs);
+    }
+}
+
+// --------------------------- score ---------------------------
+
+fn run_score(cfg: &Score) {
+    let truth_str = cfg.truth.to_string_lossy();
+    let truth: Value = serde_json::from_str(
+        &std::fs::read_to_string(&cfg.truth).unwrap_or_else(|e| panic!("read failed ({truth_str}): {e}")),
+    )
+    .unwrap_or_else(|e| panic!("schema parse error ({truth_str}): {e}"));
+    let (normalized, _, _) = compute_and_normalize(&cfg.input, &cfg.common, &Trackers::default());
+    let score = crate::score::score(&crate::norm_ir::schema_from_norm(&normalized), &truth);
+    if cfg.json {
+        println!("{}", serde_json::to_string_pretty(&score).unwrap());
+    } else {
+        print!("{}", score.render());
+    }
+}
+
+// --------------------------- validate ---------------------------
+
+fn run_validate(cfg: &Validate) {
+    let schema_str = cfg.schema.to_string_lossy();
+    let schema: Value = std::fs::read_to_string(&cfg.schema)
+        .map_err(|e| e.to_string())
+        .and_then(|src| serde_json::from_str(&src).map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| {
+            log_error(format!("--schema {schema_str}: {e}"));
+            std::process::exit(2);
+        });
+    match cfg.mode {
+        ValidateMode::Enums => {
+            let mut coverage = crate::enum_coverage::Coverage::new(&schema);
+            if coverage.is_empty() {
+                log_warn(format!("{schema_str} has no enums"));
+            }
+            for_each_document(&cfg.input, |doc, _| coverage.observe(doc));
+            let docs = coverage.docs;
+            let found = coverage.finish();
+            if cfg.json {
+                println!("{}", serde_json::to_string_pretty(&found).unwrap());
             } else {
-                let root = serde_json::from_str::<serde_json::Value>(&src).unwrap_or_else(|e| {
-                    panic!("JSON parse error ({path_str}): {e}")
-                });
-                apply_sources(jq_expr.as_ref(), &root, &path_str)
+                print!("{}", crate::enum_coverage::render(&found, docs));
+            }
+            if !found.iter().all(|e| e.exhaustive()) {
+                std::process::exit(1);
+            }
+        }
+        ValidateMode::Documents => {
+            let mut checker = crate::schema_check::Checker::new(&schema).unwrap_or_else(|e| {
+                log_error(format!("--schema {schema_str}: {e}"));
+                std::process::exit(2);
+            });
+            for_each_document(&cfg.input, |doc, _| checker.observe(doc));
+            let found = checker.finish();
+            if cfg.json {
+                println!("{}", serde_json::to_string_pretty(&found).unwrap());
+            } else {
+                print!("{}", crate::schema_check::render(&found));
+            }
+            if found.failed > 0 {
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+// --------------------------- gen-corpus ---------------------------
+
+fn run_gen_corpus(cfg: &GenCorpus) {
+    if !(0.0..=1.0).contains(&cfg.null_pad) {
+        log_error("--null-pad must be between 0 and 1");
+        std::process::exit(2);
+    }
+    let spec = crate::corpus::CorpusSpec {
+        docs: cfg.docs,
+        max_bytes: cfg.max_bytes,
+        seed: cfg.seed,
+        tuple_arity: cfg.tuple_arity,
+        arity_drift: cfg.arity_drift,
+        null_pad: cfg.null_pad,
+        enum_cardinality: cfg.enum_cardinality,
+        depth: cfg.depth,
+        blob_len: cfg.blob_len,
+    };
+    let out_str = cfg.out.to_string_lossy();
+    let mut out: Box<dyn Write> = if cfg.out == Path::new("-") {
+        Box::new(io::stdout().lock())
+    } else {
+        let file = std::fs::File::create(&cfg.out).unwrap_or_else(|e| panic!("create failed ({out_str}): {e}"));
+        Box::new(io::BufWriter::new(file))
+    };
+    let mut bytes = 0;
+    let docs = crate::corpus::generate(&spec, |doc| {
+        let line = serde_json::to_string(doc).unwrap();
+        writeln!(out, "{line}").unwrap_or_else(|e| panic!("write failed ({out_str}): {e}"));
+        bytes += line.len() as u64 + 1;
+        line.len() as u64 + 1
+    });
+    out.flush().unwrap_or_else(|e| panic!("write failed ({out_str}): {e}"));
+    if let Some(path) = cfg.truth.as_ref() {
+        write_sink(path, &serde_json::to_string_pretty(&crate::corpus::truth_schema(&spec)).unwrap()).unwrap();
+    }
+    let f = crate::human::NumberFormat::Short;
+    log_info(format!("{} docs, {} written", f.count(docs).green(), f.bytes(bytes)));
+}
+
+// --------------------------- scaffold ---------------------------
+
+fn run_scaffold(cfg: &Scaffold) {
+    let read = |path: &Path| {
+        std::fs::read_to_string(path).unwrap_or_else(|e| {
+            log_error(format!("{}: {e}", path.to_string_lossy()));
+            std::process::exit(2);
+        })
+    };
+    let norm: NTy = serde_json::from_str(&read(&cfg.ir)).unwrap_or_else(|e| {
+        log_error(format!("--ir {}: {e}", cfg.ir.to_string_lossy()));
+        std::process::exit(2);
+    });
+    let sample = read(&cfg.sample);
+    if let Err(e) = serde_json::from_str::<serde_json::Value>(&sample) {
+        log_error(format!("--sample {}: not one JSON document: {e}", cfg.sample.to_string_lossy()));
+        std::process::exit(2);
+    }
+    let options = GenOptions {
+        root_type: cfg.root_type.clone(),
+        name_collisions: cfg.name_collisions.to_possible_value().unwrap().get_name().to_string(),
+        key: None,
+        group_nullables: false,
+        codegen_profile: cfg.codegen_profile.to_possible_value().unwrap().get_name().to_string(),
+        overrides: Default::default(),
+        sentinels: Default::default(),
+        ir_transform: None,
+        rewrites: Vec::new(),
+        pii: Default::default(),
+        formats: Default::default(),
+        epochs: Default::default(),
+        redact: None,
+        uuid: false,
+        bytes: false,
+        decimal: false,
+        descriptions: Default::default(),
+        tuple_names: Default::default(),
+        aliases: Default::default(),
+        records: false,
+        share_types: false,
+        names: Default::default(),
+        retained: Default::default(),
+        dropped_arms: Default::default(),
+        required_threshold: crate::required::ALL,
+        int_enums: 0,
+        grex: Grex::DEFAULT,
+        exact_ints: !crate::inference::COLLAPSE_INT_INTO_NUMBER,
+        tightness: crate::norm_ir::MAX_TIGHTNESS,
+        evidence_counts: false,
+        examples: false,
+    };
+    let rendered = render_rust(&norm, &options, &[]);
+    warn_code_outliers(&rendered.metrics);
+    let sources = crate::scaffold::ScaffoldSources { models: &rendered.models, record: &rendered.record, sample: &sample };
+    crate::scaffold::write_project(&cfg.out, &sources)
+        .unwrap_or_else(|e| panic!("failed to write project ({}): {e}", cfg.out.to_string_lossy()));
+    log_info(format!("project written: {}; try `cargo run` there", cfg.out.to_string_lossy().green()));
+}
+
+// --------------------------- unify ---------------------------
+
+fn run_unify(cfg: &Unify) {
+    let endpoints = cfg.inputs
+        .iter()
+        .map(|path| {
+            let path_str = path.to_string_lossy();
+            let src = std::fs::read_to_string(path)
+                .unwrap_or_else(|e| panic!("read failed ({path_str}): {e}"));
+            let norm: NTy = serde_json::from_str(&src)
+                .unwrap_or_else(|e| panic!("IR JSON parse error ({path_str}): {e}"));
+            crate::unify::Endpoint {
+                name: endpoint_name(path),
+                ty: crate::norm_ir::lower_from_norm(&norm),
             }
         })
+        .collect::<Vec<_>>();
+
+    let unified = crate::unify::unify(&endpoints, &cfg.root_type, &cfg.name_collisions.policy());
+    exit_on_name_collisions(&unified.collisions);
+    unified.metrics.iter().for_each(warn_code_outliers);
+    log_info(format!(
+        "{} shared type(s) across {} endpoint(s)",
+        unified.shared.to_string().green(),
+        endpoints.len(),
+    ));
+
+    write_sink(&cfg.out.join("mod.rs"), &unified.mod_rs()).unwrap();
+    write_sink(&cfg.out.join("common.rs"), &unified.common).unwrap();
+    for (module, src) in &unified.modules {
+        write_sink(&cfg.out.join(format!("{module}.rs")), src).unwrap();
+    }
+}
+
+fn warn_code_outliers(metrics: &crate::metrics::CodeMetrics) {
+    for o in metrics.outliers() {
+        log_warn(format!("{}: {o}", metrics.module));
+    }
+}
+
+fn exit_on_name_collisions(collisions: &[crate::codegen::NameCollision]) {
+    if collisions.is_empty() {
+        return;
+    }
+    for c in collisions {
+        eprint!("error: {c}");
+    }
+    eprintln!("hint: rerun with `--name-collisions suffix` or `--name-collisions path` to resolve automatically");
+    std::process::exit(2);
+}
+
+/// `search.ir.json` → `search`
+fn endpoint_name(path: &Path) -> String {
+    let file = path.file_name().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let stem = file.strip_suffix(".json").unwrap_or(&file);
+    let stem = stem.strip_suffix(".ir").unwrap_or(stem);
+    stem.to_string()
+}
+
+// --------------------------- Core pipeline ---------------------------
+
+fn compute_and_normalize(
+    input_settings: &InputSettings,
+    common_settings: &CommonSettings,
+    trackers: &Trackers,
+) -> (NTy, Report, Option<U>) {
+    crate::inference::set_collapse_ints(crate::inference::COLLAPSE_INT_INTO_NUMBER && !common_settings.exact_ints);
+    if common_settings.interactive {
+        use std::io::IsTerminal;
+        if input_settings.input.iter().any(|i| i.as_os_str() == "-") || !io::stdin().is_terminal() {
+            log_error("--interactive reads answers from stdin, which must be a terminal (not an input)");
+            std::process::exit(2);
+        }
+    }
+    let mut overrides = match common_settings.overrides.as_ref() {
+        Some(path) if common_settings.interactive => crate::overrides::Overrides::load_or_default(path).unwrap_or_else(|e| {
+            log_error(format!("--overrides: {e}"));
+            std::process::exit(2);
+        }),
+        Some(path) => crate::overrides::Overrides::load(path).unwrap_or_else(|e| {
+            log_error(format!("--overrides: {e}"));
+            std::process::exit(2);
+        }),
+        None => crate::overrides::Overrides::default(),
+    };
+    if let Some(path) = common_settings.policy.as_ref() {
+        install_policy(path);
+    }
+    crate::inference::str::set_grex_time_budget(std::time::Duration::from_millis(common_settings.grex_time_budget));
+    let source_paths = resolve_inputs(input_settings);
+    let ndjson = input_settings.ndjson;
+    let separator = input_settings.separator.map(Separator::separator);
+    let jq_expr = input_settings.jq_expr.clone();
+    let ingest = IngestSettings {
+        ndjson,
+        jq_expr: jq_expr.clone(),
+        separator,
+        json_strings: input_settings.json_strings,
+        numeric_strings: input_settings.coerce_numeric_strings,
+        empty_as_null: input_settings.empty_strings_as_null,
+        normalize_unicode: input_settings.normalize_unicode,
+        sampling: (input_settings.max_samples.is_some() || input_settings.max_samples_per_input.is_some()).then(|| {
+            crate::sampling::Sampling {
+                method: input_settings.sampling.method(),
+                seed: input_settings.seed,
+                max: input_settings.max_samples,
+                per_input: input_settings.max_samples_per_input,
+            }
+        }),
+        max_depth: Some(input_settings.max_depth),
+    };
+    let resumed = input_settings.resume.as_ref().map(|path| {
+        let ckpt = Checkpoint::load(path, &ingest).unwrap_or_else(|e| {
+            log_error(format!("--resume: {e}"));
+            std::process::exit(2);
+        });
+        let inputs: BTreeSet<String> = source_paths.iter().map(|p| crate::portable::path_str(p)).collect();
+        if let Some(gone) = ckpt.done.iter().find(|d| !inputs.contains(&d.path)) {
+            log_error(format!("--resume: {} was read by the interrupted run but is not among the inputs", gone.path));
+            std::process::exit(2);
+        }
+        log_info(format!(
+            "resuming: {} of {} file(s) already read",
+            ckpt.done.len().to_string().green(),
+            source_paths.len(),
+        ));
+        ckpt
+    });
+    let (done, prior) = resumed.map_or_else(|| (Vec::new(), U::empty()), |c| (c.done, c.evidence));
+    let done: BTreeMap<&str, &DoneFile> = done.iter().map(|d| (d.path.as_str(), d)).collect();
+    crate::ingest::catch_interrupts();
+
+    if crate::events::machine() {
+        crate::events::emit(&Event::Inputs { files: source_paths.len() });
+        crate::events::emit(&Event::Phase { phase: "read" });
+    }
+    progress(format!("▶︎ total source files: {}", source_paths.len().to_string().green()));
+
+    progress(format!(
+        "{} ▶︎ file(s) pipeline: {}",
+        format!("[{}]", get_current_pretty_time()).bright_magenta(),
+        "began".blue()
+    ));
+
+    let combined = crate::ingest::read(&source_paths, &ingest, input_settings.require_match, trackers, &done);
+    if crate::ingest::interrupted() {
+        crate::ingest::save_checkpoint(&input_settings.checkpoint, &ingest, prior, combined, source_paths.len());
+    }
+    let combined = combined.into_iter().map(|f| f.expect("only skipped when interrupted"));
+    if !done.is_empty() && trackers.any() {
+        log_warn(format!(
+            "side statistics (columns, keys, histograms, …) only cover the {} file(s) read in this run",
+            source_paths.len() - done.len(),
+        ));
+    }
+
+    // Per-file attribution is order-dependent by design (first file to
+    // introduce a kind is blamed), so it runs serially in input order.
+    let (per_file, per_file_sig): (Vec<_>, Vec<_>) = combined.unzip();
+    let mut report = Report {
+        sources: crate::report::stratify(per_file_sig),
+        ingest,
+        ..Report::default()
+    };
+    report.manifest = crate::manifest::manifest_hash(&report.inputs(), &report.ingest);
+    let docs = report.docs();
+    let (combined, extra) = per_file
+        .into_par_iter()
         .reduce(
-            || U::empty(),
-            |a, b| U::join(&a, &b)
+            || (U::empty(), Extra::default()),
+            |(a, xa), (b, xb)| (U::join(&a, &b), Extra::join(xa, xb))
         );
+    let combined = U::join(&prior, &combined);
+    if let Some(keys) = extra.keys {
+        report.key_candidates = keys.candidates().iter().map(|p| p.to_string()).collect();
+    }
+    report.columns = extra.columns.map(|c| c.summarize());
+    report.nullable_groups = extra.null_groups.map(|g| {
+        g.groups().iter().map(|grp| grp.iter().map(|p| p.to_string()).collect()).collect()
+    });
+    report.discriminator = extra.disc.and_then(|d| d.detect());
+    report.timeline = extra.timeline.map(Timeline::summarize);
+    report.sizes = extra.sizes.as_ref().map(SizeStats::summarize);
+    report.samples = extra.samples.map(|s| s.docs).unwrap_or_default();
+    report.provenance = extra.provenance;
+    report.jq_empty = extra.jq_empty;
+    if report.jq_empty > 0 {
+        log_info(format!("{} document(s) had no --jq-expr output and were skipped", report.jq_empty));
+    }
+    if let Some(failed) = extra.failed.as_ref().filter(|_| input_settings.on_error == OnError::Fail) {
+        for d in &failed.first {
+            log_error(format!("{d}"));
+        }
+        log_error(format!("--on-error fail: {} document(s) could not be read", failed.count()));
+        std::process::exit(2);
+    }
+    if let Some(failed) = extra.failed {
+        // collected by the workers, logged here in a stable order
+        warn_skipped(&failed, docs);
+        report.parse_failed = failed;
+    }
 
-    eprintln!("{}", format!(
+    if crate::events::machine() {
+        crate::events::emit(&Event::Phase { phase: "normalize" });
+    }
+    progress(format!(
         "{} ▶︎ file(s) pipeline: {}",
         format!("[{}]", get_current_pretty_time()).bright_magenta(),
         "normalizing".blue()
-    ).cyan());
+    ));
+
+    report.evidence_id = crate::manifest::evidence_id(&combined);
+    let evidence = trackers.evidence.then(|| combined.clone());
+    let mut combined = combined;
+    if let Some(share) = common_settings.min_arm_share {
+        report.outliers = crate::outliers::find(&combined, share, &overrides);
+        for x in &report.outliers {
+            log_warn(format!("dropped the {} arm at {}: {} of {} value(s)", x.kind, x.path, x.count, x.total));
+        }
+        crate::outliers::drop(&mut combined, &crate::outliers::rules(&report.outliers));
+    }
+    report.empty_strings = crate::sentinel::empty_strings(&combined, report.ingest.empty_as_null);
 
     // let mut u = combined;
     // U::normalize_mut(&mut u);
-    let result = crate::norm_ir::normalize_to_norm_consume(combined);
+    if let Some(path) = common_settings.overrides.as_ref() && common_settings.interactive {
+        resolve_interactively(&combined, docs, &mut overrides, path);
+    }
+    if common_settings.strict_inference {
+        let ambiguities = crate::overrides::ambiguities(&combined, docs, &overrides);
+        if !ambiguities.is_empty() {
+            for a in &ambiguities {
+                log_error(format!("{a}"));
+            }
+            log_error(format!("--strict-inference: {} ambiguous decision(s)", ambiguities.len()));
+            std::process::exit(2);
+        }
+    }
+    let required_threshold = common_settings.required_threshold.unwrap_or(crate::required::ALL);
+    if common_settings.required_threshold.is_some() {
+        report.required = crate::required::near(&combined, required_threshold, &overrides);
+        for x in report.required.iter().filter(|x| x.required) {
+            log_info(format!("required at {}: non-null in {} of {} object(s)", x.path, x.non_null, x.objects));
+        }
+    }
+    let grex = common_settings.grex();
+    report.patterns_skipped = crate::patterns::survey(&combined, &overrides, grex);
+    for x in &report.patterns_skipped {
+        log_warn(format!("{}: no pattern, grex skipped: {}", x.path, x.reason.describe()));
+    }
+    report.too_deep = crate::depth::survey(&combined, &overrides);
+    for p in &report.too_deep {
+        log_warn(format!("{p}: nested deeper than --max-depth ({}): typed as any JSON", input_settings.max_depth));
+    }
+    report.widths = crate::width::survey(&combined, &overrides);
+    for w in report.widths.iter().filter(|w| w.lowered == crate::width::Lowered::ForcedMap) {
+        log_warn(format!(
+            "{}: {} distinct keys, over max_object_fields ({}): typed as a map",
+            w.path,
+            w.keys,
+            crate::inference::caps::max_object_fields(),
+        ));
+    }
+    report.merged_keys = crate::key_forms::survey(&combined, &overrides);
+    for m in &report.merged_keys {
+        log_info(format!("{}: merged {} other spelling(s) of the key", m.path, m.forms.len()));
+    }
+    let int_enums = common_settings.int_enums.map_or(0, |n| n as usize);
+    let result = crate::norm_ir::normalize_with(combined, &overrides, required_threshold, int_enums, grex);
+    (report.unions, report.unexpected_nulls) = crate::unions::diagnose(&result);
+    if let Some(numbers) = extra.numbers {
+        let lowered = crate::norm_ir::lower_from_norm(&result);
+        let numbers = numbers.summarize(&lowered);
+        if let Some(markers) = extra.markers {
+            report.sentinels = Some(crate::sentinel::detect(&numbers, markers, &lowered));
+        }
+        if trackers.histograms {
+            report.numbers = Some(numbers);
+        }
+    }
+    if let Some(pii) = extra.pii {
+        report.pii = Some(crate::pii::detect(pii, &crate::norm_ir::lower_from_norm(&result)));
+    }
+    if let (Some(stats), Some(formats)) = (extra.formats, trackers.formats.as_ref()) {
+        report.formats = Some(crate::formats::detect(stats, &crate::norm_ir::lower_from_norm(&result), formats));
+    }
+    report.overrides = overrides;
 
-    eprintln!("{}", format!(
+    progress(format!(
         "{} ▶︎ file(s) pipeline: {}",
         format!("[{}]", get_current_pretty_time()).bright_magenta(),
         "finished".green()
-    ).cyan());
+    ));
 
     // u
-    result
+    (result, report, evidence)
+}
+
+/// Say how many documents `failed` skipped out of those read, listing the first.
+fn warn_skipped(failed: &ParseFailures, docs: u64) {
+    log_warn(format!(
+        "{} document(s) could not be read and were skipped ({:.2}% of {})",
+        failed.count(),
+        100.0 * failed.count() as f64 / (docs + failed.count()) as f64,
+        docs + failed.count(),
+    ));
+    for d in &failed.first {
+        log_warn(d);
+    }
+    if failed.count() > failed.first.len() as u64 {
+        log_warn(format!("… and {} more", failed.count() - failed.first.len() as u64));
+    }
+}
+
+/// Read `--policy` and use it for everything from here on (it is part of the
+/// policy hash, so headers and checkpoints record it).
+fn install_policy(path: &Path) {
+    let policy = crate::inference::caps::load(path).unwrap_or_else(|e| {
+        log_error(format!("--policy: {e}"));
+        std::process::exit(2);
+    });
+    log_info(format!(
+        "{} literal cap rule(s), tuples of at most {}, records of at most {} fields from {}",
+        policy.rules.len(),
+        policy.max_tuple_arity,
+        policy.max_object_fields,
+        path.to_string_lossy(),
+    ));
+    crate::inference::caps::install(policy);
+}
+
+/// Prompt for every ambiguity (re-checked after each answer, since choosing a shape
+/// changes the paths below it), saving `overrides` to `path` as answers come in.
+fn resolve_interactively(u: &U, docs: u64, overrides: &mut crate::overrides::Overrides, path: &Path) {
+    use crate::overrides::{ambiguities, evidence_at, examples, Decision};
+
+    let mut skipped = std::collections::BTreeSet::<String>::new();
+    loop {
+        let pending = ambiguities(u, docs, overrides);
+        let Some(a) = pending.iter().find(|a| !skipped.contains(&a.path.to_string())) else { break };
+
+        eprintln!();
+        eprintln!("{} {}: {}", "?".yellow().bold(), a.path.to_string().green(), a.question().bold());
+        eprintln!("  {}", a.detail);
+        if let Some(ev) = evidence_at(u, &a.path) {
+            if let Some(arr) = ev.arr.as_ref().filter(|arr| arr.cols.len() > 1) {
+                for (i, col) in arr.cols.iter().enumerate() {
+                    eprintln!("  [{i}] {}", examples(col, 5).join(", "));
+                }
+            } else {
+                eprintln!("  examples: {}", examples(ev, 5).join(", "));
+            }
+        }
+
+        let mut choices: Vec<&str> = a.choices().to_vec();
+        if !choices.contains(&"accept") {
+            choices.push("accept");
+        }
+        choices.extend(["skip", "quit"]);
+        let menu: Vec<String> = choices.iter().map(|c| format!("[{}]{}", &c[..1], &c[1..])).collect();
+        let answer = loop {
+            eprint!("  {} > ", menu.join("  "));
+            let mut line = String::new();
+            if io::stdin().read_line(&mut line).unwrap_or(0) == 0 {
+                break "quit";
+            }
+            let line = line.trim().to_ascii_lowercase();
+            if let Some(c) = choices.iter().find(|c| !line.is_empty() && c.starts_with(line.as_str())) {
+                break *c;
+            }
+        };
+        let decision = match answer {
+            "list" => Decision::List,
+            "tuple" => Decision::Tuple,
+            "union" => Decision::Union,
+            "accept" => Decision::Accept,
+            "skip" => {
+                skipped.insert(a.path.to_string());
+                continue;
+            }
+            _ => break,
+        };
+        overrides.0.insert(a.path.to_string(), decision);
+        overrides
+            .save(path)
+            .unwrap_or_else(|e| panic!("failed to write overrides ({}): {e}", path.to_string_lossy()));
+    }
+    log_info(format!(
+        "{} override(s) in {}",
+        overrides.0.len().to_string().green(),
+        path.to_string_lossy().blue(),
+    ));
 }
 
 // --------------------------- Helpers ---------------------------
 
+/// Human progress line on stderr (nothing under `--machine`).
+pub(crate) fn progress(line: String) {
+    if !crate::events::machine() {
+        eprintln!("{}", line.cyan());
+    }
+}
+
+pub(crate) fn log_info(msg: impl std::fmt::Display) {
+    if crate::events::machine() {
+        crate::events::emit(&Event::Info { message: msg.to_string() });
+    } else {
+        eprintln!("{}", format!("{} » {msg}", "[INFO]".bright_magenta()).cyan());
+    }
+}
+
+/// Errors go to stderr even with `--machine`, usually right before exit code 2.
+pub(crate) fn log_error(msg: impl std::fmt::Display) {
+    eprintln!("{} {msg}", "error:".red().bold());
+}
+
+pub(crate) fn log_warn(msg: impl std::fmt::Display) {
+    if crate::events::machine() {
+        crate::events::emit(&Event::Warning { message: msg.to_string() });
+    } else {
+        eprintln!("{}", format!("{} » {msg}", "[WARN]".yellow()).cyan());
+    }
+}
+
 fn get_current_pretty_time() -> String {
     use chrono::Local;
     let now = Local::now();
     now.format("%Y-%m-%d %H:%M:%S").to_string()
 }
 
-fn resolve_file_path_patterns<I>(patterns: I) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>>
-where
-    I: IntoIterator,
-    I::Item: AsRef<str>,
-{
-    fn has_glob_chars(s: &str) -> bool {
-        s.bytes()
-            .any(|b| matches!(b, b'*' | b'?' | b'[' | b'{' ))
+/// Resolve `--input` (glob expansion, ignore files, `--input-cache`); with `--list-inputs`,
+/// print them and exit.
+fn resolve_inputs(input: &InputSettings) -> Vec<PathBuf> {
+    let resolved = crate::inputs::resolve(
+        &input.input,
+        input.input_cache.as_deref(),
+        input.follow_symlinks,
+        input.no_ignore,
+    )
+    .unwrap_or_else(|e| {
+        log_error(format!("--input: {e}"));
+        std::process::exit(2);
+    });
+    if resolved.ignored > 0 {
+        log_info(format!(
+            "{} entries skipped by {}",
+            resolved.ignored.to_string().yellow(),
+            crate::inputs::IGNORE_FILE,
+        ));
+    }
+    let paths = resolved.files;
+    if input.list_inputs {
+        // raw bytes, so non-UTF-8 names survive piping
+        let mut out = io::stdout().lock();
+        for p in &paths {
+            out.write_all(p.as_os_str().as_encoded_bytes()).and_then(|_| out.write_all(b"\n")).expect("write stdout");
+        }
+        log_info(format!(
+            "{} input(s) from {} pattern(s)",
+            paths.len().to_string().green(),
+            input.input.len(),
+        ));
+        std::process::exit(0);
+    }
+    paths
+}
+
+/// `gen` outputs bound for stdout: printed as they are produced, or gathered
+/// into one JSON object printed by [`StdoutSink::finish`] (`--stdout-format envelope`).
+struct StdoutSink {
+    envelope: Option<serde_json::Map<String, Value>>,
+    /// `(output, path)` of every file written so far.
+    written: Vec<(String, String)>,
+}
+
+impl StdoutSink {
+    fn new(format: StdoutFormat) -> Self {
+        StdoutSink { envelope: (format == StdoutFormat::Envelope).then(serde_json::Map::new), written: Vec::new() }
+    }
+
+    /// Write output `key` to `path` ('-' for stdout).
+    fn write(&mut self, key: &str, path: &Path, contents: &str) {
+        if path == Path::new("-") {
+            return self.print(key, contents);
+        }
+        write_sink(path, contents).unwrap();
+        self.wrote(key, path);
     }
 
-    let mut out = Vec::<PathBuf>::new();
-    for raw in patterns {
-        let p = raw.as_ref();
-        if p == "-" {
-            out.push(PathBuf::from("-"));
-            continue;
+    /// Output `key` was written to `path` (by [`StdoutSink::write`] or directly).
+    fn wrote(&mut self, key: &str, path: &Path) {
+        if crate::events::machine() {
+            crate::events::emit(&Event::Artifact { output: key, path: &path.to_string_lossy(), contents: None });
         }
+        self.written.push((key.to_string(), path.to_string_lossy().to_string()));
+    }
 
-        if has_glob_chars(p) {
-            let mut matched_any = false;
-            for entry in glob::glob(p)? {
-                match entry {
-                    Ok(path) => {
-                        matched_any = true;
-                        out.push(path);
-                    }
-                    Err(e) => return Err(Box::new(e)),
-                }
+    /// Print output `key` (a `--stdout` stream).
+    fn print(&mut self, key: &str, contents: &str) {
+        match &mut self.envelope {
+            Some(env) => {
+                env.insert(key.to_string(), envelope_value(key, contents));
             }
-            if !matched_any {
-                return Err(format!("glob pattern matched no files: {p}").into());
+            None if crate::events::machine() => {
+                crate::events::emit(&Event::Artifact { output: key, path: "-", contents: Some(envelope_value(key, contents)) });
             }
-        } else {
-            out.push(PathBuf::from(p));
+            None => println!("{contents}"),
         }
     }
-    let out = out
-        .into_iter()
-        .map(|x| {
-            x.to_str().unwrap().to_owned()
-        })
-        .collect::<indexmap::IndexSet<_>>()
-        .into_iter()
-        .map(|x| PathBuf::from(x))
-        .collect::<Vec<_>>();
-    Ok(out)
+
+    fn finish(self) {
+        if let Some(env) = self.envelope {
+            write_sink(Path::new("-"), &serde_json::to_string_pretty(&env).unwrap()).unwrap();
+        }
+    }
+}
+
+/// JSON outputs (schema, IR, evidence, …) nest as values; everything else is a string.
+fn envelope_value(key: &str, contents: &str) -> Value {
+    match key {
+        "schema" | "ir_json" | "evidence" | "evidence_json" | "names" | "literals" | "summary" => serde_json::from_str(contents).expect("JSON output parses"),
+        _ => Value::String(contents.to_string()),
+    }
 }
 
 fn write_sink(path: &Path, contents: &str) -> io::Result<()> {
@@ -403,15 +2805,15 @@ fn write_sink(path: &Path, contents: &str) -> io::Result<()> {
         stdout.flush()?;
         Ok(())
     } else {
-        eprintln!("{}", format!(
+        progress(format!(
             "{} » {}",
             "[saving]".bright_magenta(),
-            path.to_str().unwrap().blue(),
-        ).cyan());
+            path.to_string_lossy().blue(),
+        ));
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)?;
         }
-        std::fs::write(path, contents)
+        crate::portable::write(path, contents)
     }
 }
 
@@ -1,12 +1,228 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 
-use crate::ir::{Field, Ty};
+use serde::{Deserialize, Serialize};
+
+use crate::ir::{Field, IntWidth, Mark, StrFormat, Ty};
+use crate::jpath::{JPath, Seg};
+use crate::metrics::{CodeMetrics, Item, ItemKind};
+use crate::epoch::EpochUnit;
+use crate::formats::Format;
+use crate::pii::Tag;
 
 pub struct Codegen {
     out: String,
     used: BTreeSet<String>, // ensure stable, unique names per node path
+    /// Structural key → type path for compound types defined elsewhere
+    /// (e.g. a shared `common` module); matching subtrees are referenced, not emitted.
+    reuse: BTreeMap<String, String>,
+    /// When set, structurally identical compound types are emitted once and shared.
+    dedupe: bool,
+    /// Structural key → name of every compound type emitted so far (only tracked with `dedupe`).
+    emitted: BTreeMap<String, String>,
+    /// Path → name of the shared definition there (see [`Codegen::with_shared`]).
+    shared: BTreeMap<String, String>,
+    naming: NamingPolicy,
+    /// Name → shape of the type that claimed it first (for collision diagnostics).
+    owners: BTreeMap<String, Shape>,
+    collisions: Vec<NameCollision>,
+    /// Record key as walk-path segments (root name excluded); its newtype derives `Hash`.
+    key: Option<Vec<String>>,
+    /// Rust type emitted at the key path.
+    key_ty: Option<String>,
+    /// Root columns (field names / tuple indices) to collapse into one `Option<…Group>` each.
+    groups: Vec<Vec<String>>,
+    /// Root tuple position → position after grouping (for key access).
+    regrouped: BTreeMap<usize, usize>,
+    /// Set once a group was actually collapsed (see [`Codegen::generated_config`]).
+    grouped: bool,
+    profile: CodegenProfile,
+    /// Every named type emitted, for [`Codegen::metrics`].
+    items: Vec<Item>,
+    /// JSON location of the type being walked (unlike `path`, no union arms or name hints).
+    at: Vec<Seg>,
+    /// One row per struct field / tuple position emitted, for the `SCHEMA` table.
+    fields: Vec<FieldRow>,
+    /// What the emitted types promise, for [`Codegen::contract_tests`].
+    contracts: Vec<Contract>,
+    /// Path → personal-data tag (see [`Codegen::with_pii`]).
+    pii: BTreeMap<String, Tag>,
+    /// Path → declared string format (see [`Codegen::with_formats`]).
+    formats: BTreeMap<String, Format>,
+    /// Path → unit of integers read as timestamps (see [`Codegen::with_epochs`]).
+    epochs: BTreeMap<String, EpochUnit>,
+    /// Path → description (see [`Codegen::with_descriptions`]).
+    descriptions: BTreeMap<String, String>,
+    /// Path → a few values seen there (see [`Codegen::with_examples`]).
+    examples: BTreeMap<String, Vec<serde_json::Value>>,
+    /// Tuple position → field name (see [`Codegen::with_tuple_names`]).
+    tuple_names: BTreeMap<String, String>,
+    /// Field path → other spellings of its key (see [`Codegen::with_aliases`]).
+    aliases: BTreeMap<String, Vec<String>>,
+    /// Emit the record stream API (see [`Codegen::with_records`]).
+    records: bool,
+    /// `is_<format>()` functions emitted so far.
+    validators: BTreeSet<String>,
+    /// Type UUID strings as `::uuid::Uuid` (see [`Codegen::with_uuid`]).
+    uuid: bool,
+    /// Decode hex / base64 strings (see [`Codegen::with_bytes`]).
+    bytes: bool,
+    /// Read decimal-amount strings as `Decimal` (see [`Codegen::with_decimal`]).
+    decimal: bool,
+    /// Patterns were synthesized (see [`Codegen::with_patterns`]).
+    patterns: bool,
+    /// `--tightness` the IR was loosened to (see [`Codegen::with_tightness`]).
+    tightness: u8,
+    redact: Option<RedactMode>,
+    /// Newtypes over a string / integer / number, which `redact()` can blank.
+    scalars: BTreeMap<String, Scalar>,
+    /// Union enum → `(variant, type)` of each arm.
+    unions: BTreeMap<String, Vec<(String, String)>>,
+    /// Naming-manifest key → name to use instead of the derived one (see [`Codegen::with_names`]).
+    pinned: BTreeMap<String, String>,
+    /// Naming-manifest key → name of every type named so far.
+    names: BTreeMap<String, String>,
+    /// Union arms and tagged variants being walked: their values are read from
+    /// a buffered `serde_json::Value`, which a `RawValue` can't be taken from.
+    buffered: u32,
+}
+
+/// How `redact()` treats tagged string fields (see [`Codegen::with_redact`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RedactMode {
+    /// Empty strings, zeroed numbers, `None` for optional fields.
+    Blank,
+    /// Strings replaced by a stable digest, so equal values still match.
+    Hash,
+}
+
+impl RedactMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RedactMode::Blank => "blank",
+            RedactMode::Hash => "hash",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scalar {
+    Str,
+    Int,
+    Num,
+    /// Decoded hex / base64 (see [`Codegen::with_bytes`]).
+    Bytes,
+    /// A decimal amount (see [`Codegen::with_decimal`]).
+    Decimal,
+}
+
+/// A generated field, as described by the `FieldMeta` table.
+#[derive(Debug, Clone)]
+struct FieldRow {
+    owner: String,
+    field: String,
+    /// JSON key; `None` for tuple positions.
+    name: Option<String>,
+    path: String,
+    required: bool,
+    nullable: bool,
+    kind: &'static str,
+    format: Option<String>,
+    pii: Option<Tag>,
+    /// Rust type as declared on the owner (`Option<…>` included).
+    ty: String,
+}
+
+/// Trade-off between strictness and the size / compile time of the generated code.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CodegenProfile {
+    /// Bespoke deserializers: bounds, patterns, URI schemes, precise union errors.
+    #[default]
+    Strict,
+    /// Derive-only serde: scalars stay plain integers / `f64` / `String`, unions are `untagged`,
+    /// identical types are shared. Drops range, pattern and URI checks.
+    Small,
+}
+
+impl CodegenProfile {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CodegenProfile::Strict => "strict",
+            CodegenProfile::Small => "small",
+        }
+    }
+}
+
+/// What to do when two different types derive the same Rust name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CollisionPolicy {
+    /// `Name`, `Name1`, `Name2`, … in emission order.
+    #[default]
+    Suffix,
+    /// Qualify the later type with the root, then its parents (`Card` → `SearchCard`, `SearchAdCard`), falling back to suffixes.
+    Path,
+    /// Record a [`NameCollision`] (still emitting with a suffix) so the caller can fail the run.
+    Error,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NamingPolicy {
+    pub collisions: CollisionPolicy,
+}
+
+/// Coarse description of a generated type, enough to explain a name clash.
+#[derive(Debug, Clone)]
+pub struct Shape {
+    pub kind: String,
+    pub fields: Vec<String>,
+}
+
+impl Shape {
+    fn of(t: &Ty) -> Self {
+        let (kind, fields) = match t {
+            Ty::Object { fields, .. } => (
+                format!("object with {} field(s)", fields.len()),
+                fields.iter().map(|f| f.name.clone()).collect(),
+            ),
+            Ty::ArrayTuple { elems, .. } => (format!("tuple of {} element(s)", elems.len()), Vec::new()),
+            Ty::OneOf(arms) => (format!("union of {} arm(s)", arms.len()), Vec::new()),
+            Ty::Integer { enum_, .. } if !enum_.is_empty() => (
+                format!("integer enum of {} variant(s)", enum_.len()),
+                enum_.iter().map(i64::to_string).collect(),
+            ),
+            Ty::Integer { .. } => ("integer newtype".to_string(), Vec::new()),
+            Ty::Number { .. } => ("number newtype".to_string(), Vec::new()),
+            Ty::String { enum_, .. } if !enum_.is_empty() => (format!("string enum of {} variant(s)", enum_.len()), enum_.clone()),
+            Ty::String { .. } => ("string newtype".to_string(), Vec::new()),
+            other => (format!("{other:?}"), Vec::new()),
+        };
+        Shape { kind, fields }
+    }
+}
+
+/// Two different types wanted the same name under [`CollisionPolicy::Error`].
+#[derive(Debug, Clone)]
+pub struct NameCollision {
+    pub name: String,
+    /// JSON-ish path of the type that lost the name.
+    pub path: String,
+    pub existing: Shape,
+    pub incoming: Shape,
+}
+
+impl std::fmt::Display for NameCollision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "type name `{}` derived for two different shapes (second at {}):", self.name, self.path)?;
+        writeln!(f, "  existing: {}", self.existing.kind)?;
+        writeln!(f, "  incoming: {}", self.incoming.kind)?;
+        let only = |a: &Shape, b: &Shape| a.fields.iter().filter(|x| !b.fields.contains(x)).cloned().collect::<Vec<_>>();
+        let (gone, new) = (only(&self.existing, &self.incoming), only(&self.incoming, &self.existing));
+        if !gone.is_empty() { writeln!(f, "  only in existing: {}", gone.join(", "))?; }
+        if !new.is_empty() { writeln!(f, "  only in incoming: {}", new.join(", "))?; }
+        Ok(())
+    }
 }
 
 impl Codegen {
@@ -14,14 +230,523 @@ impl Codegen {
         Self {
             out: String::new(),
             used: BTreeSet::new(),
+            reuse: BTreeMap::new(),
+            dedupe: false,
+            emitted: BTreeMap::new(),
+            shared: BTreeMap::new(),
+            naming: NamingPolicy::default(),
+            owners: BTreeMap::new(),
+            collisions: Vec::new(),
+            key: None,
+            key_ty: None,
+            groups: Vec::new(),
+            regrouped: BTreeMap::new(),
+            grouped: false,
+            profile: CodegenProfile::Strict,
+            items: Vec::new(),
+            at: Vec::new(),
+            fields: Vec::new(),
+            contracts: Vec::new(),
+            pii: BTreeMap::new(),
+            formats: BTreeMap::new(),
+            epochs: BTreeMap::new(),
+            descriptions: BTreeMap::new(),
+            examples: BTreeMap::new(),
+            tuple_names: BTreeMap::new(),
+            aliases: BTreeMap::new(),
+            records: false,
+            validators: BTreeSet::new(),
+            uuid: false,
+            bytes: false,
+            decimal: false,
+            patterns: crate::inference::ENABLE_GREX,
+            tightness: crate::norm_ir::MAX_TIGHTNESS,
+            redact: None,
+            scalars: BTreeMap::new(),
+            unions: BTreeMap::new(),
+            pinned: BTreeMap::new(),
+            names: BTreeMap::new(),
+            buffered: 0,
+        }
+    }
+
+    /// [`CodegenProfile::Small`] also turns on [`Codegen::with_dedupe`].
+    pub fn with_profile(mut self, profile: CodegenProfile) -> Self {
+        self.profile = profile;
+        if profile == CodegenProfile::Small {
+            self.dedupe = true;
+        }
+        self
+    }
+
+    /// Personal-data tags by JSON path, listed in the `SCHEMA` table; a tag on a
+    /// list's items (`$.phones[*]`) also marks the list field.
+    pub fn with_pii(mut self, tags: &BTreeMap<String, Tag>) -> Self {
+        self.pii = tags.clone();
+        self
+    }
+
+    /// Declared string formats by JSON path (see [`crate::formats`]): each gets an
+    /// `is_<format>()` function, and strings at those paths a newtype checked by it.
+    /// The `small` profile lists them in the `SCHEMA` table without checking.
+    pub fn with_formats(mut self, formats: &BTreeMap<String, Format>) -> Self {
+        self.formats = formats.clone();
+        self
+    }
+
+    /// Integers at these paths (see [`crate::epoch`]) become `UnixSeconds` / `UnixMillis`,
+    /// newtypes over `::chrono::DateTime<::chrono::Utc>` read through `serde_with` (the
+    /// consuming crate needs `chrono` and `serde_with` with its `chrono_0_4` feature).
+    pub fn with_epochs(mut self, epochs: &BTreeMap<String, EpochUnit>) -> Self {
+        self.epochs = epochs.clone();
+        self
+    }
+
+    /// Descriptions by JSON path (see [`crate::descriptions`]), leading the doc comments of
+    /// the struct fields and tuple positions there.
+    pub fn with_descriptions(mut self, descriptions: &BTreeMap<String, String>) -> Self {
+        self.descriptions = descriptions.clone();
+        self
+    }
+
+    /// Values seen by JSON path (see [`crate::norm_ir::examples`]), listed in the doc
+    /// comments of the struct fields and tuple positions there.
+    pub fn with_examples(mut self, examples: &BTreeMap<String, Vec<serde_json::Value>>) -> Self {
+        self.examples = examples.clone();
+        self
+    }
+
+    /// Field names for tuple positions by JSON path (`$.points[*][0]` → `latitude`): a tuple
+    /// with any named position becomes a struct with named fields (`_1`, `_2`, … for the
+    /// rest), still read from an array.
+    pub fn with_tuple_names(mut self, names: &BTreeMap<String, String>) -> Self {
+        self.tuple_names = names.clone();
+        self
+    }
+
+    /// Other spellings of keys by field path (see [`crate::key_forms`]), each read as
+    /// the field too: `#[serde(alias = "…")]`.
+    pub fn with_aliases(mut self, aliases: &BTreeMap<String, Vec<String>>) -> Self {
+        self.aliases = aliases.clone();
+        self
+    }
+
+    /// Field names of the `len` positions of the tuple at `at`, if any is named.
+    fn tuple_members(&self, at: &[Seg], len: usize) -> Option<Vec<String>> {
+        let mut at = JPath(at.to_vec());
+        let named: Vec<Option<String>> = (0..len)
+            .map(|i| {
+                at.0.push(Seg::Index(i));
+                let name = self.tuple_names.get(&at.to_string()).map(|n| to_field_name(n));
+                at.0.pop();
+                name
+            })
+            .collect();
+        if named.iter().all(Option::is_none) {
+            return None;
+        }
+        let mut used = BTreeSet::new();
+        Some(named.into_iter().enumerate().map(|(i, n)| {
+            let name = n.filter(|n| !used.contains(n)).unwrap_or_else(|| format!("_{i}"));
+            used.insert(name.clone());
+            name
+        }).collect())
+    }
+
+    /// The description of the slot being walked.
+    fn description(&self) -> Option<String> {
+        self.descriptions.get(&JPath(self.at.clone()).to_string()).cloned()
+    }
+
+    /// Doc comment line listing the examples of the slot being walked.
+    fn examples_doc(&self) -> Option<String> {
+        let examples = self.examples.get(&JPath(self.at.clone()).to_string())?;
+        let listed: Vec<String> = examples.iter().map(|v| format!("`{}`", doc_safe(&v.to_string()))).collect();
+        Some(format!("Examples: {}.", listed.join(", ")))
+    }
+
+    /// Strings detected as UUIDs become `::uuid::Uuid` (the consuming crate needs `uuid`
+    /// with its `serde` feature) instead of a checked string newtype; in every profile.
+    pub fn with_uuid(mut self, on: bool) -> Self {
+        self.uuid = on;
+        self
+    }
+
+    /// Whether normalization synthesized string patterns (`--grex`), for
+    /// [`Codegen::generated_config`]; the models check whatever patterns the IR holds.
+    pub fn with_patterns(mut self, on: bool) -> Self {
+        self.patterns = on;
+        self
+    }
+
+    /// The `--tightness` the IR was loosened to, for [`Codegen::generated_config`]:
+    /// checks on constraints stripped from the IR are reported off.
+    pub fn with_tightness(mut self, tightness: u8) -> Self {
+        self.tightness = tightness;
+        self
+    }
+
+    /// Strings detected as hex or base64 become newtypes over the decoded `Vec<u8>`
+    /// instead of checked strings; in every profile.
+    pub fn with_bytes(mut self, on: bool) -> Self {
+        self.bytes = on;
+        self
+    }
+
+    fn decodes(&self, format: Option<StrFormat>) -> bool {
+        self.bytes && format.is_some_and(|f| f.content_encoding().is_some())
+    }
+
+    /// Strings detected as decimal amounts become newtypes over `::rust_decimal::Decimal`
+    /// (the consuming crate needs `rust_decimal`), read through a converting deserializer
+    /// instead of checked as strings; in every profile.
+    pub fn with_decimal(mut self, on: bool) -> Self {
+        self.decimal = on;
+        self
+    }
+
+    fn reads_decimal(&self, format: Option<StrFormat>) -> bool {
+        self.decimal && format == Some(StrFormat::Decimal)
+    }
+
+    /// Emit `redact(&mut self)` on every type holding tagged fields (directly or
+    /// below), blanking or hashing them; see [`Codegen::with_pii`].
+    pub fn with_redact(mut self, mode: Option<RedactMode>) -> Self {
+        self.redact = mode;
+        self
+    }
+
+    fn small(&self) -> bool {
+        self.profile == CodegenProfile::Small
+    }
+
+    /// Collapse each group of root columns (always null together in the evidence) into a
+    /// single optional struct. The root is deserialized as `{Root}Raw` first, then converted,
+    /// rejecting records where a group is only partially filled.
+    pub fn with_nullable_groups(mut self, groups: &[Vec<JPath>]) -> Self {
+        self.groups = groups
+            .iter()
+            .map(|g| g.iter().filter_map(|p| match p.segs() {
+                [Seg::Field(n)] => Some(n.clone()),
+                [Seg::Index(i)] => Some(i.to_string()),
+                _ => None,
+            }).collect())
+            .collect();
+        self
+    }
+
+    /// Mark `path` (relative to the root record) as the primary key; call before `emit`,
+    /// then [`Codegen::emit_record_key`] afterwards.
+    pub fn with_key(mut self, path: &JPath) -> Self {
+        self.key = Some(path.segs().iter().map(|s| match s {
+            Seg::Field(n) => n.clone(),
+            Seg::Index(i) => i.to_string(),
+            Seg::Items => "Item".to_string(),
+        }).collect());
+        self
+    }
+
+    fn is_key_path(&self, path: &[String]) -> bool {
+        self.key.as_ref().is_some_and(|k| path.len() == k.len() + 1 && path[1..] == k[..])
+    }
+
+    /// `, Hash` for the key's newtype, so it can index a `HashMap`.
+    fn key_derives(&self, path: &[String]) -> &'static str {
+        if self.is_key_path(path) { ", Hash" } else { "" }
+    }
+
+    /// Emit `key()` on the record type plus `HashMap` indexing helpers.
+    /// `root` must be the IR passed to `emit`, and `record` the name it returned.
+    pub fn emit_record_key(&mut self, root: &Ty, record: &str, key: &JPath) -> Result<(), String> {
+        let mut cur = root;
+        let mut access = String::new();
+        for (k, seg) in key.segs().iter().enumerate() {
+            cur = match (seg, cur) {
+                (Seg::Field(n), Ty::Object { fields, .. }) => {
+                    let f = fields.iter().find(|f| &f.name == n)
+                        .ok_or_else(|| format!("key {key}: no field `{n}` in {record}"))?;
+                    if !f.required || matches!(f.ty, Ty::Nullable(_)) {
+                        return Err(format!("key {key}: `{n}` is not present and non-null in every record"));
+                    }
+                    access.push('.');
+                    access.push_str(&to_field_name(n));
+                    &f.ty
+                }
+                (Seg::Index(i), Ty::ArrayTuple { elems, min_items, .. }) => {
+                    let e = elems.get(*i).ok_or_else(|| format!("key {key}: tuple has only {} element(s)", elems.len()))?;
+                    if (*i as u32) >= *min_items || matches!(e, Ty::Nullable(_)) {
+                        return Err(format!("key {key}: position {i} is not present and non-null in every record"));
+                    }
+                    let at = if access.is_empty() { self.regrouped.get(i).copied().unwrap_or(*i) } else { *i };
+                    let names = (k > 0 || self.groups.is_empty()).then(|| self.tuple_members(&key.segs()[..k], elems.len())).flatten();
+                    let member = names.map_or_else(|| at.to_string(), |n| n[*i].clone());
+                    access.push('.');
+                    access.push_str(&member);
+                    e
+                }
+                _ => return Err(format!("key {key} does not resolve to a field of {record} (only object fields and tuple positions can be keys)")),
+            };
         }
+        if key.segs().is_empty() || !matches!(cur, Ty::String { .. } | Ty::Integer { .. }) {
+            return Err(format!("key {key} must select a string or integer field"));
+        }
+        let key_ty = self.key_ty.clone().ok_or_else(|| format!("key {key} was not emitted"))?;
+
+        self.out.push_str(&format!(
+r#"impl {record} {{
+    /// Primary key (`{key}`).
+    pub fn key(&self) -> &{key_ty} {{ &self{access} }}
+}}
+
+/// Index records by [`{record}::key`]; on duplicate keys the last record wins.
+pub fn index_by_key<I>(records: I) -> ::std::collections::HashMap<{key_ty}, {record}>
+where
+    I: ::core::iter::IntoIterator<Item = {record}>,
+{{
+    records.into_iter().map(|r| (::core::clone::Clone::clone(r.key()), r)).collect()
+}}
+
+/// Index records by [`{record}::key`], failing on the first duplicate key.
+pub fn try_index_by_key<I>(records: I) -> ::std::result::Result<::std::collections::HashMap<{key_ty}, {record}>, {key_ty}>
+where
+    I: ::core::iter::IntoIterator<Item = {record}>,
+{{
+    let mut out = ::std::collections::HashMap::new();
+    for r in records {{
+        let k = ::core::clone::Clone::clone(r.key());
+        if out.contains_key(&k) {{
+            return Err(k);
+        }}
+        out.insert(k, r);
+    }}
+    Ok(out)
+}}
+
+"#));
+        Ok(())
+    }
+
+    /// The root is one record of a stream: also emit `read_records` (an iterator over the
+    /// records of NDJSON, one per line) and `parse_records`, failing with a `RecordError`
+    /// that names the line.
+    pub fn with_records(mut self, on: bool) -> Self {
+        self.records = on;
+        self
+    }
+
+    fn emit_records_api(&mut self, record: &str) {
+        self.out.push_str(&format!(
+r#"/// A record stream that could not be read: an I/O error, or the line (1-based)
+/// holding a record that doesn't fit [`{record}`].
+#[derive(Debug)]
+pub enum RecordError {{
+    Io(::std::io::Error),
+    Record {{ line: usize, error: ::serde_json::Error }},
+}}
+
+impl ::core::fmt::Display for RecordError {{
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {{
+        match self {{
+            RecordError::Io(e) => write!(f, "{{e}}"),
+            RecordError::Record {{ line, error }} => write!(f, "line {{line}}: {{error}}"),
+        }}
+    }}
+}}
+
+impl ::std::error::Error for RecordError {{}}
+
+/// The records of an NDJSON stream, one per line; blank lines are skipped.
+pub fn read_records<R: ::std::io::BufRead>(r: R) -> impl ::core::iter::Iterator<Item = ::std::result::Result<{record}, RecordError>> {{
+    r.lines().enumerate().filter_map(|(i, line)| match line {{
+        ::std::result::Result::Err(e) => ::core::option::Option::Some(::std::result::Result::Err(RecordError::Io(e))),
+        ::std::result::Result::Ok(l) if l.trim().is_empty() => ::core::option::Option::None,
+        ::std::result::Result::Ok(l) => ::core::option::Option::Some(
+            ::serde_json::from_str(&l).map_err(|error| RecordError::Record {{ line: i + 1, error }}),
+        ),
+    }})
+}}
+
+/// Every record of an NDJSON stream, or the first that can't be read.
+pub fn parse_records(src: &str) -> ::std::result::Result<::std::vec::Vec<{record}>, RecordError> {{
+    read_records(src.as_bytes()).collect()
+}}
+
+"#));
+    }
+
+    pub fn with_naming(mut self, naming: NamingPolicy) -> Self {
+        self.naming = naming;
+        self
+    }
+
+    /// Name the types at these naming-manifest keys (see [`Codegen::names`]) as given
+    /// instead of deriving a name; collisions are still resolved by the naming policy.
+    pub fn with_names(mut self, pinned: &BTreeMap<String, String>) -> Self {
+        self.pinned = pinned.iter().map(|(k, v)| (k.clone(), to_type_name(v))).collect();
+        self
+    }
+
+    /// The naming manifest: JSON path → name of every type emitted so far. A path holding
+    /// more than one named type (union arms, sentinel wrappers, `…Raw` structs) lists the
+    /// later ones in walk order as `PATH~2`, `PATH~3`, …
+    pub fn names(&self) -> &BTreeMap<String, String> { &self.names }
+
+    /// Clashes recorded under [`CollisionPolicy::Error`]; non-empty means the output shouldn't be used.
+    pub fn collisions(&self) -> &[NameCollision] { &self.collisions }
+
+    /// Reference (instead of emit) any compound type whose structural key is in `reuse`.
+    pub fn with_reuse(mut self, reuse: BTreeMap<String, String>) -> Self {
+        self.reuse = reuse;
+        self
+    }
+
+    /// Emit each structurally distinct object/tuple/union type only once.
+    pub fn with_dedupe(mut self, on: bool) -> Self {
+        self.dedupe = on;
+        self
+    }
+
+    /// Types shared between paths (see [`crate::share`]), by path: each is emitted once,
+    /// named after its definition. Turns on [`Codegen::with_dedupe`].
+    pub fn with_shared(mut self, names: &BTreeMap<String, String>) -> Self {
+        self.shared = names.clone();
+        self.dedupe = true;
+        self
     }
+
     pub fn into_string(self) -> String { self.out }
 
-    pub fn emit(&mut self, root: &Ty, root_name: &str) {
+    /// The source emitted so far.
+    pub fn source(&self) -> &str { &self.out }
+
+    /// The JSON paths the emitted type `item` (or its field `member`, every one of that
+    /// name) was generated for, where codegen named it after a path (see [`crate::rust_check`]).
+    pub fn origin(&self, item: &str, member: Option<&str>) -> Vec<String> {
+        if let Some(member) = member {
+            let rows: Vec<String> = self
+                .fields
+                .iter()
+                .filter(|r| r.owner == item && r.field.trim_start_matches("r#") == member)
+                .map(|r| r.path.clone())
+                .collect();
+            if !rows.is_empty() {
+                return rows;
+            }
+        }
+        // `$.a~2`: the second type named at `$.a`
+        self.names.iter().filter(|(_, name)| *name == item).map(|(at, _)| at.split('~').next().unwrap_or(at).to_string()).take(1).collect()
+    }
+
+    /// Size and shape of everything emitted so far, as module `module`.
+    pub fn metrics(&self, module: &str) -> CodeMetrics {
+        CodeMetrics { module: module.to_string(), lines: self.out.lines().count(), items: self.items.clone() }
+    }
+
+    fn note(&mut self, name: &str, kind: ItemKind, members: usize, path: &[String]) {
+        self.items.push(Item {
+            name: name.to_string(),
+            kind,
+            members,
+            path: path.join("."),
+            depth: path.len().saturating_sub(1),
+        });
+    }
+
+    /// `generated_config.rs`: constants describing the checks the emitted models enforce,
+    /// so consuming crates can assert them (e.g. `const _: () = assert!(DENY_UNKNOWN_FIELDS);`).
+    /// Call after `emit`; `key` is the record key passed to [`Codegen::emit_record_key`], if any.
+    pub fn generated_config(&self, key: Option<&JPath>) -> String {
+        let key = match key {
+            Some(k) => format!("::core::option::Option::Some({:?})", k.to_string()),
+            None => "::core::option::Option::None".to_string(),
+        };
+        // what `norm_ir::loosen` kept at this tightness
+        let (bounds, enums, formats) = (self.tightness >= 3, self.tightness >= 2, self.tightness >= 1);
+        format!(
+r#"// AUTOGENERATED: checks enforced by the generated models (json-osi {version})
+
+/// `--codegen-profile` the models were generated with (`strict` or `small`).
+pub const PROFILE: &str = {profile:?};
+/// Integer newtypes reject values outside the observed range.
+pub const CHECK_INT_BOUNDS: bool = {int_bounds};
+/// Number newtypes reject values outside the observed range (f64 tolerant).
+pub const CHECK_NUM_BOUNDS: bool = {num_bounds};
+/// Strings with a synthesized regex reject non-matching values.
+pub const CHECK_PATTERNS: bool = {patterns};
+/// Small human-ish string sets are closed enums.
+pub const STRING_ENUMS: bool = {enums};
+/// URI-like strings must start with a known scheme.
+pub const CHECK_URI_SCHEMES: bool = {uri};
+/// Strings detected as a well-known format (`uuid`, `email`, `ipv4`, `ipv6`, `hostname`,
+/// `hex`, `base64`, `decimal`) must match it.
+pub const CHECK_STRING_FORMATS: bool = {string_formats};
+/// Strings detected as UUIDs are `uuid::Uuid`.
+pub const UUID_TYPE: bool = {uuid};
+/// Hex / base64 strings are decoded to bytes.
+pub const DECODE_BYTES: bool = {bytes};
+/// Decimal-amount strings are read as `rust_decimal::Decimal`.
+pub const DECIMAL_TYPE: bool = {decimal};
+/// Objects reject fields that never appeared in the evidence.
+pub const DENY_UNKNOWN_FIELDS: bool = true;
+/// Tuples reject extra elements and missing required elements.
+pub const CHECK_TUPLE_ARITY: bool = true;
+/// Root columns that were always null together are collapsed and must be all-or-none.
+pub const NULLABLE_GROUPS: bool = {grouped};
+/// Primary key of the root record, if `key()` / `index_by_key` were generated.
+pub const RECORD_KEY: ::core::option::Option<&str> = {key};
+"#,
+            version = env!("CARGO_PKG_VERSION"),
+            profile = self.profile.as_str(),
+            int_bounds = crate::inference::CHECK_INT_BOUNDS && bounds && !self.small(),
+            num_bounds = crate::inference::CHECK_NUM_BOUNDS && bounds && !self.small(),
+            patterns = self.patterns && enums && !self.small(),
+            uri = formats && !self.small(),
+            string_formats = formats && !self.small(),
+            uuid = self.uuid && formats,
+            bytes = self.bytes && formats,
+            decimal = self.decimal && formats,
+            enums = crate::inference::ENABLE_STRING_ENUMS && enums,
+            grouped = self.grouped,
+        )
+    }
+
+    /// Structural key → emitted name for every object/tuple/union type so far (with `dedupe`).
+    pub fn emitted(&self) -> &BTreeMap<String, String> { &self.emitted }
+
+    /// Emit a full module: prelude plus `root` under `root_name`.
+    /// Returns the Rust type that `root` resolved to.
+    pub fn emit(&mut self, root: &Ty, root_name: &str) -> String {
+        self.emit_prelude();
+        self.used.insert("FieldMeta".to_string());
+        if self.records {
+            self.used.insert("RecordError".to_string());
+        }
+        let record = self.emit_item(root, root_name);
+        self.emit_field_meta();
+        if let Some(mode) = self.redact {
+            self.emit_redact(mode);
+        }
+        if self.records {
+            self.emit_records_api(&record);
+        }
+        record
+    }
+
+    /// File header and the `Null` helper type; emit once per module.
+    pub fn emit_prelude(&mut self) {
         self.header();
         self.emit_null_type();
-        self.walk(root, &mut Vec::new(), root_name.to_string());
+    }
+
+    /// Emit `t` (and everything it needs) under the name hint `name`, without a prelude.
+    pub fn emit_item(&mut self, t: &Ty, name: &str) -> String {
+        self.walk(t, &mut vec![name.to_string()], name.to_string())
+    }
+
+    /// Like [`Codegen::emit_item`], but `path` is where `t` lives in its source document;
+    /// it is used to qualify the name under [`CollisionPolicy::Path`].
+    pub fn emit_item_at(&mut self, t: &Ty, name: &str, path: Vec<String>) -> String {
+        self.walk(t, &mut path.clone(), name.to_string())
     }
 
     fn header(&mut self) {
@@ -30,6 +755,10 @@ impl Codegen {
 r#"// AUTOGENERATED: strict types + deserializers (fully-qualified paths)
 "#
         );
+        if self.small() {
+            // no bespoke number checks, so no tolerance helpers
+            return;
+        }
                 self.out.push_str(
 r#"// F64 tolerance helpers (absolute + relative)
 const __ABS_TOL: f64 = 1e-12;
@@ -41,147 +770,1264 @@ fn __tol(b: f64) -> f64 {
     if t.is_finite() { t } else { 0.0 }
 }
 
-#[inline] fn __ge_f64(x: f64, b: f64) -> bool { x + __tol(b) >= b }
-#[inline] fn __le_f64(x: f64, b: f64) -> bool { x <= b + __tol(b) }
+#[inline] fn __ge_f64(x: f64, b: f64) -> bool { x + __tol(b) >= b }
+#[inline] fn __le_f64(x: f64, b: f64) -> bool { x <= b + __tol(b) }
+"#
+        );
+    }
+
+    fn emit_null_type(&mut self) {
+        self.out.push_str(
+r#"#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Null;
+
+impl<'de> ::serde::Deserialize<'de> for Null {
+    fn deserialize<D>(de: D) -> ::std::result::Result<Self, D::Error>
+    where
+        D: ::serde::Deserializer<'de>,
+    {
+        // Accept only explicit null
+        struct V;
+        impl<'de> ::serde::de::Visitor<'de> for V {
+            type Value = Null;
+            fn expecting(&self, f:&mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                write!(f, "null")
+            }
+            fn visit_unit<E>(self) -> ::std::result::Result<Null, E>
+            where
+                E: ::serde::de::Error
+            {
+                Ok(Null)
+            }
+            fn visit_none<E>(self) -> ::std::result::Result<Null, E>
+            where
+                E: ::serde::de::Error
+            {
+                Ok(Null)
+            }
+        }
+        de.deserialize_option(V)
+    }
+}
+"#
+        );
+    }
+
+    fn unique(&mut self, base: &str, t: &Ty, path: &[String]) -> String {
+        let at = JPath(self.at.clone()).to_string();
+        let key = (1..)
+            .map(|n| if n == 1 { at.clone() } else { format!("{at}~{n}") })
+            .find(|k| !self.names.contains_key(k))
+            .expect("unbounded");
+        let name = match self.pinned.get(&key).cloned() {
+            Some(pinned) => self.claim(&pinned, t, path),
+            None => self.claim(base, t, path),
+        };
+        self.names.insert(key, name.clone());
+        name
+    }
+
+    fn claim(&mut self, base: &str, t: &Ty, path: &[String]) -> String {
+        if !self.used.contains(base) {
+            self.used.insert(base.to_string());
+            self.owners.insert(base.to_string(), Shape::of(t));
+            return base.to_string();
+        }
+        let base = match self.naming.collisions {
+            CollisionPolicy::Suffix => base.to_string(),
+            CollisionPolicy::Path => {
+                // Prefix with the source (root) name, then with each enclosing
+                // path in turn; a prefix the name already starts with adds nothing.
+                let qualified = (1..path.len())
+                    .map(|k| to_type_name(&path[..k].join("_")))
+                    .filter(|prefix| !base.starts_with(prefix.as_str()))
+                    .map(|prefix| format!("{prefix}{base}"))
+                    .find(|q| !self.used.contains(q));
+                if let Some(qualified) = qualified {
+                    self.used.insert(qualified.clone());
+                    self.owners.insert(qualified.clone(), Shape::of(t));
+                    return qualified;
+                }
+                base.to_string()
+            }
+            CollisionPolicy::Error => {
+                self.collisions.push(NameCollision {
+                    name: base.to_string(),
+                    path: path.join("."),
+                    existing: self.owners.get(base).cloned().unwrap_or_else(|| Shape::of(&Ty::Never)),
+                    incoming: Shape::of(t),
+                });
+                base.to_string()
+            }
+        };
+        let mut n = base.clone();
+        let mut i = 1;
+        while self.used.contains(&n) {
+            n = format!("{base}{i}");
+            i += 1;
+        }
+        self.used.insert(n.clone());
+        n
+    }
+
+    fn walk(&mut self, t: &Ty, path: &mut Vec<String>, hint: String) -> String {
+        let name = self.walk_reusing(t, path, hint);
+        if self.is_key_path(path) {
+            self.key_ty = Some(name.clone());
+        }
+        name
+    }
+
+    fn walk_reusing(&mut self, t: &Ty, path: &mut Vec<String>, hint: String) -> String {
+        let shared_enum = self.small() && is_enum(t);
+        if (self.dedupe || !self.reuse.is_empty()) && (is_compound(t) || shared_enum) {
+            let hint = match self.shared.get(&JPath(self.at.clone()).to_string()) {
+                Some(name) if is_compound(t) => name.clone(),
+                _ => hint,
+            };
+            // a type holding raw values reads them differently when buffered
+            let key = match structural_key(t) {
+                key if self.buffered > 0 && holds_raw(t) => format!("buffered {key}"),
+                key => key,
+            };
+            if let Some(name) = self.reuse.get(&key).or_else(|| self.emitted.get(&key)) {
+                return name.clone();
+            }
+            let name = self.walk_inner(t, path, hint);
+            if self.dedupe {
+                self.emitted.insert(key, name.clone());
+            }
+            return name;
+        }
+        self.walk_inner(t, path, hint)
+    }
+
+    fn walk_inner(&mut self, t: &Ty, path: &mut Vec<String>, hint: String) -> String {
+        if matches!(t, Ty::Integer { .. }) && let Some(&unit) = self.epochs.get(&JPath(self.at.clone()).to_string()) {
+            return self.emit_epoch_type(unit);
+        }
+        match t {
+            Ty::Nullable(inner) => {
+                let inner_name = self.walk(inner, path, hint);
+                format!("::core::option::Option<{inner_name}>")
+            }
+            Ty::Null => "Null".into(),
+            Ty::Bool => "bool".into(),
+            Ty::Integer { enum_, .. } if !enum_.is_empty() => self.emit_int_enum(t, path, &hint),
+            Ty::Integer { min, max, from_string: true, .. } if self.small() => {
+                self.emit_num_or_string_type();
+                format!("NumOrString<{}>", IntWidth::of(*min, *max).rust())
+            }
+            Ty::Number  { from_string: true, .. } if self.small() => {
+                self.emit_num_or_string_type();
+                "NumOrString<f64>".into()
+            }
+            Ty::StringOrNumber { .. } => {
+                self.emit_number_like_type();
+                "NumberLike".into()
+            }
+            Ty::Integer { min, max, .. } if self.small() => IntWidth::of(*min, *max).rust().into(),
+            Ty::Number  { .. } if self.small() => "f64".into(),
+            Ty::Integer { .. } => {
+                let nm = self.emit_int_newtype(t, path, &hint);
+                self.scalars.insert(nm.clone(), Scalar::Int);
+                nm
+            }
+            Ty::Number  { .. } => {
+                let nm = self.emit_num_newtype(t, path, &hint);
+                self.scalars.insert(nm.clone(), Scalar::Num);
+                nm
+            }
+            Ty::String  { enum_, format, .. } => {
+                let nm = self.emit_string_kind(t, path, &hint);
+                if (enum_.is_empty() || enum_.len() > 32) && !nm.starts_with("::") {
+                    let scalar = if self.decodes(*format) {
+                        Scalar::Bytes
+                    } else if self.reads_decimal(*format) {
+                        Scalar::Decimal
+                    } else {
+                        Scalar::Str
+                    };
+                    self.scalars.insert(nm.clone(), scalar);
+                }
+                nm
+            }
+
+            Ty::ArrayList { item, .. } => {
+                self.at.push(Seg::Items);
+                let inner = self.walk(item, &mut path_with(path, "Item"), format!("{hint}Item"));
+                self.at.pop();
+                format!("::std::vec::Vec<{inner}>")
+            }
+
+            // keys are ids, so they stay strings whatever their syntax
+            Ty::Map { value, .. } => {
+                self.at.push(Seg::Items);
+                let inner = self.walk(value, &mut path_with(path, "Value"), format!("{hint}Value"));
+                self.at.pop();
+                format!("::std::collections::BTreeMap<::std::string::String, {inner}>")
+            }
+
+            Ty::ArrayTuple { elems, min_items, max_items, .. } => {
+                let type_name = self.unique(&to_type_name(&hint), t, path);
+
+                // the root tuple regrouped by --group-nullables keeps its positional fields
+                let grouped = path.len() == 1 && !self.groups.is_empty();
+                let names = (!grouped).then(|| self.tuple_members(&self.at, elems.len())).flatten();
+
+                // materialize field types
+                let mut cols = Positions { types: Vec::with_capacity(elems.len()), docs: Vec::with_capacity(elems.len()), names };
+                for (i, e) in elems.iter().enumerate() {
+                    self.at.push(Seg::Index(i));
+                    let mut child = self.walk(e, &mut path_with(path, i), format!("{hint}{i}"));
+                    let col_nullable = matches!(e, Ty::Nullable(_));
+                    if (((i as u32) >= *min_items) || col_nullable) && !is_option_type(&child) {
+                        child = format!("::core::option::Option<{child}>");
+                    }
+                    let member = cols.names.as_ref().map_or_else(|| i.to_string(), |n| n[i].clone());
+                    self.field_row(&type_name, member, None, e, (i as u32) < *min_items && !col_nullable, &child);
+                    cols.docs.push(paragraphs([self.description(), self.examples_doc()]));
+                    self.at.pop();
+                    cols.types.push(child);
+                }
+                self.note(&type_name, ItemKind::Tuple, cols.types.len(), path);
+                self.contracts.push(Contract::Tuple {
+                    name: type_name.clone(),
+                    members: (0..cols.types.len()).map(|i| cols.names.as_ref().map_or_else(|| i.to_string(), |n| n[i].clone())).collect(),
+                    named: cols.names.is_some(),
+                    min: *min_items as usize,
+                });
+
+                if grouped {
+                    let groups = self.root_groups(|m| m.parse::<usize>().ok().filter(|&i| cols.types.get(i).is_some_and(|f| is_option_type(f))));
+                    if !groups.is_empty() {
+                        let raw = self.unique(&format!("{type_name}Raw"), t, path);
+                        self.fields.iter_mut().filter(|r| r.owner == type_name).for_each(|r| r.owner = raw.clone());
+                        if let Some(Contract::Tuple { name, .. }) = self.contracts.last_mut() {
+                            *name = raw.clone();
+                        }
+                        if min_items == max_items {
+                            self.emit_len_fixed_tuple(&raw, &cols, *min_items as usize);
+                        } else {
+                            self.emit_len_range_tuple(&raw, &cols, *min_items as usize, *max_items as usize);
+                        }
+                        self.emit_grouped_tuple(&type_name, &raw, &cols.types, &groups, t, path, &hint);
+                        return type_name;
+                    }
+                }
+
+                // exact arity
+                if min_items == max_items {
+                    let req = *min_items as usize;
+                    self.emit_len_fixed_tuple(&type_name, &cols, req);
+                    return type_name;
+                }
+
+                // lenient (min..=max) tuple
+                self.emit_len_range_tuple(&type_name, &cols, *min_items as usize, *max_items as usize);
+                type_name
+            }
+
+            Ty::Object { fields, .. } => {
+                let type_name = self.unique(&to_type_name(&hint), t, path);
+
+                // materialize field types first; nested items must not land inside the struct body
+                let mut members = ::std::vec::Vec::with_capacity(fields.len());
+                let mut docs = ::std::vec::Vec::with_capacity(fields.len());
+                let mut attrs = ::std::vec::Vec::with_capacity(fields.len());
+                let mut aliases = ::std::vec::Vec::with_capacity(fields.len());
+                let first_row = self.fields.len();
+                for Field { name, ty, required, present, support } in fields {
+                    let fname = to_field_name(name);
+                    self.at.push(Seg::Field(name.clone()));
+                    let mut ty_str = self.walk(ty, &mut path_with(path, name), format!("{hint}{}", to_type_name(name)));
+                    // a missing key and null both read as `None`; a key every object
+                    // has must be there, even when null
+                    if !*required && !matches!(ty, Ty::Nullable(_)) {
+                        ty_str = format!("::core::option::Option<{ty_str}>");
+                    }
+                    attrs.push(match (*required, *present) {
+                        (true, _) => None,
+                        (false, true) => Some("#[serde(deserialize_with = \"::serde::Deserialize::deserialize\")]"),
+                        (false, false) => Some("#[serde(default)]"),
+                    });
+                    self.field_row(&type_name, fname.clone(), Some(name), ty, *required, &ty_str);
+                    let description = self.description();
+                    let examples = self.examples_doc();
+                    aliases.push(self.aliases.get(&JPath(self.at.clone()).to_string()).cloned().unwrap_or_default());
+                    self.at.pop();
+                    let support = support.map(|s| s.describe("objects"));
+                    let observed = match (step_doc(ty), support) {
+                        (Some(step), Some(support)) => Some(format!("{step} {support}")),
+                        (step, support) => step.or(support),
+                    };
+                    docs.push(paragraphs([description, observed, examples]));
+                    members.push((name, fname, ty_str));
+                }
+                self.note(&type_name, ItemKind::Struct, members.len(), path);
+
+                let groups = if path.len() == 1 && !self.groups.is_empty() {
+                    self.root_groups(|m| members.iter().position(|(n, _, ty)| *n == m && is_option_type(ty)))
+                } else {
+                    Vec::new()
+                };
+                let struct_name = if groups.is_empty() {
+                    type_name.clone()
+                } else {
+                    // the fields live on the raw struct; the grouped one regroups them
+                    let raw = self.unique(&format!("{type_name}Raw"), t, path);
+                    self.fields.iter_mut().skip(first_row).filter(|r| r.owner == type_name).for_each(|r| r.owner = raw.clone());
+                    raw
+                };
+                self.contracts.push(Contract::Struct {
+                    name: struct_name.clone(),
+                    fields: members.iter().map(|(name, fname, _)| (fname.clone(), name.to_string())).collect(),
+                });
+
+                self.out.push_str("#[derive(Debug, ::serde::Deserialize)]\n");
+                self.out.push_str("#[serde(deny_unknown_fields)]\n");
+                self.out.push_str(&format!("pub struct {} {{\n", struct_name));
+                for ((((name, fname, ty_str), doc), attr), aliases) in members.iter().zip(&docs).zip(&attrs).zip(&aliases) {
+                    push_doc(&mut self.out, doc.as_deref());
+                    if let Some(attr) = attr {
+                        self.out.push_str(&format!("    {attr}\n"));
+                    }
+                    if fname != *name {
+                        self.out.push_str(&format!("    #[serde(rename = {name:?})]\n"));
+                    }
+                    for alias in aliases {
+                        self.out.push_str(&format!("    #[serde(alias = {alias:?})]\n"));
+                    }
+                    self.out.push_str(&format!("    pub {}: {},\n", fname, ty_str));
+                }
+                self.out.push_str("}\n\n");
+                if !groups.is_empty() {
+                    let members: Vec<(String, String)> = members.into_iter().map(|(_, f, ty)| (f, ty)).collect();
+                    self.emit_grouped_struct(&type_name, &struct_name, &members, &groups, t, path, &hint);
+                }
+                type_name
+            }
+
+            Ty::OneOf(arms) => {
+                // Enum wrapper with try-each-arm using ::serde_json::from_value
+                let type_name = self.unique(&to_type_name(&hint), t, path);
+                let mut var_names = ::std::vec::Vec::new();
+                let mut arm_types = ::std::vec::Vec::new();
+                self.buffered += 1;
+                for (i, a) in arms.iter().enumerate() {
+                    let v_name = format!("V{}", i);
+                    var_names.push(v_name);
+                    arm_types.push(self.walk(a, &mut path_with(path, format!("Alt{i}")), format!("{hint}Alt{}", i)));
+                }
+                self.buffered -= 1;
+                self.note(&type_name, ItemKind::Union, arm_types.len(), path);
+                self.unions.insert(type_name.clone(), var_names.iter().cloned().zip(arm_types.iter().cloned()).collect());
+                self.emit_union_enum_simple(&type_name, &var_names, &arm_types);
+                type_name
+            }
+
+            // internally tagged: serde picks the variant by the tag, no trial parses
+            Ty::Tagged { tag, variants, .. } => {
+                let type_name = self.unique(&to_type_name(&hint), t, path);
+                let mut arms: Vec<(String, String)> = Vec::with_capacity(variants.len());
+                let mut renames = Vec::with_capacity(variants.len());
+                self.buffered += 1;
+                for (value, v) in variants {
+                    let base = to_type_name(value);
+                    let mut var = base.clone();
+                    let mut i = 1;
+                    while arms.iter().any(|(a, _)| *a == var) {
+                        var = format!("{base}{i}");
+                        i += 1;
+                    }
+                    let ty = self.walk(v, &mut path_with(path, &var), format!("{hint}{var}"));
+                    renames.push(value);
+                    arms.push((var, ty));
+                }
+                self.buffered -= 1;
+                self.note(&type_name, ItemKind::Union, arms.len(), path);
+                self.out.push_str(&format!("#[derive(Debug, ::serde::Deserialize)]\n#[serde(tag = {tag:?})]\npub enum {type_name} {{\n"));
+                for ((var, ty), value) in arms.iter().zip(renames) {
+                    self.out.push_str(&format!("    #[serde(rename = {value:?})]\n    {var}({ty}),\n"));
+                }
+                self.out.push_str("}\n\n");
+                self.unions.insert(type_name.clone(), arms);
+                type_name
+            }
+
+            Ty::Sentinel { .. } => self.emit_sentinel(t, path, &hint),
+
+            Ty::JsonString(inner) => {
+                let inner_name = self.walk(inner, path, hint);
+                self.emit_json_string_type();
+                format!("JsonString<{inner_name}>")
+            }
+
+            // nested past `--max-depth`: kept as parsed
+            Ty::Any => "::serde_json::Value".into(),
+            // `passthrough`: left as written, unless a union already parsed it
+            Ty::Raw if self.buffered > 0 => "::serde_json::Value".into(),
+            Ty::Raw => "::std::boxed::Box<::serde_json::value::RawValue>".into(),
+
+            Ty::Never => "Null".into(), // unreachable fallback
+        }
+    }
+
+    /// `JsonString<T>`: a string decoded as the JSON document inside it; emit once per module.
+    fn emit_json_string_type(&mut self) {
+        if !self.validators.insert("JsonString".into()) {
+            return;
+        }
+        self.out.push_str(
+r#"/// A string holding a JSON document, deserialized as `T`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonString<T>(pub T);
+
+impl<T> ::core::ops::Deref for JsonString<T> {
+    type Target = T;
+    fn deref(&self) -> &T { &self.0 }
+}
+
+impl<'de, T: ::serde::de::DeserializeOwned> ::serde::Deserialize<'de> for JsonString<T> {
+    fn deserialize<D>(de: D) -> ::std::result::Result<Self, D::Error>
+    where
+        D: ::serde::Deserializer<'de>,
+    {
+        let s = <::std::string::String as ::serde::Deserialize>::deserialize(de)?;
+        ::serde_json::from_str::<T>(&s)
+            .map(JsonString)
+            .map_err(|e| ::serde::de::Error::custom(::std::format!("in JSON string: {e}")))
+    }
+}
+"#
+        );
+    }
+
+    /// `NumOrString<T>`: a number written as a JSON number or as a string; emit once per module.
+    fn emit_num_or_string_type(&mut self) {
+        if !self.validators.insert("NumOrString".into()) {
+            return;
+        }
+        self.out.push_str(
+r#"/// A number written either as a JSON number or as a string spelling one (`"12"`, `"4.5"`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NumOrString<T>(pub T);
+
+impl<T> ::core::ops::Deref for NumOrString<T> {
+    type Target = T;
+    fn deref(&self) -> &T { &self.0 }
+}
+
+impl<'de, T> ::serde::Deserialize<'de> for NumOrString<T>
+where
+    T: ::serde::Deserialize<'de> + ::core::str::FromStr,
+    T::Err: ::core::fmt::Display,
+{
+    fn deserialize<D>(de: D) -> ::std::result::Result<Self, D::Error>
+    where
+        D: ::serde::Deserializer<'de>,
+    {
+        #[derive(::serde::Deserialize)]
+        #[serde(untagged)]
+        enum Repr<T> {
+            Num(T),
+            Str(::std::string::String),
+        }
+        match <Repr<T> as ::serde::Deserialize>::deserialize(de)? {
+            Repr::Num(x) => Ok(NumOrString(x)),
+            // JSON number characters only: no "NaN", "inf" or "+1"
+            Repr::Str(s) if !s.starts_with(|c: char| c == '-' || c.is_ascii_digit())
+                || !s.bytes().all(|b| b.is_ascii_digit() || b"+-.eE".contains(&b)) =>
+            {
+                Err(::serde::de::Error::custom(::std::format!("{s:?} is not a number")))
+            }
+            Repr::Str(s) => s.parse::<T>().map(NumOrString).map_err(|e| {
+                ::serde::de::Error::custom(::std::format!("{s:?} is not a number: {e}"))
+            }),
+        }
+    }
+}
+"#
+        );
+    }
+
+    /// `NumberLike`: a number kept as written, JSON number or string; emit once per module.
+    /// `UnixSeconds` or `UnixMillis`, emitted once.
+    fn emit_epoch_type(&mut self, unit: EpochUnit) -> String {
+        let (nm, adapter, doc) = match unit {
+            EpochUnit::Seconds => ("UnixSeconds", "TimestampSeconds", "whole seconds"),
+            EpochUnit::Millis => ("UnixMillis", "TimestampMilliSeconds", "milliseconds"),
+        };
+        if !self.validators.insert(nm.into()) {
+            return nm.into();
+        }
+        self.out.push_str(&format!(
+r#"/// A Unix timestamp in {doc} since 1970, read as a UTC time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, ::serde::Deserialize, ::serde::Serialize)]
+#[serde(transparent)]
+pub struct {nm}(
+    #[serde(with = "::serde_with::As::<::serde_with::{adapter}<i64>>")]
+    pub ::chrono::DateTime<::chrono::Utc>,
+);
+
+impl ::core::ops::Deref for {nm} {{
+    type Target = ::chrono::DateTime<::chrono::Utc>;
+    fn deref(&self) -> &Self::Target {{ &self.0 }}
+}}
+
+"#
+        ));
+        nm.into()
+    }
+
+    fn emit_number_like_type(&mut self) {
+        if !self.validators.insert("NumberLike".into()) {
+            return;
+        }
+        self.out.push_str(
+r#"/// A number written either as a JSON number or as a string spelling one (`4`, `"4.5"`),
+/// kept as written; read it through the accessors.
+#[derive(Debug, Clone, PartialEq, ::serde::Serialize)]
+#[serde(untagged)]
+pub enum NumberLike {
+    Number(::serde_json::Number),
+    String(::std::string::String),
+}
+
+impl NumberLike {
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            NumberLike::Number(n) => n.as_f64().unwrap_or(f64::NAN),
+            NumberLike::String(s) => s.parse().unwrap_or(f64::NAN),
+        }
+    }
+    /// The value as an integer, if it is one (`4`, `"4"`; not `4.0`).
+    pub fn as_i64(&self) -> ::core::option::Option<i64> {
+        match self {
+            NumberLike::Number(n) => n.as_i64(),
+            NumberLike::String(s) => s.parse().ok(),
+        }
+    }
+    /// The value as written, without the quotes.
+    pub fn as_str(&self) -> ::std::borrow::Cow<'_, str> {
+        match self {
+            NumberLike::Number(n) => ::std::borrow::Cow::Owned(n.to_string()),
+            NumberLike::String(s) => ::std::borrow::Cow::Borrowed(s),
+        }
+    }
+    pub fn is_string(&self) -> bool {
+        matches!(self, NumberLike::String(_))
+    }
+}
+
+impl ::core::fmt::Display for NumberLike {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        f.write_str(&self.as_str())
+    }
+}
+
+impl<'de> ::serde::Deserialize<'de> for NumberLike {
+    fn deserialize<D>(de: D) -> ::std::result::Result<Self, D::Error>
+    where
+        D: ::serde::Deserializer<'de>,
+    {
+        #[derive(::serde::Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Num(::serde_json::Number),
+            Str(::std::string::String),
+        }
+        match <Repr as ::serde::Deserialize>::deserialize(de)? {
+            Repr::Num(n) => Ok(NumberLike::Number(n)),
+            // JSON number syntax only: no "NaN", " 4", "+1" or "007"
+            Repr::Str(s) => match ::serde_json::from_str::<::serde_json::Number>(&s) {
+                Ok(_) if s.trim() == s => Ok(NumberLike::String(s)),
+                _ => Err(::serde::de::Error::custom(::std::format!("{s:?} is not a number"))),
+            },
+        }
+    }
+}
+"#
+        );
+    }
+
+    // ---- sentinels ----
+
+    /// Markers are checked on the raw value before `inner` gets a look at it.
+    fn emit_sentinel(&mut self, t: &Ty, path: &mut Vec<String>, hint: &str) -> String {
+        let Ty::Sentinel { inner, marks, as_null } = t else { unreachable!() };
+        let inner_name = self.walk(inner, path, format!("{hint}Value"));
+        let nm = self.unique(&to_type_name(hint), t, path);
+        let cond = |m: &Mark| match m {
+            Mark::Int(n) => format!("val.as_i64() == ::core::option::Option::Some({n})"),
+            Mark::Str(s) => format!("val.as_str() == ::core::option::Option::Some({s:?})"),
+        };
+        let listed = marks.iter().map(|m| format!("`{m}`")).collect::<Vec<_>>().join(", ");
+
+        if *as_null {
+            self.note(&nm, ItemKind::Newtype, 1, path);
+            let any = marks.iter().map(cond).collect::<Vec<_>>().join(" || ");
+            let convention = if marks.contains(&Mark::Str(String::new())) {
+                "\n/// The data writes missing values as empty strings as well as null."
+            } else {
+                ""
+            };
+            self.out.push_str(&format!(
+r#"/// {listed} and null read as `None`.{convention}
+#[derive(Debug)]
+pub struct {nm}(pub ::core::option::Option<{inner_name}>);
+impl ::core::ops::Deref for {nm} {{
+    type Target = ::core::option::Option<{inner_name}>;
+    fn deref(&self) -> &Self::Target {{ &self.0 }}
+}}
+impl<'de> ::serde::Deserialize<'de> for {nm} {{
+    fn deserialize<D>(de: D) -> ::std::result::Result<Self, D::Error>
+    where
+        D: ::serde::Deserializer<'de>,
+    {{
+        let val = ::serde_json::Value::deserialize(de)?;
+        if val.is_null() || {any} {{
+            return Ok({nm}(::core::option::Option::None));
+        }}
+        ::serde_json::from_value::<{inner_name}>(val)
+            .map(|x| {nm}(::core::option::Option::Some(x)))
+            .map_err(::serde::de::Error::custom)
+    }}
+}}
+
+"#
+            ));
+            return nm;
+        }
+
+        self.note(&nm, ItemKind::Enum, marks.len() + 1, path);
+        let mut used: BTreeSet<String> = BTreeSet::from(["Value".to_string()]);
+        let variants: Vec<(String, &Mark)> = marks
+            .iter()
+            .map(|m| {
+                let lit = match m {
+                    Mark::Int(n) if *n < 0 => format!("minus {}", n.unsigned_abs()),
+                    Mark::Int(n) => n.to_string(),
+                    Mark::Str(s) if s.trim().is_empty() => "empty".to_string(),
+                    Mark::Str(s) => s.clone(),
+                };
+                (variant_ident_for(&lit, &mut used), m)
+            })
+            .collect();
+        self.out.push_str(&format!("#[derive(Debug)]
+pub enum {nm} {{
+    Value({inner_name}),
+"));
+        for (ident, m) in &variants {
+            self.out.push_str(&format!("    /// `{m}`
+    {ident},
+"));
+        }
+        self.out.push_str(&format!(
+r#"}}
+impl<'de> ::serde::Deserialize<'de> for {nm} {{
+    fn deserialize<D>(de: D) -> ::std::result::Result<Self, D::Error>
+    where
+        D: ::serde::Deserializer<'de>,
+    {{
+        let val = ::serde_json::Value::deserialize(de)?;
+"#
+        ));
+        for (ident, m) in &variants {
+            self.out.push_str(&format!("        if {} {{ return Ok({nm}::{ident}); }}
+", cond(m)));
+        }
+        self.out.push_str(&format!(
+r#"        ::serde_json::from_value::<{inner_name}>(val)
+            .map({nm}::Value)
+            .map_err(::serde::de::Error::custom)
+    }}
+}}
+
+"#
+        ));
+        nm
+    }
+
+    /// Newtype over a string that must be the format `name`, checked by the function `check`.
+    fn emit_format_newtype(&mut self, t: &Ty, path: &[String], hint: &str, name: &str, check: &str) -> String {
+        let nm = self.unique(&to_type_name(hint), t, path);
+        self.note(&nm, ItemKind::Newtype, 1, path);
+        self.out.push_str(&format!(
+            "#[repr(transparent)]\n#[derive(Debug, Clone, PartialEq, Eq{})]\npub struct {}(pub ::std::string::String);\n",
+            self.key_derives(path), nm
+        ));
+        self.out.push_str(&format!(
+r#"impl ::core::ops::Deref for {nm} {{
+    type Target = ::std::string::String;
+    fn deref(&self) -> &Self::Target {{ &self.0 }}
+}}
+impl {nm} {{
+    /// `s`, if it is a valid `{name}`.
+    pub fn new(s: impl ::core::convert::Into<::std::string::String>) -> ::core::option::Option<Self> {{
+        let s = s.into();
+        {check}(&s).then_some({nm}(s))
+    }}
+}}
+impl<'de> ::serde::Deserialize<'de> for {nm} {{
+    fn deserialize<D>(de: D) -> ::std::result::Result<Self, D::Error>
+    where
+        D: ::serde::Deserializer<'de>,
+    {{
+        let s = <::std::string::String as ::serde::Deserialize>::deserialize(de)?;
+        if !{check}(&s) {{
+            return Err(::serde::de::Error::custom("{nm}: not a valid {name}"));
+        }}
+        Ok({nm}(s))
+    }}
+}}
+"#));
+        nm
+    }
+
+    /// `is_<format>()`, emitted once per format; returns its name.
+    fn emit_validator(&mut self, f: &Format) -> String {
+        let snake = to_field_name(&f.name);
+        let snake = snake.trim_start_matches("r#");
+        let func = format!("is_{snake}");
+        if !self.validators.insert(func.clone()) {
+            return func;
+        }
+        let rx = format!("RE_FORMAT_{}", snake.to_uppercase());
+        let extra = f.check.as_ref().map(|c| format!(" && {c}(s)")).unwrap_or_default();
+        self.out.push_str(&format!(
+r#"static {rx}: ::once_cell::sync::Lazy<::regex::Regex> = ::once_cell::sync::Lazy::new(|| ::regex::Regex::new({re:?}).unwrap());
+/// Whether `s` is a valid `{name}`.
+pub fn {func}(s: &str) -> bool {{
+    {rx}.is_match(s){extra}
+}}
+"#, re = f.regex, name = f.name));
+        func
+    }
+
+    /// `is_<format>()` for a well-known format, emitted once; returns its name.
+    fn emit_known_validator(&mut self, f: StrFormat) -> String {
+        let func = format!("is_{}", f.as_str());
+        if !self.validators.insert(func.clone()) {
+            return func;
+        }
+        let body = match (f, f.regex()) {
+            (_, Some(re)) => {
+                let rx = format!("RE_FORMAT_{}", f.as_str().to_uppercase());
+                self.out.push_str(&format!(
+                    "static {rx}: ::once_cell::sync::Lazy<::regex::Regex> = ::once_cell::sync::Lazy::new(|| ::regex::Regex::new({re:?}).unwrap());\n"
+                ));
+                format!("{rx}.is_match(s)")
+            }
+            (StrFormat::Ipv4, None) => "s.parse::<::std::net::Ipv4Addr>().is_ok()".to_string(),
+            (_, None) => "s.parse::<::std::net::Ipv6Addr>().is_ok()".to_string(),
+        };
+        self.out.push_str(&format!(
+r#"/// Whether `s` is a valid `{name}`.
+pub fn {func}(s: &str) -> bool {{
+    {body}
+}}
+"#, name = f.as_str()));
+        func
+    }
+
+    /// Newtype over the bytes a hex / base64 string decodes to.
+    fn emit_bytes_newtype(&mut self, t: &Ty, path: &[String], hint: &str, f: StrFormat) -> String {
+        let decode = self.emit_decoder(f);
+        let nm = self.unique(&to_type_name(hint), t, path);
+        self.note(&nm, ItemKind::Newtype, 1, path);
+        self.out.push_str(&format!(
+            "#[derive(Debug, Clone, PartialEq, Eq{})]\npub struct {}(pub ::std::vec::Vec<u8>);\n",
+            self.key_derives(path), nm
+        ));
+        self.out.push_str(&format!(
+r#"impl ::core::ops::Deref for {nm} {{
+    type Target = [u8];
+    fn deref(&self) -> &Self::Target {{ &self.0 }}
+}}
+impl<'de> ::serde::Deserialize<'de> for {nm} {{
+    fn deserialize<D>(de: D) -> ::std::result::Result<Self, D::Error>
+    where
+        D: ::serde::Deserializer<'de>,
+    {{
+        let s = <::std::string::String as ::serde::Deserialize>::deserialize(de)?;
+        {decode}(&s)
+            .map({nm})
+            .ok_or_else(|| ::serde::de::Error::custom("{nm}: not valid {name}"))
+    }}
+}}
+"#, name = f.as_str()));
+        nm
+    }
+
+    /// Newtype over the `Decimal` a decimal-amount string spells; thousands
+    /// separators are dropped, and it serializes back without them.
+    fn emit_decimal_newtype(&mut self, t: &Ty, path: &[String], hint: &str) -> String {
+        let check = self.emit_known_validator(StrFormat::Decimal);
+        let nm = self.unique(&to_type_name(hint), t, path);
+        self.note(&nm, ItemKind::Newtype, 1, path);
+        self.out.push_str(&format!(
+            "#[derive(Debug, Clone, Copy, PartialEq, Eq{})]\npub struct {}(pub ::rust_decimal::Decimal);\n",
+            self.key_derives(path), nm
+        ));
+        self.out.push_str(&format!(
+r#"impl ::core::ops::Deref for {nm} {{
+    type Target = ::rust_decimal::Decimal;
+    fn deref(&self) -> &Self::Target {{ &self.0 }}
+}}
+impl<'de> ::serde::Deserialize<'de> for {nm} {{
+    fn deserialize<D>(de: D) -> ::std::result::Result<Self, D::Error>
+    where
+        D: ::serde::Deserializer<'de>,
+    {{
+        let s = <::std::string::String as ::serde::Deserialize>::deserialize(de)?;
+        if !{check}(&s) {{
+            return Err(::serde::de::Error::custom("{nm}: not a valid decimal"));
+        }}
+        s.replace(',', "")
+            .parse::<::rust_decimal::Decimal>()
+            .map({nm})
+            .map_err(|e| ::serde::de::Error::custom(::std::format!("{nm}: {{e}}")))
+    }}
+}}
+impl ::serde::Serialize for {nm} {{
+    fn serialize<S>(&self, ser: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: ::serde::Serializer,
+    {{
+        ser.collect_str(&self.0)
+    }}
+}}
+"#));
+        nm
+    }
+
+    /// `decode_<format>()`, emitted once; returns its name.
+    fn emit_decoder(&mut self, f: StrFormat) -> String {
+        let func = format!("decode_{}", f.as_str());
+        if !self.validators.insert(func.clone()) {
+            return func;
+        }
+        let body = match f {
+            StrFormat::Hex => r#"if s.len() % 2 != 0 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return ::core::option::Option::None;
+    }
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok()).collect()"#,
+            _ => r#"fn sextet(c: u8) -> ::core::option::Option<u32> {
+        match c {
+            b'A'..=b'Z' => ::core::option::Option::Some((c - b'A') as u32),
+            b'a'..=b'z' => ::core::option::Option::Some((c - b'a') as u32 + 26),
+            b'0'..=b'9' => ::core::option::Option::Some((c - b'0') as u32 + 52),
+            b'+' => ::core::option::Option::Some(62),
+            b'/' => ::core::option::Option::Some(63),
+            _ => ::core::option::Option::None,
+        }
+    }
+    let s = s.as_bytes();
+    if s.len() % 4 != 0 {
+        return ::core::option::Option::None;
+    }
+    let mut out = ::std::vec::Vec::with_capacity(s.len() / 4 * 3);
+    for (i, chunk) in s.chunks(4).enumerate() {
+        let pad = if (i + 1) * 4 == s.len() { chunk.iter().rev().take_while(|&&c| c == b'=').count() } else { 0 };
+        if pad > 2 {
+            return ::core::option::Option::None;
+        }
+        let mut n = 0u32;
+        for &c in &chunk[..4 - pad] {
+            n = (n << 6) | sextet(c)?;
+        }
+        n <<= 6 * pad as u32;
+        out.extend_from_slice(&n.to_be_bytes()[1..4 - pad]);
+    }
+    ::core::option::Option::Some(out)"#,
+        };
+        self.out.push_str(&format!(
+r#"/// The bytes `s` encodes as {name}, if it is valid {name}.
+pub fn {func}(s: &str) -> ::core::option::Option<::std::vec::Vec<u8>> {{
+    {body}
+}}
+"#, name = f.as_str()));
+        func
+    }
+
+    // ---- field metadata ----
+
+    fn field_row(&mut self, owner: &str, field: String, name: Option<&str>, ty: &Ty, required: bool, rust_ty: &str) {
+        let (nullable, kind, format) = describe(ty);
+        let path = JPath(self.at.clone()).to_string();
+        let pii = self.pii.get(&path).or_else(|| self.pii.get(&format!("{path}[*]"))).copied();
+        let declared = self.formats.get(&path).or_else(|| self.formats.get(&format!("{path}[*]")));
+        let epoch = self.epochs.get(&path).or_else(|| self.epochs.get(&format!("{path}[*]")));
+        let format = declared
+            .map(|f| f.name.clone())
+            .or(epoch.map(|u| u.format().to_string()))
+            .or(format.map(str::to_string));
+        self.fields.push(FieldRow {
+            owner: owner.to_string(),
+            field,
+            name: name.map(str::to_string),
+            path,
+            required,
+            nullable,
+            kind,
+            format,
+            pii,
+            ty: rust_ty.to_string(),
+        });
+    }
+
+    /// `contract_tests.rs`: a unit test per emitted struct, tuple and string enum,
+    /// pinning what the schema said about it (fields and their keys, arity, enum
+    /// values), so a hand edit to the generated models fails CI by name. Exhaustive
+    /// patterns stop compiling when a field, position or variant is added or removed;
+    /// serde is asked for the keys and values it reads. `models` is the module path
+    /// the tests import from. Call after `emit`.
+    pub fn contract_tests(&self, models: &str) -> String {
+        let mut s = format!(
+            "// AUTOGENERATED: contract tests for the generated models ({})\n// Types must keep the fields, tuple arities and enum values the schema gave them.\n\nuse {models}::*;\n",
+            crate::manifest::tool(),
+        );
+        for c in &self.contracts {
+            s.push('\n');
+            match c {
+                Contract::Struct { name, fields } => {
+                    let pattern: Vec<String> = fields.iter().map(|(f, _)| format!("{f}: _")).collect();
+                    s.push_str(&format!("#[test]\nfn {}_fields() {{\n", snake(name)));
+                    s.push_str(&format!("    let _ = |v: &{name}| {{\n        let {name} {{ {} }} = v;\n    }};\n", pattern.join(", ")));
+                    if !fields.is_empty() {
+                        let keys: Vec<String> = fields.iter().map(|(_, k)| format!("{k:?}")).collect();
+                        let unknown = probe(fields.iter().map(|(_, k)| k));
+                        let doc = serde_json::json!({ unknown: null }).to_string();
+                        s.push_str(&format!("    let e = ::serde_json::from_str::<{name}>({doc:?}).unwrap_err().to_string();\n"));
+                        s.push_str(&format!("    for key in [{}] {{\n", keys.join(", ")));
+                        s.push_str(&format!("        assert!(e.contains(&format!(\"`{{key}}`\")), \"{name} no longer reads the key {{key:?}}: {{e}}\");\n    }}\n"));
+                    }
+                    s.push_str("}\n");
+                }
+                Contract::Tuple { name, members, named, min } => {
+                    let pattern = if *named {
+                        let fields: Vec<String> = members.iter().map(|m| format!("{m}: _")).collect();
+                        format!("{name} {{ {} }}", fields.join(", "))
+                    } else {
+                        format!("{name}({})", vec!["_"; members.len()].join(", "))
+                    };
+                    s.push_str(&format!("#[test]\nfn {}_arity() {{\n", snake(name)));
+                    s.push_str(&format!("    let _ = |v: &{name}| {{\n        let {pattern} = v;\n    }};\n"));
+                    if *min > 0 {
+                        s.push_str(&format!("    assert!(::serde_json::from_str::<{name}>(\"[]\").is_err(), \"{name} reads an empty array; it needs {min} element(s)\");\n"));
+                    }
+                    s.push_str("}\n");
+                }
+                Contract::Enum { name, variants, unknown } => {
+                    let arms: Vec<String> = variants.iter().map(|(v, _)| format!("{name}::{v}")).collect();
+                    let pairs: Vec<String> = variants.iter().map(|(v, json)| format!("({json:?}, {name}::{v})")).collect();
+                    s.push_str(&format!("#[test]\nfn {}_variants() {{\n", snake(name)));
+                    s.push_str(&format!("    let _ = |v: &{name}| match v {{\n        {} => {{}}\n    }};\n", arms.join(" | ")));
+                    s.push_str(&format!("    for (json, variant) in [{}] {{\n", pairs.join(", ")));
+                    s.push_str(&format!("        assert_eq!(::serde_json::from_str::<{name}>(json).ok(), Some(variant), \"{name} no longer reads {{json}}\");\n"));
+                    s.push_str("        assert_eq!(::serde_json::to_string(&variant).unwrap(), json);\n    }\n");
+                    s.push_str(&format!("    assert!(::serde_json::from_str::<{name}>({unknown:?}).is_err(), \"{name} reads values outside the enum\");\n"));
+                    s.push_str("}\n");
+                }
+            }
+        }
+        s
+    }
+
+    /// `FieldMeta` and the `SCHEMA` table listing every field emitted so far, so
+    /// consumers can walk the schema at runtime (logging, masking, metrics).
+    fn emit_field_meta(&mut self) {
+        self.out.push_str(
+r#"/// One field of a generated type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldMeta {
+    /// Rust type the field is declared on.
+    pub owner: &'static str,
+    /// Rust field name; `"0"`, `"1"`, … for tuple positions.
+    pub field: &'static str,
+    /// JSON key; `None` for tuple positions.
+    pub name: ::core::option::Option<&'static str>,
+    /// JSON path of the field; a type shared between paths lists the first one.
+    pub path: &'static str,
+    /// Present and non-null in every record.
+    pub required: bool,
+    /// May be null (or a marker value read as null).
+    pub nullable: bool,
+    /// `null`, `boolean`, `integer`, `number`, `string`, `array`, `object` or `union`.
+    pub kind: &'static str,
+    /// `enum`, `uri`, `uuid`, `email`, `ipv4`, `ipv6`, `hostname`, `hex`, `base64`, `decimal`, `json`,
+    /// `pattern`, `sentinel`, `unix-time` or `unix-time-ms` (`--epoch-timestamps`), when the values
+    /// have one; the name of a declared format (`--formats`) otherwise.
+    pub format: ::core::option::Option<&'static str>,
+    /// Personal data (`email`, `phone`, `name`, `location`), if `--pii` tagged it.
+    pub pii: ::core::option::Option<&'static str>,
+    /// `low`, `medium` or `high` for personal data.
+    pub sensitivity: ::core::option::Option<&'static str>,
+}
+
+/// Every field of every generated type, innermost types first.
+pub static SCHEMA: &[FieldMeta] = &[
 "#
         );
+        let opt = |s: Option<&str>| match s {
+            Some(s) => format!("::core::option::Option::Some({s:?})"),
+            None => "::core::option::Option::None".to_string(),
+        };
+        for r in &self.fields {
+            self.out.push_str(&format!(
+                "    FieldMeta {{ owner: {:?}, field: {:?}, name: {}, path: {:?}, required: {}, nullable: {}, kind: {:?}, format: {}, pii: {}, sensitivity: {} }},\n",
+                r.owner, r.field, opt(r.name.as_deref()), r.path, r.required, r.nullable, r.kind, opt(r.format.as_deref()),
+                opt(r.pii.map(|t| t.kind.as_str())), opt(r.pii.map(|t| t.sensitivity.as_str())),
+            ));
+        }
+        self.out.push_str("];\n\n");
     }
 
-    fn emit_null_type(&mut self) {
-        self.out.push_str(
-r#"#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct Null;
+    // ---- redaction ----
 
-impl<'de> ::serde::Deserialize<'de> for Null {
-    fn deserialize<D>(de: D) -> ::std::result::Result<Self, D::Error>
-    where
-        D: ::serde::Deserializer<'de>,
-    {
-        // Accept only explicit null
-        struct V;
-        impl<'de> ::serde::de::Visitor<'de> for V {
-            type Value = Null;
-            fn expecting(&self, f:&mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-                write!(f, "null")
+    /// `redact(&mut self)` on every struct, tuple and union holding a tagged field,
+    /// directly or through the types below it.
+    fn emit_redact(&mut self, mode: RedactMode) {
+        let mut owners: Vec<(&str, Vec<&FieldRow>)> = Vec::new();
+        for r in &self.fields {
+            match owners.iter_mut().find(|(o, _)| *o == r.owner) {
+                Some((_, rows)) => rows.push(r),
+                None => owners.push((&r.owner, vec![r])),
             }
-            fn visit_unit<E>(self) -> ::std::result::Result<Null, E>
-            where
-                E: ::serde::de::Error
-            {
-                Ok(Null)
+        }
+        let mut needs: BTreeSet<String> = BTreeSet::new();
+        loop {
+            let before = needs.len();
+            for (owner, rows) in &owners {
+                if rows.iter().any(|r| r.pii.is_some() || needs.contains(innermost(&r.ty))) {
+                    needs.insert(owner.to_string());
+                }
             }
-            fn visit_none<E>(self) -> ::std::result::Result<Null, E>
-            where
-                E: ::serde::de::Error
-            {
-                Ok(Null)
+            for (union, arms) in &self.unions {
+                if arms.iter().any(|(_, a)| needs.contains(innermost(a))) {
+                    needs.insert(union.clone());
+                }
+            }
+            if needs.len() == before {
+                break;
             }
         }
-        de.deserialize_option(V)
+        if needs.is_empty() {
+            return;
+        }
+
+        let cx = RedactCx { mode, needs: &needs, scalars: &self.scalars };
+        let doc = match mode {
+            RedactMode::Blank => "/// Blank the fields tagged as personal data (`--pii`), here and in nested types.",
+            RedactMode::Hash => "/// Hash the string fields tagged as personal data (`--pii`) and zero the numeric ones,\n    /// here and in nested types.",
+        };
+        let mut out = String::new();
+        if mode == RedactMode::Hash {
+            out.push_str(
+r#"/// FNV-1a digest used by `redact()`: stable across builds, so equal values still
+/// match after redaction. Not a cryptographic hash; short values can be guessed.
+fn __pii_hash(s: &str) -> ::std::string::String {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in s.bytes() {
+        h ^= b as u64;
+        h = h.wrapping_mul(0x0100_0000_01b3);
     }
+    ::std::format!("{h:016x}")
 }
+
 "#
-        );
+            );
+        }
+        for (owner, rows) in &owners {
+            if !needs.contains(*owner) {
+                continue;
+            }
+            out.push_str(&format!("impl {owner} {{\n    {doc}\n    pub fn redact(&mut self) {{\n"));
+            for r in rows {
+                let place = format!("self.{}", r.field);
+                match redact_stmt(&place, &r.ty, r.pii.is_some(), &cx, 0) {
+                    Some(stmt) => out.push_str(&format!("        {stmt}\n")),
+                    None if r.pii.is_some() => out.push_str(&format!("        // {}: `{}` can't be redacted in place\n", r.path, r.ty)),
+                    None => {}
+                }
+            }
+            out.push_str("    }\n}\n\n");
+        }
+        for (union, arms) in &self.unions {
+            if !needs.contains(union) {
+                continue;
+            }
+            out.push_str(&format!("impl {union} {{\n    {doc}\n    pub fn redact(&mut self) {{\n        match self {{\n"));
+            for (var, arm) in arms {
+                if let Some(stmt) = redact_stmt("(*x)", arm, false, &cx, 0) {
+                    out.push_str(&format!("            {union}::{var}(x) => {{ {stmt} }}\n"));
+                }
+            }
+            out.push_str("            _ => {}\n        }\n    }\n}\n\n");
+        }
+        self.out.push_str(&out);
     }
 
-    fn unique(&mut self, base: &str) -> String {
-        let mut n = base.to_string();
-        let mut i = 1;
-        while self.used.contains(&n) {
-            n = format!("{base}{i}");
-            i += 1;
-        }
-        self.used.insert(n.clone());
-        n
+    // ---- nullable groups ----
+
+    /// Resolve configured groups against the root's members; keeps groups with 2+ optional members.
+    fn root_groups(&self, resolve: impl Fn(&str) -> Option<usize>) -> Vec<Vec<usize>> {
+        self.groups
+            .iter()
+            .map(|g| g.iter().filter_map(|m| resolve(m)).collect::<Vec<_>>())
+            .filter(|g| g.len() >= 2)
+            .collect()
     }
 
-    fn walk(&mut self, t: &Ty, path: &mut Vec<String>, hint: String) -> String {
-        match t {
-            Ty::Nullable(inner) => {
-                let inner_name = self.walk(inner, path, hint);
-                format!("::core::option::Option<{inner_name}>")
-            }
-            Ty::Null => "Null".into(),
-            Ty::Bool => "bool".into(),
-            Ty::Integer { .. } => self.emit_int_newtype(t, path, &hint),
-            Ty::Number  { .. } => self.emit_num_newtype(t, path, &hint),
-            Ty::String  { .. } => self.emit_string_kind(t, path, &hint),
+    /// `Ok(Some(..))` if every member is set, `Ok(None)` if none is, otherwise an error.
+    fn group_match(&mut self, var: &str, group: &str, sources: &[String], build: &str, what: &str) {
+        let somes: Vec<String> = (0..sources.len()).map(|i| format!("::core::option::Option::Some(x{i})")).collect();
+        let nones = vec!["::core::option::Option::None"; sources.len()];
+        self.out.push_str(&format!(
+            "        let {var}: ::core::option::Option<{group}> = match ({}) {{\n            ({}) => ::core::option::Option::Some({build}),\n            ({}) => ::core::option::Option::None,\n            _ => return Err(::std::string::String::from({what:?})),\n        }};\n",
+            sources.join(", "),
+            somes.join(", "),
+            nones.join(", "),
+        ));
+    }
 
-            Ty::ArrayList { item, .. } => {
-                let inner = self.walk(item, path, format!("{hint}Item"));
-                format!("::std::vec::Vec<{inner}>")
+    #[allow(clippy::too_many_arguments)]
+    fn emit_grouped_tuple(&mut self, name: &str, raw: &str, fields: &[String], groups: &[Vec<usize>], t: &Ty, path: &[String], hint: &str) {
+        self.grouped = true;
+        let mut group_names = Vec::with_capacity(groups.len());
+        for g in groups {
+            let gname = self.unique(&to_type_name(&format!("{hint}{}Group", g[0])), t, path);
+            self.out.push_str(&format!("/// columns {:?} of [`{name}`]: always null together in the evidence\n", g));
+            self.out.push_str(&format!("#[derive(Debug)]\npub struct {gname}(\n"));
+            for &i in g {
+                self.out.push_str(&format!("    pub {},\n", option_inner(&fields[i]).0));
             }
+            self.out.push_str(");\n\n");
+            group_names.push(gname);
+        }
 
-            Ty::ArrayTuple { elems, min_items, max_items } => {
-                let type_name = self.unique(&to_type_name(&hint));
-
-                // materialize field types
-                let mut fields = ::std::vec::Vec::with_capacity(elems.len());
-                for (i, e) in elems.iter().enumerate() {
-                    let mut child = self.walk(e, &mut path_with(path, i), format!("{hint}{i}"));
-                    let col_nullable = matches!(e, Ty::Nullable(_));
-                    if (((i as u32) >= *min_items) || col_nullable) && !is_option_type(&child) {
-                        child = format!("::core::option::Option<{child}>");
-                    }
-                    fields.push(child);
-                }
+        let mut cols = Vec::new();
+        self.regrouped.clear();
+        for (i, f) in fields.iter().enumerate() {
+            match groups.iter().position(|g| g.contains(&i)) {
+                Some(k) if groups[k][0] == i => cols.push(format!("::core::option::Option<{}>", group_names[k])),
+                Some(_) => continue,
+                None => cols.push(f.clone()),
+            }
+            self.regrouped.insert(i, cols.len() - 1);
+        }
+        self.out.push_str(&format!("/// [`{raw}`] with nullable column groups collapsed\n"));
+        self.out.push_str(&format!("#[derive(Debug, ::serde::Deserialize)]\n#[serde(try_from = \"{raw}\")]\npub struct {name}(\n"));
+        for c in &cols {
+            self.out.push_str(&format!("    pub {},\n", wrap_tuple_field(c)));
+        }
+        self.out.push_str(");\n\n");
 
-                // exact arity
-                if min_items == max_items {
-                    let req = *min_items as usize;
-                    self.emit_len_fixed_tuple(&type_name, &fields, req);
-                    return type_name;
-                }
+        self.out.push_str(&format!(
+            "impl ::core::convert::TryFrom<{raw}> for {name} {{\n    type Error = ::std::string::String;\n    fn try_from(r: {raw}) -> ::std::result::Result<Self, Self::Error> {{\n"
+        ));
+        for (k, g) in groups.iter().enumerate() {
+            let sources: Vec<String> = g.iter().map(|&i| flattened(format!("r.{i}"), &fields[i])).collect();
+            let build = format!("{}({})", group_names[k], (0..g.len()).map(|i| format!("x{i}")).collect::<Vec<_>>().join(", "));
+            let what = format!("columns {g:?} must be all null or all present");
+            self.group_match(&format!("g{k}"), &group_names[k].clone(), &sources, &build, &what);
+        }
+        let args: Vec<String> = (0..fields.len())
+            .filter_map(|i| match groups.iter().position(|g| g.contains(&i)) {
+                Some(k) if groups[k][0] == i => Some(format!("g{k}")),
+                Some(_) => None,
+                None => Some(format!("r.{i}")),
+            })
+            .collect();
+        self.out.push_str(&format!("        Ok({name}({}))\n    }}\n}}\n\n", args.join(", ")));
+    }
 
-                // lenient (min..=max) tuple
-                self.emit_len_range_tuple(&type_name, &fields, *min_items as usize, *max_items as usize);
-                type_name
+    #[allow(clippy::too_many_arguments)]
+    fn emit_grouped_struct(&mut self, name: &str, raw: &str, members: &[(String, String)], groups: &[Vec<usize>], t: &Ty, path: &[String], hint: &str) {
+        self.grouped = true;
+        let mut group_names = Vec::with_capacity(groups.len());
+        let mut group_fields = Vec::with_capacity(groups.len());
+        // group fields sit beside the ungrouped members; neither may repeat a name
+        let taken: BTreeSet<&str> = (0..members.len())
+            .filter(|i| !groups.iter().any(|g| g.contains(i)))
+            .map(|i| members[i].0.as_str())
+            .collect();
+        for g in groups {
+            let stem = common_stem(g.iter().map(|&i| members[i].0.as_str()));
+            let gname = self.unique(&to_type_name(&format!("{hint}_{stem}_group")), t, path);
+            let fields: Vec<&str> = g.iter().map(|&i| members[i].0.as_str()).collect();
+            self.out.push_str(&format!("/// `{}` of [`{name}`]: always null together in the evidence\n", fields.join("`, `")));
+            self.out.push_str(&format!("#[derive(Debug)]\npub struct {gname} {{\n"));
+            for &i in g {
+                self.out.push_str(&format!("    pub {}: {},\n", members[i].0, option_inner(&members[i].1).0));
             }
-
-            Ty::Object { fields } => {
-                let type_name = self.unique(&to_type_name(&hint));
-                self.out.push_str("#[derive(Debug, ::serde::Deserialize)]\n");
-                self.out.push_str("#[serde(deny_unknown_fields)]\n");
-                self.out.push_str(&format!("pub struct {} {{\n", type_name));
-                for Field { name, ty, required } in fields {
-                    let fname = to_field_name(name);
-                    let mut ty_str = self.walk(ty, path, format!("{hint}{}", to_type_name(name)));
-                    if !*required {
-                        ty_str = format!("::core::option::Option<{ty_str}>");
-                    }
-                    self.out.push_str(&format!("    pub {}: {},\n", fname, ty_str));
-                }
-                self.out.push_str("}\n\n");
-                type_name
+            self.out.push_str("}\n\n");
+            group_names.push(gname);
+            let base = format!("{stem}_group");
+            let mut field = base.clone();
+            let mut i = 1;
+            while taken.contains(field.as_str()) || group_fields.contains(&field) {
+                field = format!("{base}{i}");
+                i += 1;
             }
+            group_fields.push(field);
+        }
 
-            Ty::OneOf(arms) => {
-                // Enum wrapper with try-each-arm using ::serde_json::from_value
-                let type_name = self.unique(&to_type_name(&hint));
-                let mut var_names = ::std::vec::Vec::new();
-                let mut arm_types = ::std::vec::Vec::new();
-                for (i, a) in arms.iter().enumerate() {
-                    let v_name = format!("V{}", i);
-                    var_names.push(v_name);
-                    arm_types.push(self.walk(a, &mut path_with(path, i), format!("{hint}Alt{}", i)));
+        self.out.push_str(&format!("/// [`{raw}`] with nullable field groups collapsed\n"));
+        self.out.push_str(&format!("#[derive(Debug, ::serde::Deserialize)]\n#[serde(try_from = \"{raw}\")]\npub struct {name} {{\n"));
+        let mut inits = Vec::new();
+        for (i, (fname, ty)) in members.iter().enumerate() {
+            match groups.iter().position(|g| g.contains(&i)) {
+                Some(k) if groups[k][0] == i => {
+                    self.out.push_str(&format!("    pub {}: ::core::option::Option<{}>,\n", group_fields[k], group_names[k]));
+                    inits.push(format!("{}: g{k}", group_fields[k]));
+                }
+                Some(_) => {}
+                None => {
+                    self.out.push_str(&format!("    pub {fname}: {ty},\n"));
+                    inits.push(format!("{fname}: r.{fname}"));
                 }
-                self.emit_union_enum_simple(&type_name, &var_names, &arm_types);
-                type_name
             }
+        }
+        self.out.push_str("}\n\n");
 
-            Ty::Never => "Null".into(), // unreachable fallback
+        self.out.push_str(&format!(
+            "impl ::core::convert::TryFrom<{raw}> for {name} {{\n    type Error = ::std::string::String;\n    fn try_from(r: {raw}) -> ::std::result::Result<Self, Self::Error> {{\n"
+        ));
+        for (k, g) in groups.iter().enumerate() {
+            let sources: Vec<String> = g.iter().map(|&i| flattened(format!("r.{}", members[i].0), &members[i].1)).collect();
+            let build = format!(
+                "{} {{ {} }}",
+                group_names[k],
+                g.iter().enumerate().map(|(j, &i)| format!("{}: x{j}", members[i].0)).collect::<Vec<_>>().join(", ")
+            );
+            let fields: Vec<&str> = g.iter().map(|&i| members[i].0.as_str()).collect();
+            let what = format!("fields {} must be all null or all present", fields.join(", "));
+            self.group_match(&format!("g{k}"), &group_names[k].clone(), &sources, &build, &what);
         }
+        self.out.push_str(&format!("        Ok({name} {{ {} }})\n    }}\n}}\n\n", inits.join(", ")));
     }
 
     // ---- tuples ----
 
-    fn emit_len_fixed_tuple(&mut self, name: &str, field_types: &[String], required_len: usize) {
-        self.out.push_str(&format!("/// tuple len={} (required exactly {})\n", field_types.len(), required_len));
-        self.out.push_str(&format!("#[derive(Debug)]\npub struct {}(\n", name));
-        for f in field_types {
-            self.out.push_str(&format!("    pub {},\n", wrap_tuple_field(f)));
+    /// Derive-only tuple (small profile); serde enforces the length, absent trailing
+    /// optional slots default to `None`.
+    // serde's derived `visit_seq` also reads a struct with named fields from an array
+    fn emit_derived_tuple(&mut self, name: &str, cols: &Positions, min_len: usize, max_len: usize) {
+        self.out.push_str(&format!("/// tuple len={} (accepts {}..={} elements)\n", cols.types.len(), min_len, max_len));
+        self.out.push_str("#[derive(Debug, ::serde::Deserialize)]\n");
+        cols.declare(&mut self.out, name, min_len);
+    }
+
+    fn emit_len_fixed_tuple(&mut self, name: &str, cols: &Positions, required_len: usize) {
+        if self.small() {
+            return self.emit_derived_tuple(name, cols, required_len, required_len);
         }
-        self.out.push_str(");\n\n");
+        let field_types = &cols.types;
+        self.out.push_str(&format!("/// tuple len={} (required exactly {})\n", field_types.len(), required_len));
+        self.out.push_str("#[derive(Debug)]\n");
+        cols.declare(&mut self.out, name, field_types.len());
 
         // one-pass visitor: read exactly required_len, then verify no extras
         self.out.push_str(&format!(
@@ -218,22 +2064,20 @@ r#"impl<'de> ::serde::Deserialize<'de> for {name} {{
              }\n"
         );
 
-        self.out.push_str(&format!("                Ok({name}(\n", name = name));
-        for i in 0..field_types.len() {
-            self.out.push_str(&format!("                    a{i},\n"));
-        }
+        self.out.push_str(&format!("                Ok({})\n", cols.build(name)));
         self.out.push_str(
-            "                ))\n            }\n        }\n        de.deserialize_seq(V)\n    }\n}\n\n"
+            "            }\n        }\n        de.deserialize_seq(V)\n    }\n}\n\n"
         );
     }
 
-    fn emit_len_range_tuple(&mut self, name: &str, field_types: &[String], min_len: usize, max_len: usize) {
-        self.out.push_str(&format!("/// tuple len={} (required first {} slots); accepts {}..={} elements\n", field_types.len(), min_len, min_len, max_len));
-        self.out.push_str(&format!("#[derive(Debug)]\npub struct {}(\n", name));
-        for f in field_types {
-            self.out.push_str(&format!("    pub {},\n", wrap_tuple_field(f)));
+    fn emit_len_range_tuple(&mut self, name: &str, cols: &Positions, min_len: usize, max_len: usize) {
+        if self.small() {
+            return self.emit_derived_tuple(name, cols, min_len, max_len);
         }
-        self.out.push_str(");\n\n");
+        let field_types = &cols.types;
+        self.out.push_str(&format!("/// tuple len={} (required first {} slots); accepts {}..={} elements\n", field_types.len(), min_len, min_len, max_len));
+        self.out.push_str("#[derive(Debug)]\n");
+        cols.declare(&mut self.out, name, field_types.len());
 
         self.out.push_str(&format!(
 r#"impl<'de> ::serde::Deserialize<'de> for {name} {{
@@ -282,18 +2126,24 @@ r#"impl<'de> ::serde::Deserialize<'de> for {name} {{
              }\n"
         );
 
-        self.out.push_str(&format!("                Ok({name}(\n", name = name));
-        for i in 0..field_types.len() {
-            self.out.push_str(&format!("                    a{i},\n"));
-        }
+        self.out.push_str(&format!("                Ok({})\n", cols.build(name)));
         self.out.push_str(
-            "                ))\n            }\n        }\n        de.deserialize_seq(V)\n    }\n}\n\n"
+            "            }\n        }\n        de.deserialize_seq(V)\n    }\n}\n\n"
         );
     }
 
     // ---- unions (tagless) ----
 
     fn emit_union_enum_simple(&mut self, name: &str, variants: &[String], tys: &[String]) {
+        if self.small() {
+            // serde's untagged enum: same arm order, one generic error message
+            self.out.push_str(&format!("#[derive(Debug, ::serde::Deserialize)]\n#[serde(untagged)]\npub enum {} {{\n", name));
+            for (v, t) in variants.iter().zip(tys.iter()) {
+                self.out.push_str(&format!("    {}({}),\n", v, t));
+            }
+            self.out.push_str("}\n\n");
+            return;
+        }
         // 1) the enum shell
         self.out.push_str(&format!("#[derive(Debug)]\npub enum {} {{\n", name));
         for (v, t) in variants.iter().zip(tys.iter()) {
@@ -344,17 +2194,66 @@ r#"        if errs.is_empty() {
 
     // ---- numbers ----
 
-fn emit_int_newtype(&mut self, t: &Ty, _path: &mut Vec<String>, hint: &str) -> String {
-    let Ty::Integer { min, max } = t else { unreachable!() };
-    let nm = self.unique(&to_type_name(hint));
+    /// A closed set of integers: a fieldless `#[repr(i64)]` enum whose
+    /// discriminants are the values, read and written as JSON numbers.
+    fn emit_int_enum(&mut self, t: &Ty, path: &[String], hint: &str) -> String {
+        let Ty::Integer { enum_, .. } = t else { unreachable!() };
+        let nm = self.unique(&to_type_name(hint), t, path);
+        self.note(&nm, ItemKind::Enum, enum_.len(), path);
+        let variants: Vec<(String, i64)> = enum_
+            .iter()
+            .map(|&x| (if x < 0 { format!("Minus{}", x.unsigned_abs()) } else { format!("V{x}") }, x))
+            .collect();
+        let unknown = (0..).find(|x| !enum_.contains(x)).expect("a free value");
+        self.contracts.push(Contract::Enum {
+            name: nm.clone(),
+            variants: variants.iter().map(|(v, x)| (v.clone(), x.to_string())).collect(),
+            unknown: unknown.to_string(),
+        });
 
-    self.out.push_str(&format!(
-        "#[repr(transparent)]\n#[derive(Debug, Clone, Copy, PartialEq, Eq)]\npub struct {}(pub i64);\n",
+        // shared between paths under `small`, so always hashable there
+        let hash = if self.small() { ", Hash" } else { self.key_derives(path) };
+        self.out.push_str(&format!("#[repr(i64)]\n#[derive(Debug, Clone, Copy, PartialEq, Eq{hash})]\npub enum {nm} {{\n"));
+        for (ident, x) in &variants {
+            self.out.push_str(&format!("    {ident} = {x},\n"));
+        }
+        self.out.push_str("}\n");
+
+        let expected = enum_.iter().map(i64::to_string).collect::<Vec<_>>().join(", ");
+        self.out.push_str(&format!(
+            "impl<'de> ::serde::Deserialize<'de> for {nm} {{\n    fn deserialize<D>(de: D) -> ::std::result::Result<Self, D::Error>\n    where D: ::serde::Deserializer<'de> {{\n        let x = <i64 as ::serde::Deserialize>::deserialize(de)?;\n        match x {{\n"
+        ));
+        for (ident, x) in &variants {
+            self.out.push_str(&format!("            {x} => Ok({nm}::{ident}),\n"));
+        }
+        self.out.push_str(&format!(
+            "            _ => Err(::serde::de::Error::invalid_value(::serde::de::Unexpected::Signed(x), &\"one of {expected}\")),\n        }}\n    }}\n}}\n"
+        ));
+        self.out.push_str(&format!(
+            "impl ::serde::Serialize for {nm} {{\n    fn serialize<S>(&self, ser: S) -> ::std::result::Result<S::Ok, S::Error>\n    where S: ::serde::Serializer {{\n        ser.serialize_i64(*self as i64)\n    }}\n}}\n\n"
+        ));
         nm
+    }
+
+fn emit_int_newtype(&mut self, t: &Ty, path: &[String], hint: &str) -> String {
+    let Ty::Integer { min, max, from_string, .. } = t else { unreachable!() };
+    let int = IntWidth::of(*min, *max).rust();
+    let read = if *from_string {
+        self.emit_num_or_string_type();
+        format!("<NumOrString<{int}> as ::serde::Deserialize>::deserialize(de)?.0")
+    } else {
+        format!("<{int} as ::serde::Deserialize>::deserialize(de)?")
+    };
+    let nm = self.unique(&to_type_name(hint), t, path);
+    self.note(&nm, ItemKind::Newtype, 1, path);
+
+    self.out.push_str(&format!(
+        "{}#[repr(transparent)]\n#[derive(Debug, Clone, Copy, PartialEq, Eq{})]\npub struct {}(pub {int});\n",
+        step_doc(t).map(|d| format!("/// {d}\n")).unwrap_or_default(), self.key_derives(path), nm
     ));
     self.out.push_str(&format!(
 r#"impl ::core::ops::Deref for {nm} {{
-    type Target = i64;
+    type Target = {int};
     fn deref(&self) -> &Self::Target {{ &self.0 }}
 }}
 impl<'de> ::serde::Deserialize<'de> for {nm} {{
@@ -362,7 +2261,7 @@ impl<'de> ::serde::Deserialize<'de> for {nm} {{
     where
         D: ::serde::Deserializer<'de>,
     {{
-        let x = <i64 as ::serde::Deserialize>::deserialize(de)?;
+        let x = {read};
         {min_check}{max_check}
         Ok({nm}(x))
     }}
@@ -381,13 +2280,20 @@ impl<'de> ::serde::Deserialize<'de> for {nm} {{
     nm
 }
 
-fn emit_num_newtype(&mut self, t: &Ty, _path: &mut Vec<String>, hint: &str) -> String {
-    let Ty::Number { min, max } = t else { unreachable!() };
-    let nm = self.unique(&to_type_name(hint));
+fn emit_num_newtype(&mut self, t: &Ty, path: &[String], hint: &str) -> String {
+    let Ty::Number { min, max, from_string, .. } = t else { unreachable!() };
+    let read = if *from_string {
+        self.emit_num_or_string_type();
+        "<NumOrString<f64> as ::serde::Deserialize>::deserialize(de)?.0"
+    } else {
+        "<f64 as ::serde::Deserialize>::deserialize(de)?"
+    };
+    let nm = self.unique(&to_type_name(hint), t, path);
+    self.note(&nm, ItemKind::Newtype, 1, path);
 
     self.out.push_str(&format!(
-        "#[repr(transparent)]\n#[derive(Debug, Clone, Copy, PartialEq)]\npub struct {}(pub f64);\n",
-        nm
+        "{}#[repr(transparent)]\n#[derive(Debug, Clone, Copy, PartialEq)]\npub struct {}(pub f64);\n",
+        step_doc(t).map(|d| format!("/// {d}\n")).unwrap_or_default(), nm
     ));
     self.out.push_str(&format!(
 r#"impl ::core::ops::Deref for {nm} {{
@@ -399,7 +2305,7 @@ impl<'de> ::serde::Deserialize<'de> for {nm} {{
     where
         D: ::serde::Deserializer<'de>,
     {{
-        let x = <f64 as ::serde::Deserialize>::deserialize(de)?;
+        let x = {read};
         if !x.is_finite() {{ return Err(::serde::de::Error::custom("{nm}: non-finite number")); }}
         {min_check}{max_check}
         Ok({nm}(x))
@@ -426,26 +2332,59 @@ impl<'de> ::serde::Deserialize<'de> for {nm} {{
 
     // ---- strings ----
 
-    fn emit_string_kind(&mut self, t: &Ty, _path: &mut Vec<String>, hint: &str) -> String {
-        let Ty::String { enum_, pattern, format_uri } = t else { unreachable!() };
+    fn emit_string_kind(&mut self, t: &Ty, path: &[String], hint: &str) -> String {
+        let Ty::String { enum_, enum_counts, pattern, format_uri, format, .. } = t else { unreachable!() };
+        let uuid = self.uuid && *format == Some(StrFormat::Uuid);
+        if let Some(f) = format.filter(|_| self.decodes(*format)) {
+            return self.emit_bytes_newtype(t, path, hint, f);
+        }
+        if self.reads_decimal(*format) {
+            return self.emit_decimal_newtype(t, path, hint);
+        }
 
         // tiny enum
         if !enum_.is_empty() && enum_.len() <= 32 {
-            let nm = self.unique(&to_type_name(hint));
-
-            let mut lits: ::std::vec::Vec<::std::string::String> = enum_.iter().cloned().collect();
-            lits.sort_unstable();
+            let nm = self.unique(&to_type_name(hint), t, path);
+            self.note(&nm, ItemKind::Enum, enum_.len(), path);
 
+            // in the IR's order: most frequent first, when counted
             let mut used: BTreeSet<String> = BTreeSet::new();
-            let mut variants: ::std::vec::Vec<(String, String)> = ::std::vec::Vec::with_capacity(lits.len());
-            for lit in &lits {
+            let mut variants: ::std::vec::Vec<(String, String)> = ::std::vec::Vec::with_capacity(enum_.len());
+            for lit in enum_ {
                 let ident = variant_ident_for(lit, &mut used);
                 variants.push((ident, lit.clone()));
             }
+            self.contracts.push(Contract::Enum {
+                name: nm.clone(),
+                variants: variants.iter().map(|(v, lit)| (v.clone(), serde_json::Value::from(lit.as_str()).to_string())).collect(),
+                unknown: serde_json::Value::from(probe(enum_.iter())).to_string(),
+            });
+            let total: u64 = enum_counts.iter().sum();
+            let docs: Vec<String> = if enum_counts.len() == enum_.len() && total > 0 {
+                enum_counts
+                    .iter()
+                    .map(|&n| match n {
+                        0 => "    /// Not seen in this corpus (kept from earlier runs).\n".to_string(),
+                        n => format!("    /// Seen {n} of {total} times ({:.1}%).\n", n as f64 * 100.0 / total as f64),
+                    })
+                    .collect()
+            } else {
+                vec![String::new(); enum_.len()]
+            };
+
+            if self.small() {
+                // shared between paths, so always hashable (the key may reuse it)
+                self.out.push_str(&format!("#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ::serde::Deserialize, ::serde::Serialize)]\npub enum {} {{\n", nm));
+                for ((ident, lit), doc) in variants.iter().zip(&docs) {
+                    self.out.push_str(&format!("{doc}    #[serde(rename = {lit:?})]\n    {},\n", ident));
+                }
+                self.out.push_str("}\n\n");
+                return nm;
+            }
 
-            self.out.push_str(&format!("#[derive(Debug, Clone, Copy, PartialEq, Eq)]\npub enum {} {{\n", nm));
-            for (ident, _) in &variants {
-                self.out.push_str(&format!("    {},\n", ident));
+            self.out.push_str(&format!("#[derive(Debug, Clone, Copy, PartialEq, Eq{})]\npub enum {} {{\n", self.key_derives(path), nm));
+            for ((ident, _), doc) in variants.iter().zip(&docs) {
+                self.out.push_str(&format!("{doc}    {},\n", ident));
             }
             self.out.push_str("}\n");
 
@@ -470,12 +2409,32 @@ impl<'de> ::serde::Deserialize<'de> for {nm} {{
             return nm;
         }
 
+        if self.small() {
+            return if uuid { "::uuid::Uuid" } else { "::std::string::String" }.into();
+        }
+
+        // declared format newtype
+        if let Some(f) = self.formats.get(&JPath(self.at.clone()).to_string()).cloned() {
+            let check = self.emit_validator(&f);
+            return self.emit_format_newtype(t, path, hint, &f.name, &check);
+        }
+
+        // well-known format
+        if uuid {
+            return "::uuid::Uuid".into();
+        }
+        if let Some(f) = *format {
+            let check = self.emit_known_validator(f);
+            return self.emit_format_newtype(t, path, hint, f.as_str(), &check);
+        }
+
         // pattern newtype
         if let ::core::option::Option::Some(pat) = pattern {
-            let nm = self.unique(&to_type_name(hint));
+            let nm = self.unique(&to_type_name(hint), t, path);
+            self.note(&nm, ItemKind::Newtype, 1, path);
             self.out.push_str(&format!(
-                "#[repr(transparent)]\n#[derive(Debug, Clone, PartialEq, Eq)]\npub struct {}(pub ::std::string::String);\n",
-                nm
+                "#[repr(transparent)]\n#[derive(Debug, Clone, PartialEq, Eq{})]\npub struct {}(pub ::std::string::String);\n",
+                self.key_derives(path), nm
             ));
             self.out.push_str(&format!(
                 r#"impl ::core::ops::Deref for {nm} {{
@@ -510,10 +2469,11 @@ impl<'de> ::serde::Deserialize<'de> for {nm} {{
 
         // URI newtype
         if *format_uri {
-            let nm = self.unique(&to_type_name(hint));
+            let nm = self.unique(&to_type_name(hint), t, path);
+            self.note(&nm, ItemKind::Newtype, 1, path);
             self.out.push_str(&format!(
-                "#[repr(transparent)]\n#[derive(Debug, Clone, PartialEq, Eq)]\npub struct {}(pub ::std::string::String);\n",
-                nm
+                "#[repr(transparent)]\n#[derive(Debug, Clone, PartialEq, Eq{})]\npub struct {}(pub ::std::string::String);\n",
+                self.key_derives(path), nm
             ));
             self.out.push_str(&format!(
 r#"impl ::core::ops::Deref for {nm} {{
@@ -543,12 +2503,312 @@ impl<'de> ::serde::Deserialize<'de> for {nm} {{
 
 // ---------- helpers ----------
 
-fn path_with(path: &mut ::std::vec::Vec<::std::string::String>, idx: usize) -> ::std::vec::Vec<::std::string::String> {
-    let mut p = path.clone();
-    p.push(idx.to_string());
+/// Types that become named Rust items with their own structure.
+struct RedactCx<'a> {
+    mode: RedactMode,
+    /// Types that have a `redact()` method.
+    needs: &'a BTreeSet<String>,
+    scalars: &'a BTreeMap<String, Scalar>,
+}
+
+/// Statement redacting `place` (of Rust type `ty`): through options and lists,
+/// then blank / hash it when `tagged`, or call the type's own `redact()`.
+fn redact_stmt(place: &str, ty: &str, tagged: bool, cx: &RedactCx, depth: usize) -> Option<String> {
+    let x = format!("x{depth}");
+    if let Some(inner) = generic_arg(ty, "::core::option::Option<") {
+        if tagged && cx.mode == RedactMode::Blank {
+            return Some(format!("{place} = ::core::option::Option::None;"));
+        }
+        let body = redact_stmt(&format!("(*{x})"), inner, tagged, cx, depth + 1)?;
+        return Some(format!("if let ::core::option::Option::Some({x}) = {place}.as_mut() {{ {body} }}"));
+    }
+    if let Some(inner) = generic_arg(ty, "JsonString<").or_else(|| generic_arg(ty, "NumOrString<")) {
+        return redact_stmt(&format!("{place}.0"), inner, tagged, cx, depth);
+    }
+    if let Some(inner) = generic_arg(ty, "::std::vec::Vec<") {
+        let body = redact_stmt(&format!("(*{x})"), inner, tagged, cx, depth + 1)?;
+        return Some(format!("for {x} in {place}.iter_mut() {{ {body} }}"));
+    }
+    if let Some(inner) = generic_arg(ty, MAP_PREFIX) {
+        let body = redact_stmt(&format!("(*{x})"), inner, tagged, cx, depth + 1)?;
+        return Some(format!("for {x} in {place}.values_mut() {{ {body} }}"));
+    }
+    if !tagged {
+        return cx.needs.contains(ty).then(|| format!("{place}.redact();"));
+    }
+    let (target, scalar) = match ty {
+        "::uuid::Uuid" => return Some(format!("{place} = ::uuid::Uuid::nil();")),
+        "NumberLike" => return Some(format!("{place} = NumberLike::Number(::serde_json::Number::from(0));")),
+        "UnixSeconds" | "UnixMillis" => return Some(format!("{place}.0 = ::chrono::DateTime::<::chrono::Utc>::UNIX_EPOCH;")),
+        "::std::string::String" => (place.to_string(), Scalar::Str),
+        "u32" | "i64" | "u64" | "i128" => (place.to_string(), Scalar::Int),
+        "f64" => (place.to_string(), Scalar::Num),
+        _ => (format!("{place}.0"), *cx.scalars.get(ty)?),
+    };
+    Some(match (scalar, cx.mode) {
+        (Scalar::Str, RedactMode::Blank) => format!("{target}.clear();"),
+        (Scalar::Str, RedactMode::Hash) => format!("{target} = __pii_hash(&{target});"),
+        (Scalar::Int, _) => format!("{target} = 0;"),
+        (Scalar::Num, _) => format!("{target} = 0.0;"),
+        (Scalar::Bytes, _) => format!("{target}.clear();"),
+        (Scalar::Decimal, _) => format!("{target} = ::rust_decimal::Decimal::ZERO;"),
+    })
+}
+
+/// `T` in `{wrapper}T>`.
+fn generic_arg<'a>(ty: &'a str, wrapper: &str) -> Option<&'a str> {
+    ty.trim().strip_prefix(wrapper)?.strip_suffix('>')
+}
+
+/// How map types start; the value type follows.
+const MAP_PREFIX: &str = "::std::collections::BTreeMap<::std::string::String, ";
+
+/// The named type inside any `Option<…>` / `Vec<…>` / `BTreeMap<String, …>` / `JsonString<…>` wrappers.
+fn innermost(ty: &str) -> &str {
+    let inner = generic_arg(ty, "::core::option::Option<")
+        .or_else(|| generic_arg(ty, "::std::vec::Vec<"))
+        .or_else(|| generic_arg(ty, MAP_PREFIX))
+        .or_else(|| generic_arg(ty, "JsonString<"));
+    match inner {
+        Some(inner) => innermost(inner),
+        None => ty.trim(),
+    }
+}
+
+/// Doc line for numbers that were all multiples of a step.
+fn step_doc(t: &Ty) -> Option<String> {
+    match t {
+        Ty::Nullable(inner) | Ty::Sentinel { inner, .. } => step_doc(inner),
+        Ty::Integer { multiple_of: Some(m), .. } => Some(format!("Always a multiple of {m}.")),
+        Ty::Number { multiple_of: Some(m), .. } => Some(format!("Always a multiple of {m}.")),
+        _ => None,
+    }
+}
+
+/// Nullability, kind and format of a field's type, for the `SCHEMA` table.
+fn describe(t: &Ty) -> (bool, &'static str, Option<&'static str>) {
+    match t {
+        Ty::Nullable(inner) => {
+            let (_, kind, format) = describe(inner);
+            (true, kind, format)
+        }
+        Ty::Sentinel { inner, as_null, .. } => {
+            let (nullable, kind, _) = describe(inner);
+            (nullable || *as_null, kind, Some("sentinel"))
+        }
+        Ty::Null | Ty::Never => (true, "null", None),
+        Ty::Bool => (false, "boolean", None),
+        Ty::Integer { enum_, .. } => (false, "integer", (!enum_.is_empty()).then_some("enum")),
+        Ty::Number { .. } => (false, "number", None),
+        Ty::StringOrNumber { .. } => (false, "number", Some("string-or-number")),
+        Ty::String { enum_, pattern, format_uri, format, .. } => {
+            let format = if !enum_.is_empty() && enum_.len() <= 32 {
+                Some("enum")
+            } else if pattern.is_some() {
+                Some("pattern")
+            } else if *format_uri {
+                Some("uri")
+            } else {
+                format.map(StrFormat::as_str)
+            };
+            (false, "string", format)
+        }
+        Ty::JsonString(_) => (false, "string", Some("json")),
+        Ty::ArrayList { .. } | Ty::ArrayTuple { .. } => (false, "array", None),
+        Ty::Object { .. } => (false, "object", None),
+        Ty::Tagged { .. } => (false, "union", Some("tagged")),
+        Ty::Map { .. } => (false, "object", Some("map")),
+        Ty::OneOf(arms) => (arms.iter().any(|a| describe(a).0), "union", None),
+        Ty::Any => (true, "any", None),
+        Ty::Raw => (true, "any", Some("raw")),
+    }
+}
+
+/// `t` has a [`Ty::Raw`] somewhere.
+fn holds_raw(t: &Ty) -> bool {
+    match t {
+        Ty::Raw => true,
+        Ty::ArrayList { item: inner, .. }
+        | Ty::Map { value: inner, .. }
+        | Ty::JsonString(inner)
+        | Ty::Nullable(inner)
+        | Ty::Sentinel { inner, .. } => holds_raw(inner),
+        Ty::ArrayTuple { elems, .. } | Ty::OneOf(elems) => elems.iter().any(holds_raw),
+        Ty::Object { fields, .. } => fields.iter().any(|f| holds_raw(&f.ty)),
+        Ty::Tagged { variants, .. } => variants.iter().any(|(_, v)| holds_raw(v)),
+        Ty::Never
+        | Ty::Null
+        | Ty::Bool
+        | Ty::Integer { .. }
+        | Ty::Number { .. }
+        | Ty::StringOrNumber { .. }
+        | Ty::String { .. }
+        | Ty::Any => false,
+    }
+}
+
+/// A closed set of strings or integers, emitted as a fieldless enum.
+fn is_enum(t: &Ty) -> bool {
+    match t {
+        Ty::String { enum_, .. } => !enum_.is_empty(),
+        Ty::Integer { enum_, .. } => !enum_.is_empty(),
+        _ => false,
+    }
+}
+
+pub fn is_compound(t: &Ty) -> bool {
+    matches!(t, Ty::Object { .. } | Ty::ArrayTuple { .. } | Ty::OneOf(_) | Ty::Tagged { .. })
+}
+
+/// Canonical structural identity of a type; equal keys generate identical Rust
+/// (up to enum frequencies and evidence counts, which only change doc comments).
+pub fn structural_key(t: &Ty) -> String {
+    format!("{:?}", uncounted(t))
+}
+
+/// `t` without enum frequencies or evidence counts.
+fn uncounted(t: &Ty) -> Ty {
+    let boxed = |t: &Ty| Box::new(uncounted(t));
+    match t {
+        Ty::String { enum_, pattern, format_uri, format, min_length, max_length, .. } => Ty::String {
+            enum_: enum_.clone(),
+            enum_counts: Vec::new(),
+            pattern: pattern.clone(),
+            format_uri: *format_uri,
+            format: *format,
+            min_length: *min_length,
+            max_length: *max_length,
+            stats: None,
+        },
+        Ty::ArrayList { item, min_items, max_items, .. } => {
+            Ty::ArrayList { item: boxed(item), min_items: *min_items, max_items: *max_items, stats: None }
+        }
+        Ty::ArrayTuple { elems, min_items, max_items, .. } => Ty::ArrayTuple {
+            elems: elems.iter().map(uncounted).collect(),
+            min_items: *min_items,
+            max_items: *max_items,
+            stats: None,
+        },
+        Ty::Object { fields, .. } => Ty::Object {
+            fields: fields.iter().map(|f| Field { name: f.name.clone(), ty: uncounted(&f.ty), required: f.required, present: f.present, support: None }).collect(),
+            stats: None,
+        },
+        Ty::Map { key, value, .. } => Ty::Map { key: boxed(key), value: boxed(value), stats: None },
+        Ty::Tagged { tag, variants, .. } => Ty::Tagged {
+            tag: tag.clone(),
+            variants: variants.iter().map(|(v, t)| (v.clone(), uncounted(t))).collect(),
+            stats: None,
+        },
+        Ty::JsonString(inner) => Ty::JsonString(boxed(inner)),
+        Ty::OneOf(arms) => Ty::OneOf(arms.iter().map(uncounted).collect()),
+        Ty::Nullable(inner) => Ty::Nullable(boxed(inner)),
+        Ty::Sentinel { inner, marks, as_null } => Ty::Sentinel { inner: boxed(inner), marks: marks.clone(), as_null: *as_null },
+        Ty::Integer { .. } | Ty::Number { .. } | Ty::StringOrNumber { .. } => {
+            let mut t = t.clone();
+            if let Some(stats) = t.stats_mut() {
+                *stats = None;
+            }
+            t
+        }
+        Ty::Never | Ty::Null | Ty::Bool | Ty::Any | Ty::Raw => t.clone(),
+    }
+}
+
+fn path_with(path: &[String], seg: impl ToString) -> Vec<String> {
+    let mut p = path.to_vec();
+    p.push(seg.to_string());
     p
 }
 
+/// A promise an emitted type makes, checked by [`Codegen::contract_tests`].
+enum Contract {
+    /// A record: Rust field and JSON key of every field.
+    Struct { name: String, fields: Vec<(String, String)> },
+    /// A tuple: its members (`--tuple-names`, or positional), the first `min` required.
+    Tuple { name: String, members: Vec<String>, named: bool, min: usize },
+    /// A closed enum: variant and value (as JSON), and a value outside it.
+    Enum { name: String, variants: Vec<(String, String)>, unknown: String },
+}
+
+/// A key or value none of `taken` is, for contract tests to be refused with.
+fn probe<'a>(taken: impl Iterator<Item = &'a String> + Clone) -> String {
+    (0..).map(|i| format!("__contract{}__", "_".repeat(i))).find(|p| !taken.clone().any(|t| t == p)).unwrap()
+}
+
+/// `RootStatus` → `root_status`, for test names.
+fn snake(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 && !out.ends_with('_') {
+            out.push('_');
+        }
+        out.push(c.to_ascii_lowercase());
+    }
+    out
+}
+
+/// The positions of a tuple struct.
+struct Positions {
+    types: Vec<String>,
+    docs: Vec<Option<String>>,
+    /// Field names from `--tuple-names`; a tuple struct without.
+    names: Option<Vec<String>>,
+}
+
+impl Positions {
+    /// The struct; positions from `min_len` on are `#[serde(default)]`.
+    fn declare(&self, out: &mut String, name: &str, min_len: usize) {
+        let (open, close) = if self.names.is_some() { (" {", "}\n\n") } else { ("(", ");\n\n") };
+        out.push_str(&format!("pub struct {name}{open}\n"));
+        for (i, (ty, doc)) in self.types.iter().zip(&self.docs).enumerate() {
+            push_doc(out, doc.as_deref());
+            let default = if i >= min_len { "#[serde(default)] " } else { "" };
+            match &self.names {
+                Some(names) => out.push_str(&format!("    {default}pub {}: {ty},\n", names[i])),
+                None => out.push_str(&format!("    {default}pub {},\n", wrap_tuple_field(ty))),
+            }
+        }
+        out.push_str(close);
+    }
+
+    /// The struct built from the elements read into `a0`, `a1`, ….
+    fn build(&self, name: &str) -> String {
+        let mut s = format!("{name}{}\n", if self.names.is_some() { " {" } else { "(" });
+        for i in 0..self.types.len() {
+            match &self.names {
+                Some(names) => s.push_str(&format!("                    {}: a{i},\n", names[i])),
+                None => s.push_str(&format!("                    a{i},\n")),
+            }
+        }
+        s.push_str(if self.names.is_some() { "                }" } else { "                )" });
+        s
+    }
+}
+
+/// The paragraphs of a doc comment, those there are.
+fn paragraphs<const N: usize>(parts: [Option<String>; N]) -> Option<String> {
+    let parts: Vec<String> = parts.into_iter().flatten().collect();
+    (!parts.is_empty()).then(|| parts.join("\n\n"))
+}
+
+/// `s` with the characters rustc rejects in comments (bidirectional overrides
+/// and isolates) escaped.
+fn doc_safe(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}' => format!("\\u{:04X}", c as u32),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// Doc comment lines for a struct field or tuple position.
+fn push_doc(out: &mut String, doc: Option<&str>) {
+    for line in doc.into_iter().flat_map(str::lines) {
+        out.push_str(&format!("    ///{}{line}\n", if line.is_empty() { "" } else { " " }));
+    }
+}
+
 fn wrap_tuple_field(t: &str) -> ::std::string::String { t.to_string() }
 
 fn f64_lit(x: f64) -> ::std::string::String {
@@ -578,7 +2838,7 @@ fn hash8(s: &str) -> ::std::string::String {
     ::std::format!("{:08x}", (h.finish() as u32))
 }
 
-fn to_type_name(hint: &str) -> ::std::string::String {
+pub fn to_type_name(hint: &str) -> ::std::string::String {
     let mut s = ::std::string::String::with_capacity(hint.len().max(1));
     let mut up = true;
     for c in hint.chars() {
@@ -597,7 +2857,7 @@ fn to_type_name(hint: &str) -> ::std::string::String {
     s
 }
 
-fn to_field_name(name: &str) -> ::std::string::String {
+pub fn to_field_name(name: &str) -> ::std::string::String {
     let mut out = ::std::string::String::new();
     let mut last_underscore = false;
     for ch in name.chars() {
@@ -639,7 +2899,80 @@ fn variant_ident_for(lit: &str, used: &mut BTreeSet<String>) -> ::std::string::S
     out
 }
 
+/// `T` from `::core::option::Option<T>` (however deeply nested), and the nesting depth.
+fn option_inner(s: &str) -> (&str, usize) {
+    let mut t = s.trim();
+    let mut depth = 0;
+    while let Some(r) = t.strip_prefix("::core::option::Option<").and_then(|r| r.strip_suffix('>')) {
+        t = r;
+        depth += 1;
+    }
+    (t, depth)
+}
+
+/// `expr` flattened to a single `Option` (absent and null both become `None`).
+fn flattened(expr: String, ty: &str) -> String {
+    let depth = option_inner(ty).1;
+    expr + &".flatten()".repeat(depth.saturating_sub(1))
+}
+
+/// Shared leading `_`-separated words of snake_case names (`ad_url`, `ad_price` → `ad`),
+/// or the first name when there are none.
+fn common_stem<'a>(mut names: impl Iterator<Item = &'a str>) -> String {
+    let first = names.next().unwrap_or_default();
+    let mut stem: Vec<&str> = first.split('_').collect();
+    for n in names {
+        let common = stem.iter().zip(n.split('_')).take_while(|(a, b)| *a == b).count();
+        stem.truncate(common);
+    }
+    let stem = stem.join("_");
+    if stem.trim_matches('_').is_empty() { first.to_string() } else { stem }
+}
+
 fn is_option_type(s: &str) -> bool {
     let t = s.trim();
     t.starts_with("::core::option::Option<") && t.ends_with('>')
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::Field;
+
+    fn object(fields: &[(&str, Ty)]) -> Ty {
+        Ty::Object {
+            fields: fields
+                .iter()
+                .map(|(name, ty)| Field { name: name.to_string(), ty: ty.clone(), required: true, present: true, support: None })
+                .collect(),
+            stats: None,
+        }
+    }
+
+    fn int() -> Ty {
+        Ty::Integer { min: None, max: None, from_string: false, multiple_of: None, enum_: vec![], stats: None }
+    }
+
+    #[test]
+    fn path_collisions_are_qualified_by_the_root() {
+        let naming = NamingPolicy { collisions: CollisionPolicy::Path };
+        let mut cg = Codegen::new().with_naming(naming);
+        cg.emit_prelude();
+        let search = cg.emit_item_at(&object(&[("a", int())]), "Card", vec!["search".into(), "card".into()]);
+        let feed = cg.emit_item_at(&object(&[("b", int())]), "Card", vec!["feed".into(), "card".into()]);
+        let nested = cg.emit_item_at(&object(&[("c", int())]), "Card", vec!["feed".into(), "ad".into(), "card".into()]);
+        assert_eq!((search.as_str(), feed.as_str(), nested.as_str()), ("Card", "FeedCard", "FeedAdCard"));
+        assert!(cg.collisions().is_empty());
+    }
+
+    #[test]
+    fn path_collisions_without_a_distinct_prefix_fall_back_to_suffixes() {
+        let naming = NamingPolicy { collisions: CollisionPolicy::Path };
+        let mut cg = Codegen::new().with_naming(naming);
+        let root = object(&[("a", object(&[("b", object(&[("x", int())]))])), ("a_b", object(&[("y", int())]))]);
+        cg.emit(&root, "Root");
+        let out = cg.into_string();
+        assert!(out.contains("pub struct RootAB {"), "{out}");
+        assert!(out.contains("pub struct RootAB1 {"), "{out}");
+    }
+}
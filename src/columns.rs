@@ -0,0 +1,424 @@
+//! Column statistics for the root record (`--column-stats`).
+//!
+//! A "column" is a top-level slot of each document: an object field or a tuple
+//! position. Everything here is approximate and bounded: distinct counts come
+//! from HyperLogLog sketches (exact while small), so memory scales with the
+//! number of columns, never with the number of documents.
+//!
+//! - Uniqueness: a column is unique when its distinct estimate matches the
+//!   number of non-null values it had.
+//! - Functional dependencies: `A → B` holds when, over documents where both
+//!   are non-null, the pair `(A, B)` has no more distinct values than `A` alone.
+//! - Nullable groups: optional columns that are null (or absent) in exactly the
+//!   same documents. This one is exact: a partition refined by every document.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use serde_json::Value;
+
+use crate::jpath::{JPath, Seg};
+
+/// Columns beyond this are still counted, but get no pair sketches (pairs grow quadratically).
+pub const MAX_FD_COLUMNS: usize = 32;
+/// Stop tracking new columns past this (dynamic-key roots).
+pub const MAX_COLUMNS: usize = 256;
+/// Dependencies backed by fewer rows are noise.
+pub const MIN_FD_ROWS: u64 = 8;
+/// Relative slack for comparing two estimates (HLL σ ≈ 2.3% at p=11).
+const EST_SLACK: f64 = 0.05;
+
+// ------------------------------ HyperLogLog ------------------------------ //
+
+const HLL_P: u32 = 11;
+const HLL_M: usize = 1 << HLL_P;
+
+/// Exact (a sorted set of hashes) while small, a dense register array after.
+#[derive(Clone)]
+pub enum Hll {
+    Sparse(Vec<u64>),
+    Dense(Box<[u8; HLL_M]>),
+}
+
+/// Sparse sets larger than this switch to registers (same memory, by then).
+const SPARSE_MAX: usize = HLL_M / 8;
+
+impl std::fmt::Debug for Hll {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Hll(≈{:.0})", self.estimate())
+    }
+}
+
+impl Default for Hll {
+    fn default() -> Self { Hll::Sparse(Vec::new()) }
+}
+
+fn dense_insert(regs: &mut [u8; HLL_M], h: u64) {
+    let idx = (h >> (64 - HLL_P)) as usize;
+    let rank = ((h << HLL_P) | (1 << (HLL_P - 1))).leading_zeros() as u8 + 1;
+    if rank > regs[idx] {
+        regs[idx] = rank;
+    }
+}
+
+impl Hll {
+    pub fn insert_hash(&mut self, h: u64) {
+        match self {
+            Hll::Sparse(xs) => {
+                if let Err(at) = xs.binary_search(&h) {
+                    xs.insert(at, h);
+                }
+                if xs.len() > SPARSE_MAX {
+                    self.densify();
+                }
+            }
+            Hll::Dense(regs) => dense_insert(regs, h),
+        }
+    }
+
+    fn densify(&mut self) {
+        if let Hll::Sparse(xs) = self {
+            let mut regs = Box::new([0u8; HLL_M]);
+            xs.iter().for_each(|&h| dense_insert(&mut regs, h));
+            *self = Hll::Dense(regs);
+        }
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        match (&mut *self, other) {
+            (Hll::Dense(a), Hll::Dense(b)) => {
+                for (a, b) in a.iter_mut().zip(b.iter()) {
+                    *a = (*a).max(*b);
+                }
+            }
+            (_, Hll::Sparse(xs)) => xs.iter().for_each(|&h| self.insert_hash(h)),
+            (Hll::Sparse(_), Hll::Dense(_)) => {
+                self.densify();
+                self.merge(other);
+            }
+        }
+    }
+
+    pub fn estimate(&self) -> f64 {
+        let regs = match self {
+            Hll::Sparse(xs) => return xs.len() as f64,
+            Hll::Dense(regs) => regs,
+        };
+        let m = HLL_M as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = regs.iter().map(|&r| 2f64.powi(-(r as i32))).sum();
+        let raw = alpha * m * m / sum;
+        let zeros = regs.iter().filter(|&&r| r == 0).count();
+        if raw <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln() // linear counting for small cardinalities
+        } else {
+            raw
+        }
+    }
+}
+
+fn hash_of<T: Hash + ?Sized>(x: &T) -> u64 {
+    let mut h = DefaultHasher::new();
+    x.hash(&mut h);
+    h.finish()
+}
+
+/// Canonical hash of a JSON value (key order matters for objects; fine for equality of captures).
+fn hash_value(v: &Value) -> u64 {
+    hash_of(&v.to_string())
+}
+
+// ------------------------------ Tracking --------------------------------- //
+
+#[derive(Debug, Default, Clone)]
+struct Column {
+    non_null: u64,
+    distinct: Hll,
+}
+
+/// Sketches over documents where both columns of a pair are non-null.
+#[derive(Debug, Default, Clone)]
+struct Pair {
+    rows: u64,
+    a: Hll,
+    b: Hll,
+    ab: Hll,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ColumnStats {
+    docs: u64,
+    columns: BTreeMap<JPath, Column>,
+    /// Keyed by `(a, b)` with `a < b`.
+    pairs: BTreeMap<(JPath, JPath), Pair>,
+    /// Set once any document had more than [`MAX_FD_COLUMNS`] non-null columns.
+    fd_overflow: bool,
+}
+
+impl ColumnStats {
+    pub fn observe(&mut self, v: &Value) {
+        self.docs += 1;
+        let mut cells: Vec<(JPath, u64)> = Vec::new();
+        let mut see = |seg: Seg, x: &Value| {
+            if !x.is_null() {
+                cells.push((JPath(vec![seg]), hash_value(x)));
+            }
+        };
+        match v {
+            Value::Object(m) => m.iter().for_each(|(k, x)| see(Seg::Field(k.clone()), x)),
+            Value::Array(xs) => xs.iter().enumerate().for_each(|(i, x)| see(Seg::Index(i), x)),
+            _ => return,
+        }
+        cells.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for (p, h) in &cells {
+            if !self.columns.contains_key(p) && self.columns.len() >= MAX_COLUMNS {
+                continue;
+            }
+            let c = self.columns.entry(p.clone()).or_default();
+            c.non_null += 1;
+            c.distinct.insert_hash(*h);
+        }
+
+        if self.fd_overflow || cells.len() > MAX_FD_COLUMNS {
+            self.fd_overflow = true;
+            self.pairs.clear();
+            return;
+        }
+        for i in 0..cells.len() {
+            for j in (i + 1)..cells.len() {
+                let ((pa, ha), (pb, hb)) = (&cells[i], &cells[j]);
+                let pair = self.pairs.entry((pa.clone(), pb.clone())).or_default();
+                pair.rows += 1;
+                pair.a.insert_hash(*ha);
+                pair.b.insert_hash(*hb);
+                pair.ab.insert_hash(hash_of(&(ha, hb)));
+            }
+        }
+    }
+
+    pub fn join(mut a: Self, b: Self) -> Self {
+        a.docs += b.docs;
+        for (p, cb) in b.columns {
+            if !a.columns.contains_key(&p) && a.columns.len() >= MAX_COLUMNS {
+                continue;
+            }
+            let ca = a.columns.entry(p).or_default();
+            ca.non_null += cb.non_null;
+            ca.distinct.merge(&cb.distinct);
+        }
+        a.fd_overflow |= b.fd_overflow;
+        if a.fd_overflow {
+            a.pairs.clear();
+            return a;
+        }
+        for (k, pb) in b.pairs {
+            let pa = a.pairs.entry(k).or_default();
+            pa.rows += pb.rows;
+            pa.a.merge(&pb.a);
+            pa.b.merge(&pb.b);
+            pa.ab.merge(&pb.ab);
+        }
+        a
+    }
+
+    pub fn summarize(&self) -> ColumnSummary {
+        let columns = self.columns
+            .iter()
+            .map(|(p, c)| {
+                let distinct = c.distinct.estimate().min(c.non_null as f64);
+                ColumnInfo {
+                    path: p.to_string(),
+                    non_null: c.non_null,
+                    distinct: distinct.round() as u64,
+                    unique: c.non_null >= 2 && distinct >= c.non_null as f64 * (1.0 - EST_SLACK),
+                }
+            })
+            .collect();
+
+        let mut dependencies = Vec::new();
+        for ((pa, pb), pair) in &self.pairs {
+            if pair.rows < MIN_FD_ROWS {
+                continue;
+            }
+            let (a, b, ab) = (pair.a.estimate(), pair.b.estimate(), pair.ab.estimate());
+            let rows = pair.rows as f64;
+            // Unique determinants and constant dependents are trivially functional.
+            let nontrivial = |det: f64, dep: f64| det < rows * (1.0 - EST_SLACK) && dep >= 1.5;
+            if nontrivial(a, b) && ab <= a * (1.0 + EST_SLACK) {
+                dependencies.push((pa.to_string(), pb.to_string()));
+            }
+            if nontrivial(b, a) && ab <= b * (1.0 + EST_SLACK) {
+                dependencies.push((pb.to_string(), pa.to_string()));
+            }
+        }
+
+        ColumnSummary { docs: self.docs, columns, dependencies, fd_skipped: self.fd_overflow }
+    }
+}
+
+/// Partition of columns by "non-null in exactly the same documents".
+///
+/// Each document splits every block into the columns it filled and the rest;
+/// joining two workers' partitions is their common refinement. Columns never
+/// seen non-null belong to no block.
+#[derive(Debug, Default, Clone)]
+pub struct NullGroups {
+    docs: u64,
+    non_null: BTreeMap<JPath, u64>,
+    blocks: Vec<BTreeSet<JPath>>,
+}
+
+impl NullGroups {
+    pub fn observe(&mut self, v: &Value) {
+        let filled: BTreeSet<JPath> = match v {
+            Value::Object(m) => m.iter()
+                .filter(|(_, x)| !x.is_null())
+                .map(|(k, _)| JPath(vec![Seg::Field(k.clone())]))
+                .collect(),
+            Value::Array(xs) => xs.iter()
+                .enumerate()
+                .filter(|(_, x)| !x.is_null())
+                .map(|(i, _)| JPath(vec![Seg::Index(i)]))
+                .collect(),
+            _ => BTreeSet::new(),
+        };
+        let mut single = NullGroups { docs: 1, ..NullGroups::default() };
+        for p in &filled {
+            single.non_null.insert(p.clone(), 1);
+        }
+        if !filled.is_empty() {
+            single.blocks.push(filled);
+        }
+        *self = Self::join(std::mem::take(self), single);
+    }
+
+    pub fn join(a: Self, b: Self) -> Self {
+        let block_of = |g: &Self| -> BTreeMap<JPath, usize> {
+            g.blocks.iter().enumerate().flat_map(|(i, bl)| bl.iter().map(move |p| (p.clone(), i))).collect()
+        };
+        let (ia, ib) = (block_of(&a), block_of(&b));
+        let mut refined: BTreeMap<(Option<usize>, Option<usize>), BTreeSet<JPath>> = BTreeMap::new();
+        for p in ia.keys().chain(ib.keys()) {
+            refined.entry((ia.get(p).copied(), ib.get(p).copied())).or_default().insert(p.clone());
+        }
+        let mut non_null = a.non_null;
+        for (p, n) in b.non_null {
+            *non_null.entry(p).or_default() += n;
+        }
+        NullGroups { docs: a.docs + b.docs, non_null, blocks: refined.into_values().collect() }
+    }
+
+    /// Groups of two or more columns that are sometimes null, always together; in column order.
+    pub fn groups(&self) -> Vec<Vec<JPath>> {
+        let mut out: Vec<Vec<JPath>> = self.blocks
+            .iter()
+            .filter(|bl| bl.len() >= 2)
+            .filter(|bl| bl.iter().all(|p| self.non_null.get(p).is_some_and(|&n| n < self.docs)))
+            .map(|bl| bl.iter().cloned().collect())
+            .collect();
+        out.sort();
+        out
+    }
+}
+
+// ------------------------------ Summary ---------------------------------- //
+
+#[derive(Debug, Default, Clone)]
+pub struct ColumnSummary {
+    pub docs: u64,
+    pub columns: Vec<ColumnInfo>,
+    /// `(determinant, dependent)`: each determinant value maps to one dependent value.
+    pub dependencies: Vec<(String, String)>,
+    /// True when documents were too wide for pairwise tracking.
+    pub fd_skipped: bool,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ColumnInfo {
+    pub path: String,
+    pub non_null: u64,
+    /// Approximate distinct non-null values.
+    pub distinct: u64,
+    pub unique: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn paths(groups: Vec<Vec<JPath>>) -> Vec<Vec<String>> {
+        groups.into_iter().map(|g| g.iter().map(JPath::to_string).collect()).collect()
+    }
+
+    fn docs(n: usize) -> Vec<Value> {
+        (0..n)
+            .map(|i| {
+                let filled = i % 3 == 0;
+                json!({
+                    "id": i,
+                    "parity": i % 2,
+                    "half": i % 2 * 10,
+                    "lat": if filled { json!(1.5) } else { Value::Null },
+                    "lon": if filled { json!(2.5) } else { Value::Null },
+                    "note": if i % 5 == 0 { json!("x") } else { Value::Null },
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn null_groups_are_columns_null_together() {
+        let mut g = NullGroups::default();
+        docs(30).iter().for_each(|d| g.observe(d));
+        // `id`, `parity`, `half` are never null; `note` is null alone
+        assert_eq!(paths(g.groups()), vec![vec!["$.lat".to_string(), "$.lon".to_string()]]);
+    }
+
+    #[test]
+    fn null_groups_join_like_one_pass() {
+        let all = docs(30);
+        let (mut a, mut b, mut whole) = (NullGroups::default(), NullGroups::default(), NullGroups::default());
+        all[..11].iter().for_each(|d| a.observe(d));
+        all[11..].iter().for_each(|d| b.observe(d));
+        all.iter().for_each(|d| whole.observe(d));
+        assert_eq!(NullGroups::join(a, b).groups(), whole.groups());
+        // a document with only `lat` splits that group, and makes the
+        // never-null columns a group null together
+        let mut c = NullGroups::default();
+        c.observe(&json!({ "lat": 1 }));
+        assert_eq!(paths(NullGroups::join(whole, c).groups()), vec![vec!["$.half", "$.id", "$.parity"]]);
+    }
+
+    #[test]
+    fn column_stats_find_keys_and_dependencies() {
+        let all = docs(64);
+        let (mut a, mut b) = (ColumnStats::default(), ColumnStats::default());
+        all[..20].iter().for_each(|d| a.observe(d));
+        all[20..].iter().for_each(|d| b.observe(d));
+        let s = ColumnStats::join(a, b).summarize();
+        assert_eq!(s.docs, 64);
+        let col = |p: &str| s.columns.iter().find(|c| c.path == p).unwrap();
+        assert!(col("$.id").unique);
+        assert_eq!((col("$.parity").distinct, col("$.parity").unique), (2, false));
+        assert_eq!(col("$.lat").non_null, 22);
+        let dep = |a: &str, b: &str| s.dependencies.contains(&(a.to_string(), b.to_string()));
+        assert!(dep("$.parity", "$.half") && dep("$.half", "$.parity"));
+        // a unique key determines everything, trivially
+        assert!(!dep("$.id", "$.parity"));
+        assert!(!s.fd_skipped);
+    }
+
+    #[test]
+    fn wide_documents_skip_dependencies() {
+        let wide: serde_json::Map<String, Value> = (0..=MAX_FD_COLUMNS).map(|i| (format!("c{i}"), json!(i))).collect();
+        let mut s = ColumnStats::default();
+        s.observe(&json!({ "a": 1, "b": 2 }));
+        s.observe(&Value::Object(wide));
+        let s = s.summarize();
+        assert!(s.fd_skipped && s.dependencies.is_empty());
+        assert_eq!(s.columns.len(), MAX_FD_COLUMNS + 3);
+    }
+}
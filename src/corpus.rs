@@ -0,0 +1,153 @@
+//! Synthetic corpora for stress-testing inference (`json-osi gen-corpus`).
+//!
+//! Every document has the same skeleton, with knobs for the traits inference
+//! has to get right:
+//! - `id`: a running integer
+//! - `kind`: one of `enum_cardinality` strings (`k0`, `k1`, …)
+//! - `record`: a tuple whose positions cycle through string / number / integer /
+//!   boolean, `tuple_arity` long plus up to `arity_drift` trailing positions,
+//!   each position after the first null with probability `null_pad`
+//! - `node`: `{ value, children: [node, …] }` nested `depth` levels
+//! - `blob`: a string of `blob_len` characters, to control byte size
+//!
+//! [`truth_schema`] describes exactly that shape, for `json-osi score --truth`.
+//! Output is deterministic for a given spec and seed.
+
+use serde_json::{json, Map, Value};
+
+/// SplitMix64: small, fast, and good enough for test data.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..n` (`n > 0`).
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    /// True with probability `p`.
+    fn chance(&mut self, p: f64) -> bool {
+        ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CorpusSpec {
+    pub docs: u64,
+    /// Stop early once this many bytes are written.
+    pub max_bytes: Option<u64>,
+    pub seed: u64,
+    pub tuple_arity: usize,
+    pub arity_drift: usize,
+    pub null_pad: f64,
+    pub enum_cardinality: usize,
+    pub depth: usize,
+    pub blob_len: usize,
+}
+
+const POSITION_KINDS: [&str; 4] = ["string", "number", "integer", "boolean"];
+
+fn position_kind(i: usize) -> &'static str {
+    POSITION_KINDS[i % POSITION_KINDS.len()]
+}
+
+fn scalar(kind: &str, rng: &mut Rng) -> Value {
+    match kind {
+        "string" => Value::from(format!("s{}", rng.below(100_000))),
+        "number" => Value::from(rng.below(100_000) as f64 / 100.0 + 0.5),
+        "integer" => Value::from(rng.below(1000)),
+        _ => Value::from(rng.chance(0.5)),
+    }
+}
+
+fn node(level: usize, spec: &CorpusSpec, rng: &mut Rng) -> Value {
+    let mut m = Map::new();
+    m.insert("value".into(), Value::from(rng.below(1000)));
+    if level < spec.depth {
+        let children = (0..1 + rng.below(2)).map(|_| node(level + 1, spec, rng)).collect();
+        m.insert("children".into(), Value::Array(children));
+    }
+    Value::Object(m)
+}
+
+fn document(n: u64, spec: &CorpusSpec, rng: &mut Rng) -> Value {
+    let arity = spec.tuple_arity + rng.below(spec.arity_drift as u64 + 1) as usize;
+    let record: Vec<Value> = (0..arity)
+        .map(|i| {
+            if i > 0 && rng.chance(spec.null_pad) {
+                Value::Null
+            } else {
+                scalar(position_kind(i), rng)
+            }
+        })
+        .collect();
+    let blob: String = (0..spec.blob_len).map(|_| (b'a' + rng.below(26) as u8) as char).collect();
+    json!({
+        "id": n,
+        "kind": format!("k{}", rng.below(spec.enum_cardinality.max(1) as u64)),
+        "record": record,
+        "node": node(0, spec, rng),
+        "blob": blob,
+    })
+}
+
+/// Generate the corpus, handing each document to `emit`, which returns the
+/// bytes it wrote. Returns the number of documents.
+pub fn generate(spec: &CorpusSpec, mut emit: impl FnMut(&Value) -> u64) -> u64 {
+    let mut rng = Rng(spec.seed);
+    let mut bytes = 0;
+    for n in 0..spec.docs {
+        if spec.max_bytes.is_some_and(|max| bytes >= max) {
+            return n;
+        }
+        bytes += emit(&document(n, spec, &mut rng));
+    }
+    spec.docs
+}
+
+fn node_schema(level: usize, spec: &CorpusSpec) -> Value {
+    let mut props = Map::new();
+    props.insert("value".into(), json!({ "type": "integer" }));
+    let mut required = vec!["value"];
+    if level < spec.depth {
+        props.insert("children".into(), json!({ "type": "array", "items": node_schema(level + 1, spec) }));
+        required.push("children");
+    }
+    json!({ "type": "object", "properties": props, "required": required })
+}
+
+/// The JSON Schema every document of `spec` conforms to (nesting unrolled, no `$ref`s).
+pub fn truth_schema(spec: &CorpusSpec) -> Value {
+    let max_arity = spec.tuple_arity + spec.arity_drift;
+    let positions: Vec<Value> = (0..max_arity)
+        .map(|i| {
+            let kind = position_kind(i);
+            if i > 0 && spec.null_pad > 0.0 { json!({ "type": [kind, "null"] }) } else { json!({ "type": kind }) }
+        })
+        .collect();
+    let kinds: Vec<String> = (0..spec.enum_cardinality.max(1)).map(|k| format!("k{k}")).collect();
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "type": "object",
+        "properties": {
+            "id": { "type": "integer", "minimum": 0 },
+            "kind": { "type": "string", "enum": kinds },
+            "record": {
+                "type": "array",
+                "prefixItems": positions,
+                "minItems": spec.tuple_arity,
+                "maxItems": max_arity,
+            },
+            "node": node_schema(0, spec),
+            "blob": { "type": "string" },
+        },
+        "required": ["id", "kind", "record", "node", "blob"],
+    })
+}
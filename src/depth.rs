@@ -0,0 +1,50 @@
+//! Slots nested past `--max-depth`, for the report.
+//!
+//! Observation stops descending at the configured depth, so a deeply nested or
+//! adversarial document can't exhaust the stack here or later in
+//! normalization and codegen. What lies below is recorded as opaque (see
+//! `U::opaque`): "any JSON", a `true`-like schema and `serde_json::Value` in
+//! the models. The survey lists where that happened.
+
+use crate::inference::U;
+use crate::jpath::{JPath, Seg};
+use crate::overrides::Overrides;
+
+/// The slots of `u` holding values nested past `--max-depth`, in path order.
+pub fn survey(u: &U, ov: &Overrides) -> Vec<JPath> {
+    let mut out = Vec::new();
+    walk(u, &JPath::root(), ov, &mut out);
+    out
+}
+
+fn walk(u: &U, at: &JPath, ov: &Overrides, out: &mut Vec<JPath>) {
+    if ov.passthrough(at) {
+        return;
+    }
+    if u.opaque {
+        out.push(at.clone());
+    }
+    // an embedded document's paths are the string's own (see `NTy::JsonString`)
+    if let Some(json) = u.str_.as_ref().and_then(|s| s.json.as_deref()) {
+        walk(json, at, ov, out);
+    }
+    if let Some(arr) = &u.arr {
+        let tuple = ov.tuple_at(at).unwrap_or_else(|| crate::inference::decide_tuple(arr));
+        if tuple && !arr.cols.is_empty() {
+            for (i, c) in arr.cols.iter().enumerate() {
+                walk(c, &at.child(Seg::Index(i)), ov, out);
+            }
+        } else {
+            walk(&arr.item, &at.child(Seg::Items), ov, out);
+        }
+    }
+    let Some(obj) = &u.obj else { return };
+    match crate::inference::decide_map(obj) {
+        Some(value) => walk(&value, &at.child(Seg::Items), ov, out),
+        None => {
+            for (name, f) in &obj.fields {
+                walk(&f.ty, &at.child(Seg::Field(name.clone())), ov, out);
+            }
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! Field descriptions from a data dictionary (`gen --dictionary FILE`).
+//!
+//! What a reverse-engineered field or tuple position means lives in people's
+//! heads, or in a spreadsheet. Exported as CSV, the first column is a path and
+//! the second what it means:
+//!
+//! ```text
+//! path,description
+//! $.events[*][0],"Event time, Unix seconds"
+//! $.events[*][3],Retry count; 0 on the first attempt
+//! ```
+//!
+//! A first row whose path doesn't start with `$` is a header; further columns
+//! are ignored. Each description becomes `description` in the JSON Schema and
+//! the doc comment of the Rust field at that path.
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::jpath::JPath;
+
+/// Path → description.
+pub type Descriptions = BTreeMap<String, String>;
+
+/// Read a dictionary, keyed by normalized path.
+pub fn load(path: &std::path::Path) -> Result<Descriptions, String> {
+    let src = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut out = Descriptions::new();
+    for (i, (line, row)) in records(src.trim_start_matches('\u{feff}'))?.into_iter().enumerate() {
+        let Some(raw) = row.first().map(|p| p.trim()).filter(|p| !p.is_empty()) else { continue };
+        if i == 0 && !raw.starts_with('$') {
+            continue;
+        }
+        let at = raw.parse::<JPath>().map_err(|e| format!("line {line}: {e}"))?.to_string();
+        let text = row.get(1).map_or("", |d| d.trim());
+        if text.is_empty() {
+            continue;
+        }
+        if out.insert(at.clone(), text.to_string()).is_some() {
+            return Err(format!("line {line}: {at} is described twice"));
+        }
+    }
+    Ok(out)
+}
+
+/// CSV records (RFC 4180: quoted fields may hold commas, newlines and `""`)
+/// with the line each starts on.
+fn records(src: &str) -> Result<Vec<(usize, Vec<String>)>, String> {
+    let mut out = Vec::new();
+    let (mut row, mut field) = (Vec::new(), String::new());
+    let (mut line, mut start) = (1, 1);
+    let mut chars = src.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            '\n' if quoted => {
+                line += 1;
+                field.push('\n');
+            }
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                out.push((start, std::mem::take(&mut row)));
+                line += 1;
+                start = line;
+            }
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(format!("line {start}: unterminated quoted field"));
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        out.push((start, row));
+    }
+    Ok(out)
+}
+
+/// Set `description` on the schema of every described slot.
+pub fn annotate(schema: &mut Value, descriptions: &Descriptions) {
+    for (path, text) in descriptions {
+        let Ok(path) = path.parse::<JPath>() else { continue };
+        if let Some(node) = crate::norm_ir::schema_at(schema, path.segs()) {
+            node["description"] = Value::from(text.as_str());
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! Discriminator detection for root objects (`--schema-conditionals`).
+//!
+//! Objects are merged into one shape during inference, which loses "field X
+//! only appears when `kind` is `ad`". This tracker keeps, for every top-level
+//! string field with few distinct values, per-value field presence counts.
+//! The field whose values best explain which other fields appear is the
+//! discriminator; its per-value contracts become JSON Schema `if`/`then`.
+
+use std::collections::BTreeMap;
+
+use serde_json::{json, Map, Value};
+
+use crate::inference::{STRING_ENUM_MAX, STRING_ENUM_MAX_LEN};
+
+#[derive(Debug, Default, Clone)]
+pub struct DiscTracker {
+    docs: u64,
+    /// Field → per-value presence, or `None` once the field can't be a discriminator.
+    cols: BTreeMap<String, Option<Col>>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Col {
+    /// Documents where this field was a string.
+    seen: u64,
+    values: BTreeMap<String, Presence>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Presence {
+    docs: u64,
+    /// Field → (present, present and non-null).
+    fields: BTreeMap<String, (u64, u64)>,
+}
+
+impl Presence {
+    fn add(&mut self, other: &Presence) {
+        self.docs += other.docs;
+        for (k, (p, n)) in &other.fields {
+            let e = self.fields.entry(k.clone()).or_default();
+            e.0 += p;
+            e.1 += n;
+        }
+    }
+}
+
+/// What a discriminator value implies for the rest of the object.
+#[derive(Debug, Clone)]
+pub struct Variant {
+    pub value: String,
+    pub docs: u64,
+    /// Non-null in every document with this value (but not in all documents overall).
+    pub required: Vec<String>,
+    /// Present elsewhere, never present with this value.
+    pub absent: Vec<String>,
+    /// Present with this value, but always null.
+    pub null: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Discriminator {
+    pub field: String,
+    pub variants: Vec<Variant>,
+}
+
+impl DiscTracker {
+    pub fn observe(&mut self, v: &Value) {
+        self.docs += 1;
+        let Value::Object(m) = v else { return };
+        let mut presence = Presence { docs: 1, ..Presence::default() };
+        for (k, x) in m {
+            presence.fields.insert(k.clone(), (1, u64::from(!x.is_null())));
+        }
+        for (k, x) in m {
+            let Value::String(s) = x else {
+                self.cols.insert(k.clone(), None);
+                continue;
+            };
+            let slot = self.cols.entry(k.clone()).or_insert_with(|| Some(Col::default()));
+            let Some(col) = slot else { continue };
+            if s.len() > STRING_ENUM_MAX_LEN {
+                *slot = None;
+                continue;
+            }
+            col.seen += 1;
+            col.values.entry(s.clone()).or_default().add(&presence);
+            if col.values.len() > STRING_ENUM_MAX {
+                *slot = None;
+            }
+        }
+    }
+
+    pub fn join(mut a: Self, b: Self) -> Self {
+        a.docs += b.docs;
+        for (k, cb) in b.cols {
+            let slot = a.cols.entry(k).or_insert_with(|| Some(Col::default()));
+            match (slot.as_mut(), cb) {
+                (Some(ca), Some(cb)) => {
+                    ca.seen += cb.seen;
+                    for (v, p) in cb.values {
+                        ca.values.entry(v).or_default().add(&p);
+                    }
+                    if ca.values.len() > STRING_ENUM_MAX {
+                        *slot = None;
+                    }
+                }
+                _ => *slot = None,
+            }
+        }
+        a
+    }
+
+    /// The field (a string in every document, 2+ values) whose values imply the most
+    /// per-variant constraints; `None` if no field implies any.
+    pub fn detect(&self) -> Option<Discriminator> {
+        let mut best: Option<(usize, Discriminator)> = None;
+        for (field, col) in &self.cols {
+            let Some(col) = col else { continue };
+            if col.seen != self.docs || col.values.len() < 2 {
+                continue;
+            }
+            let mut overall = Presence::default();
+            col.values.values().for_each(|p| overall.add(p));
+
+            let variants: Vec<Variant> = col.values
+                .iter()
+                .map(|(value, p)| {
+                    let mut v = Variant { value: value.clone(), docs: p.docs, required: vec![], absent: vec![], null: vec![] };
+                    for (k, &(_, all_non_null)) in &overall.fields {
+                        if k == field {
+                            continue;
+                        }
+                        let (present, non_null) = p.fields.get(k).copied().unwrap_or_default();
+                        if present == 0 {
+                            v.absent.push(k.clone());
+                        } else if non_null == 0 && all_non_null > 0 {
+                            v.null.push(k.clone());
+                        } else if non_null == p.docs && all_non_null < overall.docs {
+                            v.required.push(k.clone());
+                        }
+                    }
+                    v
+                })
+                .collect();
+
+            let score: usize = variants.iter().map(|v| v.required.len() + v.absent.len() + v.null.len()).sum();
+            if score > 0 && best.as_ref().is_none_or(|(s, _)| score > *s) {
+                best = Some((score, Discriminator { field: field.clone(), variants }));
+            }
+        }
+        best.map(|(_, d)| d)
+    }
+}
+
+impl Discriminator {
+    /// Add one `if`/`then` per value to an object schema (under `allOf`).
+    /// Non-object schemas (e.g. a nullable root) are left untouched.
+    pub fn apply(&self, schema: &mut Value) {
+        let Some(obj) = schema.as_object_mut() else { return };
+        if obj.get("type") != Some(&Value::from("object")) {
+            return;
+        }
+        let clauses: Vec<Value> = self.variants
+            .iter()
+            .map(|v| {
+                let mut props = Map::new();
+                for k in &v.absent {
+                    props.insert(k.clone(), Value::Bool(false));
+                }
+                for k in &v.null {
+                    props.insert(k.clone(), json!({ "type": "null" }));
+                }
+                let mut then = Map::new();
+                if !props.is_empty() {
+                    then.insert("properties".into(), Value::Object(props));
+                }
+                if !v.required.is_empty() {
+                    then.insert("required".into(), json!(v.required));
+                }
+                json!({
+                    "if": {
+                        "properties": { self.field.clone(): { "const": v.value } },
+                        "required": [self.field],
+                    },
+                    "then": then,
+                })
+            })
+            .collect();
+        obj.insert("allOf".into(), Value::Array(clauses));
+    }
+}
@@ -0,0 +1,372 @@
+//! Value distributions of numeric slots (`--histograms`).
+//!
+//! Every path holding numbers gets a merging t-digest (bounded number of
+//! centroids, accurate at the tails), so the report can show quantiles and a
+//! coarse histogram. Integers with sentinel shapes (`-1`, `999999`, `i32::MAX`,
+//! …) are also counted exactly: a large share of one such value, far from the
+//! rest of the distribution, usually means "missing" or "unknown" and is better
+//! modeled as an enum arm or a nullable marker than as part of the range.
+//!
+//! Array elements are tracked both per position (`[0]`, `[1]`, …) and pooled
+//! (`[*]`); the report keeps whichever the inferred type ended up using.
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::ir::Ty;
+use crate::jpath::{JPath, Seg};
+
+/// t-digest compression: at most ~2× this many centroids.
+const COMPRESSION: f64 = 100.0;
+/// Values buffered before a compression pass.
+const BUFFER: usize = 512;
+/// Stop tracking new numeric paths past this.
+pub const MAX_SLOTS: usize = 256;
+/// Tuple positions tracked individually per array.
+pub const MAX_POSITIONS: usize = 16;
+/// Share of a slot's values a sentinel must have to be reported.
+const SENTINEL_MIN_SHARE: f64 = 0.01;
+
+// -------------------------------- t-digest -------------------------------- //
+
+#[derive(Debug, Clone, Default)]
+pub struct Digest {
+    /// `(mean, weight)`, sorted by mean after [`Digest::compress`].
+    centroids: Vec<(f64, f64)>,
+    buffer: Vec<f64>,
+    count: u64,
+    min: f64,
+    max: f64,
+}
+
+/// k1 scale function: centroids stay small near q = 0 and q = 1.
+fn k_scale(q: f64) -> f64 {
+    COMPRESSION / (2.0 * std::f64::consts::PI) * (2.0 * q - 1.0).clamp(-1.0, 1.0).asin()
+}
+
+impl Digest {
+    pub fn insert(&mut self, x: f64) {
+        if !x.is_finite() {
+            return;
+        }
+        if self.count == 0 || x < self.min { self.min = x; }
+        if self.count == 0 || x > self.max { self.max = x; }
+        self.count += 1;
+        self.buffer.push(x);
+        if self.buffer.len() >= BUFFER {
+            self.compress();
+        }
+    }
+
+    pub fn merge(mut a: Self, mut b: Self) -> Self {
+        if b.count == 0 {
+            return a;
+        }
+        if a.count == 0 {
+            return b;
+        }
+        a.min = a.min.min(b.min);
+        a.max = a.max.max(b.max);
+        a.count += b.count;
+        a.centroids.append(&mut b.centroids);
+        a.buffer.append(&mut b.buffer);
+        if a.buffer.len() >= BUFFER || a.centroids.len() > 2 * COMPRESSION as usize {
+            a.compress();
+        }
+        a
+    }
+
+    pub fn compress(&mut self) {
+        let mut all = std::mem::take(&mut self.centroids);
+        all.extend(self.buffer.drain(..).map(|x| (x, 1.0)));
+        if all.is_empty() {
+            return;
+        }
+        all.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let total: f64 = all.iter().map(|c| c.1).sum();
+
+        let mut out: Vec<(f64, f64)> = Vec::with_capacity(2 * COMPRESSION as usize);
+        let mut cur = all[0];
+        let mut done = 0.0;
+        let mut k_lo = k_scale(0.0);
+        for &(m, w) in &all[1..] {
+            let q_hi = (done + cur.1 + w) / total;
+            if k_scale(q_hi) - k_lo <= 1.0 {
+                cur.0 += (m - cur.0) * w / (cur.1 + w);
+                cur.1 += w;
+            } else {
+                done += cur.1;
+                k_lo = k_scale(done / total);
+                out.push(cur);
+                cur = (m, w);
+            }
+        }
+        out.push(cur);
+        self.centroids = out;
+    }
+
+    pub fn count(&self) -> u64 { self.count }
+    pub fn min(&self) -> f64 { self.min }
+    pub fn max(&self) -> f64 { self.max }
+
+    /// Approximate `q`-quantile (call [`Digest::compress`] first).
+    pub fn quantile(&self, q: f64) -> f64 {
+        let cs = &self.centroids;
+        if cs.is_empty() {
+            return f64::NAN;
+        }
+        let total: f64 = cs.iter().map(|c| c.1).sum();
+        let target = q.clamp(0.0, 1.0) * total;
+        // each centroid's mass is centered on its mean
+        let mut cum = 0.0;
+        let mut prev = (self.min, 0.0);
+        for &(m, w) in cs {
+            let mid = cum + w / 2.0;
+            if target <= mid {
+                let t = if mid > prev.1 { (target - prev.1) / (mid - prev.1) } else { 0.0 };
+                return (prev.0 + t * (m - prev.0)).clamp(self.min, self.max);
+            }
+            prev = (m, mid);
+            cum += w;
+        }
+        let t = if total > prev.1 { (target - prev.1) / (total - prev.1) } else { 1.0 };
+        (prev.0 + t * (self.max - prev.0)).clamp(self.min, self.max)
+    }
+
+    /// Approximate share of values `<= x` (call [`Digest::compress`] first).
+    pub fn cdf(&self, x: f64) -> f64 {
+        if self.count == 0 || x < self.min {
+            return 0.0;
+        }
+        if x >= self.max {
+            return 1.0;
+        }
+        let total: f64 = self.centroids.iter().map(|c| c.1).sum();
+        let mut cum = 0.0;
+        let mut prev = (self.min, 0.0);
+        for &(m, w) in &self.centroids {
+            let mid = cum + w / 2.0;
+            if x < m {
+                let t = if m > prev.0 { (x - prev.0) / (m - prev.0) } else { 0.0 };
+                return (prev.1 + t * (mid - prev.1)) / total;
+            }
+            prev = (m, mid);
+            cum += w;
+        }
+        let t = if self.max > prev.0 { (x - prev.0) / (self.max - prev.0) } else { 1.0 };
+        (prev.1 + t * (total - prev.1)) / total
+    }
+}
+
+// -------------------------------- sentinels -------------------------------- //
+
+/// `-1`, `±9…9`, `±10^n - 1` shapes, and the signed integer extremes.
+fn sentinel_shaped(n: i64) -> bool {
+    let a = n.unsigned_abs();
+    n == -1
+        || (a >= 99 && a.to_string().bytes().all(|b| b == b'9'))
+        || matches!(n, i64::MIN | i64::MAX)
+        || n == i32::MIN as i64 || n == i32::MAX as i64
+        || n == i16::MIN as i64 || n == i16::MAX as i64
+        || n == u32::MAX as i64 || n == u16::MAX as i64
+}
+
+// ---------------------------------- slots ---------------------------------- //
+
+#[derive(Debug, Clone, Default)]
+struct Slot {
+    digest: Digest,
+    /// Exact counts of sentinel-shaped integers.
+    sentinels: BTreeMap<i64, u64>,
+    /// Values below zero (exact; the digest blurs the tail around a lone negative marker).
+    negatives: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NumericStats {
+    slots: BTreeMap<JPath, Slot>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NumericSummary {
+    pub path: String,
+    pub count: u64,
+    pub min: f64,
+    pub p5: f64,
+    pub median: f64,
+    pub p95: f64,
+    pub max: f64,
+    /// Eight equal-width bins between min and max, as block characters.
+    pub sparkline: String,
+    /// `(value, count)` of likely sentinels.
+    pub sentinels: Vec<(i64, u64)>,
+}
+
+impl NumericStats {
+    pub fn observe(&mut self, v: &Value) {
+        self.walk(v, &mut JPath::root());
+    }
+
+    fn walk(&mut self, v: &Value, at: &mut JPath) {
+        match v {
+            Value::Number(n) => {
+                if !self.slots.contains_key(at) {
+                    if self.slots.len() >= MAX_SLOTS {
+                        return;
+                    }
+                    self.slots.insert(at.clone(), Slot::default());
+                }
+                let slot = self.slots.get_mut(at).expect("inserted above");
+                let x = n.as_f64().unwrap_or(f64::NAN);
+                slot.digest.insert(x);
+                if x < 0.0 {
+                    slot.negatives += 1;
+                }
+                if let Some(i) = n.as_i64().filter(|&i| sentinel_shaped(i)) {
+                    *slot.sentinels.entry(i).or_default() += 1;
+                }
+            }
+            Value::Array(xs) => {
+                for (i, x) in xs.iter().enumerate() {
+                    at.0.push(Seg::Items);
+                    self.walk(x, at);
+                    at.0.pop();
+                    if i < MAX_POSITIONS {
+                        at.0.push(Seg::Index(i));
+                        self.walk(x, at);
+                        at.0.pop();
+                    }
+                }
+            }
+            Value::Object(m) => {
+                for (k, x) in m {
+                    at.0.push(Seg::Field(k.clone()));
+                    self.walk(x, at);
+                    at.0.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn join(mut a: Self, b: Self) -> Self {
+        for (p, sb) in b.slots {
+            if !a.slots.contains_key(&p) && a.slots.len() >= MAX_SLOTS {
+                continue;
+            }
+            let sa = a.slots.entry(p).or_default();
+            sa.digest = Digest::merge(std::mem::take(&mut sa.digest), sb.digest);
+            sa.negatives += sb.negatives;
+            for (v, c) in sb.sentinels {
+                *sa.sentinels.entry(v).or_default() += c;
+            }
+        }
+        a
+    }
+
+    /// Summaries for the numeric paths of `ty`, in path order.
+    pub fn summarize(mut self, ty: &Ty) -> Vec<NumericSummary> {
+        paths_where(ty, |t| matches!(t, Ty::Integer { .. } | Ty::Number { .. } | Ty::StringOrNumber { .. }))
+            .into_iter()
+            .filter_map(|p| {
+                let mut slot = self.slots.remove(&p)?;
+                slot.digest.compress();
+                Some(summary(p, slot))
+            })
+            .collect()
+    }
+}
+
+fn summary(path: JPath, slot: Slot) -> NumericSummary {
+    let d = &slot.digest;
+    let iqr = (d.quantile(0.75) - d.quantile(0.25)).max(1.0);
+    let n = d.count() as f64;
+    let negatives = slot.negatives;
+    let sentinels = slot.sentinels
+        .into_iter()
+        .filter(|&(v, c)| {
+            let share = c as f64 / n;
+            if !(SENTINEL_MIN_SHARE..0.5).contains(&share) {
+                return false;
+            }
+            let v = v as f64;
+            // the sentinel must sit at an extreme, clearly apart from the rest
+            if v <= d.min() {
+                // a negative marker in an otherwise non-negative slot counts too
+                let rest = d.quantile((share + SENTINEL_MIN_SHARE).min(1.0));
+                rest - v > iqr || (v < 0.0 && negatives == c)
+            } else if v >= d.max() {
+                v - d.quantile((1.0 - share - SENTINEL_MIN_SHARE).max(0.0)) > iqr
+            } else {
+                false
+            }
+        })
+        .collect();
+
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let sparkline = if d.max() > d.min() {
+        let width = (d.max() - d.min()) / 8.0;
+        let mass: Vec<f64> = (0..8)
+            .map(|i| {
+                let lo = d.min() + width * i as f64;
+                let lo_cdf = if i == 0 { 0.0 } else { d.cdf(lo) };
+                d.cdf(lo + width) - lo_cdf
+            })
+            .collect();
+        let peak = mass.iter().cloned().fold(0.0, f64::max);
+        mass.iter()
+            .map(|m| BARS[((m / peak) * 7.0).round().clamp(0.0, 7.0) as usize])
+            .collect()
+    } else {
+        String::new()
+    };
+
+    NumericSummary {
+        path: path.to_string(),
+        count: d.count(),
+        min: d.min(),
+        p5: d.quantile(0.05),
+        median: d.quantile(0.5),
+        p95: d.quantile(0.95),
+        max: d.max(),
+        sparkline,
+        sentinels,
+    }
+}
+
+/// Paths of `ty` with a leaf matching `keep` (through nullables and union arms), sorted.
+pub fn paths_where(ty: &Ty, keep: fn(&Ty) -> bool) -> Vec<JPath> {
+    let mut out = Vec::new();
+    leaf_paths(ty, keep, &mut JPath::root(), &mut out);
+    // union arms can reach the same path
+    out.sort();
+    out.dedup();
+    out
+}
+
+fn leaf_paths(t: &Ty, keep: fn(&Ty) -> bool, at: &mut JPath, out: &mut Vec<JPath>) {
+    fn child(seg: Seg, t: &Ty, keep: fn(&Ty) -> bool, at: &mut JPath, out: &mut Vec<JPath>) {
+        at.0.push(seg);
+        leaf_paths(t, keep, at, out);
+        at.0.pop();
+    }
+    match t {
+        Ty::Nullable(inner) | Ty::Sentinel { inner, .. } | Ty::JsonString(inner) => leaf_paths(inner, keep, at, out),
+        Ty::OneOf(arms) => arms.iter().for_each(|a| leaf_paths(a, keep, at, out)),
+        Ty::Tagged { variants, .. } => variants.iter().for_each(|(_, v)| leaf_paths(v, keep, at, out)),
+        Ty::ArrayList { item, .. } | Ty::Map { value: item, .. } => child(Seg::Items, item, keep, at, out),
+        Ty::ArrayTuple { elems, .. } => {
+            for (i, e) in elems.iter().enumerate() {
+                child(Seg::Index(i), e, keep, at, out);
+            }
+        }
+        Ty::Object { fields, .. } => {
+            for f in fields {
+                child(Seg::Field(f.name.clone()), &f.ty, keep, at, out);
+            }
+        }
+        _ if keep(t) => out.push(at.clone()),
+        Ty::Null | Ty::Bool | Ty::Integer { .. } | Ty::Number { .. } | Ty::StringOrNumber { .. } | Ty::String { .. } | Ty::Never | Ty::Any | Ty::Raw => {}
+    }
+}
@@ -0,0 +1,170 @@
+//! How well the enums of a committed schema cover new data (`validate --mode enums`).
+//!
+//! Enums are inferred from the corpus at hand, so they go stale: variants stop
+//! occurring and new values appear. This re-reads a corpus against a schema
+//! and reports, per enum, which variants occurred and which values of the same
+//! kind fell outside it (the generated models reject those; an open enum would
+//! read them as its `Other` variant).
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::explain::collect;
+use crate::jpath::{JPath, Seg};
+
+/// Outside values listed per enum; the rest are only counted.
+pub const MAX_OUTSIDE_LISTED: usize = 20;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Count {
+    pub value: Value,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EnumCoverage {
+    pub path: String,
+    /// Each variant with how often it occurred.
+    pub variants: Vec<Count>,
+    /// Values of the variants' kind that aren't one of them, most frequent first.
+    pub outside: Vec<Count>,
+    /// Occurrences of outside values beyond the listed ones.
+    pub outside_unlisted: u64,
+}
+
+impl EnumCoverage {
+    pub fn exhaustive(&self) -> bool {
+        self.outside.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Slot {
+    path: JPath,
+    variants: Vec<(Value, u64)>,
+    outside: BTreeMap<String, (Value, u64)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Coverage {
+    pub docs: u64,
+    slots: Vec<Slot>,
+}
+
+impl Coverage {
+    /// Every `enum` of `schema`, found through `properties`, `items`,
+    /// `prefixItems`, `additionalProperties` and `oneOf` / `anyOf` arms.
+    pub fn new(schema: &Value) -> Self {
+        let mut slots = Vec::new();
+        enums(schema, &mut JPath::root(), &mut slots);
+        Coverage { docs: 0, slots }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    pub fn observe(&mut self, doc: &Value) {
+        self.docs += 1;
+        for slot in &mut self.slots {
+            let mut found = Vec::new();
+            collect(doc, slot.path.segs(), JPath::root(), &mut found);
+            for (_, v) in found {
+                if let Some((_, n)) = slot.variants.iter_mut().find(|(x, _)| x == v) {
+                    *n += 1;
+                } else if slot.variants.iter().any(|(x, _)| same_kind(x, v)) {
+                    slot.outside.entry(v.to_string()).or_insert_with(|| (v.clone(), 0)).1 += 1;
+                }
+            }
+        }
+    }
+
+    pub fn finish(self) -> Vec<EnumCoverage> {
+        self.slots
+            .into_iter()
+            .map(|s| {
+                let count = |(value, count)| Count { value, count };
+                let mut outside: Vec<Count> = s.outside.into_values().map(count).collect();
+                outside.sort_by_key(|c| std::cmp::Reverse(c.count));
+                let unlisted = outside.iter().skip(MAX_OUTSIDE_LISTED).map(|c| c.count).sum();
+                outside.truncate(MAX_OUTSIDE_LISTED);
+                let variants = s.variants.into_iter().map(count).collect();
+                EnumCoverage { path: s.path.to_string(), variants, outside, outside_unlisted: unlisted }
+            })
+            .collect()
+    }
+}
+
+fn same_kind(a: &Value, b: &Value) -> bool {
+    std::mem::discriminant(a) == std::mem::discriminant(b)
+}
+
+fn enums(schema: &Value, at: &mut JPath, out: &mut Vec<Slot>) {
+    let Some(node) = schema.as_object() else { return };
+    if let Some(values) = node.get("enum").and_then(Value::as_array) {
+        // arms of one path (nullable enums, unions) share a slot
+        match out.iter_mut().find(|s| s.path == *at) {
+            Some(slot) => {
+                for v in values {
+                    if !slot.variants.iter().any(|(x, _)| x == v) {
+                        slot.variants.push((v.clone(), 0));
+                    }
+                }
+            }
+            None => out.push(Slot {
+                path: at.clone(),
+                variants: values.iter().map(|v| (v.clone(), 0)).collect(),
+                outside: BTreeMap::new(),
+            }),
+        }
+    }
+    for key in ["oneOf", "anyOf"] {
+        for arm in node.get(key).and_then(Value::as_array).into_iter().flatten() {
+            enums(arm, at, out);
+        }
+    }
+    let mut child = |seg: Seg, s: &Value, out: &mut Vec<Slot>| {
+        at.0.push(seg);
+        enums(s, at, out);
+        at.0.pop();
+    };
+    if let Some(props) = node.get("properties").and_then(Value::as_object) {
+        for (k, s) in props {
+            child(Seg::Field(k.clone()), s, out);
+        }
+    }
+    for (i, s) in node.get("prefixItems").and_then(Value::as_array).into_iter().flatten().enumerate() {
+        child(Seg::Index(i), s, out);
+    }
+    for key in ["items", "additionalProperties"] {
+        if let Some(s) = node.get(key).filter(|s| s.is_object()) {
+            child(Seg::Items, s, out);
+        }
+    }
+}
+
+pub fn render(found: &[EnumCoverage], docs: u64) -> String {
+    let mut s = String::new();
+    let exhaustive = found.iter().filter(|e| e.exhaustive()).count();
+    let _ = writeln!(s, "enums: {} in the schema, {exhaustive} exhaustive over {docs} document(s)", found.len());
+    for e in found {
+        let seen = e.variants.iter().filter(|c| c.count > 0).count();
+        let _ = writeln!(s, "\n{}: {seen} of {} variant(s) seen", e.path, e.variants.len());
+        for c in &e.variants {
+            let _ = writeln!(s, "  {}: {}", c.value, c.count);
+        }
+        if !e.exhaustive() {
+            let _ = writeln!(s, "  outside the enum:");
+            for c in &e.outside {
+                let _ = writeln!(s, "    {}: {}", c.value, c.count);
+            }
+            if e.outside_unlisted > 0 {
+                let _ = writeln!(s, "    … and {} more occurrence(s)", e.outside_unlisted);
+            }
+        }
+    }
+    s
+}
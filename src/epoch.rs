@@ -0,0 +1,124 @@
+//! Integer slots that look like Unix timestamps.
+//!
+//! A slot whose every value falls between 2000-01-01 and 2100-01-01 when read
+//! as seconds (or as milliseconds) since 1970 is reported as a probable
+//! timestamp. With `--epoch-timestamps` the schema says so with `format`
+//! (`unix-time` or `unix-time-ms`) and the Rust models read it as
+//! `chrono::DateTime<Utc>` through `serde_with`'s timestamp adapters.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::distribution::paths_where;
+use crate::ir::Ty;
+use crate::jpath::{JPath, Seg};
+
+/// 2000-01-01T00:00:00Z, in seconds.
+pub const EPOCH_MIN_SECS: i128 = 946_684_800;
+/// 2100-01-01T00:00:00Z, in seconds.
+pub const EPOCH_MAX_SECS: i128 = 4_102_444_800;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EpochUnit {
+    Seconds,
+    Millis,
+}
+
+impl EpochUnit {
+    /// The JSON Schema `format` value.
+    pub fn format(self) -> &'static str {
+        match self {
+            EpochUnit::Seconds => "unix-time",
+            EpochUnit::Millis => "unix-time-ms",
+        }
+    }
+
+    fn per_second(self) -> i128 {
+        match self {
+            EpochUnit::Seconds => 1,
+            EpochUnit::Millis => 1000,
+        }
+    }
+
+    /// The unit every value of `min..=max` is a plausible timestamp in.
+    pub fn of(min: i128, max: i128) -> Option<Self> {
+        [EpochUnit::Seconds, EpochUnit::Millis].into_iter().find(|u| {
+            let (lo, hi) = (EPOCH_MIN_SECS * u.per_second(), EPOCH_MAX_SECS * u.per_second());
+            lo <= min && max <= hi
+        })
+    }
+
+    pub fn time(self, x: i128) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp_millis(i64::try_from(x * 1000 / self.per_second()).ok()?)
+    }
+}
+
+/// An integer slot whose range reads as timestamps.
+#[derive(Debug, Clone)]
+pub struct EpochMatch {
+    pub path: JPath,
+    pub unit: EpochUnit,
+    pub min: i128,
+    pub max: i128,
+}
+
+/// Probable timestamps among the integer paths of `ty`, in path order (a path
+/// reached by several union arms counts only if every arm agrees).
+pub fn detect(ty: &Ty) -> Vec<EpochMatch> {
+    let ints = paths_where(ty, |t| matches!(t, Ty::Integer { from_string: false, .. }));
+    let mut out = Vec::new();
+    for path in ints {
+        let mut ranges = Vec::new();
+        ranges_at(ty, path.segs(), &mut ranges);
+        let Some(((min, max), rest)) = ranges.split_first() else { continue };
+        let (Some(min), Some(max)) = (*min, *max) else { continue };
+        if rest.iter().any(|r| *r != (Some(min), Some(max))) {
+            continue;
+        }
+        if let Some(unit) = EpochUnit::of(min, max) {
+            out.push(EpochMatch { path, unit, min, max });
+        }
+    }
+    out
+}
+
+/// The integer ranges found at `segs` (one per union arm reaching it).
+fn ranges_at(t: &Ty, segs: &[Seg], out: &mut Vec<(Option<i128>, Option<i128>)>) {
+    match (t, segs.split_first()) {
+        (Ty::Nullable(inner) | Ty::Sentinel { inner, .. } | Ty::JsonString(inner), _) => ranges_at(inner, segs, out),
+        (Ty::OneOf(arms), _) => arms.iter().for_each(|a| ranges_at(a, segs, out)),
+        (Ty::Tagged { variants, .. }, _) => variants.iter().for_each(|(_, v)| ranges_at(v, segs, out)),
+        (Ty::Integer { min, max, .. }, None) => out.push((*min, *max)),
+        (Ty::ArrayList { item, .. } | Ty::Map { value: item, .. }, Some((Seg::Items, rest))) => ranges_at(item, rest, out),
+        (Ty::ArrayTuple { elems, .. }, Some((Seg::Index(i), rest))) => {
+            if let Some(e) = elems.get(*i) {
+                ranges_at(e, rest, out);
+            }
+        }
+        (Ty::Object { fields, .. }, Some((Seg::Field(k), rest))) => {
+            if let Some(f) = fields.iter().find(|f| &f.name == k) {
+                ranges_at(&f.ty, rest, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Path → unit, as recorded in artifact headers for code generation.
+pub fn rules(found: &[EpochMatch]) -> BTreeMap<String, EpochUnit> {
+    found.iter().map(|m| (m.path.to_string(), m.unit)).collect()
+}
+
+/// Set `format` on the schema of every timestamp slot.
+pub fn annotate(schema: &mut Value, rules: &BTreeMap<String, EpochUnit>) {
+    for (path, unit) in rules {
+        let Ok(path) = path.parse::<JPath>() else { continue };
+        if let Some(node) = crate::norm_ir::schema_at(schema, path.segs()) {
+            node["format"] = Value::from(unit.format());
+        }
+    }
+}
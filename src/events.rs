@@ -0,0 +1,53 @@
+//! Machine-readable progress (`gen --machine`): one JSON event per line on
+//! stdout, in place of the human log on stderr.
+//!
+//! Errors that end the run still go to stderr with exit code 2; a consumer
+//! sees the stream stop without a `finished` event.
+
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::Serialize;
+use serde_json::Value;
+
+static MACHINE: AtomicBool = AtomicBool::new(false);
+
+/// Switch to events for the rest of the process (and drop ANSI colors, so
+/// messages built for the human log read cleanly).
+pub fn enable() {
+    MACHINE.store(true, Ordering::Relaxed);
+    colored::control::set_override(false);
+}
+
+pub fn machine() -> bool {
+    MACHINE.load(Ordering::Relaxed)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    /// A pipeline phase began: `read`, `normalize`, `emit`.
+    Phase { phase: &'a str },
+    /// `--input` resolved to this many files.
+    Inputs { files: usize },
+    /// One input file was read and observed.
+    FileDone { path: &'a str, docs: u64 },
+    Info { message: String },
+    Warning { message: String },
+    /// An output was written. Outputs bound for stdout (`-`, `--stdout`) carry
+    /// their `contents` instead: JSON outputs as values, the rest as strings.
+    Artifact {
+        output: &'a str,
+        path: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        contents: Option<Value>,
+    },
+    Finished { elapsed_ms: u128 },
+}
+
+pub fn emit(event: &Event) {
+    let line = serde_json::to_string(event).expect("events serialize");
+    let mut out = std::io::stdout().lock();
+    // a consumer that stopped reading only loses progress
+    let _ = writeln!(out, "{line}").and_then(|_| out.flush());
+}
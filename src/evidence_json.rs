@@ -0,0 +1,196 @@
+//! The joined evidence as a documented JSON tree (`gen --evidence-json`), for
+//! notebooks and visualization tools that shouldn't link this crate.
+//!
+//! `--evidence` saves [`U`] as-is: compact, but its layout follows the
+//! inference internals and changes with them. This export is a stable,
+//! self-describing view of the same statistics:
+//!
+//! ```text
+//! {
+//!   "format": "json-osi-evidence-tree/1",
+//!   "evidence": "<evidence id>",
+//!   "docs": 1200,
+//!   "root": <node>,
+//!   "dependencies": [["$.a", "$.b"], …]      // --column-stats: each `a` value fixes `b`
+//! }
+//!
+//! <node> = {
+//!   "path": "$.items[*].id",                 // jpath syntax, `[*]` for list items
+//!   "kinds": ["null", "string", …],          // JSON kinds seen here
+//!   "number": {                              // only if numbers were seen
+//!     "min": 0, "max": 42,
+//!     "integers": true, "floats": false,     // which spellings were seen
+//!     "literals": [0, 1, 42]                 // distinct values kept (at most 64)
+//!   },
+//!   "string": {                              // only if strings were seen
+//!     "literals": ["a", "b"],                // distinct values kept (at most 64)
+//!     "formats": ["uuid"],                   // syntaxes every string matched
+//!     "uri": false,
+//!     "json": <node>,                        // --json-strings: the embedded documents
+//!     "numeric": { … }                       // --coerce-numeric-strings: as "number"
+//!   },
+//!   "array": {                               // only if arrays were seen
+//!     "count": 300,                          // arrays observed
+//!     "min_len": 0, "max_len": 3,
+//!     "items": <node>,                       // every element, as a list
+//!     "positions": [                         // per index, as a tuple
+//!       { "present": 300, "non_null": 280, "node": <node> }, …
+//!     ]
+//!   },
+//!   "object": {                              // only if objects were seen
+//!     "count": 1200,                         // objects observed
+//!     "fields": { "<key>": { "present": 1200, "non_null": 1190, "node": <node> }, … }
+//!   },
+//!   "column": {                              // --column-stats, root columns only
+//!     "non_null": 1190, "distinct": 1187, "unique": false
+//!   },
+//!   "distribution": {                        // --histograms, numeric slots
+//!     "count": 1190, "min": 0, "p5": 1, "median": 7, "p95": 30, "max": 42,
+//!     "sentinels": [[-1, 12]]                // (value, count) of likely markers
+//!   }
+//! }
+//! ```
+//!
+//! Counts are documents/values observed, not percentages; the `literals` lists
+//! are empty once a slot saw more distinct values than the evidence keeps.
+
+use std::collections::BTreeMap;
+
+use serde_json::{json, Map, Value};
+
+use crate::columns::ColumnInfo;
+use crate::distribution::NumericSummary;
+use crate::inference::{NumC, StrC, U};
+use crate::jpath::{JPath, Seg};
+use crate::report::Report;
+
+pub const FORMAT: &str = "json-osi-evidence-tree/1";
+
+/// Per-path side statistics attached to the nodes they describe.
+struct Extras<'a> {
+    columns: BTreeMap<String, &'a ColumnInfo>,
+    numbers: BTreeMap<String, &'a NumericSummary>,
+}
+
+pub fn export(u: &U, report: &Report) -> Value {
+    let extras = Extras {
+        columns: report.columns.iter().flat_map(|c| &c.columns).map(|c| (c.path.clone(), c)).collect(),
+        numbers: report.numbers.iter().flatten().map(|n| (n.path.clone(), n)).collect(),
+    };
+    let mut out = json!({
+        "format": FORMAT,
+        "evidence": report.evidence_id,
+        "docs": report.docs(),
+        "root": node(u, &JPath::root(), &extras),
+    });
+    if let Some(columns) = &report.columns {
+        out["dependencies"] = json!(columns.dependencies);
+    }
+    out
+}
+
+fn node(u: &U, at: &JPath, extras: &Extras) -> Value {
+    let kinds: Vec<&str> = [
+        (u.nullable, "null"),
+        (u.has_bool, "boolean"),
+        (u.num.is_some(), "number"),
+        (u.str_.is_some(), "string"),
+        (u.arr.is_some(), "array"),
+        (u.obj.is_some(), "object"),
+    ]
+    .into_iter()
+    .filter_map(|(seen, kind)| seen.then_some(kind))
+    .collect();
+
+    let mut n = Map::new();
+    let path = at.to_string();
+    n.insert("path".into(), json!(path));
+    n.insert("kinds".into(), json!(kinds));
+    if let Some(num) = &u.num {
+        n.insert("number".into(), number(num));
+    }
+    if let Some(s) = &u.str_ {
+        n.insert("string".into(), string(s, at));
+    }
+    if let Some(arr) = &u.arr {
+        let positions: Vec<Value> = arr.cols
+            .iter()
+            .enumerate()
+            .map(|(i, col)| json!({
+                "present": arr.present.get(i).copied().unwrap_or(0),
+                "non_null": arr.non_null.get(i).copied().unwrap_or(0),
+                "node": node(col, &at.child(Seg::Index(i)), extras),
+            }))
+            .collect();
+        n.insert("array".into(), json!({
+            "count": arr.samples,
+            "min_len": arr.len_min,
+            "max_len": arr.len_max,
+            "items": node(&arr.item, &at.child(Seg::Items), extras),
+            "positions": positions,
+        }));
+    }
+    if let Some(obj) = &u.obj {
+        let fields: Map<String, Value> = obj.fields
+            .iter()
+            .map(|(k, f)| {
+                (k.clone(), json!({
+                    "present": f.present_in,
+                    "non_null": f.non_null_in,
+                    "node": node(&f.ty, &at.child(Seg::Field(k.clone())), extras),
+                }))
+            })
+            .collect();
+        let mut object = json!({ "count": obj.seen_objects, "fields": fields });
+        if let Some(w) = &obj.wide {
+            object["wide"] = json!({
+                "keys": w.keys,
+                "present": w.present,
+                "node": node(&w.value, &at.child(Seg::Items), extras),
+            });
+        }
+        n.insert("object".into(), object);
+    }
+    if let Some(c) = extras.columns.get(&path) {
+        n.insert("column".into(), json!({ "non_null": c.non_null, "distinct": c.distinct, "unique": c.unique }));
+    }
+    if let Some(d) = extras.numbers.get(&path) {
+        n.insert("distribution".into(), json!({
+            "count": d.count,
+            "min": d.min,
+            "p5": d.p5,
+            "median": d.median,
+            "p95": d.p95,
+            "max": d.max,
+            "sentinels": d.sentinels,
+        }));
+    }
+    Value::Object(n)
+}
+
+fn number(num: &NumC) -> Value {
+    json!({
+        "min": num.min_f64.0,
+        "max": num.max_f64.0,
+        "integers": num.saw_int || num.saw_uint,
+        "floats": num.saw_float,
+        "literals": num.lits_f64.iter().map(|x| x.0).collect::<Vec<_>>(),
+    })
+}
+
+fn string(s: &StrC, at: &JPath) -> Value {
+    let mut out = json!({
+        "literals": s.lits,
+        "formats": s.formats.iter().map(|f| f.as_str()).collect::<Vec<_>>(),
+        "uri": s.is_uri,
+    });
+    // The embedded documents share the string's path, as they do everywhere
+    // else; the side statistics describe the string itself, not them.
+    if let Some(doc) = &s.json {
+        out["json"] = node(doc, at, &Extras { columns: BTreeMap::new(), numbers: BTreeMap::new() });
+    }
+    if let Some(num) = &s.numeric {
+        out["numeric"] = number(num);
+    }
+    out
+}
@@ -0,0 +1,145 @@
+//! Concrete values behind the type inferred at one path (`explain --witnesses N`).
+//!
+//! Evidence only keeps sufficient statistics, so "why is this field both a
+//! string and an array?" can't be answered from `U`. This re-reads the input
+//! and keeps, per JSON kind found at the path, the first N values as witnesses.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use serde_json::Value;
+
+use crate::jpath::{JPath, Seg};
+
+/// Witness values longer than this (compact JSON) are cut.
+const MAX_WITNESS_LEN: usize = 80;
+
+#[derive(Debug, Clone)]
+pub struct Witness {
+    pub value: Value,
+    /// Concrete path (wildcards filled in).
+    pub path: JPath,
+    /// Where the document came from (`file:line`, `file#doc`), if provenance is on.
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct KindWitnesses {
+    pub count: u64,
+    pub docs: u64,
+    pub first: Vec<Witness>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Witnesses {
+    path: JPath,
+    max: usize,
+    pub docs: u64,
+    /// Documents where the path resolved to at least one value.
+    pub matched: u64,
+    /// JSON kind (`string`, `integer`, …) → witnesses.
+    pub kinds: BTreeMap<&'static str, KindWitnesses>,
+}
+
+fn kind_of(v: &Value) -> &'static str {
+    match v {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Every value at `segs` below `v`, with its concrete path; `[*]` fans out
+/// over list items and map values.
+pub fn collect<'a>(v: &'a Value, segs: &[Seg], at: JPath, out: &mut Vec<(JPath, &'a Value)>) {
+    let Some((seg, rest)) = segs.split_first() else {
+        out.push((at, v));
+        return;
+    };
+    match (seg, v) {
+        (Seg::Field(k), Value::Object(m)) => {
+            if let Some(x) = m.get(k) {
+                collect(x, rest, at.child(seg.clone()), out);
+            }
+        }
+        (Seg::Index(i), Value::Array(xs)) => {
+            if let Some(x) = xs.get(*i) {
+                collect(x, rest, at.child(seg.clone()), out);
+            }
+        }
+        (Seg::Items, Value::Array(xs)) => {
+            for (i, x) in xs.iter().enumerate() {
+                collect(x, rest, at.child(Seg::Index(i)), out);
+            }
+        }
+        (Seg::Items, Value::Object(m)) => {
+            for (k, x) in m {
+                collect(x, rest, at.child(Seg::Field(k.clone())), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl Witnesses {
+    pub fn new(path: JPath, max: usize) -> Self {
+        Witnesses { path, max, docs: 0, matched: 0, kinds: BTreeMap::new() }
+    }
+
+    pub fn observe(&mut self, doc: &Value, source: Option<&str>) {
+        self.docs += 1;
+        let mut found = Vec::new();
+        collect(doc, self.path.segs(), JPath::root(), &mut found);
+        if found.is_empty() {
+            return;
+        }
+        self.matched += 1;
+        let mut seen_here = Vec::<&'static str>::new();
+        for (path, v) in found {
+            let kind = kind_of(v);
+            let k = self.kinds.entry(kind).or_default();
+            k.count += 1;
+            if !seen_here.contains(&kind) {
+                seen_here.push(kind);
+                k.docs += 1;
+            }
+            if k.first.len() < self.max {
+                k.first.push(Witness { value: v.clone(), path, source: source.map(str::to_string) });
+            }
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut s = String::new();
+        let total: u64 = self.kinds.values().map(|k| k.count).sum();
+        let _ = writeln!(
+            s,
+            "{}: {} value(s) in {} of {} document(s), {} kind(s)",
+            self.path, total, self.matched, self.docs, self.kinds.len()
+        );
+        let wildcard = self.path.segs().contains(&Seg::Items);
+        for (kind, k) in &self.kinds {
+            let _ = writeln!(s, "\n{kind}: {} value(s) in {} document(s)", k.count, k.docs);
+            for w in &k.first {
+                let mut val = w.value.to_string();
+                if val.chars().count() > MAX_WITNESS_LEN {
+                    val = val.chars().take(MAX_WITNESS_LEN).collect::<String>() + "…";
+                }
+                let mut line = format!("  {val}");
+                if wildcard {
+                    let _ = write!(line, "  at {}", w.path);
+                }
+                if let Some(src) = &w.source {
+                    let _ = write!(line, "  ({src})");
+                }
+                s.push_str(&line);
+                s.push('\n');
+            }
+        }
+        s
+    }
+}
@@ -0,0 +1,244 @@
+//! Named string formats declared by the user (`--formats FILE`).
+//!
+//! One declaration per line (`#` starts a comment):
+//!
+//! ```text
+//! format "place_id" = regex "^ChIJ[0-9A-Za-z_-]{14,}$"
+//! format "iban"     = regex "^[A-Z]{2}[0-9]{2}[A-Z0-9]{11,30}$" check "crate::checks::iban"
+//! ```
+//!
+//! Every string slot is checked against every regex while the evidence is
+//! gathered; a slot whose values all match gets the first such format. The
+//! schema says so with `format`, and the Rust models get an `is_<name>()`
+//! function and a newtype that only holds valid values. `check` names a
+//! `fn(&str) -> bool` of the consuming crate that generated code calls too
+//! (it can't be run against the evidence, so inference relies on the regex).
+
+use std::collections::BTreeMap;
+
+use regex::{Regex, RegexSet};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::distribution::{paths_where, MAX_POSITIONS, MAX_SLOTS};
+use crate::ir::Ty;
+use crate::jpath::{JPath, Seg};
+
+/// One `format` declaration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Format {
+    pub name: String,
+    pub regex: String,
+    /// Path of an extra `fn(&str) -> bool` the generated code calls.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub check: Option<String>,
+}
+
+/// The declarations of a formats file, with their regexes compiled together.
+#[derive(Debug, Clone)]
+pub struct Formats {
+    pub defs: Vec<Format>,
+    set: RegexSet,
+}
+
+impl Formats {
+    pub fn load(path: &std::path::Path) -> Result<Self, String> {
+        let src = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        Self::parse(&src).map_err(|e| format!("{}:{e}", path.display()))
+    }
+
+    /// Errors start with the 1-based line number.
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let mut defs: Vec<Format> = Vec::new();
+        for (i, line) in src.lines().enumerate() {
+            let def = parse_line(line).map_err(|e| format!("{}: {e}", i + 1))?;
+            let Some(def) = def else { continue };
+            if defs.iter().any(|d| d.name == def.name) {
+                return Err(format!("{}: format {:?} is declared twice", i + 1, def.name));
+            }
+            defs.push(def);
+        }
+        let set = RegexSet::new(defs.iter().map(|d| &d.regex)).map_err(|e| e.to_string())?;
+        Ok(Formats { defs, set })
+    }
+}
+
+/// `None` for blank and comment lines.
+fn parse_line(line: &str) -> Result<Option<Format>, String> {
+    let mut rest = line.trim_start();
+    if rest.is_empty() || rest.starts_with('#') {
+        return Ok(None);
+    }
+    keyword(&mut rest, "format")?;
+    let name = quoted(&mut rest)?;
+    keyword(&mut rest, "=")?;
+    keyword(&mut rest, "regex")?;
+    let regex = quoted(&mut rest)?;
+    let check = if rest.starts_with("check") {
+        keyword(&mut rest, "check")?;
+        Some(quoted(&mut rest)?)
+    } else {
+        None
+    };
+    if !(rest.is_empty() || rest.starts_with('#')) {
+        return Err(format!("unexpected `{rest}`"));
+    }
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(format!("format name {name:?} must be letters, digits, `_` or `-`"));
+    }
+    Regex::new(&regex).map_err(|e| format!("format {name:?}: {e}"))?;
+    if let Some(f) = &check {
+        let ident = |s: &str| s.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_') && s.chars().all(|c| c.is_alphanumeric() || c == '_');
+        if !f.split("::").all(ident) {
+            return Err(format!("format {name:?}: check {f:?} is not a Rust path"));
+        }
+    }
+    Ok(Some(Format { name, regex, check }))
+}
+
+fn keyword(rest: &mut &str, expect: &str) -> Result<(), String> {
+    *rest = rest.strip_prefix(expect).ok_or_else(|| format!("expected `{expect}`"))?.trim_start();
+    Ok(())
+}
+
+/// A `"…"` string; `\"` is a quote, every other backslash is kept (regexes need them).
+fn quoted(rest: &mut &str) -> Result<String, String> {
+    let body = rest.strip_prefix('"').ok_or("expected a quoted string")?;
+    let mut out = String::new();
+    let mut chars = body.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => {
+                *rest = body[i + 1..].trim_start();
+                return Ok(out);
+            }
+            '\\' if body[i + 1..].starts_with('"') => {
+                out.push('"');
+                chars.next();
+            }
+            c => out.push(c),
+        }
+    }
+    Err("unterminated string".to_string())
+}
+
+// --------------------------------- tracking --------------------------------- //
+
+#[derive(Debug, Clone, Default)]
+struct Slot {
+    strings: u64,
+    /// Per declaration, strings that matched its regex.
+    matched: Vec<u64>,
+}
+
+/// Matches per string slot (`[*]` pools list items; the first tuple positions
+/// are also tracked on their own).
+#[derive(Debug, Clone, Default)]
+pub struct FormatStats {
+    slots: BTreeMap<JPath, Slot>,
+}
+
+impl FormatStats {
+    pub fn observe(&mut self, v: &Value, formats: &Formats) {
+        self.walk(v, formats, &mut JPath::root());
+    }
+
+    fn walk(&mut self, v: &Value, formats: &Formats, at: &mut JPath) {
+        match v {
+            Value::String(s) => {
+                if !self.slots.contains_key(at) {
+                    if self.slots.len() >= MAX_SLOTS {
+                        return;
+                    }
+                    let matched = vec![0; formats.defs.len()];
+                    self.slots.insert(at.clone(), Slot { strings: 0, matched });
+                }
+                let slot = self.slots.get_mut(at).expect("inserted above");
+                slot.strings += 1;
+                for i in formats.set.matches(s).iter() {
+                    slot.matched[i] += 1;
+                }
+            }
+            Value::Array(xs) => {
+                for (i, x) in xs.iter().enumerate() {
+                    at.0.push(Seg::Items);
+                    self.walk(x, formats, at);
+                    at.0.pop();
+                    if i < MAX_POSITIONS {
+                        at.0.push(Seg::Index(i));
+                        self.walk(x, formats, at);
+                        at.0.pop();
+                    }
+                }
+            }
+            Value::Object(m) => {
+                for (k, x) in m {
+                    at.0.push(Seg::Field(k.clone()));
+                    self.walk(x, formats, at);
+                    at.0.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn join(mut a: Self, b: Self) -> Self {
+        for (p, sb) in b.slots {
+            if !a.slots.contains_key(&p) && a.slots.len() >= MAX_SLOTS {
+                continue;
+            }
+            let sa = a.slots.entry(p).or_default();
+            sa.strings += sb.strings;
+            sa.matched.resize(sb.matched.len().max(sa.matched.len()), 0);
+            for (x, y) in sa.matched.iter_mut().zip(sb.matched) {
+                *x += y;
+            }
+        }
+        a
+    }
+}
+
+// -------------------------------- assigning --------------------------------- //
+
+/// A string slot whose every value matched a declared format.
+#[derive(Debug, Clone)]
+pub struct FormatMatch {
+    pub path: JPath,
+    pub format: Format,
+    pub values: u64,
+    /// Later declarations that matched every value too.
+    pub also: Vec<String>,
+}
+
+/// Formats of the string paths of `ty`, in path order.
+pub fn detect(stats: FormatStats, ty: &Ty, formats: &Formats) -> Vec<FormatMatch> {
+    let leaves = paths_where(ty, |t| matches!(t, Ty::String { .. }));
+    let mut out = Vec::new();
+    for path in leaves {
+        let Some(slot) = stats.slots.get(&path).filter(|s| s.strings > 0) else { continue };
+        let mut all = (0..formats.defs.len()).filter(|&i| slot.matched[i] == slot.strings);
+        let Some(first) = all.next() else { continue };
+        out.push(FormatMatch {
+            path,
+            format: formats.defs[first].clone(),
+            values: slot.strings,
+            also: all.map(|i| formats.defs[i].name.clone()).collect(),
+        });
+    }
+    out
+}
+
+/// Path → format, as recorded in artifact headers for code generation.
+pub fn rules(found: &[FormatMatch]) -> BTreeMap<String, Format> {
+    found.iter().map(|m| (m.path.to_string(), m.format.clone())).collect()
+}
+
+/// Set `format` on the schema of every slot with a declared format.
+pub fn annotate(schema: &mut Value, rules: &BTreeMap<String, Format>) {
+    for (path, f) in rules {
+        let Ok(path) = path.parse::<JPath>() else { continue };
+        if let Some(node) = crate::norm_ir::schema_at(schema, path.segs()) {
+            node["format"] = Value::from(f.name.as_str());
+        }
+    }
+}
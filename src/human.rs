@@ -0,0 +1,76 @@
+//! Formatting of large counts for people (`--number-format`), shared by the
+//! log and the report: `1.2B docs`, `3.4 GB`, `12.3k docs/s`.
+
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NumberFormat {
+    /// Three significant digits with a suffix: `950`, `12.3k`, `1.2B`.
+    #[default]
+    Short,
+    /// Every digit, in groups of three: `1,234,567`.
+    Grouped,
+    /// Every digit, as is.
+    Raw,
+}
+
+const COUNT_UNITS: &[&str] = &["", "k", "M", "B", "T"];
+const BYTE_UNITS: &[&str] = &["B", "kB", "MB", "GB", "TB", "PB"];
+
+/// `n` scaled by 1000 until it fits three digits, with its unit.
+fn scaled(n: u64, units: &[&'static str]) -> (String, &'static str) {
+    if n < 1000 {
+        return (n.to_string(), units[0]);
+    }
+    let mut x = n as f64;
+    let mut i = 0;
+    while i + 1 < units.len() && x >= 999.95 {
+        x /= 1000.0;
+        i += 1;
+    }
+    let digits = if x < 99.95 { format!("{x:.1}") } else { format!("{x:.0}") };
+    (digits, units[i])
+}
+
+fn grouped(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+impl NumberFormat {
+    pub fn count(self, n: u64) -> String {
+        match self {
+            NumberFormat::Short => {
+                let (digits, unit) = scaled(n, COUNT_UNITS);
+                format!("{digits}{unit}")
+            }
+            NumberFormat::Grouped => grouped(n),
+            NumberFormat::Raw => n.to_string(),
+        }
+    }
+
+    /// Decimal units (1 kB = 1000 B).
+    pub fn bytes(self, n: u64) -> String {
+        match self {
+            NumberFormat::Short => {
+                let (digits, unit) = scaled(n, BYTE_UNITS);
+                format!("{digits} {unit}")
+            }
+            NumberFormat::Grouped => format!("{} B", grouped(n)),
+            NumberFormat::Raw => format!("{n} B"),
+        }
+    }
+
+    /// `n` per second over `elapsed` (the caller adds the unit, e.g. `12.3k docs/s`).
+    pub fn rate(self, n: u64, elapsed: Duration) -> String {
+        let per_sec = n as f64 / elapsed.as_secs_f64().max(1e-3);
+        self.count(per_sec.round() as u64)
+    }
+}
@@ -13,14 +13,22 @@ pub mod str;
 pub mod num;
 pub mod obj;
 pub mod arr;
+pub mod stream;
+pub mod caps;
+pub mod unicode;
+mod unicode_tables;
 
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 use ordered_float::OrderedFloat;
 
-pub use str::StrC;
-pub use num::NumC;
+pub use str::{StrC, StrFormat};
+pub use num::{NumC, Step};
 pub use obj::{ObjC, FieldC};
 pub use arr::ArrC;
+pub use stream::{observe_reader_with, observe_str, observe_str_with};
 
 // ------------------------------- Policy ---------------------------------- //
 
@@ -30,12 +38,36 @@ pub const STRING_ENUM_MAX: usize = 8;                    // small, human-ish enu
 pub const STRING_ENUM_MAX_LEN: usize = 16;               // max literal length for enum
 pub const KEEP_NUM_ATOMS_OUTSIDE_INTERVAL: bool = false; // simplest: widen
 
+// objects read as maps (`BTreeMap<String, V>`) instead of records
+pub const MAP_MIN_KEYS: usize = 64;            // this many keys of one value kind
+pub const MAP_MIN_ID_KEYS: usize = 8;          // …or this many when every key is id-like
+pub const MAP_MAX_KEY_PRESENCE: f64 = 0.5;     // mean share of objects holding each key
+
+// objects of clearly different shapes read as a union of records
+pub const SHAPE_MIN_SIMILARITY: f64 = 0.5;     // key-set Jaccard index to share a record
+
+// numbers that are all multiples of a step (`multipleOf`)
+pub const STEP_MIN_VALUES: usize = 5;          // distinct values before a step counts
+pub const STEP_MAX_DECIMALS: u32 = 6;          // decimals a float may have and keep one
+
 // literal caps to avoid ballooning before normalize prunes
 pub const MAX_STR_LITS: usize = 64;
 pub const MAX_NUM_LITS: usize = 64;
+/// Longer arrays are lists: no per-position evidence (`max_tuple_arity` in `--policy`).
+pub const MAX_TUPLE_ARITY: usize = 256;
+/// More distinct keys make a map, whatever they look like (`max_object_fields` in `--policy`).
+pub const MAX_OBJECT_FIELDS: usize = 1024;
 
-/// Feature flag: disable regex synthesis entirely (for testing memory/shape).
-/// When false, no patterns are synthesized; non-enum, non-URI strings become plain strings.
+/// Default `--max-depth`. serde_json refuses documents nested deeper than 128,
+/// but `--json-strings` documents nest inside strings, each parsed anew.
+pub const MAX_DEPTH: u32 = 64;
+
+// example values kept per slot however many literals the caps keep (`gen --examples`)
+pub const MAX_EXAMPLES: usize = 3;
+pub const MAX_EXAMPLE_LEN: usize = 80;         // longer strings aren't examples
+
+/// Default `--grex`: when false, no patterns are synthesized; non-enum,
+/// non-URI strings become plain strings (see [`str::Grex`]).
 pub const ENABLE_GREX: bool = false;
 
 /// Feature flag: enable tiny, human-ish string enums inferred from literals.
@@ -51,10 +83,88 @@ pub const CHECK_INT_BOUNDS: bool = false;
 /// f64 uses tolerant compare
 pub const CHECK_NUM_BOUNDS: bool = false;
 
+/// Feature flag: a slot holding numbers and strings that all spell numbers (`4`, `"4.5"`)
+/// normalizes to one `StringOrNumber` (a `NumberLike` in Rust) instead of a two-arm union.
+/// `--coerce-numeric-strings` still reads such strings as plain numbers.
+pub const ENABLE_STRING_OR_NUMBER: bool = true;
+
+/// Feature flag: a slot holding integers and floats normalizes to one `Number` (`f64`),
+/// and a union's `Integer` arm folds into its `Number` arm. When false the slot reads as
+/// `oneOf [integer, number]`, an untagged enum that keeps integers exact in Rust;
+/// `--exact-ints` turns it off for one run (see [`set_collapse_ints`]).
+pub const COLLAPSE_INT_INTO_NUMBER: bool = true;
+
+static COLLAPSE_INTS: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+
+/// Set whether integer and number arms fold into one `Number` in this process
+/// (first call wins); [`COLLAPSE_INT_INTO_NUMBER`] until then.
+pub fn set_collapse_ints(on: bool) {
+    let _ = COLLAPSE_INTS.set(on);
+}
+
+/// Whether integer and number arms fold into one `Number` in this process.
+pub fn collapse_ints() -> bool {
+    COLLAPSE_INTS.get().copied().unwrap_or(COLLAPSE_INT_INTO_NUMBER)
+}
+
+/// Every policy knob, one `NAME=value` per line. Hashed into generation headers:
+/// equal fingerprints mean equal evidence normalizes (and generates) identically.
+/// Add new policy constants here; defaults of runtime flags (`--grex` and its
+/// limits) are not policy, the flags are recorded in the generation options.
+/// A `--policy` file installed with [`caps::install`] is part of it too.
+pub fn policy_fingerprint() -> String {
+    let mut lines = vec![
+        format!("STRING_ENUM_MAX={STRING_ENUM_MAX}"),
+        format!("STRING_ENUM_MAX_LEN={STRING_ENUM_MAX_LEN}"),
+        format!("KEEP_NUM_ATOMS_OUTSIDE_INTERVAL={KEEP_NUM_ATOMS_OUTSIDE_INTERVAL}"),
+        format!("MAX_STR_LITS={MAX_STR_LITS}"),
+        format!("MAX_NUM_LITS={MAX_NUM_LITS}"),
+        format!("MAX_TUPLE_ARITY={MAX_TUPLE_ARITY}"),
+        format!("MAX_OBJECT_FIELDS={MAX_OBJECT_FIELDS}"),
+        format!("ENABLE_STRING_ENUMS={ENABLE_STRING_ENUMS}"),
+        format!("CHECK_INT_BOUNDS={CHECK_INT_BOUNDS}"),
+        format!("CHECK_NUM_BOUNDS={CHECK_NUM_BOUNDS}"),
+        format!("ENABLE_STRING_OR_NUMBER={ENABLE_STRING_OR_NUMBER}"),
+        format!("COLLAPSE_INT_INTO_NUMBER={COLLAPSE_INT_INTO_NUMBER}"),
+        format!("GREX_MAX_DISTINCT={}", str::GREX_MAX_DISTINCT),
+        format!("GREX_MAX_INPUT_CHARS={}", str::GREX_MAX_INPUT_CHARS),
+        format!("BLOB_MIN_LEN={}", str::BLOB_MIN_LEN),
+        format!("MAP_MIN_KEYS={MAP_MIN_KEYS}"),
+        format!("MAP_MIN_ID_KEYS={MAP_MIN_ID_KEYS}"),
+        format!("MAP_MAX_KEY_PRESENCE={MAP_MAX_KEY_PRESENCE}"),
+        format!("TAG_FIELDS={}", obj::TAG_FIELDS.join(",")),
+        format!("SHAPE_MIN_SIMILARITY={SHAPE_MIN_SIMILARITY}"),
+        format!("MAX_SHAPES={}", obj::MAX_SHAPES),
+        format!("STEP_MIN_VALUES={STEP_MIN_VALUES}"),
+        format!("STEP_MAX_DECIMALS={STEP_MAX_DECIMALS}"),
+        format!("MAX_EXAMPLES={MAX_EXAMPLES}"),
+        format!("MAX_EXAMPLE_LEN={MAX_EXAMPLE_LEN}"),
+    ];
+    // only when installed, so fingerprints without --policy stay as they were
+    lines.extend(caps::fingerprint());
+    lines.join("\n")
+}
+
+/// The examples of two joined slots: the [`MAX_EXAMPLES`] distinct values
+/// hashing lowest (by `key`), so the same ones are kept whatever order
+/// evidence is joined in.
+pub(crate) fn join_examples<T: Clone + Ord>(a: &[T], b: &[T], key: impl Fn(&T) -> u64) -> Vec<T> {
+    let mut out: Vec<T> = a.iter().chain(b).cloned().collect();
+    out.sort_by(|x, y| key(x).cmp(&key(y)).then_with(|| x.cmp(y)));
+    out.dedup();
+    out.truncate(MAX_EXAMPLES);
+    out
+}
+
+/// FNV-1a of `bytes`, for [`join_examples`].
+pub(crate) fn example_key(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xCBF2_9CE4_8422_2325, |h, &b| (h ^ b as u64).wrapping_mul(0x0000_0100_0000_01B3))
+}
+
 
 // ------------------------------ State (CNF) ------------------------------- //
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct U {
     pub nullable: bool,
     pub has_bool: bool,
@@ -62,12 +172,45 @@ pub struct U {
     pub str_: Option<StrC>,
     pub arr: Option<ArrC>,
     pub obj: Option<ObjC>,
+    #[serde(default, skip_serializing_if = "ArmCounts::is_empty")]
+    pub counts: ArmCounts,
+    /// Some array or object here nested past the depth limit (see
+    /// [`ObserveOpts::max_depth`]): the slot holds any JSON.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub opaque: bool,
+}
+
+/// How many observed values fell in each non-null arm of a [`U`]; zero in
+/// older evidence (and for arms only ever joined in from it).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArmCounts {
+    pub bool: u64,
+    pub num: u64,
+    pub str_: u64,
+    pub arr: u64,
+    pub obj: u64,
+}
+
+impl ArmCounts {
+    pub fn is_empty(&self) -> bool {
+        *self == ArmCounts::default()
+    }
+
+    fn join(a: Self, b: Self) -> Self {
+        ArmCounts {
+            bool: a.bool + b.bool,
+            num: a.num + b.num,
+            str_: a.str_ + b.str_,
+            arr: a.arr + b.arr,
+            obj: a.obj + b.obj,
+        }
+    }
 }
 
 impl U {
     pub fn empty() -> Self { Self::default() }
     pub fn is_bottom(&self) -> bool {
-        !self.nullable && !self.has_bool
+        !self.nullable && !self.has_bool && !self.opaque
             && self.num.is_none() && self.str_.is_none()
             && self.arr.is_none() && self.obj.is_none()
     }
@@ -78,53 +221,186 @@ impl U {
             && self.str_.is_none()
             && self.arr.is_none()
             && self.obj.is_none()
+            && !self.opaque
+    }
+    /// An array or object past the depth limit, not looked into.
+    pub(crate) fn opaque() -> Self {
+        U { opaque: true, ..U::default() }
+    }
+    /// This one observed value, with its arm counted.
+    pub(crate) fn counted(mut self) -> Self {
+        self.counts = ArmCounts {
+            bool: u64::from(self.has_bool),
+            num: u64::from(self.num.is_some()),
+            str_: u64::from(self.str_.is_some()),
+            arr: u64::from(self.arr.is_some()),
+            obj: u64::from(self.obj.is_some()),
+        };
+        self
+    }
+
+    /// Whether this one observed value counts as null in presence statistics.
+    pub(crate) fn observed_null(&self, opts: ObserveOpts) -> bool {
+        self.is_exact_null() || (opts.empty_as_null && self.str_.as_ref().is_some_and(|s| s.empties > 0))
     }
 }
 
 // ------------------------------ Observe ---------------------------------- //
 
+/// Opt-in observation modes; the default observes values as they are.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ObserveOpts {
+    /// Strings holding a JSON object or array are parsed and their documents
+    /// observed too, recursively (`--json-strings`).
+    pub json_strings: bool,
+    /// Strings spelling a JSON number (`"12"`, `"-4.5e3"`) are observed as that
+    /// number too (`--coerce-numeric-strings`).
+    pub numeric_strings: bool,
+    /// Empty strings don't count as present, non-null values in presence
+    /// statistics, so fields holding them aren't required (`--empty-strings-as-null`).
+    pub empty_as_null: bool,
+    /// Keys and string literals are compared in NFC without invisible characters;
+    /// a field keeps the keys it was spelled with otherwise (`--normalize-unicode`,
+    /// see [`unicode`]).
+    pub normalize_unicode: bool,
+    /// Levels of arrays and objects looked into from here (the document root,
+    /// or a string's document); one nested deeper is observed as any JSON
+    /// ([`U::opaque`]) instead (`--max-depth`). `None`: no limit.
+    pub max_depth: Option<u32>,
+}
+
+impl ObserveOpts {
+    /// The options for the values inside an array or object.
+    fn below(self) -> Self {
+        ObserveOpts { max_depth: self.max_depth.map(|d| d.saturating_sub(1)), ..self }
+    }
+
+    /// Whether an array or object observed with these is past the depth limit.
+    fn too_deep(self) -> bool {
+        self.max_depth == Some(0)
+    }
+}
+
 pub fn observe_value(v: &Value) -> U {
-    match v {
+    observe_value_with(v, ObserveOpts::default())
+}
+
+pub fn observe_value_with(v: &Value, opts: ObserveOpts) -> U {
+    let u = match v {
         Value::Null => U { nullable: true, ..U::default() },
         Value::Bool(_) => U { has_bool: true, ..U::default() },
         Value::Number(n) => {
-            let mut num = NumC::default();
             if let Some(i) = n.as_i64() {
-                let f = OrderedFloat(i as f64);
-                num.saw_int = true;
-                num.lits_f64.insert(f);
-                num.min_f64 = f;
-                num.max_f64 = f;
+                observe_number(NumKind::Int(i))
             } else if let Some(u) = n.as_u64() {
-                let f = OrderedFloat(u as f64);
-                num.saw_uint = true;
-                num.lits_f64.insert(f);
-                num.min_f64 = f;
-                num.max_f64 = f;
+                observe_number(NumKind::Uint(u))
             } else if let Some(f) = n.as_f64() {
-                let f = OrderedFloat(f);
-                num.saw_float = true;
-                num.lits_f64.insert(f);
-                num.min_f64 = f;
-                num.max_f64 = f;
+                observe_number(NumKind::Float(f))
+            } else {
+                U { num: Some(NumC::default()), ..U::default() }
             }
-            U { num: Some(num), ..U::default() }
         }
-        Value::String(s) => {
-            let mut str_c = StrC::default();
-            str_c.lits.insert(s.clone());
-            // str_c.lcp = Some(s.clone());
-            str_c.is_uri = str::looks_like_uri(s);
-            U { str_: Some(str_c), ..U::default() }
+        Value::String(s) => observe_string(s.clone(), opts),
+        Value::Array(_) | Value::Object(_) if opts.too_deep() => return U::opaque(),
+        Value::Array(xs) => observe_array(xs, opts.below()),
+        Value::Object(m) => observe_object(m, opts.below()),
+    };
+    u.counted()
+}
+
+/// How a JSON number was written: fits `i64`, only fits `u64`, or neither.
+#[derive(Clone, Copy)]
+enum NumKind {
+    Int(i64),
+    Uint(u64),
+    Float(f64),
+}
+
+fn observe_number(kind: NumKind) -> U {
+    let mut num = NumC::default();
+    let f = match kind {
+        NumKind::Int(i) => {
+            num.saw_int = true;
+            num.int_range = Some((i.into(), i.into()));
+            num.step = Some(Step::of_int(i.into()));
+            i as f64
         }
-        Value::Array(xs) => observe_array(xs),
-        Value::Object(m) => observe_object(m),
+        NumKind::Uint(u) => {
+            num.saw_uint = true;
+            num.int_range = Some((u.into(), u.into()));
+            num.step = Some(Step::of_int(u.into()));
+            u as f64
+        }
+        NumKind::Float(f) => {
+            num.saw_float = true;
+            num.step = Step::of_float(f);
+            f
+        }
+    };
+    let f = OrderedFloat(f);
+    num.lits_f64.insert(f);
+    num.examples = vec![f];
+    num.min_f64 = f;
+    num.max_f64 = f;
+    U { num: Some(num), ..U::default() }
+}
+
+fn observe_string(s: String, opts: ObserveOpts) -> U {
+    // str_c.lcp = Some(s.clone());
+    let is_uri = str::looks_like_uri(&s);
+    let formats = StrFormat::of(&s);
+    let json = opts.json_strings.then(|| embedded_json(&s, opts)).flatten().map(Box::new);
+    let numeric = opts.numeric_strings.then(|| numeric_string(&s)).flatten();
+    let spells_numbers = numeric.is_some()
+        || (s.starts_with(|c: char| c == '-' || c.is_ascii_digit()) && numeric_string(&s).is_some());
+    let empties = u64::from(s.is_empty());
+    let len = s.chars().count() as u64;
+    let len_range = Some((len, len));
+    let examples = if len as usize <= MAX_EXAMPLE_LEN { vec![s.clone()] } else { Vec::new() };
+    // the literal by identity; lengths and formats are of the string as written
+    let lit = if opts.normalize_unicode { unicode::normalize(&s).into_owned() } else { s };
+    let lit_counts = [(lit.clone(), 1)].into();
+    U {
+        str_: Some(StrC { lits: [lit].into(), lit_counts, is_uri, formats, json, numeric, spells_numbers, empties, len_range, examples, ..StrC::default() }),
+        ..U::default()
+    }
+}
+
+/// Evidence for the number `s` spells in JSON syntax, without surrounding spaces.
+/// Leading zeros (`"007"`, zip codes and the like) don't count, and neither do
+/// integers too big for `u64`: as floats they would lose digits.
+fn numeric_string(s: &str) -> Option<NumC> {
+    if s.trim() != s {
+        return None;
+    }
+    let n: serde_json::Number = serde_json::from_str(s).ok()?;
+    let integral = !s.contains(['.', 'e', 'E']);
+    let u = if let Some(i) = n.as_i64() {
+        observe_number(NumKind::Int(i))
+    } else if let Some(u) = n.as_u64() {
+        observe_number(NumKind::Uint(u))
+    } else if !integral {
+        observe_number(NumKind::Float(n.as_f64()?))
+    } else {
+        return None;
+    };
+    u.num
+}
+
+/// Evidence for the document `s` holds, if it is a JSON object or array
+/// (bare scalars like `"12"` or `"true"` stay strings).
+fn embedded_json(s: &str, opts: ObserveOpts) -> Option<U> {
+    let t = s.trim_start();
+    if !(t.starts_with('{') || t.starts_with('[')) {
+        return None;
     }
+    observe_str_with(s, opts).ok()
 }
 
 // const TUPLEIZE_SMALL_HOMOGENEOUS_LIMIT: usize = 2;
 
-fn observe_array(xs: &Vec<Value>) -> U {
+/// `opts`: of the elements.
+fn observe_array(xs: &Vec<Value>, opts: ObserveOpts) -> U {
     let mut arr = ArrC::default();
     arr.samples = 1;
     let len = xs.len() as u32;
@@ -133,44 +409,59 @@ fn observe_array(xs: &Vec<Value>) -> U {
 
     // list evidence
     let mut item = U::empty();
-    for el in xs { item = U::join(&item, &observe_value(el)); }
+    for el in xs { item = U::join_inner(&item, &observe_value_with(el, opts)); }
     arr.item = Box::new(item);
 
-    // tuple evidence + counts
+    // tuple evidence + counts, unless too long to be a tuple
+    if xs.len() > caps::max_tuple_arity() {
+        return U { arr: Some(arr), ..U::default() };
+    }
     for (i, el) in xs.iter().enumerate() {
         if arr.cols.len() <= i {
             arr.cols.resize_with(i + 1, U::empty);
             arr.present.resize(i + 1, 0);
             arr.non_null.resize(i + 1, 0);
         }
-        arr.cols[i] = U::join(&arr.cols[i], &observe_value(el));
+        let col = observe_value_with(el, opts);
         arr.present[i] += 1;
-        if !matches!(el, Value::Null) { arr.non_null[i] += 1; }
+        if !col.observed_null(opts) { arr.non_null[i] += 1; }
+        arr.cols[i] = U::join_inner(&arr.cols[i], &col);
     }
 
     U { arr: Some(arr), ..U::default() }
 }
 
-fn observe_object(map: &Map<String, Value>) -> U {
+/// `opts`: of the values.
+fn observe_object(map: &Map<String, Value>, opts: ObserveOpts) -> U {
     let mut obj = ObjC::default();
     obj.seen_objects = 1;
     for (k, v) in map {
-        let ty = observe_value(v);
-        let non_null = !matches!(v, Value::Null);
-        obj.fields.insert(k.clone(), FieldC {
-            ty,
-            present_in: 1,
-            non_null_in: if non_null { 1 } else { 0 },
-        });
+        let ty = observe_value_with(v, opts);
+        let non_null = !ty.observed_null(opts);
+        obj.observe_field(k.clone(), ty, non_null, opts);
     }
+    obj.note_shape();
+    obj.note_tags();
     U { obj: Some(obj), ..U::default() }
 }
 
 // -------------------------------- Join (⊔) -------------------------------- //
 
 impl U {
+    /// Join the evidence of whole documents, applying each slot's literal caps.
     pub fn join(a: &Self, b: &Self) -> Self {
+        let mut out = Self::join_inner(a, b);
+        caps::apply(&mut out);
+        out
+    }
+
+    /// Join evidence below the document root without knowing where (array
+    /// items while observing one document, the values of a map): literals are
+    /// capped at the loosest cap of any slot, leaving each slot's own cap to
+    /// the join of whole documents.
+    pub fn join_inner(a: &Self, b: &Self) -> Self {
         let mut out = U::empty();
+        let caps = caps::loosest();
 
         out.nullable = a.nullable || b.nullable;
         out.has_bool = a.has_bool || b.has_bool;
@@ -178,13 +469,13 @@ impl U {
         out.num = match (&a.num, &b.num) {
             (None, None) => None,
             (Some(x), None) | (None, Some(x)) => Some(x.clone()),
-            (Some(x), Some(y)) => Some(NumC::join(x, y)),
+            (Some(x), Some(y)) => Some(NumC::join_capped(x, y, caps.numbers)),
         };
 
         out.str_ = match (&a.str_, &b.str_) {
             (None, None) => None,
             (Some(x), None) | (None, Some(x)) => Some(x.clone()),
-            (Some(x), Some(y)) => Some(StrC::join(x, y)),
+            (Some(x), Some(y)) => Some(StrC::join(x, y, caps)),
         };
 
         out.arr = match (&a.arr, &b.arr) {
@@ -199,6 +490,8 @@ impl U {
             (Some(x), Some(y)) => Some(ObjC::join(x, y)),
         };
 
+        out.counts = ArmCounts::join(a.counts, b.counts);
+        out.opaque = a.opaque || b.opaque;
         out
     }
 }
@@ -277,84 +570,6 @@ impl U {
 //     }
 // }
 
-/// New, conservative normalizer (v2): decide tuple vs list *before* recursing.
-/// Semantics are identical to v1, but it avoids deep work on columns that
-/// will be discarded when the array is a list.
-///
-/// Keep v1 (`U::normalize_mut`) for comparison.
-pub fn normalize2_mut(u: &mut U) {
-    // ---- Numbers: same policy as v1 ----
-    if let Some(num) = &mut u.num {
-        if num.min_f64.is_finite() && num.max_f64.is_finite() {
-            num.lits_f64 = num
-                .lits_f64
-                .iter()
-                .cloned()
-                .filter(|x| {
-                    !(num.min_f64 <= *x && *x <= num.max_f64) && KEEP_NUM_ATOMS_OUTSIDE_INTERVAL
-                })
-                .collect();
-        }
-    }
-
-    // ---- Strings: tiny enum (flagged) else pattern (flagged) / URI / plain ----
-    if let Some(str_c) = &mut u.str_ {
-        let tiny = crate::inference::ENABLE_STRING_ENUMS
-            && str_c.lits.len() <= STRING_ENUM_MAX
-            && str_c
-                .lits
-                .iter()
-                .all(|s| s.len() <= STRING_ENUM_MAX_LEN && crate::inference::str::looks_humanish(s));
-
-        if !tiny {
-            if !str_c.is_uri {
-                if crate::inference::ENABLE_GREX {
-                    let key_now = crate::inference::str::grex_cache_key(&str_c.lits);
-                    if str_c.grex_cache_key != Some(key_now) {
-                        str_c.pattern_synth = crate::inference::str::synth_regex_with_grex(&str_c.lits);
-                        str_c.grex_cache_key = Some(key_now);
-                    }
-                } else {
-                    str_c.pattern_synth = None;
-                }
-                str_c.lits.clear(); // collapse atoms regardless
-            } else {
-                str_c.lits.clear();
-            }
-        }
-    }
-
-    // ---- Arrays: DECIDE FIRST, then recurse accordingly ----
-    if let Some(arr) = &mut u.arr {
-        // Decide tuple vs list using *only* counts/lengths (cheap).
-        let is_tuple = decide_tuple(arr);
-
-        // Always normalize the pooled list hypothesis.
-        normalize2_mut(&mut arr.item);
-
-        if !is_tuple {
-            // Collapse early: skip normalizing per-position children entirely.
-            arr.cols.clear();
-            arr.present.clear();
-            arr.non_null.clear();
-        } else {
-            for c in &mut arr.cols {
-                normalize2_mut(c);
-            }
-            // keep len_min/len_max as observed; lower/codegen enforce tuple shape
-        }
-    }
-
-    // ---- Objects: recurse into fields (same as v1) ----
-    if let Some(obj) = &mut u.obj {
-        for f in obj.fields.values_mut() {
-            normalize2_mut(&mut f.ty);
-        }
-    }
-    // Union flattening not needed here; done in lowering.
-}
-
-
 /// Return true if we have *proof* this is a tuple:
 ///  - exact arity (all arrays same length), or
 ///  - at least one position is an exact-null pad across all samples.
@@ -381,6 +596,125 @@ pub fn decide_tuple(arr: &ArrC) -> bool {
     false
 }
 
+/// Evidence for the values of `obj` if it reads as a map from ids to values of
+/// one kind rather than as a record:
+///  - at least [`MAP_MIN_ID_KEYS`] keys, all id-like (`"1042"`, `"9f3c-…"`), or
+///  - at least [`MAP_MIN_KEYS`] keys that come and go between objects (a wide
+///    record repeats its keys in every object),
+///
+/// and every value, joined, has at most one non-null kind. Past
+/// [`caps::max_object_fields`] keys it is a map whatever the keys and values
+/// (and its fields were folded into [`ObjC::wide`]).
+pub fn decide_map(obj: &ObjC) -> Option<U> {
+    if let Some(wide) = &obj.wide {
+        return Some(wide.value.clone());
+    }
+    let n = obj.fields.len();
+    let wide = n > caps::max_object_fields();
+    if n < MAP_MIN_ID_KEYS {
+        return None;
+    }
+    if !wide && !obj.fields.keys().all(|k| looks_like_id(k)) {
+        if n < MAP_MIN_KEYS {
+            return None;
+        }
+        let present: u64 = obj.fields.values().map(|f| f.present_in).sum();
+        let presence = present as f64 / (n as f64 * obj.seen_objects as f64);
+        if obj.seen_objects >= 2 && presence > MAP_MAX_KEY_PRESENCE {
+            return None;
+        }
+    }
+    let value = obj.fields.values().fold(U::empty(), |acc, f| U::join_inner(&acc, &f.ty));
+    let kinds = [value.has_bool, value.num.is_some(), value.str_.is_some(), value.arr.is_some(), value.obj.is_some()];
+    (wide || kinds.into_iter().filter(|k| *k).count() <= 1).then_some(value)
+}
+
+/// The tag field and its per-value evidence if `obj` reads as a tagged union:
+/// one of [`obj::TAG_FIELDS`] held a short string in every object, with two or
+/// more values, and some other field appears with one value but never with another.
+pub fn decide_tagged(obj: &ObjC) -> Option<(&'static str, &BTreeMap<String, ObjC>)> {
+    obj::TAG_FIELDS.into_iter().find_map(|tag| {
+        let values = obj.variants.get(tag)?.as_ref().filter(|v| v.len() >= 2)?;
+        let shapes_differ = obj.fields.keys().filter(|k| *k != tag).any(|k| {
+            let with = values.values().filter(|v| v.fields.contains_key(k)).count();
+            with > 0 && with < values.len()
+        });
+        shapes_differ.then_some((tag, values))
+    })
+}
+
+/// Per-record evidence if the objects in `obj` fall into clusters of clearly
+/// different shapes. Key sets join the first cluster (most common first) they
+/// share at least [`SHAPE_MIN_SIMILARITY`] of their keys with (Jaccard index);
+/// it takes two or more clusters, each with a key every one of its objects had
+/// and no other cluster's did, so any object fits exactly one record.
+pub fn decide_shapes(obj: &ObjC) -> Option<Vec<ObjC>> {
+    struct Cluster<'a> {
+        keys: BTreeSet<&'a String>,
+        common: BTreeSet<&'a String>,
+        shapes: Vec<&'a obj::KeySet>,
+    }
+    let mut shapes: Vec<&obj::KeySet> = obj.shapes.as_ref()?.iter().collect();
+    if shapes.len() < 2 {
+        return None;
+    }
+    shapes.sort_by_key(|s| std::cmp::Reverse(s.count));
+
+    let mut clusters: Vec<Cluster> = Vec::new();
+    for shape in shapes {
+        let keys: BTreeSet<&String> = shape.keys.iter().collect();
+        let similar = |c: &Cluster| {
+            let union = c.keys.union(&keys).count();
+            union == 0 || c.keys.intersection(&keys).count() as f64 / union as f64 >= SHAPE_MIN_SIMILARITY
+        };
+        match clusters.iter_mut().find(|c| similar(c)) {
+            Some(c) => {
+                c.common = &c.common & &keys;
+                c.keys.extend(keys);
+                c.shapes.push(shape);
+            }
+            None => clusters.push(Cluster { common: keys.clone(), keys, shapes: vec![shape] }),
+        }
+    }
+    if clusters.len() < 2 {
+        return None;
+    }
+    let distinct = |i: usize| {
+        clusters[i].common.iter().any(|k| clusters.iter().enumerate().all(|(j, c)| j == i || !c.keys.contains(k)))
+    };
+    if !(0..clusters.len()).all(distinct) {
+        return None;
+    }
+
+    // field evidence is per slot, not per shape: each record gets all of a
+    // field's values, required only where its objects always had it, never null
+    let records = clusters
+        .iter()
+        .map(|c| {
+            let fields = c.keys.iter().map(|&k| {
+                let f = &obj.fields[k];
+                let present_in: u64 = c.shapes.iter().filter(|s| s.keys.contains(k)).map(|s| s.count).sum();
+                let non_null_in = if f.non_null_in == f.present_in { present_in } else { 0 };
+                (k.clone(), FieldC { ty: f.ty.clone(), present_in, non_null_in, aliases: f.aliases.clone() })
+            });
+            ObjC {
+                fields: fields.collect(),
+                seen_objects: c.shapes.iter().map(|s| s.count).sum(),
+                ..ObjC::default()
+            }
+        })
+        .collect();
+    Some(records)
+}
+
+/// Keys spelled like ids: decimal numbers, or hashes / uuids (8+ hex digits and
+/// separators, some of them decimal) — not `c01`-style field names.
+fn looks_like_id(k: &str) -> bool {
+    let digits = k.bytes().filter(u8::is_ascii_digit).count();
+    (digits > 0 && digits == k.len())
+        || (k.len() >= 8 && digits > 0 && k.bytes().all(|b| b.is_ascii_hexdigit() || matches!(b, b'-' | b'_' | b':' | b'.')))
+}
+
 // ------------------------------- Utilities -------------------------------- //
 
 
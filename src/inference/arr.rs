@@ -1,6 +1,8 @@
+use serde::{Deserialize, Serialize};
+
 use super::U;
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct ArrC {
     pub len_min: u32,
     pub len_max: u32,
@@ -17,13 +19,17 @@ impl ArrC {
         out.len_min = a.len_min.min(b.len_min);
         out.len_max = a.len_max.max(b.len_max);
         out.samples = a.samples + b.samples;
-        out.item = Box::new(U::join(&a.item, &b.item));
+        out.item = Box::new(U::join_inner(&a.item, &b.item));
+        // once an array too long to be a tuple was seen, the slot is a list
+        if out.len_max as usize > super::caps::max_tuple_arity() {
+            return out;
+        }
     
         let n = a.cols.len().max(b.cols.len());
         out.cols = (0..n).map(|i| {
             let ai = a.cols.get(i).cloned().unwrap_or_else(missing_nullable);
             let bi = b.cols.get(i).cloned().unwrap_or_else(missing_nullable);
-            U::join(&ai, &bi)
+            U::join_inner(&ai, &bi)
         }).collect();
     
         out.present = (0..n).map(|i| {
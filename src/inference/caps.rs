@@ -0,0 +1,290 @@
+//! Literal caps per slot (`literal_caps` in the `--policy` file).
+//!
+//! A slot keeps its distinct strings and numbers (for enums and patterns) until
+//! it holds more than a cap, then drops them for good. [`super::MAX_STR_LITS`]
+//! and [`super::MAX_NUM_LITS`] are the caps everywhere by default; the policy
+//! file overrides them by depth and by path:
+//!
+//! ```json
+//! { "literal_caps": [
+//!     { "path": "$.events[*].id", "strings": 0 },
+//!     { "min_depth": 4, "strings": 8, "numbers": 8 },
+//!     { "max_depth": 1, "strings": 256 }
+//! ] }
+//! ```
+//!
+//! A rule applies to the slots at or below its `path` (where `[*]` also
+//! matches tuple positions) whose depth (path segments below `$`) is within
+//! `min_depth..=max_depth`. For each kind, the first applying rule that caps
+//! it wins. Every join keeps at most the loosest cap; joins of whole documents
+//! then apply each slot's own.
+//!
+//! The same file sets `max_tuple_arity` (default [`super::MAX_TUPLE_ARITY`]): an
+//! array longer than that is a list, so no per-position evidence is kept for it,
+//! nor for any array of its slot once one such array was seen. And
+//! `max_object_fields` (default [`super::MAX_OBJECT_FIELDS`]): a slot whose
+//! objects held more distinct keys than that is a map, whatever its keys and
+//! values look like; its fields are folded into one key and one value type
+//! (see [`super::obj::Wide`]), so its evidence stops growing with the keys.
+
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+
+use super::{ObjC, U};
+use crate::jpath::{JPath, Seg};
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawPolicy {
+    #[serde(default)]
+    literal_caps: Vec<RawRule>,
+    max_tuple_arity: Option<usize>,
+    max_object_fields: Option<usize>,
+}
+
+/// A `--policy` file, read.
+#[derive(Debug, Clone)]
+pub struct Policy {
+    pub rules: Vec<Rule>,
+    pub max_tuple_arity: usize,
+    pub max_object_fields: usize,
+}
+
+/// A `literal_caps` entry as written.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRule {
+    path: Option<String>,
+    min_depth: Option<usize>,
+    max_depth: Option<usize>,
+    /// Distinct strings a slot keeps.
+    strings: Option<usize>,
+    /// Distinct numbers a slot keeps.
+    numbers: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub path: JPath,
+    pub min_depth: usize,
+    pub max_depth: usize,
+    pub caps: (Option<usize>, Option<usize>),
+}
+
+impl Rule {
+    fn applies(&self, at: &[Seg]) -> bool {
+        let pattern = self.path.segs();
+        (self.min_depth..=self.max_depth).contains(&at.len())
+            && pattern.len() <= at.len()
+            && pattern.iter().zip(at).all(|(p, s)| p == s || (*p == Seg::Items && matches!(s, Seg::Index(_))))
+    }
+}
+
+/// Distinct strings and numbers a slot keeps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Caps {
+    pub strings: usize,
+    pub numbers: usize,
+}
+
+impl Caps {
+    pub const DEFAULT: Caps = Caps { strings: super::MAX_STR_LITS, numbers: super::MAX_NUM_LITS };
+}
+
+struct Installed {
+    rules: Vec<Rule>,
+    loosest: Caps,
+    max_tuple_arity: usize,
+    max_object_fields: usize,
+}
+
+static INSTALLED: OnceLock<Installed> = OnceLock::new();
+
+/// Read a policy file.
+pub fn load(path: &std::path::Path) -> Result<Policy, String> {
+    let src = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let policy: RawPolicy = serde_json::from_str(&src).map_err(|e| format!("{}: {e}", path.display()))?;
+    let rules = policy
+        .literal_caps
+        .into_iter()
+        .map(|r| {
+            let path = match r.path {
+                Some(p) => p.parse::<JPath>()?,
+                None => JPath::root(),
+            };
+            Ok(Rule {
+                path,
+                min_depth: r.min_depth.unwrap_or(0),
+                max_depth: r.max_depth.unwrap_or(usize::MAX),
+                caps: (r.strings, r.numbers),
+            })
+        })
+        .collect::<Result<_, String>>()
+        .map_err(|e| format!("{}: {e}", path.display()))?;
+    Ok(Policy {
+        rules,
+        max_tuple_arity: policy.max_tuple_arity.unwrap_or(super::MAX_TUPLE_ARITY),
+        max_object_fields: policy.max_object_fields.unwrap_or(super::MAX_OBJECT_FIELDS),
+    })
+}
+
+/// Use `policy` for every observation and join from now on (once, before any
+/// evidence is collected).
+pub fn install(policy: Policy) {
+    let Policy { rules, max_tuple_arity, max_object_fields } = policy;
+    let loosest = rules.iter().fold(Caps::DEFAULT, |c, r| Caps {
+        strings: c.strings.max(r.caps.0.unwrap_or(0)),
+        numbers: c.numbers.max(r.caps.1.unwrap_or(0)),
+    });
+    let _ = INSTALLED.set(Installed { rules, loosest, max_tuple_arity, max_object_fields });
+}
+
+/// The installed policy, one `NAME=value` per line, for
+/// [`super::policy_fingerprint`]; nothing when no policy is installed.
+pub fn fingerprint() -> Vec<String> {
+    let Some(i) = INSTALLED.get() else { return Vec::new() };
+    let cap = |c: Option<usize>| c.map_or_else(|| "-".to_string(), |n| n.to_string());
+    let mut lines: Vec<String> = i.rules
+        .iter()
+        .map(|r| format!("POLICY_RULE={} {}..={} strings={} numbers={}", r.path, r.min_depth, r.max_depth, cap(r.caps.0), cap(r.caps.1)))
+        .collect();
+    lines.push(format!("POLICY_MAX_TUPLE_ARITY={}", i.max_tuple_arity));
+    lines.push(format!("POLICY_MAX_OBJECT_FIELDS={}", i.max_object_fields));
+    lines
+}
+
+fn rules() -> &'static [Rule] {
+    INSTALLED.get().map_or(&[], |i| i.rules.as_slice())
+}
+
+/// The caps of the slot at `at`.
+pub fn at(at: &[Seg]) -> Caps {
+    caps_under(rules(), at)
+}
+
+fn caps_under(rules: &[Rule], at: &[Seg]) -> Caps {
+    let applying = || rules.iter().filter(|r| r.applies(at));
+    Caps {
+        strings: applying().find_map(|r| r.caps.0).unwrap_or(super::MAX_STR_LITS),
+        numbers: applying().find_map(|r| r.caps.1).unwrap_or(super::MAX_NUM_LITS),
+    }
+}
+
+/// Longest array that keeps per-position (tuple) evidence.
+pub fn max_tuple_arity() -> usize {
+    INSTALLED.get().map_or(super::MAX_TUPLE_ARITY, |i| i.max_tuple_arity)
+}
+
+/// Most distinct keys a slot's objects may hold and still be a record.
+pub fn max_object_fields() -> usize {
+    INSTALLED.get().map_or(super::MAX_OBJECT_FIELDS, |i| i.max_object_fields)
+}
+
+/// The largest caps of any slot.
+pub fn loosest() -> Caps {
+    INSTALLED.get().map_or(Caps::DEFAULT, |i| i.loosest)
+}
+
+/// Drop the literals of every slot of `u` holding more than its cap (the
+/// joins only applied the loosest); nothing to do without rules.
+pub(super) fn apply(u: &mut U) {
+    if !rules().is_empty() {
+        apply_at(u, &mut JPath::root());
+    }
+}
+
+fn apply_at(u: &mut U, at: &mut JPath) {
+    let caps = self::at(at.segs());
+    if let Some(s) = u.str_.as_mut().filter(|s| s.lits.len() > caps.strings) {
+        s.lits.clear();
+        s.lit_counts.clear();
+    }
+    if let Some(n) = u.num.as_mut().filter(|n| n.lits_f64.len() > caps.numbers) {
+        n.lits_f64.clear();
+    }
+    let mut below = |seg: Seg, u: &mut U| {
+        at.0.push(seg);
+        apply_at(u, at);
+        at.0.pop();
+    };
+    if let Some(arr) = u.arr.as_mut() {
+        below(Seg::Items, &mut arr.item);
+        for (i, c) in arr.cols.iter_mut().enumerate() {
+            below(Seg::Index(i), c);
+        }
+    }
+    if let Some(obj) = u.obj.as_mut() {
+        for_fields(obj, &mut |k, f| below(Seg::Field(k.to_string()), f));
+        if let Some(wide) = obj.wide.as_mut() {
+            below(Seg::Items, &mut wide.value);
+        }
+    }
+}
+
+/// Every field type of `obj`, including those of the per-tag copies.
+fn for_fields(obj: &mut ObjC, f: &mut impl FnMut(&str, &mut U)) {
+    for (k, field) in obj.fields.iter_mut() {
+        f(k, &mut field.ty);
+    }
+    for variant in obj.variants.values_mut().flatten().flat_map(|m| m.values_mut()) {
+        for_fields(variant, f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_str(name: &str, src: &str) -> Result<Policy, String> {
+        let path = std::env::temp_dir().join(format!("json-osi-caps-{}-{name}.json", std::process::id()));
+        std::fs::write(&path, src).unwrap();
+        let policy = load(&path);
+        let _ = std::fs::remove_file(&path);
+        policy
+    }
+
+    fn segs(path: &str) -> Vec<Seg> {
+        path.parse::<JPath>().unwrap().0
+    }
+
+    #[test]
+    fn first_applying_rule_that_caps_a_kind_wins() {
+        let policy = load_str("rules", r#"{ "literal_caps": [
+            { "path": "$.events[*].id", "strings": 0 },
+            { "min_depth": 2, "strings": 8, "numbers": 8 },
+            { "max_depth": 1, "strings": 256 }
+        ] }"#).unwrap();
+        let rules = &policy.rules;
+        assert_eq!(caps_under(rules, &segs("$.events[*].id")), Caps { strings: 0, numbers: 8 });
+        // `[*]` in a rule also matches tuple positions, and the rule covers what is below
+        assert_eq!(caps_under(rules, &segs("$.events[3].id.x")), Caps { strings: 0, numbers: 8 });
+        assert_eq!(caps_under(rules, &segs("$.events[*].name")), Caps { strings: 8, numbers: 8 });
+        assert_eq!(caps_under(rules, &segs("$.events")), Caps { strings: 256, numbers: Caps::DEFAULT.numbers });
+        assert_eq!(caps_under(&[], &segs("$.events")), Caps::DEFAULT);
+    }
+
+    #[test]
+    fn a_rule_applies_within_its_depths_at_or_below_its_path() {
+        let rule = Rule { path: "$.a[*]".parse().unwrap(), min_depth: 2, max_depth: 3, caps: (Some(1), None) };
+        assert!(rule.applies(&segs("$.a[*]")));
+        assert!(rule.applies(&segs("$.a[0].b")));
+        assert!(!rule.applies(&segs("$.a")));
+        assert!(!rule.applies(&segs("$.b[*]")));
+        assert!(!rule.applies(&segs("$.a[*].b.c")));
+        // positions don't match `[*]` the other way round
+        let rule = Rule { path: "$[0]".parse().unwrap(), min_depth: 0, max_depth: usize::MAX, caps: (Some(1), None) };
+        assert!(!rule.applies(&segs("$[*]")));
+    }
+
+    #[test]
+    fn load_defaults_and_rejects() {
+        let policy = load_str("empty", "{}").unwrap();
+        assert!(policy.rules.is_empty());
+        assert_eq!((policy.max_tuple_arity, policy.max_object_fields), (crate::inference::MAX_TUPLE_ARITY, crate::inference::MAX_OBJECT_FIELDS));
+        let policy = load_str("limits", r#"{ "max_tuple_arity": 3, "max_object_fields": 10 }"#).unwrap();
+        assert_eq!((policy.max_tuple_arity, policy.max_object_fields), (3, 10));
+        assert!(load_str("unknown", r#"{ "literal_caps": [{ "strings": 1, "bytes": 2 }] }"#).unwrap_err().contains("bytes"));
+        assert!(load_str("badpath", r#"{ "literal_caps": [{ "path": "events" }] }"#).unwrap_err().contains("invalid path"));
+    }
+}
@@ -1,7 +1,8 @@
 use std::collections::BTreeSet;
 use ordered_float::OrderedFloat;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct NumC {
     pub lits_f64: BTreeSet<OrderedFloat<f64>>,
     pub min_f64: OrderedFloat<f64>,
@@ -9,14 +10,95 @@ pub struct NumC {
     pub saw_int: bool,
     pub saw_uint: bool,
     pub saw_float: bool,
+    /// Exact least and greatest integer seen, while `min_f64` / `max_f64` round
+    /// past 2^53; `None` once a float was seen (or in older evidence).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub int_range: Option<(i128, i128)>,
+    /// A step every value is a multiple of; `None` once a float had more than
+    /// [`super::STEP_MAX_DECIMALS`] decimals (or in older evidence).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step: Option<Step>,
+    /// A few of the values (see [`super::join_examples`]).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<OrderedFloat<f64>>,
+}
+
+/// Values that are all multiples of `gcd / 10^scale`, e.g. `{ gcd: 25, scale: 2 }`
+/// for 0.25, 0.5 and 1.75 (`gcd` is 0 while only zeros were seen).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Step {
+    pub gcd: u128,
+    pub scale: u32,
+}
+
+impl Step {
+    pub fn of_int(x: i128) -> Self {
+        Step { gcd: x.unsigned_abs(), scale: 0 }
+    }
+
+    /// From the shortest decimal spelling of `x`; `None` past
+    /// [`super::STEP_MAX_DECIMALS`] decimals or beyond `u128`.
+    pub fn of_float(x: f64) -> Option<Self> {
+        if !x.is_finite() {
+            return None;
+        }
+        let s = x.abs().to_string();
+        let (int, frac) = s.split_once('.').unwrap_or((&s, ""));
+        let scale = frac.len() as u32;
+        if scale > super::STEP_MAX_DECIMALS {
+            return None;
+        }
+        Some(Step { gcd: format!("{int}{frac}").parse().ok()?, scale })
+    }
+
+    pub fn join(a: Self, b: Self) -> Option<Self> {
+        let scale = a.scale.max(b.scale);
+        let up = |s: Self| s.gcd.checked_mul(10u128.checked_pow(scale - s.scale)?);
+        let (mut x, mut y) = (up(a)?, up(b)?);
+        while y != 0 {
+            (x, y) = (y, x % y);
+        }
+        let mut out = Step { gcd: x, scale };
+        // canonical: 0.5 rather than 0.50
+        while out.scale > 0 && out.gcd != 0 && out.gcd.is_multiple_of(10) {
+            out = Step { gcd: out.gcd / 10, scale: out.scale - 1 };
+        }
+        Some(out)
+    }
+
+    pub fn value(self) -> f64 {
+        self.gcd as f64 / 10f64.powi(self.scale as i32)
+    }
 }
 
 
 impl NumC {
-    pub(super) fn join(a: &Self, b: &Self) -> Self {
+    /// The distinct values, ascending, when every one seen was an integer
+    /// and is still kept (`None` past a literal cap or beyond 2^53, where the
+    /// floats they're kept as round).
+    pub fn int_values(&self) -> Option<Vec<i64>> {
+        const EXACT: f64 = (1u64 << 53) as f64;
+        if self.saw_float || self.lits_f64.is_empty() {
+            return None;
+        }
+        self.lits_f64
+            .iter()
+            .map(|x| (x.0.fract() == 0.0 && x.0.abs() <= EXACT).then_some(x.0 as i64))
+            .collect()
+    }
+
+    pub(crate) fn join(a: &Self, b: &Self) -> Self {
+        Self::join_capped(a, b, super::MAX_NUM_LITS)
+    }
+
+    /// Keeping at most `cap` distinct numbers.
+    pub(crate) fn join_capped(a: &Self, b: &Self, cap: usize) -> Self {
         let mut out = NumC::default();
-        out.lits_f64 = &a.lits_f64 | &b.lits_f64;
-        if out.lits_f64.len() > super::MAX_NUM_LITS {
+        // as for strings: no literals left means a cap dropped them
+        if !a.lits_f64.is_empty() && !b.lits_f64.is_empty() {
+            out.lits_f64 = &a.lits_f64 | &b.lits_f64;
+        }
+        if out.lits_f64.len() > cap {
             out.lits_f64.clear(); // cap: treat as tokens → interval only
         }
         out.min_f64 = a.min_f64.min(b.min_f64);
@@ -24,6 +106,15 @@ impl NumC {
         out.saw_int = a.saw_int || b.saw_int;
         out.saw_uint = a.saw_uint || b.saw_uint;
         out.saw_float = a.saw_float || b.saw_float;
+        out.int_range = match (a.int_range, b.int_range) {
+            (Some((a0, a1)), Some((b0, b1))) => Some((a0.min(b0), a1.max(b1))),
+            _ => None,
+        };
+        out.step = match (a.step, b.step) {
+            (Some(x), Some(y)) => Step::join(x, y),
+            _ => None,
+        };
+        out.examples = super::join_examples(&a.examples, &b.examples, |x| super::example_key(&x.0.to_bits().to_le_bytes()));
         out
     }
 }
@@ -1,23 +1,169 @@
-use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
+use serde::{Deserialize, Serialize};
 use super::U;
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct ObjC {
     pub fields: BTreeMap<String, FieldC>,
     pub seen_objects: u64,
+    /// Per tag value, the objects that had it, for each of [`TAG_FIELDS`] that was a
+    /// short string in every object so far (`None` once one wasn't). Fields are merged
+    /// into one shape above; these keep the shapes apart for tagged unions.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variants: BTreeMap<String, Option<BTreeMap<String, ObjC>>>,
+    /// The distinct key sets objects came with, sorted (`None` once there were more
+    /// than [`MAX_SHAPES`]); clustered into separate records by [`super::decide_shapes`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shapes: Option<Vec<KeySet>>,
+    /// Set once the slot's objects held more than [`super::caps::max_object_fields`]
+    /// distinct keys: every field folded into it, and `fields` empty from then on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wide: Option<Box<Wide>>,
 }
 
-#[derive(Clone, Debug, Default)]
+/// The keys and values of an object slot too wide to be a record, without
+/// per-key evidence: the slot is a map, and its evidence stops growing with
+/// every new key.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Wide {
+    /// Distinct keys, at least: exact within one object, the most any side
+    /// had in a join (the keys themselves aren't kept).
+    pub keys: u64,
+    /// Every key, as a string.
+    pub key: U,
+    /// Every value.
+    pub value: U,
+    /// Keys over all objects.
+    pub present: u64,
+}
+
+/// One set of keys objects were seen with, and how many were.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct KeySet {
+    pub keys: BTreeSet<String>,
+    pub count: u64,
+}
+
+/// Field names conventionally holding a tagged union's variant name.
+pub const TAG_FIELDS: [&str; 7] = ["type", "kind", "tag", "_type", "@type", "$type", "op"];
+
+/// Most tag values kept per field, as for string enums.
+pub const MAX_TAG_VALUES: usize = super::STRING_ENUM_MAX;
+
+/// Most distinct key sets kept per object slot; past that, optional fields vary
+/// too freely for the shapes to mean much.
+pub const MAX_SHAPES: usize = 16;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct FieldC {
     pub ty: U,
     pub present_in: u64,
     pub non_null_in: u64, // for "required" = present & non-null
+    /// Other spellings of the key, merged into this one by
+    /// [`super::ObserveOpts::normalize_unicode`].
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub aliases: BTreeSet<String>,
 }
 
 impl ObjC {
+    /// Add the field `k` of this single object. Normalizing keys, it goes under
+    /// its normalized key, keeping `k` as an alias if that differs; two keys of
+    /// the object normalizing alike are one field.
+    pub(super) fn observe_field(&mut self, k: String, ty: U, non_null: bool, opts: super::ObserveOpts) {
+        let (key, alias) = match opts.normalize_unicode.then(|| super::unicode::normalize(&k).into_owned()) {
+            Some(n) if n != k => (n, Some(k)),
+            _ => (k, None),
+        };
+        if let Some(wide) = self.wide.as_mut() {
+            wide.fold(key, &ty, 1);
+            wide.keys += 1;
+            return;
+        }
+        let aliases = alias.into_iter().collect();
+        match self.fields.entry(key) {
+            Entry::Vacant(e) => {
+                e.insert(FieldC { ty, present_in: 1, non_null_in: u64::from(non_null), aliases });
+            }
+            Entry::Occupied(mut e) => {
+                let field = e.get_mut();
+                field.ty = U::join_inner(&field.ty, &ty);
+                field.non_null_in = field.non_null_in.max(u64::from(non_null));
+                field.aliases.extend(aliases);
+            }
+        }
+        if self.fields.len() > super::caps::max_object_fields() {
+            self.fold();
+        }
+    }
+
+    /// Fold every field into [`ObjC::wide`]; the key sets and tag values go too.
+    fn fold(&mut self) {
+        let wide = self.wide.get_or_insert_with(Box::default);
+        wide.keys = wide.keys.max(self.fields.len() as u64);
+        for (k, f) in std::mem::take(&mut self.fields) {
+            wide.fold(k, &f.ty, f.present_in);
+        }
+        self.variants.clear();
+        self.shapes = None;
+    }
+
+    /// Record this single object's key set (see [`ObjC::shapes`]).
+    pub(super) fn note_shape(&mut self) {
+        if self.wide.is_some() {
+            return;
+        }
+        self.shapes = Some(vec![KeySet { keys: self.fields.keys().cloned().collect(), count: 1 }]);
+    }
+
+    /// Record this single object under its tag values (see [`ObjC::variants`]).
+    pub(super) fn note_tags(&mut self) {
+        for tag in TAG_FIELDS {
+            let Some(value) = self.fields.get(tag).and_then(|f| tag_value(&f.ty)) else { continue };
+            let variant = ObjC { fields: self.fields.clone(), seen_objects: self.seen_objects, ..ObjC::default() };
+            self.variants.insert(tag.to_string(), Some([(value, variant)].into()));
+        }
+    }
+
     pub(super) fn join(a: &Self, b: &Self) -> Self {
+        if a.wide.is_some() || b.wide.is_some() {
+            return Self::join_wide(a, b);
+        }
         let mut out = Self::default();
         out.seen_objects = a.seen_objects + b.seen_objects;
+
+        // a tag field missing on either side was missing from some object
+        for tag in a.variants.keys().chain(b.variants.keys()) {
+            let joined = match (a.variants.get(tag), b.variants.get(tag)) {
+                (Some(Some(va)), Some(Some(vb))) => {
+                    let mut values = va.clone();
+                    for (value, ob) in vb {
+                        let joined = match values.get(value) {
+                            Some(oa) => ObjC::join(oa, ob),
+                            None => ob.clone(),
+                        };
+                        values.insert(value.clone(), joined);
+                    }
+                    (values.len() <= MAX_TAG_VALUES).then_some(values)
+                }
+                _ => None,
+            };
+            out.variants.insert(tag.clone(), joined);
+        }
+
+        out.shapes = match (&a.shapes, &b.shapes) {
+            (Some(sa), Some(sb)) => {
+                let mut shapes = sa.clone();
+                for s in sb {
+                    match shapes.binary_search_by(|x| x.keys.cmp(&s.keys)) {
+                        Ok(i) => shapes[i].count += s.count,
+                        Err(i) => shapes.insert(i, s.clone()),
+                    }
+                }
+                (shapes.len() <= MAX_SHAPES).then_some(shapes)
+            }
+            _ => None,
+        };
     
         // merge keys from a
         for (k, fa) in &a.fields {
@@ -27,13 +173,15 @@ impl ObjC {
                         ty: fa.ty.clone(),
                         present_in: fa.present_in,
                         non_null_in: fa.non_null_in,
+                        aliases: fa.aliases.clone(),
                     });
                 }
                 Some(fb) => {
                     out.fields.insert(k.clone(), FieldC {
-                        ty: U::join(&fa.ty, &fb.ty),
+                        ty: U::join_inner(&fa.ty, &fb.ty),
                         present_in: fa.present_in + fb.present_in,
                         non_null_in: fa.non_null_in + fb.non_null_in,
+                        aliases: fa.aliases.union(&fb.aliases).cloned().collect(),
                     });
                 }
             }
@@ -45,11 +193,52 @@ impl ObjC {
                     ty: fb.ty.clone(),
                     present_in: fb.present_in,
                     non_null_in: fb.non_null_in,
+                    aliases: fb.aliases.clone(),
                 });
             }
         }
-    
+        if out.fields.len() > super::caps::max_object_fields() {
+            out.fold();
+        }
+        out
+    }
+
+    /// [`ObjC::join`] with either side folded: so is the result.
+    fn join_wide(a: &Self, b: &Self) -> Self {
+        let mut out = a.clone();
+        out.fold();
+        let wide = out.wide.as_mut().expect("folded");
+        match &b.wide {
+            Some(w) => {
+                wide.keys = wide.keys.max(w.keys);
+                wide.key = U::join_inner(&wide.key, &w.key);
+                wide.value = U::join_inner(&wide.value, &w.value);
+                wide.present += w.present;
+            }
+            None => wide.keys = wide.keys.max(b.fields.len() as u64),
+        }
+        for (k, f) in &b.fields {
+            wide.fold(k.clone(), &f.ty, f.present_in);
+        }
+        out.seen_objects = a.seen_objects + b.seen_objects;
         out
     }
 }
 
+impl Wide {
+    /// Add the values of key `k`, seen in `present` objects.
+    fn fold(&mut self, k: String, ty: &U, present: u64) {
+        self.key = U::join_inner(&self.key, &super::observe_value(&serde_json::Value::String(k)));
+        self.value = U::join_inner(&self.value, ty);
+        self.present += present;
+    }
+}
+
+
+/// The one short string `u` holds, if that is all it holds.
+fn tag_value(u: &U) -> Option<String> {
+    let s = u.str_.as_ref()?;
+    let only_string = !u.nullable && !u.has_bool && u.num.is_none() && u.arr.is_none() && u.obj.is_none();
+    let value = s.lits.first().filter(|v| only_string && s.lits.len() == 1 && v.len() <= super::STRING_ENUM_MAX_LEN)?;
+    Some(value.clone())
+}
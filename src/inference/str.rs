@@ -1,11 +1,45 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{LazyLock, Mutex, OnceLock};
+use std::time::Duration;
 
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Default)]
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct StrC {
     pub lits: BTreeSet<String>,
+    /// How often each of `lits` was seen; empty when that isn't known for all
+    /// of them (evidence from before it was tracked).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub lit_counts: BTreeMap<String, u64>,
     // pub lcp: Option<String>,
     pub is_uri: bool,
+    /// Well-known syntaxes every string so far matched.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub formats: BTreeSet<StrFormat>,
+    /// Evidence for the JSON documents the strings hold, while every string so far
+    /// held a JSON object or array (only looked for with [`super::ObserveOpts::json_strings`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json: Option<Box<super::U>>,
+    /// The numbers the strings spell, while every string so far was a JSON number
+    /// (only looked for with [`super::ObserveOpts::numeric_strings`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub numeric: Option<super::NumC>,
+    /// Every string so far spelled a JSON number, whether or not they were read
+    /// as numbers (see [`super::ENABLE_STRING_OR_NUMBER`]).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub spells_numbers: bool,
+    /// How many of the strings were empty (`""`).
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub empties: u64,
+    /// Shortest and longest string, in characters, however many distinct ones
+    /// were seen; `None` in older evidence.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub len_range: Option<(u64, u64)>,
+    /// A few of the strings, as written (see [`super::join_examples`]); none
+    /// longer than [`super::MAX_EXAMPLE_LEN`] characters.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<String>,
     
     /// Regex synthesized during normalize (via grex). Prefer this over LCP.
     pub pattern_synth: Option<String>,
@@ -15,18 +49,111 @@ pub struct StrC {
     pub grex_cache_key: Option<(usize, usize, u64)>,
 }
 
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
+
 // ------- Regex synthesis policy (grex integration) -------
 
-/// Minimum distinct literals before we even consider synthesizing a regex.
-const GREX_MIN_SAMPLES: usize = 3;
+/// Minimum distinct literals before we even consider synthesizing a regex
+/// (default `--grex-min-samples`).
+pub const GREX_MIN_SAMPLES: usize = 3;
 
 /// Hard cap on the length of a generated regex. If grex exceeds this,
-/// we treat the field as an arbitrary string (no pattern).
-const GREX_MAX_PATTERN_LEN: usize = 256;
+/// we treat the field as an arbitrary string (no pattern). Default `--grex-max-len`.
+pub const GREX_MAX_PATTERN_LEN: usize = 256;
 
 /// Guard against regexes that are basically giant whitelists made of many
-/// alternations. This is a coarse, top-level `|` count threshold.
-const GREX_MAX_ALTS: usize = 32;
+/// alternations. This is a coarse, top-level `|` count threshold. Default
+/// `--grex-max-alts`.
+pub const GREX_MAX_ALTS: usize = 32;
+
+/// Regex synthesis settings of one run (`--grex`, `--grex-min-samples`,
+/// `--grex-max-len`, `--grex-max-alts`); [`Grex::DEFAULT`] is the policy's.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Grex {
+    /// Synthesize patterns at all (see [`super::ENABLE_GREX`]).
+    pub enabled: bool,
+    /// See [`GREX_MIN_SAMPLES`].
+    pub min_samples: usize,
+    /// See [`GREX_MAX_PATTERN_LEN`].
+    pub max_len: usize,
+    /// See [`GREX_MAX_ALTS`].
+    pub max_alts: usize,
+}
+
+impl Grex {
+    pub const DEFAULT: Grex = Grex {
+        enabled: super::ENABLE_GREX,
+        min_samples: GREX_MIN_SAMPLES,
+        max_len: GREX_MAX_PATTERN_LEN,
+        max_alts: GREX_MAX_ALTS,
+    };
+
+    pub fn is_default(&self) -> bool {
+        *self == Self::DEFAULT
+    }
+
+    /// Whether grex's `pattern` passes the guardrails.
+    fn keeps(&self, pattern: &str) -> bool {
+        pattern.len() <= self.max_len && !too_many_alternations(pattern, self.max_alts)
+    }
+}
+
+impl Default for Grex {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// grex's time grows steeply with its input: past this many distinct literals,
+/// or this many characters over all of them, it isn't run (plain string).
+pub(crate) const GREX_MAX_DISTINCT: usize = 512;
+pub(crate) const GREX_MAX_INPUT_CHARS: usize = 16 * 1024;
+
+/// Default wall-clock budget of one grex run, in milliseconds (`--grex-time-budget`).
+pub const GREX_TIME_BUDGET_MS: u64 = 2_000;
+
+static GREX_TIME_BUDGET: OnceLock<Duration> = OnceLock::new();
+
+/// Set the wall-clock budget of every grex run in this process (first call wins).
+pub fn set_grex_time_budget(budget: Duration) {
+    let _ = GREX_TIME_BUDGET.set(budget);
+}
+
+fn grex_time_budget() -> Duration {
+    GREX_TIME_BUDGET.get().copied().unwrap_or(Duration::from_millis(GREX_TIME_BUDGET_MS))
+}
+
+/// Why a slot's literals weren't given to grex, or its run was abandoned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GrexSkip {
+    /// More distinct literals than [`GREX_MAX_DISTINCT`].
+    Distinct(usize),
+    /// More characters than [`GREX_MAX_INPUT_CHARS`] over the literals.
+    Chars(usize),
+    /// Still running after the time budget, in milliseconds.
+    Timeout(u64),
+}
+
+impl GrexSkip {
+    pub fn describe(&self) -> String {
+        match self {
+            GrexSkip::Distinct(n) => format!("{n} distinct strings (at most {GREX_MAX_DISTINCT})"),
+            GrexSkip::Chars(n) => format!("{n} characters (at most {GREX_MAX_INPUT_CHARS})"),
+            GrexSkip::Timeout(ms) => format!("still running after {ms} ms"),
+        }
+    }
+}
+
+type GrexOutcome = Result<String, GrexSkip>;
+
+/// grex's outcomes by [`grex_cache_key`], before the guardrails: the report's
+/// survey and every normalization of the same evidence ask for the same literal
+/// sets, and a run that timed out once isn't waited for again.
+static GREX_OUTCOMES: LazyLock<Mutex<GrexOutcomes>> = LazyLock::new(Default::default);
+
+type GrexOutcomes = HashMap<(usize, usize, u64), GrexOutcome>;
 
 
 /// Compute a cheap, deterministic fingerprint of the current literal set.
@@ -53,8 +180,8 @@ pub fn grex_cache_key(samples: &BTreeSet<String>) -> (usize, usize, u64) {
 
 /// Very coarse “structure” guardrail: reject regexes with too many top-level '|'.
 /// We don’t try to parse; this is just a cheap cutoff to avoid giant whitelists.
-fn too_many_alternations(rx: &str) -> bool {
-    rx.as_bytes().iter().filter(|&&b| b == b'|').count() > GREX_MAX_ALTS
+fn too_many_alternations(rx: &str, max_alts: usize) -> bool {
+    rx.as_bytes().iter().filter(|&&b| b == b'|').count() > max_alts
 }
 
 /// Build an anchored regex with grex over the *full* literal set.
@@ -63,53 +190,204 @@ fn too_many_alternations(rx: &str) -> bool {
 /// - Deterministic order (sort) for stable codegen.
 /// - No prefix/anchor surgery: we take grex's anchored `^...$` as-is.
 /// - Guardrails: drop result if too long or too alternation-heavy.
-pub fn synth_regex_with_grex(samples: &BTreeSet<String>) -> Option<String> {
+/// - Effort limits: see [`try_synth_regex_with_grex`].
+pub fn synth_regex_with_grex(samples: &BTreeSet<String>, grex: Grex) -> Option<String> {
+    try_synth_regex_with_grex(samples, grex).ok().flatten()
+}
+
+/// [`synth_regex_with_grex`], saying why grex wasn't run to the end: too many
+/// literals or characters to try, or still running after the time budget (see
+/// [`set_grex_time_budget`]). A run past its budget is left to finish on its own
+/// thread, its result unused; the pattern falls back to a plain string either way.
+pub fn try_synth_regex_with_grex(samples: &BTreeSet<String>, grex: Grex) -> Result<Option<String>, GrexSkip> {
     use grex::RegExpBuilder;
     
-    if !super::ENABLE_GREX {
-        return None; // hard-disable pattern generation
+    if !grex.enabled {
+        return Ok(None); // pattern generation disabled (`--grex`)
     }
 
-    if samples.len() < GREX_MIN_SAMPLES {
-        return None;
+    if samples.len() < grex.min_samples {
+        return Ok(None);
     }
 
     // Normalize exactly as your pipeline expects to validate; at minimum trim.
-    let mut lits: Vec<&str> = samples
+    let mut lits: Vec<String> = samples
         .iter()
         .map(|s| s.trim())
         .filter(|s| !s.is_empty())
+        .map(str::to_string)
         .collect();
 
     // After trimming, we may dip below the minimum.
-    if lits.len() < GREX_MIN_SAMPLES {
-        return None;
+    if lits.len() < grex.min_samples {
+        return Ok(None);
+    }
+    if lits.len() > GREX_MAX_DISTINCT {
+        return Err(GrexSkip::Distinct(lits.len()));
+    }
+    let chars: usize = lits.iter().map(|s| s.chars().count()).sum();
+    if chars > GREX_MAX_INPUT_CHARS {
+        return Err(GrexSkip::Chars(chars));
+    }
+
+    let key = grex_cache_key(samples);
+    if let Some(outcome) = GREX_OUTCOMES.lock().unwrap_or_else(|e| e.into_inner()).get(&key) {
+        return outcome.clone().map(|pattern| grex.keeps(&pattern).then_some(pattern));
     }
 
     lits.sort_unstable();
 
     // grex 1.4.5: build() returns `^...$`.
-    let rx = RegExpBuilder::from(&lits).build();
-
-    if rx.len() > GREX_MAX_PATTERN_LEN || too_many_alternations(&rx) {
-        return None; // fall back to enum/LCP/plain string
-    }
-    Some(rx)
+    let budget = grex_time_budget();
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(RegExpBuilder::from(&lits).build());
+    });
+    let outcome = rx.recv_timeout(budget).map_err(|_| GrexSkip::Timeout(budget.as_millis() as u64));
+    GREX_OUTCOMES.lock().unwrap_or_else(|e| e.into_inner()).insert(key, outcome.clone());
+    // past the guardrails: fall back to enum/LCP/plain string
+    outcome.map(|pattern| grex.keeps(&pattern).then_some(pattern))
 }
 
 impl StrC {
-    pub(super) fn join(a: &Self, b: &Self) -> Self {
+    /// Few, short, human-ish literals, kept as an enum (when string enums are enabled).
+    pub(crate) fn tiny_enum(&self) -> bool {
+        super::ENABLE_STRING_ENUMS
+            && self.lits.len() <= super::STRING_ENUM_MAX
+            && self.lits.iter().all(|s| s.len() <= super::STRING_ENUM_MAX_LEN && looks_humanish(s))
+    }
+
+    /// Whether normalization gives the literals to grex for a pattern.
+    pub(crate) fn wants_pattern(&self, grex: Grex) -> bool {
+        grex.enabled && !self.tiny_enum() && !self.is_uri && self.formats.is_empty()
+    }
+
+    pub(super) fn join(a: &Self, b: &Self, caps: super::caps::Caps) -> Self {
         let mut out = StrC::default();
-        out.lits = &a.lits | &b.lits;
-        if out.lits.len() > super::MAX_STR_LITS {
+        // every string observed is a literal: none left means a cap dropped them, for good
+        if !a.lits.is_empty() && !b.lits.is_empty() {
+            out.lits = &a.lits | &b.lits;
+        }
+        if out.lits.len() > caps.strings {
             out.lits.clear();
+        } else if !out.lits.is_empty() && a.lit_counts.len() == a.lits.len() && b.lit_counts.len() == b.lits.len() {
+            out.lit_counts = a.lit_counts.clone();
+            for (s, n) in &b.lit_counts {
+                *out.lit_counts.entry(s.clone()).or_default() += n;
+            }
         }
         // out.lcp = lcp_join(a.lcp.as_deref(), b.lcp.as_deref());
         out.is_uri = a.is_uri && b.is_uri;
+        out.formats = &a.formats & &b.formats;
+        out.json = match (&a.json, &b.json) {
+            (Some(x), Some(y)) => Some(Box::new(super::U::join_inner(x, y))),
+            _ => None,
+        };
+        out.numeric = match (&a.numeric, &b.numeric) {
+            (Some(x), Some(y)) => Some(super::NumC::join_capped(x, y, caps.numbers)),
+            _ => None,
+        };
+        out.spells_numbers = a.spells_numbers && b.spells_numbers;
+        out.empties = a.empties + b.empties;
+        out.len_range = match (a.len_range, b.len_range) {
+            (Some((a0, a1)), Some((b0, b1))) => Some((a0.min(b0), a1.max(b1))),
+            _ => None,
+        };
+        out.examples = super::join_examples(&a.examples, &b.examples, |s| super::example_key(s.as_bytes()));
         out
     }
 }
 
+/// Shortest string taken for a hex / base64 blob; shorter ones are too often words or ids.
+pub(crate) const BLOB_MIN_LEN: usize = 32;
+
+/// Well-known string syntaxes, checked on every observed string; a slot whose
+/// strings all match one gets it as its JSON Schema `format` (first in this order).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StrFormat {
+    /// Hyphenated `8-4-4-4-12` hex digits, any version or case.
+    Uuid,
+    /// `local@host.tld`, see [`looks_like_email`].
+    Email,
+    /// Dotted decimal, no leading zeros.
+    Ipv4,
+    /// Any textual form `std::net::Ipv6Addr` parses.
+    Ipv6,
+    /// At least two labels ending in an alphabetic top-level one, see [`looks_like_hostname`].
+    Hostname,
+    /// An even number of hex digits, at least [`BLOB_MIN_LEN`].
+    Hex,
+    /// Padded standard base64, at least [`BLOB_MIN_LEN`] long and not all letters.
+    Base64,
+    /// A decimal amount with a fractional part (`19.99`, `-0.5`, `1,299.00`), see [`looks_like_decimal`].
+    Decimal,
+}
+
+impl StrFormat {
+    pub const ALL: [StrFormat; 8] = [
+        StrFormat::Uuid, StrFormat::Email, StrFormat::Ipv4, StrFormat::Ipv6, StrFormat::Hostname,
+        StrFormat::Hex, StrFormat::Base64, StrFormat::Decimal,
+    ];
+
+    /// The JSON Schema `format` value (for blobs, see [`StrFormat::content_encoding`]).
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StrFormat::Uuid => "uuid",
+            StrFormat::Email => "email",
+            StrFormat::Ipv4 => "ipv4",
+            StrFormat::Ipv6 => "ipv6",
+            StrFormat::Hostname => "hostname",
+            StrFormat::Hex => "hex",
+            StrFormat::Base64 => "base64",
+            StrFormat::Decimal => "decimal",
+        }
+    }
+
+    /// The JSON Schema `contentEncoding` of binary data encoded as text; such
+    /// strings get that instead of a `format`.
+    pub fn content_encoding(self) -> Option<&'static str> {
+        match self {
+            StrFormat::Hex => Some("base16"),
+            StrFormat::Base64 => Some("base64"),
+            _ => None,
+        }
+    }
+
+    pub fn matches(self, s: &str) -> bool {
+        match self {
+            StrFormat::Uuid => looks_like_uuid(s),
+            StrFormat::Email => looks_like_email(s),
+            StrFormat::Ipv4 => s.parse::<std::net::Ipv4Addr>().is_ok(),
+            StrFormat::Ipv6 => s.parse::<std::net::Ipv6Addr>().is_ok(),
+            StrFormat::Hostname => looks_like_hostname(s),
+            StrFormat::Hex => looks_like_hex_blob(s),
+            StrFormat::Base64 => looks_like_base64_blob(s),
+            StrFormat::Decimal => looks_like_decimal(s),
+        }
+    }
+
+    /// A regex accepting what [`StrFormat::matches`] does, for generated code (blobs of
+    /// any length: the minimum only keeps detection from guessing on short strings);
+    /// `None` for the IP formats, which generated code parses with `std::net` too.
+    pub fn regex(self) -> Option<&'static str> {
+        match self {
+            StrFormat::Uuid => Some("^[0-9A-Fa-f]{8}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{12}$"),
+            StrFormat::Email => Some(r"^[A-Za-z0-9!#$%&'*+/=?^_`{|}~.-]{1,64}@([A-Za-z0-9]([A-Za-z0-9-]{0,61}[A-Za-z0-9])?\.)+[A-Za-z]{2,63}$"),
+            StrFormat::Hostname => Some(r"^([A-Za-z0-9]([A-Za-z0-9-]{0,61}[A-Za-z0-9])?\.)+[A-Za-z]{2,63}$"),
+            StrFormat::Hex => Some("^([0-9A-Fa-f]{2})*$"),
+            StrFormat::Base64 => Some("^([A-Za-z0-9+/]{4})*([A-Za-z0-9+/]{2}==|[A-Za-z0-9+/]{3}=)?$"),
+            StrFormat::Decimal => Some(r"^-?([0-9]+|[0-9]{1,3}(,[0-9]{3})+)\.[0-9]+$"),
+            StrFormat::Ipv4 | StrFormat::Ipv6 => None,
+        }
+    }
+
+    /// The formats `s` matches.
+    pub fn of(s: &str) -> BTreeSet<StrFormat> {
+        Self::ALL.into_iter().filter(|f| f.matches(s)).collect()
+    }
+}
+
 fn lcp_join(a: Option<&str>, b: Option<&str>) -> Option<String> {
     match (a, b) {
         (Some(x), Some(y)) => {
@@ -143,6 +421,88 @@ pub fn looks_like_uri(s: &str) -> bool {
     s.starts_with("http://") || s.starts_with("https://") || s.starts_with("mailto:") || s.starts_with("tel:")
 }
 
+pub fn looks_like_uuid(s: &str) -> bool {
+    s.len() == 36
+        && s.bytes().enumerate().all(|(i, b)| match i {
+            8 | 13 | 18 | 23 => b == b'-',
+            _ => b.is_ascii_hexdigit(),
+        })
+}
+
+/// Dot-separated labels of ASCII letters, digits and inner `-` (1 to 63 long), the
+/// last one alphabetic and at least 2 long; bare words and dotted numbers don't count.
+pub fn looks_like_hostname(s: &str) -> bool {
+    let label = |l: &str| {
+        (1..=63).contains(&l.len())
+            && l.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+            && !l.starts_with('-')
+            && !l.ends_with('-')
+    };
+    let mut labels = s.split('.').peekable();
+    let mut n = 0;
+    while let Some(l) = labels.next() {
+        if !label(l) || (labels.peek().is_none() && (n == 0 || l.len() < 2 || !l.bytes().all(|b| b.is_ascii_alphabetic()))) {
+            return false;
+        }
+        n += 1;
+    }
+    true
+}
+
+/// Up to 64 ASCII letters, digits or ``!#$%&'*+/=?^_`{|}~.-``, then `@` and a hostname.
+pub fn looks_like_email(s: &str) -> bool {
+    let Some((local, host)) = s.split_once('@') else { return false };
+    (1..=64).contains(&local.len())
+        && local.bytes().all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+/=?^_`{|}~.-".contains(&b))
+        && looks_like_hostname(host)
+}
+
+pub fn looks_like_hex_blob(s: &str) -> bool {
+    s.len() >= BLOB_MIN_LEN && s.len().is_multiple_of(2) && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Letters-only strings are valid base64 too, but read as text more often than not.
+pub fn looks_like_base64_blob(s: &str) -> bool {
+    let body = s.trim_end_matches('=');
+    s.len() >= BLOB_MIN_LEN
+        && s.len().is_multiple_of(4)
+        && s.len() - body.len() <= 2
+        && body.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'/')
+        && !body.bytes().all(|b| b.is_ascii_alphabetic())
+}
+
+/// An optional `-`, an integer part (plain digits, or grouped by threes with
+/// `,`), `.` and at least one digit. Without the fractional part it would be an
+/// id or a count as often as an amount.
+pub fn looks_like_decimal(s: &str) -> bool {
+    let body = s.strip_prefix('-').unwrap_or(s);
+    let Some((int, frac)) = body.split_once('.') else { return false };
+    let digits = |p: &str| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit());
+    let grouped = || {
+        let mut groups = int.split(',');
+        let first = groups.next().unwrap_or_default();
+        (1..=3).contains(&first.len()) && digits(first) && groups.all(|g| g.len() == 3 && digits(g))
+    };
+    digits(frac) && (digits(int) || grouped())
+}
+
+/// The schema pattern for decimal strings: [`StrFormat::Decimal`]'s, narrowed to
+/// the digits after the point when every one of `lits` has as many, and to plain
+/// integer parts when none is grouped. Unnarrowed without literals.
+pub fn decimal_pattern(lits: &BTreeSet<String>) -> String {
+    let scales: BTreeSet<usize> = lits.iter().filter_map(|s| s.split_once('.')).map(|(_, f)| f.len()).collect();
+    let frac = match (scales.len(), scales.first()) {
+        (1, Some(n)) => format!("[0-9]{{{n}}}"),
+        _ => "[0-9]+".to_string(),
+    };
+    let int = if !lits.is_empty() && lits.iter().all(|s| !s.contains(',')) {
+        "[0-9]+"
+    } else {
+        "([0-9]+|[0-9]{1,3}(,[0-9]{3})+)"
+    };
+    format!(r"^-?{int}\.{frac}$")
+}
+
 pub fn looks_humanish(s: &str) -> bool {
     // lightweight: letters/digits/space/dash/underscore and not too long
     s.len() <= super::STRING_ENUM_MAX_LEN &&
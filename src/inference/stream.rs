@@ -0,0 +1,130 @@
+//! Observe a document while it is parsed, without building a `serde_json::Value`.
+//!
+//! The visitor below produces exactly what `observe_value` would for the same
+//! text, but each array element is joined into its array's evidence as soon as
+//! it is read, so a huge document never exists in memory as a tree: memory
+//! follows the size of the evidence, not of the document.
+
+use std::fmt;
+
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+
+use super::{observe_number, observe_string, ArrC, NumKind, ObjC, ObserveOpts, U};
+
+/// Evidence for one JSON document in `text`.
+pub fn observe_str(text: &str) -> serde_json::Result<U> {
+    observe_str_with(text, ObserveOpts::default())
+}
+
+/// [`observe_str`] with opt-in modes, as `observe_value_with`.
+pub fn observe_str_with(text: &str, opts: ObserveOpts) -> serde_json::Result<U> {
+    let mut de = serde_json::Deserializer::from_str(text);
+    let u = Observe(opts).deserialize(&mut de)?;
+    de.end()?;
+    Ok(u)
+}
+
+/// [`observe_str_with`] for a document read from `reader`: only the parser's
+/// buffer is held, never the whole text.
+pub fn observe_reader_with(reader: impl std::io::Read, opts: ObserveOpts) -> serde_json::Result<U> {
+    let mut de = serde_json::Deserializer::from_reader(reader);
+    let u = Observe(opts).deserialize(&mut de)?;
+    de.end()?;
+    Ok(u)
+}
+
+struct Observe(ObserveOpts);
+
+impl<'de> DeserializeSeed<'de> for Observe {
+    type Value = U;
+
+    fn deserialize<D: Deserializer<'de>>(self, de: D) -> Result<U, D::Error> {
+        de.deserialize_any(self).map(U::counted)
+    }
+}
+
+impl<'de> Visitor<'de> for Observe {
+    type Value = U;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<U, E> {
+        Ok(U { nullable: true, ..U::default() })
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<U, E> {
+        self.visit_unit()
+    }
+
+    fn visit_bool<E: de::Error>(self, _: bool) -> Result<U, E> {
+        Ok(U { has_bool: true, ..U::default() })
+    }
+
+    fn visit_i64<E: de::Error>(self, i: i64) -> Result<U, E> {
+        Ok(observe_number(NumKind::Int(i)))
+    }
+
+    fn visit_u64<E: de::Error>(self, u: u64) -> Result<U, E> {
+        // as `Number::as_i64` first, like `observe_value`
+        let kind = i64::try_from(u).map_or(NumKind::Uint(u), NumKind::Int);
+        Ok(observe_number(kind))
+    }
+
+    fn visit_f64<E: de::Error>(self, f: f64) -> Result<U, E> {
+        Ok(observe_number(NumKind::Float(f)))
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<U, E> {
+        Ok(observe_string(s.to_owned(), self.0))
+    }
+
+    fn visit_string<E: de::Error>(self, s: String) -> Result<U, E> {
+        Ok(observe_string(s, self.0))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<U, A::Error> {
+        if self.0.too_deep() {
+            while seq.next_element::<de::IgnoredAny>()?.is_some() {}
+            return Ok(U::opaque());
+        }
+        let opts = self.0.below();
+        let mut arr = ArrC { samples: 1, ..ArrC::default() };
+        let mut item = U::empty();
+        let (mut len, max) = (0u32, super::caps::max_tuple_arity());
+        while let Some(el) = seq.next_element_seed(Observe(opts))? {
+            len += 1;
+            if len as usize <= max {
+                arr.non_null.push(u64::from(!el.observed_null(opts)));
+                arr.present.push(1);
+                arr.cols.push(U::join_inner(&U::empty(), &el));
+            } else if len as usize == max + 1 {
+                // too long to be a tuple
+                (arr.cols, arr.present, arr.non_null) = Default::default();
+            }
+            item = U::join_inner(&item, &el);
+        }
+        arr.len_min = len;
+        arr.len_max = len;
+        arr.item = Box::new(item);
+        Ok(U { arr: Some(arr), ..U::default() })
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<U, A::Error> {
+        if self.0.too_deep() {
+            while map.next_entry::<de::IgnoredAny, de::IgnoredAny>()?.is_some() {}
+            return Ok(U::opaque());
+        }
+        let opts = self.0.below();
+        let mut obj = ObjC { seen_objects: 1, ..ObjC::default() };
+        while let Some(k) = map.next_key::<String>()? {
+            let ty = map.next_value_seed(Observe(opts))?;
+            let non_null = !ty.observed_null(opts);
+            obj.observe_field(k, ty, non_null, opts);
+        }
+        obj.note_shape();
+        obj.note_tags();
+        Ok(U { obj: Some(obj), ..U::default() })
+    }
+}
@@ -0,0 +1,178 @@
+//! Unicode normalization of keys and string literals (`--normalize-unicode`).
+//!
+//! `"café"` typed on a Mac and `"café"` pasted from a web page can differ in
+//! bytes: a precomposed `é` against `e` and a combining acute accent. Keys also
+//! pick up zero-width spaces and joiners, or a byte order mark, from copy and
+//! paste. Each spelling would be a field of its own. [`normalize`] drops the
+//! invisible characters ([`INVISIBLE`]) and puts the rest in Normalization Form
+//! C (canonical composition, [UAX #15]), so they are all the same key.
+//!
+//! [UAX #15]: https://www.unicode.org/reports/tr15/
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use super::unicode_tables::{COMBINING_CLASSES, DECOMPOSITIONS, NO_COMPOSITION};
+
+/// Characters without a glyph that [`normalize`] drops: zero-width space,
+/// non-joiner and joiner, word joiner, byte order mark, soft hyphen.
+pub const INVISIBLE: [char; 6] = ['\u{200B}', '\u{200C}', '\u{200D}', '\u{2060}', '\u{FEFF}', '\u{AD}'];
+
+/// `s` without [`INVISIBLE`] characters, in NFC; borrowed when already so.
+pub fn normalize(s: &str) -> Cow<'_, str> {
+    if s.is_ascii() || is_normal(s) {
+        return Cow::Borrowed(s);
+    }
+    let mut chars: Vec<char> = Vec::with_capacity(s.len());
+    for c in s.chars().filter(|c| !INVISIBLE.contains(c)) {
+        decompose(c, &mut chars);
+    }
+    reorder(&mut chars);
+    compose(&mut chars);
+    Cow::Owned(chars.into_iter().collect())
+}
+
+/// Nothing to drop, decompose or compose: no invisible characters, and only
+/// code points that are their own decomposition and never combine.
+fn is_normal(s: &str) -> bool {
+    s.chars().all(|c| {
+        c.is_ascii()
+            || (!INVISIBLE.contains(&c)
+                && !is_hangul(c)
+                && !(HANGUL_L..HANGUL_L + 19).contains(&(c as u32))
+                && decomposition(c).is_none()
+                && combining_class(c) == 0
+                && !composes(c))
+    })
+}
+
+// Hangul syllables are composed from their jamo arithmetically.
+const HANGUL_S: u32 = 0xAC00;
+const HANGUL_L: u32 = 0x1100;
+const HANGUL_V: u32 = 0x1161;
+const HANGUL_T: u32 = 0x11A7;
+const HANGUL_V_COUNT: u32 = 21;
+const HANGUL_T_COUNT: u32 = 28;
+const HANGUL_N_COUNT: u32 = HANGUL_V_COUNT * HANGUL_T_COUNT;
+const HANGUL_S_COUNT: u32 = 19 * HANGUL_N_COUNT;
+
+fn is_hangul(c: char) -> bool {
+    (HANGUL_S..HANGUL_S + HANGUL_S_COUNT).contains(&(c as u32))
+}
+
+fn decomposition(c: char) -> Option<(u32, u32)> {
+    let i = DECOMPOSITIONS.binary_search_by_key(&(c as u32), |d| d.0).ok()?;
+    Some((DECOMPOSITIONS[i].1, DECOMPOSITIONS[i].2))
+}
+
+fn combining_class(c: char) -> u8 {
+    let c = c as u32;
+    match COMBINING_CLASSES.binary_search_by(|&(lo, hi, _)| {
+        if hi < c {
+            std::cmp::Ordering::Less
+        } else if lo > c {
+            std::cmp::Ordering::Greater
+        } else {
+            std::cmp::Ordering::Equal
+        }
+    }) {
+        Ok(i) => COMBINING_CLASSES[i].2,
+        Err(_) => 0,
+    }
+}
+
+/// Pairs that compose, to what.
+fn compositions() -> &'static HashMap<(u32, u32), u32> {
+    static COMPOSITIONS: OnceLock<HashMap<(u32, u32), u32>> = OnceLock::new();
+    COMPOSITIONS.get_or_init(|| {
+        DECOMPOSITIONS
+            .iter()
+            .filter(|&&(c, _, second)| second != 0 && NO_COMPOSITION.binary_search(&c).is_err())
+            .map(|&(c, first, second)| ((first, second), c))
+            .collect()
+    })
+}
+
+/// Whether `c` is the second of a composing pair.
+fn composes(c: char) -> bool {
+    static SECONDS: OnceLock<std::collections::HashSet<u32>> = OnceLock::new();
+    SECONDS.get_or_init(|| compositions().keys().map(|&(_, second)| second).collect()).contains(&(c as u32))
+}
+
+/// Full canonical decomposition of `c`, appended to `out`.
+fn decompose(c: char, out: &mut Vec<char>) {
+    if is_hangul(c) {
+        let s = c as u32 - HANGUL_S;
+        let t = s % HANGUL_T_COUNT;
+        out.extend(char::from_u32(HANGUL_L + s / HANGUL_N_COUNT));
+        out.extend(char::from_u32(HANGUL_V + (s % HANGUL_N_COUNT) / HANGUL_T_COUNT));
+        if t != 0 {
+            out.extend(char::from_u32(HANGUL_T + t));
+        }
+        return;
+    }
+    match decomposition(c) {
+        Some((first, second)) => {
+            for part in [first, second].into_iter().filter(|&p| p != 0).filter_map(char::from_u32) {
+                decompose(part, out);
+            }
+        }
+        None => out.push(c),
+    }
+}
+
+/// Canonical ordering: each run of non-starters sorted by combining class, stably.
+fn reorder(chars: &mut [char]) {
+    let mut i = 0;
+    while i < chars.len() {
+        if combining_class(chars[i]) == 0 {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < chars.len() && combining_class(chars[i]) != 0 {
+            i += 1;
+        }
+        chars[start..i].sort_by_key(|&c| combining_class(c));
+    }
+}
+
+/// Canonical composition, in place.
+fn compose(chars: &mut Vec<char>) {
+    let table = compositions();
+    let mut out: Vec<char> = Vec::with_capacity(chars.len());
+    // index in `out` of the last starter, and the class of the last character after it
+    let mut starter: Option<usize> = None;
+    let mut last_class = 0u8;
+    for &c in chars.iter() {
+        let class = combining_class(c);
+        if let Some(s) = starter {
+            let blocked = out.len() > s + 1 && (last_class == 0 || last_class >= class);
+            if !blocked && let Some(composed) = pair(table, out[s], c) {
+                out[s] = composed;
+                continue;
+            }
+        }
+        if class == 0 {
+            starter = Some(out.len());
+        }
+        last_class = class;
+        out.push(c);
+    }
+    *chars = out;
+}
+
+fn pair(table: &HashMap<(u32, u32), u32>, a: char, b: char) -> Option<char> {
+    let (a, b) = (a as u32, b as u32);
+    if (HANGUL_L..HANGUL_L + 19).contains(&a) && (HANGUL_V..HANGUL_V + HANGUL_V_COUNT).contains(&b) {
+        return char::from_u32(HANGUL_S + ((a - HANGUL_L) * HANGUL_V_COUNT + (b - HANGUL_V)) * HANGUL_T_COUNT);
+    }
+    if (HANGUL_S..HANGUL_S + HANGUL_S_COUNT).contains(&a)
+        && (a - HANGUL_S).is_multiple_of(HANGUL_T_COUNT)
+        && (HANGUL_T + 1..HANGUL_T + HANGUL_T_COUNT).contains(&b)
+    {
+        return char::from_u32(a + (b - HANGUL_T));
+    }
+    table.get(&(a, b)).and_then(|&c| char::from_u32(c))
+}
@@ -0,0 +1,626 @@
+//! The read side of `gen`: inputs → documents → evidence (`U`) and the side
+//! statistics options ask for, spread over every core and tree-reduced.
+//!
+//! NDJSON lines, `--separator` records and sampled documents are cut into tasks
+//! so one big input doesn't leave the other cores idle. Ctrl-C stops at the
+//! next document and the finished files go into a checkpoint (`--resume`).
+//!
+//! Memory: NDJSON and single-document inputs are read through a buffer of a
+//! few [`crate::inputs::CHUNK_BYTES`] pieces per core, hashed as they go.
+//! `--separator` and `--max-samples` / `--max-samples-per-input` index the
+//! documents of the whole text, so they hold each file in memory. Without
+//! `--jq-expr` or side statistics a document is observed while it is parsed;
+//! with them each document is built as a `Value` first, so memory also follows
+//! the largest document.
+
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use colored::Colorize;
+use rayon::prelude::*;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::cli::{log_info, log_warn, log_error, progress};
+use crate::columns::{ColumnStats, NullGroups};
+use crate::discriminator::DiscTracker;
+use crate::distribution::NumericStats;
+use crate::events::Event;
+use crate::formats::FormatStats;
+use crate::inference::{observe_value_with, ObserveOpts, U};
+use crate::jpath::JPath;
+use crate::manifest::{Checkpoint, DoneFile, IngestSettings};
+use crate::pii::PiiStats;
+use crate::provenance::{Origin, Provenance};
+use crate::record_key::KeySniffer;
+use crate::report::{Diagnostic, ParseFailures};
+use crate::sentinel::MarkerStats;
+use crate::size::SizeStats;
+use crate::timeline::Timeline;
+
+/// Side trackers that run next to evidence gathering, each only when an option asks for it.
+#[derive(Debug, Clone, Default)]
+pub struct Trackers {
+    /// `--key auto`
+    pub keys: bool,
+    /// `--column-stats`
+    pub columns: bool,
+    /// `--histograms` or `--sentinels`
+    pub numbers: bool,
+    /// `--histograms`: numeric summaries go into the report
+    pub histograms: bool,
+    /// `--sentinels`
+    pub markers: bool,
+    /// `--column-stats` or `--group-nullables`
+    pub null_groups: bool,
+    /// `--schema-conditionals`
+    pub discriminator: bool,
+    /// `--timestamp`, `--timestamp-window`
+    pub timestamp: Option<(JPath, crate::timeline::Window)>,
+    /// `--size-estimate`
+    pub sizes: bool,
+    /// `--pii`
+    pub pii: bool,
+    /// `--formats`
+    pub formats: Option<crate::formats::Formats>,
+    /// `--evidence`: hand back the joined evidence, not just its id
+    pub evidence: bool,
+    /// `--provenance` (works from the evidence, so streaming stays possible)
+    pub provenance: bool,
+    /// Keep up to this many first documents: test fixtures for `--rust-crate`,
+    /// inputs for `--revalidate` (0: none)
+    pub samples: usize,
+}
+
+impl Trackers {
+    /// Whether any side statistic (anything beyond the evidence itself) is gathered.
+    pub fn any(&self) -> bool {
+        let Trackers { keys, columns, numbers, histograms, markers, null_groups, discriminator, timestamp, sizes, pii, formats, evidence: _, provenance: _, samples } = self;
+        *keys || *columns || *numbers || *histograms || *markers || *null_groups || *discriminator
+            || timestamp.is_some() || *sizes || *pii || formats.is_some() || *samples > 0
+    }
+}
+
+/// Everything a worker learns from a slice of the input; joined like `U`.
+#[derive(Debug, Default)]
+pub struct Observed {
+    pub u: U,
+    pub docs: u64,
+    pub extra: Extra,
+}
+
+/// Tracker state, joined alongside `U`.
+#[derive(Debug, Default)]
+pub struct Extra {
+    pub keys: Option<KeySniffer>,
+    pub columns: Option<ColumnStats>,
+    pub numbers: Option<NumericStats>,
+    pub markers: Option<MarkerStats>,
+    pub null_groups: Option<NullGroups>,
+    pub disc: Option<DiscTracker>,
+    pub timeline: Option<Timeline>,
+    pub sizes: Option<SizeStats>,
+    pub pii: Option<PiiStats>,
+    pub formats: Option<FormatStats>,
+    pub samples: Option<Samples>,
+    pub provenance: Option<Provenance>,
+    /// Documents that could not be read, skipped.
+    pub failed: Option<ParseFailures>,
+    /// Documents `--jq-expr` yielded nothing for.
+    pub jq_empty: u64,
+}
+
+/// First documents in input order, at most `cap`.
+#[derive(Debug, Default)]
+pub struct Samples {
+    pub cap: usize,
+    pub docs: Vec<Value>,
+}
+
+impl Observed {
+    pub fn observe(v: &Value, trackers: &Trackers, opts: ObserveOpts) -> Self {
+        let keys = trackers.keys.then(|| {
+            let mut k = KeySniffer::default();
+            k.observe(v);
+            k
+        });
+        let columns = trackers.columns.then(|| {
+            let mut c = ColumnStats::default();
+            c.observe(v);
+            c
+        });
+        let numbers = trackers.numbers.then(|| {
+            let mut n = NumericStats::default();
+            n.observe(v);
+            n
+        });
+        let markers = trackers.markers.then(|| {
+            let mut m = MarkerStats::default();
+            m.observe(v);
+            m
+        });
+        let null_groups = trackers.null_groups.then(|| {
+            let mut g = NullGroups::default();
+            g.observe(v);
+            g
+        });
+        let disc = trackers.discriminator.then(|| {
+            let mut d = DiscTracker::default();
+            d.observe(v);
+            d
+        });
+        let timeline = trackers.timestamp.as_ref().map(|(at, window)| {
+            let mut t = Timeline::new(at.clone(), *window);
+            t.observe(v);
+            t
+        });
+        let sizes = trackers.sizes.then(|| {
+            let mut s = SizeStats::default();
+            s.observe(v);
+            s
+        });
+        let pii = trackers.pii.then(|| {
+            let mut p = PiiStats::default();
+            p.observe(v);
+            p
+        });
+        let formats = trackers.formats.as_ref().map(|f| {
+            let mut s = FormatStats::default();
+            s.observe(v, f);
+            s
+        });
+        let samples = (trackers.samples > 0).then(|| Samples { cap: trackers.samples, docs: vec![v.clone()] });
+        Observed {
+            u: observe_value_with(v, opts),
+            docs: 1,
+            extra: Extra { keys, columns, numbers, markers, null_groups, disc, timeline, sizes, pii, formats, samples, provenance: None, failed: None, jq_empty: 0 },
+        }
+    }
+
+    /// Stands in for a document of `file` (at `line`) that could not be read: no evidence, one failure.
+    pub fn failed(file: &str, line: Option<u64>, error: String) -> Self {
+        let failed = ParseFailures::one(Diagnostic { file: file.to_string(), line, path: None, message: error });
+        Observed { extra: Extra { failed: Some(failed), ..Extra::default() }, ..Observed::default() }
+    }
+
+    pub fn join(a: Self, b: Self) -> Self {
+        Observed {
+            u: U::join(&a.u, &b.u),
+            docs: a.docs + b.docs,
+            extra: Extra::join(a.extra, b.extra),
+        }
+    }
+}
+
+impl Extra {
+    pub fn join(a: Self, b: Self) -> Self {
+        fn opt<T>(a: Option<T>, b: Option<T>, f: impl FnOnce(T, T) -> T) -> Option<T> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(f(a, b)),
+                (a, b) => a.or(b),
+            }
+        }
+        Extra {
+            keys: opt(a.keys, b.keys, KeySniffer::join),
+            columns: opt(a.columns, b.columns, ColumnStats::join),
+            numbers: opt(a.numbers, b.numbers, NumericStats::join),
+            markers: opt(a.markers, b.markers, MarkerStats::join),
+            null_groups: opt(a.null_groups, b.null_groups, NullGroups::join),
+            disc: opt(a.disc, b.disc, DiscTracker::join),
+            timeline: opt(a.timeline, b.timeline, Timeline::join),
+            sizes: opt(a.sizes, b.sizes, SizeStats::join),
+            pii: opt(a.pii, b.pii, PiiStats::join),
+            formats: opt(a.formats, b.formats, FormatStats::join),
+            samples: opt(a.samples, b.samples, |mut a, b| {
+                a.docs.extend(b.docs.into_iter().take(a.cap.saturating_sub(a.docs.len())));
+                a
+            }),
+            provenance: opt(a.provenance, b.provenance, Provenance::join),
+            failed: opt(a.failed, b.failed, ParseFailures::join),
+            jq_empty: a.jq_empty + b.jq_empty,
+        }
+    }
+}
+
+/// Fewest `--separator` documents a worker takes at a time.
+const RECORDS_PER_TASK: usize = 256;
+
+/// Standard input, when counting the documents for `--max-samples` read it
+/// before the pipeline does.
+static STDIN: Mutex<Option<String>> = Mutex::new(None);
+
+/// The text of an input ('-' for stdin).
+fn read_source(path: &Path, path_str: &str) -> String {
+    if path.as_os_str() == "-" {
+        if let Some(buf) = STDIN.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            return buf;
+        }
+        let mut buf = String::new();
+        io::stdin().read_to_string(&mut buf).expect("failed to read stdin");
+        buf
+    } else {
+        std::fs::read_to_string(path).unwrap_or_else(|e| panic!("read failed ({path_str}): {e}"))
+    }
+}
+
+/// An input opened for reading ('-' for stdin).
+fn open(path: &Path, path_str: &str) -> Box<dyn Read + Send> {
+    if path.as_os_str() == "-" {
+        Box::new(io::stdin())
+    } else {
+        Box::new(std::fs::File::open(path).unwrap_or_else(|e| panic!("read failed ({path_str}): {e}")))
+    }
+}
+
+/// Counts and hashes what passes through, for the manifest of an input that
+/// is never held whole.
+struct Hashed<R> {
+    inner: R,
+    bytes: u64,
+    hasher: Sha256,
+}
+
+impl<R: Read> Hashed<R> {
+    fn new(inner: R) -> Self {
+        Hashed { inner, bytes: 0, hasher: Sha256::new() }
+    }
+
+    /// Bytes read and their [`crate::manifest::sha256`].
+    fn finish(self) -> (u64, String) {
+        (self.bytes, crate::manifest::sha256_of(self.hasher))
+    }
+}
+
+impl<R: Read> Read for Hashed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.bytes += n as u64;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+/// The documents of `src` with the (1-based) line each starts on: its
+/// non-blank lines for NDJSON, its records for `--separator`, else all of it.
+fn documents(src: &str, ndjson: bool, separator: Option<crate::inputs::Separator>) -> Vec<(Option<usize>, &str)> {
+    if let Some(sep) = separator {
+        crate::inputs::records(src, sep).into_iter().map(|(line, text)| (Some(line), text)).collect()
+    } else if ndjson {
+        src.lines()
+            .enumerate()
+            .map(|(i, line)| (Some(i + 1), line.trim()))
+            .filter(|(_, line)| !line.is_empty())
+            .collect()
+    } else {
+        vec![(None, src)]
+    }
+}
+
+/// Read every input into evidence and tracker state, one entry per file in
+/// input order; `None` for files Ctrl-C kept from finishing. Files in `done`
+/// (`--resume`) are checked unchanged and not read again.
+pub fn read(
+    source_paths: &[PathBuf],
+    ingest: &IngestSettings,
+    require_match: bool,
+    trackers: &Trackers,
+    done: &BTreeMap<&str, &DoneFile>,
+) -> Vec<Option<PerFile>> {
+    let ndjson = ingest.ndjson;
+    let separator = ingest.separator;
+    let jq_expr = ingest.jq_expr.clone();
+    let opts = ingest.observe_opts();
+
+    // --max-samples picks over all inputs, so it needs every input's count first
+    let global_picks = ingest.sampling.filter(|s| s.max.is_some()).map(|sampling| {
+        let counts: Vec<(String, u64)> = source_paths
+            .par_iter()
+            .map(|path| {
+                let path_str = crate::portable::path_str(path);
+                let docs = if ndjson || separator.is_some() {
+                    let src = read_source(path, &path_str);
+                    let docs = documents(&src, ndjson, separator).len() as u64;
+                    if path.as_os_str() == "-" {
+                        *STDIN.lock().unwrap_or_else(|e| e.into_inner()) = Some(src);
+                    }
+                    docs
+                } else {
+                    1
+                };
+                (path_str, docs)
+            })
+            .collect();
+        let picks = sampling.select(&counts);
+        let total: u64 = counts.iter().map(|(_, n)| n).sum();
+        let kept: u64 = picks.iter().zip(&counts).map(|(p, (_, n))| p.as_ref().map_or(*n, |p| p.len() as u64)).sum();
+        log_info(format!("sampling {} of {} document(s) ({})", kept, total, sampling.describe()));
+        picks
+    });
+
+    source_paths
+        .par_iter()
+        .enumerate()
+        .map(|(index, path)| {
+            // lossy: only used for messages and the manifest (the same on every platform)
+            let path_str = crate::portable::path_str(path);
+
+            if let Some(done) = done.get(path_str.as_str()) {
+                let mut input = Hashed::new(open(path, &path_str));
+                io::copy(&mut input, &mut io::sink()).unwrap_or_else(|e| panic!("read failed ({path_str}): {e}"));
+                if input.finish().1 != done.sha256 {
+                    log_error(format!("--resume: {path_str} changed since the interrupted run; start over without --resume"));
+                    std::process::exit(2);
+                }
+                progress(format!("  ❍ resumed: {}", path_str.green()));
+                let signature = done
+                    .signature
+                    .iter()
+                    .map(|(p, kinds)| {
+                        let kinds = kinds.iter().filter_map(|k| crate::report::KINDS.iter().find(|s| *s == k).copied());
+                        (p.clone(), kinds.collect())
+                    })
+                    .collect();
+                return Some(((U::empty(), Extra::default()), (path_str, done.docs, done.bytes, signature, done.sha256.clone())));
+            }
+            if interrupted() {
+                return None;
+            }
+            if let Some(jq_filter) = jq_expr.as_ref() {
+                progress(format!(
+                    "  ❍ processing: {} » '{}'",
+                    path.to_string_lossy().green(),
+                    jq_filter.blue()
+                ));
+            } else {
+                progress(format!(
+                    "  ❍ processing: {}",
+                    path.to_string_lossy().green(),
+                ));
+            }
+
+            fn apply_sources(
+                jq_expr: Option<&String>,
+                require_match: bool,
+                input: &Value,
+                path_str: &str,
+                line: &(dyn Fn() -> Option<u64> + Sync),
+                trackers: &Trackers,
+                opts: ObserveOpts,
+            ) -> Observed {
+                let Some(expr) = jq_expr else {
+                    return Observed::observe(input, trackers, opts);
+                };
+                let outputs = match jq_documents(expr, input, require_match) {
+                    Ok(outputs) => outputs,
+                    Err(e) => return Observed::failed(path_str, line(), e),
+                };
+                if outputs.is_empty() {
+                    return Observed { extra: Extra { jq_empty: 1, ..Extra::default() }, ..Observed::default() };
+                }
+                outputs
+                    .into_par_iter()
+                    .map(|pv| Observed::observe(&pv, trackers, opts))
+                    .reduce(
+                        Observed::default,
+                        Observed::join
+                    )
+            }
+            // Without jq or side trackers nothing needs the `Value`, so evidence is
+            // built while parsing and documents never exist as trees.
+            // A document that doesn't parse is counted as failed and skipped.
+            let streaming = jq_expr.is_none() && !trackers.any();
+            let observe_doc = |text: &str, line: &(dyn Fn() -> Option<u64> + Sync)| -> Observed {
+                if streaming {
+                    return match crate::inference::observe_str_with(text, opts) {
+                        Ok(u) => Observed { u, docs: 1, extra: Extra::default() },
+                        Err(e) => Observed::failed(&path_str, line(), e.to_string()),
+                    };
+                }
+                match serde_json::from_str::<Value>(text) {
+                    Ok(v) => apply_sources(jq_expr.as_ref(), require_match, &v, &path_str, line, trackers, opts),
+                    Err(e) => Observed::failed(&path_str, line(), e.to_string()),
+                }
+            };
+            let with_provenance = |mut o: Observed, line: Option<u64>| -> Observed {
+                if trackers.provenance && !o.u.is_bottom() {
+                    let origin = Origin { file: path_str.clone(), line };
+                    o.extra.provenance = Some(Provenance::of(&o.u, &origin));
+                }
+                o
+            };
+            let observe_text = |text: &str, line: &(dyn Fn() -> Option<u64> + Sync)| -> Observed {
+                with_provenance(observe_doc(text, line), line())
+            };
+            // set when Ctrl-C stops a file part-way; it is then read again on --resume
+            let cut = AtomicBool::new(false);
+            let more = || {
+                let stop = interrupted();
+                if stop {
+                    cut.store(true, Ordering::Relaxed);
+                }
+                !stop
+            };
+            // Big files are split so their documents spread over every core (and
+            // the partial evidence tree-reduced) instead of one core folding them
+            // while the rest idle: wall-clock time follows total bytes.
+            let (Observed { u, docs, extra }, bytes, digest) = if ingest.sampling.is_some() || separator.is_some() {
+                // sampling and `--separator` index documents over the whole text
+                let src = read_source(path, &path_str);
+                let digest = crate::manifest::sha256(src.as_bytes());
+                let observed = if let Some(sampling) = ingest.sampling {
+                    let all = documents(&src, ndjson, separator);
+                    let picks = match global_picks.as_ref() {
+                        Some(picks) => picks[index].clone(),
+                        None => sampling.select(&[(path_str.clone(), all.len() as u64)]).pop().flatten(),
+                    };
+                    let picked: Vec<(Option<usize>, &str)> = match picks {
+                        Some(picks) => picks.iter().map(|&i| all[i as usize]).collect(),
+                        None => all,
+                    };
+                    picked
+                        .into_par_iter()
+                        .with_min_len(RECORDS_PER_TASK)
+                        .filter(|_| more())
+                        .map(|(line, text)| observe_text(text, &|| line.map(|l| l as u64)))
+                        .reduce(
+                            Observed::default,
+                            Observed::join
+                        )
+                } else {
+                    crate::inputs::records(&src, separator.expect("checked above"))
+                        .into_par_iter()
+                        .with_min_len(RECORDS_PER_TASK)
+                        .filter(|_| more())
+                        .map(|(line, text)| observe_text(text, &|| Some(line as u64)))
+                        .reduce(
+                            Observed::default,
+                            Observed::join
+                        )
+                };
+                (observed, src.len() as u64, digest)
+            } else {
+                let mut input = io::BufReader::new(Hashed::new(open(path, &path_str)));
+                let observed = if ndjson {
+                    // a few chunks per core at a time, so memory follows the core
+                    // count and CHUNK_BYTES rather than the size of the file
+                    let mut chunks = crate::inputs::line_chunks(&mut input, crate::inputs::CHUNK_BYTES);
+                    let batch = 2 * rayon::current_num_threads();
+                    let mut observed = Observed::default();
+                    while more() {
+                        let read = chunks
+                            .by_ref()
+                            .take(batch)
+                            .collect::<io::Result<Vec<_>>>()
+                            .unwrap_or_else(|e| panic!("read failed ({path_str}): {e}"));
+                        if read.is_empty() {
+                            break;
+                        }
+                        let part = read
+                            .into_par_iter()
+                            .map(|(first_line, chunk)| {
+                                chunk
+                                    .lines()
+                                    .enumerate()
+                                    .take_while(|_| more())
+                                    .filter_map(|(i, line)| {
+                                        let line = line.trim();
+                                        if line.is_empty() {
+                                            return None
+                                        }
+                                        Some(observe_text(line, &|| Some((first_line + i) as u64)))
+                                    })
+                                    .fold(
+                                        Observed::default(),
+                                        Observed::join
+                                    )
+                            })
+                            .reduce(
+                                Observed::default,
+                                Observed::join
+                            );
+                        observed = Observed::join(observed, part);
+                    }
+                    observed
+                } else if streaming {
+                    let observed = match crate::inference::observe_reader_with(&mut input, opts) {
+                        Ok(u) => Observed { u, docs: 1, extra: Extra::default() },
+                        Err(e) => Observed::failed(&path_str, None, e.to_string()),
+                    };
+                    with_provenance(observed, None)
+                } else {
+                    let observed = match serde_json::from_reader::<_, Value>(&mut input) {
+                        Ok(v) => apply_sources(jq_expr.as_ref(), require_match, &v, &path_str, &|| None, trackers, opts),
+                        Err(e) => Observed::failed(&path_str, None, e.to_string()),
+                    };
+                    with_provenance(observed, None)
+                };
+                // the rest of a document that failed to parse still counts towards the hash
+                io::copy(&mut input, &mut io::sink()).unwrap_or_else(|e| panic!("read failed ({path_str}): {e}"));
+                let (bytes, digest) = input.into_inner().finish();
+                (observed, bytes, digest)
+            };
+            if cut.into_inner() {
+                return None;
+            }
+            let signature = crate::report::signature_of(&u);
+            if crate::events::machine() {
+                crate::events::emit(&Event::FileDone { path: &path_str, docs });
+            }
+            Some(((u, extra), (path_str, docs, bytes, signature, digest)))
+        })
+        .collect()
+}
+
+/// The documents `expr` makes of `input`; none is an error under `--require-match`.
+pub fn jq_documents(expr: &str, input: &Value, require_match: bool) -> Result<Vec<Value>, String> {
+    let outs = crate::jq_exec::run_jaq(expr, input).map_err(|e| format!("jq failed: {e}"))?;
+    if outs.is_empty() && require_match {
+        return Err("--require-match: jq produced no output".to_string());
+    }
+    outs.iter()
+        .map(|t| serde_json::from_str(t).map_err(|e| format!("jq output not JSON: {e}")))
+        .collect()
+}
+
+pub type PerFile = ((U, Extra), (String, u64, u64, crate::report::Signature, String));
+
+/// Write the evidence of every file finished before Ctrl-C (plus what `prior`
+/// already held) to `path`, say how far the run got, and exit.
+pub fn save_checkpoint(path: &Path, ingest: &IngestSettings, prior: U, files: Vec<Option<PerFile>>, total: usize) -> ! {
+    let mut evidence = prior;
+    let mut done = Vec::new();
+    // stdin can't be read again, so a resumed run couldn't check it is unchanged
+    for ((u, _), (path, docs, bytes, signature, sha256)) in files.into_iter().flatten().filter(|(_, f)| f.0 != "-") {
+        evidence = U::join(&evidence, &u);
+        let signature = signature.into_iter().map(|(p, kinds)| (p, kinds.into_iter().map(String::from).collect())).collect();
+        done.push(DoneFile { path, sha256, docs, bytes, signature });
+    }
+    let docs: u64 = done.iter().map(|d| d.docs).sum();
+    let ckpt = Checkpoint {
+        format: crate::manifest::CHECKPOINT_FORMAT.to_string(),
+        tool: crate::manifest::tool(),
+        policy: crate::manifest::policy_hash(),
+        ingest: ingest.clone(),
+        done,
+        evidence,
+    };
+    std::fs::write(path, serde_json::to_string(&ckpt).unwrap())
+        .unwrap_or_else(|e| panic!("failed to write checkpoint ({}): {e}", path.to_string_lossy()));
+    log_warn(format!(
+        "interrupted after {} of {total} file(s), {} document(s); checkpoint saved to {}",
+        ckpt.done.len().to_string().green(),
+        docs.to_string().green(),
+        path.to_string_lossy().blue(),
+    ));
+    log_info(format!("continue with --resume {}", path.to_string_lossy()));
+    std::process::exit(130);
+}
+
+// --------------------------- Interrupts ---------------------------
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Whether Ctrl-C / SIGTERM asked the run to stop.
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+/// The first Ctrl-C (or SIGTERM) lets the current documents finish so a
+/// checkpoint can be saved; a second one exits at once.
+pub fn catch_interrupts() {
+    static ONCE: std::sync::Once = std::sync::Once::new();
+    ONCE.call_once(|| {
+        let installed = ctrlc::set_handler(|| {
+            if INTERRUPTED.swap(true, Ordering::Relaxed) {
+                std::process::exit(130);
+            }
+            log_warn("stopping at the next document; Ctrl-C again to abort");
+        });
+        if let Err(e) = installed {
+            log_warn(format!("Ctrl-C won't save a checkpoint: {e}"));
+        }
+    });
+}
@@ -0,0 +1,432 @@
+//! Resolving `--input` patterns to files.
+//!
+//! Literal paths pass through; glob patterns are expanded by walking from their
+//! literal prefix (`data/2024/*/*.json` walks `data/2024`), one rayon task per
+//! directory, pruning subdirectories that can't match the next component.
+//! Results are sorted per pattern (like `glob`) and de-duplicated across
+//! patterns in first-seen order.
+//!
+//! With `--input-cache FILE`, each pattern's listing is saved with the mtime of
+//! every directory walked. A later run reuses the listing when none of those
+//! mtimes changed (adding, removing or renaming an entry bumps its directory's
+//! mtime), so unchanged trees are only stat'ed per directory, not per file.
+//! Listings with non-UTF-8 paths are walked every time (the cache is JSON).
+//!
+//! Symlinks met while walking are skipped unless `--follow-symlinks` is set;
+//! then symlinked files match like files, and symlinked directories are
+//! descended into unless they lead back to one of their own ancestors.
+//! Literal paths are always read as given.
+//!
+//! A `.json-osi-ignore` file (gitignore syntax: `#` comments, `!` re-includes,
+//! a trailing `/` for directories only, a leading or inner `/` anchors the
+//! pattern to the file's directory) excludes entries from the walk below its
+//! directory. Files are read in the directory a pattern walks from, in every
+//! directory above it up to the current one (relative patterns), and in every
+//! directory walked; later (deeper) files and later lines win, and an ignored
+//! directory is not entered. `--no-ignore` turns this off. The cache keeps the
+//! mtime of every ignore file it read, so editing one invalidates the listing.
+
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use glob::{MatchOptions, Pattern};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub const CACHE_FORMAT: &str = "json-osi-inputs/2";
+
+pub const IGNORE_FILE: &str = ".json-osi-ignore";
+
+/// Same matching as `glob::glob`: `*` stays within one path component.
+const MATCH: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+fn has_glob_chars(s: &str) -> bool {
+    s.bytes().any(|b| matches!(b, b'*' | b'?' | b'[' | b'{'))
+}
+
+/// One pattern's matches plus the directories (and their mtimes) they came from.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Listing {
+    #[serde(default)]
+    follow_symlinks: bool,
+    #[serde(default)]
+    no_ignore: bool,
+    dirs: Vec<(PathBuf, u128)>,
+    /// Every ignore file read, with its mtime.
+    #[serde(default)]
+    ignore_files: Vec<(PathBuf, u128)>,
+    files: Vec<PathBuf>,
+    /// Entries the walk would have listed or entered but an ignore file excluded.
+    #[serde(default)]
+    ignored: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Cache {
+    format: String,
+    patterns: BTreeMap<String, Listing>,
+}
+
+fn mtime(path: &Path) -> Option<u128> {
+    let m = std::fs::metadata(on_disk(path)).ok()?.modified().ok()?;
+    Some(m.duration_since(UNIX_EPOCH).ok()?.as_nanos())
+}
+
+/// The empty prefix of a relative pattern is the current directory.
+fn on_disk(dir: &Path) -> &Path {
+    if dir.as_os_str().is_empty() { Path::new(".") } else { dir }
+}
+
+/// One line of an ignore file.
+#[derive(Debug, Clone)]
+struct Rule {
+    /// Directory of the ignore file; anchored patterns match below it.
+    dir: PathBuf,
+    pattern: Pattern,
+    negate: bool,
+    dir_only: bool,
+    /// Matched against the path below `dir` rather than the entry's name.
+    anchored: bool,
+}
+
+impl Rule {
+    fn parse(dir: &Path, line: &str) -> Option<Rule> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negate, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        let pattern = Pattern::new(line.strip_prefix('/').unwrap_or(line)).ok()?;
+        Some(Rule { dir: dir.to_path_buf(), pattern, negate, dir_only, anchored })
+    }
+
+    fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let Ok(rel) = path.strip_prefix(&self.dir) else {
+            return false;
+        };
+        if self.anchored {
+            self.pattern.matches_with(&rel.to_string_lossy(), MATCH)
+        } else {
+            rel.file_name().is_some_and(|n| self.pattern.matches_with(&n.to_string_lossy(), MATCH))
+        }
+    }
+}
+
+/// Whether the last rule matching `path` excludes it.
+fn ignored(rules: &[Rule], path: &Path, is_dir: bool) -> bool {
+    rules.iter().rev().find(|r| r.matches(path, is_dir)).is_some_and(|r| !r.negate)
+}
+
+/// The rules of `dir`'s ignore file, if it has one, recording the file in `listing`.
+fn read_ignore(dir: &Path, listing: &mut Listing) -> Vec<Rule> {
+    let file = dir.join(IGNORE_FILE);
+    let Ok(src) = std::fs::read_to_string(on_disk(&file)) else {
+        return Vec::new();
+    };
+    if let Some(m) = mtime(&file) {
+        listing.ignore_files.push((file, m));
+    }
+    src.lines().filter_map(|l| Rule::parse(dir, l)).collect()
+}
+
+/// A glob pattern split into its literal directory prefix and the components after it.
+struct Walk {
+    base: PathBuf,
+    /// Per-component patterns below `base`; `None` for `**`.
+    levels: Vec<Option<Pattern>>,
+    full: Pattern,
+    follow_symlinks: bool,
+    no_ignore: bool,
+}
+
+impl Walk {
+    fn new(raw: &str, follow_symlinks: bool, no_ignore: bool) -> Result<Self, glob::PatternError> {
+        let mut base = PathBuf::new();
+        let mut levels = Vec::new();
+        for c in Path::new(raw).components() {
+            let s = c.as_os_str().to_string_lossy();
+            if levels.is_empty() && (!has_glob_chars(&s) || matches!(c, Component::Prefix(_) | Component::RootDir)) {
+                base.push(c);
+            } else if s == "**" {
+                levels.push(None);
+            } else {
+                levels.push(Some(Pattern::new(&s)?));
+            }
+        }
+        Ok(Walk { base, levels, full: Pattern::new(raw)?, follow_symlinks, no_ignore })
+    }
+
+    /// Whether an entry at `depth` (1 = directly under `base`) named `name` can lead to a match.
+    fn may_descend(&self, depth: usize, name: &str) -> bool {
+        match self.levels.iter().position(Option::is_none) {
+            // before any `**`, each component must match its own pattern
+            Some(star) if depth <= star => self.levels[depth - 1].as_ref().is_some_and(|p| p.matches_with(name, MATCH)),
+            Some(_) => true,
+            None => depth < self.levels.len() && self.levels[depth - 1].as_ref().is_some_and(|p| p.matches_with(name, MATCH)),
+        }
+    }
+
+    fn run(&self) -> Listing {
+        // ignore files above `base`, outermost first; their directories' mtimes
+        // go into the listing so a new ignore file there is noticed
+        let mut above = Listing::default();
+        let mut rules = Vec::new();
+        if !self.no_ignore && self.base.is_relative() {
+            let mut dirs: Vec<&Path> = self.base.ancestors().skip(1).collect();
+            dirs.reverse();
+            for dir in dirs {
+                if let Some(m) = mtime(dir) {
+                    above.dirs.push((dir.to_path_buf(), m));
+                }
+                rules.extend(read_ignore(dir, &mut above));
+            }
+        }
+        // an ignored directory on the way down hides the whole walk
+        let hidden = !self.no_ignore
+            && self.base.ancestors().any(|d| !d.as_os_str().is_empty() && ignored(&rules, d, true));
+        let mut out = if hidden {
+            Listing { ignored: 1, ..Listing::default() }
+        } else {
+            self.dir(&self.base, 0, &[], &rules)
+        };
+        out.follow_symlinks = self.follow_symlinks;
+        out.no_ignore = self.no_ignore;
+        out.dirs.splice(0..0, above.dirs);
+        out.ignore_files.splice(0..0, above.ignore_files);
+        out.files.sort();
+        out
+    }
+
+    /// `ancestors` are the canonical paths above `dir` (only tracked when following symlinks);
+    /// `rules` come from the ignore files above `dir`.
+    fn dir(&self, dir: &Path, depth: usize, ancestors: &[PathBuf], rules: &[Rule]) -> Listing {
+        let Ok(rd) = std::fs::read_dir(on_disk(dir)) else {
+            return Listing::default();
+        };
+        let mut listing = Listing::default();
+        if let Some(m) = mtime(dir) {
+            listing.dirs.push((dir.to_path_buf(), m));
+        }
+        let mut rules = rules.to_vec();
+        if !self.no_ignore {
+            rules.extend(read_ignore(dir, &mut listing));
+        }
+        let mut subdirs = Vec::new();
+        for entry in rd.flatten() {
+            let path = dir.join(entry.file_name());
+            let is_dir = match entry.file_type() {
+                Ok(t) if t.is_symlink() => {
+                    if !self.follow_symlinks {
+                        continue;
+                    }
+                    std::fs::metadata(&path).map(|m| m.is_dir()).unwrap_or(false)
+                }
+                Ok(t) => t.is_dir(),
+                Err(_) => false,
+            };
+            let wanted = if is_dir {
+                self.may_descend(depth + 1, &entry.file_name().to_string_lossy())
+            } else {
+                self.full.matches_with(&path.to_string_lossy(), MATCH)
+                    && (self.no_ignore || entry.file_name() != IGNORE_FILE)
+            };
+            if !wanted {
+                continue;
+            }
+            if ignored(&rules, &path, is_dir) {
+                listing.ignored += 1;
+            } else if is_dir {
+                subdirs.push(path);
+            } else {
+                listing.files.push(path);
+            }
+        }
+        let mut below = ancestors.to_vec();
+        if self.follow_symlinks && let Ok(here) = std::fs::canonicalize(on_disk(dir)) {
+            below.push(here);
+        }
+        subdirs
+            .par_iter()
+            .filter(|d| {
+                // a link back up the tree would walk forever
+                !self.follow_symlinks || std::fs::canonicalize(d).is_ok_and(|c| !below.contains(&c))
+            })
+            .map(|d| self.dir(d, depth + 1, &below, &rules))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .fold(listing, |mut acc, l| {
+                acc.dirs.extend(l.dirs);
+                acc.ignore_files.extend(l.ignore_files);
+                acc.files.extend(l.files);
+                acc.ignored += l.ignored;
+                acc
+            })
+    }
+}
+
+/// Whether every directory and ignore file behind `l` still has the mtime it was listed with.
+fn fresh(l: &Listing, follow_symlinks: bool, no_ignore: bool) -> bool {
+    l.follow_symlinks == follow_symlinks
+        && l.no_ignore == no_ignore
+        && l.dirs.par_iter().chain(l.ignore_files.par_iter()).all(|(p, m)| mtime(p) == Some(*m))
+}
+
+/// Input files resolved from `--input` patterns.
+#[derive(Debug, Clone, Default)]
+pub struct Resolved {
+    pub files: Vec<PathBuf>,
+    /// Entries excluded by ignore files (an ignored directory counts once).
+    pub ignored: usize,
+}
+
+/// Input files for `patterns` (`-` is stdin), in order, without duplicates.
+/// Only UTF-8 patterns can be globs; anything else is a literal path.
+pub fn resolve(
+    patterns: &[PathBuf],
+    cache_path: Option<&Path>,
+    follow_symlinks: bool,
+    no_ignore: bool,
+) -> Result<Resolved, Box<dyn std::error::Error>> {
+    let mut cache = cache_path
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|src| serde_json::from_str::<Cache>(&src).ok())
+        .filter(|c| c.format == CACHE_FORMAT)
+        .unwrap_or_else(|| Cache { format: CACHE_FORMAT.to_string(), patterns: BTreeMap::new() });
+    let mut dirty = false;
+
+    let mut out = indexmap::IndexSet::<PathBuf>::new();
+    let mut ignored = 0;
+    for raw in patterns {
+        let Some(p) = raw.to_str().filter(|p| *p != "-" && has_glob_chars(p)) else {
+            out.insert(raw.clone());
+            continue;
+        };
+        let listing = match cache.patterns.get(p).filter(|l| fresh(l, follow_symlinks, no_ignore)) {
+            Some(l) => l.clone(),
+            None => {
+                let l = Walk::new(p, follow_symlinks, no_ignore)?.run();
+                let utf8 = |p: &PathBuf| p.to_str().is_some();
+                if l.files.iter().all(utf8) && l.dirs.iter().chain(&l.ignore_files).all(|(d, _)| utf8(d)) {
+                    cache.patterns.insert(p.to_string(), l.clone());
+                    dirty = true;
+                } else {
+                    dirty |= cache.patterns.remove(p).is_some();
+                }
+                l
+            }
+        };
+        if listing.files.is_empty() {
+            let why = if listing.ignored > 0 { format!(" ({} ignored by {IGNORE_FILE})", listing.ignored) } else { String::new() };
+            return Err(format!("glob pattern matched no files: {p}{why}").into());
+        }
+        ignored += listing.ignored;
+        out.extend(listing.files);
+    }
+
+    if let Some(path) = cache_path && dirty {
+        std::fs::write(path, serde_json::to_string(&cache).expect("input cache serializes"))
+            .map_err(|e| format!("--input-cache {}: {e}", path.display()))?;
+    }
+    Ok(Resolved { files: out.into_iter().collect(), ignored })
+}
+
+// --------------------------------- records ---------------------------------- //
+
+/// How one input holds several JSON documents (`--separator`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Separator {
+    /// RFC 7464 JSON text sequences: each document starts with an RS (`0x1E`).
+    Rs,
+    /// YAML-style `---` lines between documents.
+    Dashes,
+}
+
+impl Separator {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Separator::Rs => "rs",
+            Separator::Dashes => "dashes",
+        }
+    }
+}
+
+const RS: char = '\u{1e}';
+
+/// The non-blank documents in `src` with the (1-based) line each starts on.
+pub fn records(src: &str, sep: Separator) -> Vec<(usize, &str)> {
+    fn push<'a>(out: &mut Vec<(usize, &'a str)>, line: usize, text: &'a str) {
+        let t = text.trim();
+        if !t.is_empty() {
+            // blank lines before the document
+            out.push((line + text[..text.len() - text.trim_start().len()].matches('\n').count(), t));
+        }
+    }
+    let mut out = Vec::new();
+    match sep {
+        Separator::Rs => {
+            let mut line = 1;
+            for part in src.split(RS) {
+                push(&mut out, line, part);
+                line += part.matches('\n').count();
+            }
+        }
+        Separator::Dashes => {
+            let (mut start, mut from) = (1, 0);
+            let mut offset = 0;
+            for (i, l) in src.split_inclusive('\n').enumerate() {
+                if l.trim() == "---" {
+                    push(&mut out, start, &src[from..offset]);
+                    (start, from) = (i + 2, offset + l.len());
+                }
+                offset += l.len();
+            }
+            push(&mut out, start, &src[from..]);
+        }
+    }
+    out
+}
+
+/// NDJSON inputs larger than this are cut into pieces observed in parallel,
+/// so one huge file doesn't leave every other core idle.
+pub const CHUNK_BYTES: usize = 8 << 20;
+
+/// The text of `input` in pieces of about `target` bytes (cut after the first
+/// newline past it), each with the (1-based) line it starts on. Only the piece
+/// being read is buffered.
+pub fn line_chunks<R: io::BufRead>(mut input: R, target: usize) -> impl Iterator<Item = io::Result<(usize, String)>> {
+    let mut line = 1;
+    std::iter::from_fn(move || {
+        let mut piece = Vec::with_capacity(target);
+        let read = (&mut input)
+            .take(target as u64)
+            .read_to_end(&mut piece)
+            .and_then(|_| input.read_until(b'\n', &mut piece));
+        if let Err(e) = read {
+            return Some(Err(e));
+        }
+        if piece.is_empty() {
+            return None;
+        }
+        let first = line;
+        line += piece.iter().filter(|&&b| b == b'\n').count();
+        Some(String::from_utf8(piece).map(|s| (first, s)).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)))
+    })
+}
@@ -1,28 +1,98 @@
 // Strongly-typed IR for codegen. No serde_json::Value here.
 
+pub use crate::inference::StrFormat;
+pub use crate::norm_ir::Stats;
+pub use crate::sentinel::Mark;
+
 #[derive(Debug, Clone)]
 pub enum Ty {
     Never,                   // unreachable (you can avoid emitting this)
     Null,                    // exactly null
     Bool,
-    Integer { min: Option<i64>, max: Option<i64> },
-    Number  { min: Option<f64>, max: Option<f64> },
-    String  { enum_: Vec<String>, pattern: Option<String>, format_uri: bool },
+    Integer { min: Option<i128>, max: Option<i128>, from_string: bool, multiple_of: Option<i128>, enum_: Vec<i64>, stats: Option<Stats> }, // also "12"; enum_: ascending
+    Number  { min: Option<f64>, max: Option<f64>, from_string: bool, multiple_of: Option<f64>, stats: Option<Stats> },  // also "4.5"
+    StringOrNumber { min: Option<f64>, max: Option<f64>, stats: Option<Stats> }, // 4 or "4.5", kept as written
+    String  {
+        enum_: Vec<String>,
+        enum_counts: Vec<u64>, // observations of each `enum_` value; empty when unknown
+        pattern: Option<String>,
+        format_uri: bool,
+        format: Option<StrFormat>,
+        min_length: Option<u32>, // in characters
+        max_length: Option<u32>,
+        stats: Option<Stats>,    // `--evidence-counts`, as on every node with one
+    },
     ArrayList {
         item: Box<Ty>,
         min_items: Option<u32>,
         max_items: Option<u32>,
+        stats: Option<Stats>,
     },
     ArrayTuple {
         elems: Vec<Ty>,      // exact arity
         min_items: u32,      // last required index + 1 (exact for tuples)
         max_items: u32,      // == elems.len()
+        stats: Option<Stats>,
     },
     Object {
         fields: Vec<Field>,  // stable order for deterministic codegen
+        stats: Option<Stats>,
+    },
+    Map {
+        key: Box<Ty>,        // a string type describing the keys
+        value: Box<Ty>,      // every value
+        stats: Option<Stats>,
+    },
+    Tagged {
+        tag: String,         // field naming the variant; absent from the variants' fields
+        variants: Vec<(String, Ty)>, // tag value → Object
+        stats: Option<Stats>,
     },
+    JsonString(Box<Ty>),     // a string holding a JSON document of the inner type
     OneOf(Vec<Ty>),          // keep small, or rewrite to Nullable where possible
     Nullable(Box<Ty>),       // null wrapper
+    Any,                     // any JSON, nested past `--max-depth` (a `serde_json::Value` after all)
+    Raw,                     // any JSON, kept unparsed (`passthrough` in `--overrides`)
+    /// `inner` plus marker values (`-1`, `"N/A"`) modeled apart from it; only
+    /// produced by [`crate::sentinel::model`], never by lowering.
+    Sentinel {
+        inner: Box<Ty>,
+        marks: Vec<Mark>,
+        as_null: bool,       // markers (and null) read as `None`; else enum variants
+    },
+}
+
+impl Ty {
+    /// The node's evidence counts (`--evidence-counts`), for the kinds that have them.
+    pub fn stats(&self) -> Option<Stats> {
+        match self {
+            Ty::Integer { stats, .. }
+            | Ty::Number { stats, .. }
+            | Ty::StringOrNumber { stats, .. }
+            | Ty::String { stats, .. }
+            | Ty::ArrayList { stats, .. }
+            | Ty::ArrayTuple { stats, .. }
+            | Ty::Object { stats, .. }
+            | Ty::Map { stats, .. }
+            | Ty::Tagged { stats, .. } => *stats,
+            Ty::Never | Ty::Null | Ty::Bool | Ty::JsonString(_) | Ty::OneOf(_) | Ty::Nullable(_) | Ty::Sentinel { .. } | Ty::Any | Ty::Raw => None,
+        }
+    }
+
+    pub fn stats_mut(&mut self) -> Option<&mut Option<Stats>> {
+        match self {
+            Ty::Integer { stats, .. }
+            | Ty::Number { stats, .. }
+            | Ty::StringOrNumber { stats, .. }
+            | Ty::String { stats, .. }
+            | Ty::ArrayList { stats, .. }
+            | Ty::ArrayTuple { stats, .. }
+            | Ty::Object { stats, .. }
+            | Ty::Map { stats, .. }
+            | Ty::Tagged { stats, .. } => Some(stats),
+            Ty::Never | Ty::Null | Ty::Bool | Ty::JsonString(_) | Ty::OneOf(_) | Ty::Nullable(_) | Ty::Sentinel { .. } | Ty::Any | Ty::Raw => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -30,4 +100,44 @@ pub struct Field {
     pub name: String,
     pub ty: Ty,
     pub required: bool,      // present & non-null in all objects
+    pub present: bool,       // the key is in all objects (maybe null)
+    pub support: Option<crate::norm_ir::Support>, // `--evidence-counts`
+}
+
+/// The Rust integer an `Integer { min, max }` reads as: the narrowest of `u32`,
+/// `i64`, `u64` and `i128` holding the range (`i64` for an unknown one).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntWidth {
+    U32,
+    I64,
+    U64,
+    I128,
+}
+
+impl IntWidth {
+    pub fn of(min: Option<i128>, max: Option<i128>) -> Self {
+        let (Some(min), Some(max)) = (min, max) else { return IntWidth::I64 };
+        [IntWidth::U32, IntWidth::I64, IntWidth::U64]
+            .into_iter()
+            .find(|w| w.contains(min) && w.contains(max))
+            .unwrap_or(IntWidth::I128)
+    }
+
+    pub fn rust(self) -> &'static str {
+        match self {
+            IntWidth::U32 => "u32",
+            IntWidth::I64 => "i64",
+            IntWidth::U64 => "u64",
+            IntWidth::I128 => "i128",
+        }
+    }
+
+    pub fn contains(self, x: i128) -> bool {
+        match self {
+            IntWidth::U32 => u32::try_from(x).is_ok(),
+            IntWidth::I64 => i64::try_from(x).is_ok(),
+            IntWidth::U64 => u64::try_from(x).is_ok(),
+            IntWidth::I128 => true,
+        }
+    }
 }
@@ -0,0 +1,169 @@
+//! Minimal JSON path notation shared by every path-keyed option.
+//!
+//! Grammar (a strict subset of JSONPath):
+//!   `$`            the document root
+//!   `.name`        object field (identifier characters only)
+//!   `["any key"]`  object field, JSON string syntax
+//!   `[3]`          tuple position
+//!   `[*]`          every list item (or map value)
+//!
+//! Paths are matched structurally against values, evidence, and IR alike.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Seg {
+    Field(String),
+    Index(usize),
+    Items,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JPath(pub Vec<Seg>);
+
+impl JPath {
+    pub fn root() -> Self { Self(Vec::new()) }
+
+    pub fn child(&self, seg: Seg) -> Self {
+        let mut segs = self.0.clone();
+        segs.push(seg);
+        Self(segs)
+    }
+
+    pub fn segs(&self) -> &[Seg] { &self.0 }
+
+    /// Follow the path through a concrete value; `[*]` is not a single value, so it never resolves.
+    pub fn resolve<'a>(&self, v: &'a Value) -> Option<&'a Value> {
+        let mut cur = v;
+        for seg in &self.0 {
+            cur = match (seg, cur) {
+                (Seg::Field(k), Value::Object(m)) => m.get(k)?,
+                (Seg::Index(i), Value::Array(xs)) => xs.get(*i)?,
+                _ => return None,
+            };
+        }
+        Some(cur)
+    }
+}
+
+fn is_ident(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+impl fmt::Display for JPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("$")?;
+        for seg in &self.0 {
+            match seg {
+                Seg::Field(k) if is_ident(k) => write!(f, ".{k}")?,
+                Seg::Field(k) => write!(f, "[{}]", Value::from(k.as_str()))?,
+                Seg::Index(i) => write!(f, "[{i}]")?,
+                Seg::Items => f.write_str("[*]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for JPath {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let err = |at: usize, what: &str| format!("invalid path `{src}` at byte {at}: {what}");
+        let rest = src.trim();
+        let Some(mut rest) = rest.strip_prefix('$') else {
+            return Err(err(0, "paths start with `$`"));
+        };
+        let mut segs = Vec::new();
+        while !rest.is_empty() {
+            let at = src.len() - rest.len();
+            if let Some(r) = rest.strip_prefix('.') {
+                let end = r.find(['.', '[']).unwrap_or(r.len());
+                let name = &r[..end];
+                if !is_ident(name) {
+                    return Err(err(at, "expected a field name after `.`"));
+                }
+                segs.push(Seg::Field(name.to_string()));
+                rest = &r[end..];
+            } else if let Some(r) = rest.strip_prefix('[') {
+                if let Some(r) = r.strip_prefix("*]") {
+                    segs.push(Seg::Items);
+                    rest = r;
+                } else if r.starts_with('"') {
+                    // JSON string literal; let serde handle escapes.
+                    let mut de = serde_json::Deserializer::from_str(r).into_iter::<String>();
+                    let name = de.next()
+                        .and_then(|x| x.ok())
+                        .ok_or_else(|| err(at, "bad quoted field name"))?;
+                    let r = r[de.byte_offset()..].strip_prefix(']').ok_or_else(|| err(at, "expected `]`"))?;
+                    segs.push(Seg::Field(name));
+                    rest = r;
+                } else {
+                    let end = r.find(']').ok_or_else(|| err(at, "expected `]`"))?;
+                    let i = r[..end].trim().parse::<usize>().map_err(|_| err(at, "expected an index, `*`, or a quoted name"))?;
+                    segs.push(Seg::Index(i));
+                    rest = &r[end + 1..];
+                }
+            } else {
+                return Err(err(at, "expected `.` or `[`"));
+            }
+        }
+        Ok(JPath(segs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> JPath {
+        s.parse().unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    #[test]
+    fn parses_every_segment_form() {
+        let p = parse("$.a[\"b c\"][3][*].d-e");
+        let want = [Seg::Field("a".into()), Seg::Field("b c".into()), Seg::Index(3), Seg::Items, Seg::Field("d-e".into())];
+        assert_eq!(p.segs(), &want);
+        assert_eq!(parse(" $ "), JPath::root());
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for s in ["$", "$.a.b", "$[0][*]", "$[\"a.b\"][\"\"].c", "$[\"quote\\\"d\"]", "$.日本[1]"] {
+            assert_eq!(parse(s).to_string(), s);
+            assert_eq!(parse(&parse(s).to_string()), parse(s));
+        }
+        // a quoted name that needs no quotes is written bare
+        assert_eq!(parse("$[\"a\"]").to_string(), "$.a");
+    }
+
+    #[test]
+    fn errors_name_the_byte() {
+        let cases = [
+            ("a.b", "at byte 0"),
+            ("$.", "at byte 1"),
+            ("$.a..b", "at byte 3"),
+            ("$[1", "expected `]`"),
+            ("$[x]", "expected an index"),
+            ("$[\"a\"", "expected `]`"),
+            ("$a", "expected `.` or `[`"),
+        ];
+        for (src, want) in cases {
+            let e = src.parse::<JPath>().unwrap_err();
+            assert!(e.contains(want), "{src}: {e}");
+        }
+    }
+
+    #[test]
+    fn resolve_follows_fields_and_positions_only() {
+        let v = serde_json::json!({ "a": [10, { "b": true }] });
+        assert_eq!(parse("$.a[1].b").resolve(&v), Some(&Value::Bool(true)));
+        assert_eq!(parse("$.a[2]").resolve(&v), None);
+        assert_eq!(parse("$.a[*]").resolve(&v), None);
+        assert_eq!(parse("$[0]").resolve(&v), None);
+    }
+}
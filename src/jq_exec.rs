@@ -1,8 +1,10 @@
 use anyhow::{anyhow, Result};
-use jaq_core::{compile::Undefined, load, Compiler, Ctx, RcIter};
+use jaq_core::{box_iter::box_once, compile::Undefined, load, Compiler, Ctx, Error, Exn, Native, RcIter};
 use jaq_json::Val;
 use serde_json::Value;
 
+use crate::norm_ir::NTy;
+
 pub fn run_jaq(filter_src: &str, input: &Value) -> Result<Vec<String>> {
     let loader = load::Loader::new(jaq_std::defs().chain(jaq_json::defs()));
     let arena = load::Arena::default();
@@ -13,7 +15,7 @@ pub fn run_jaq(filter_src: &str, input: &Value) -> Result<Vec<String>> {
         .map_err(format_parse_errors)?;      // now infers fine
 
     let filter = Compiler::default()
-        .with_funs(jaq_std::funs().chain(jaq_json::funs()))
+        .with_funs(jaq_std::funs().chain(jaq_json::funs()).map(sandboxed))
         .compile(modules)
         .map_err(format_undefined_errors)?;  // ditto
 
@@ -28,6 +30,30 @@ pub fn run_jaq(filter_src: &str, input: &Value) -> Result<Vec<String>> {
     Ok(out)
 }
 
+/// The builtins of [`crate::sandbox::JQ_DENIED`] fail when called. They stay
+/// defined: the standard library builds on them (`halt_error/0`, `debug/1`).
+fn sandboxed(f: jaq_std::Filter<Native<Val>>) -> jaq_std::Filter<Native<Val>> {
+    let (name, args, native) = f;
+    if !crate::sandbox::JQ_DENIED.contains(&name) {
+        return (name, args, native);
+    }
+    let denied = Native::new(|_, _| {
+        box_once(Err(Exn::from(Error::str("builtin disabled: it reaches outside the document (environment, clock, exit or log)"))))
+    });
+    (name, args, denied)
+}
+
+/// Run an `--ir-transform` program over the normalized IR (in its `--ir-json`
+/// form); it must produce exactly one value that reads back as an IR.
+pub fn transform_ir(program: &str, ir: &NTy) -> Result<NTy> {
+    let input = serde_json::to_value(ir)?;
+    let outs = run_jaq(program, &input)?;
+    let [out] = outs.as_slice() else {
+        return Err(anyhow!("expected one output, got {}", outs.len()));
+    };
+    serde_json::from_str(out).map_err(|e| anyhow!("output is not a normalized IR: {e}"))
+}
+
 fn format_parse_errors(
     errs: Vec<(load::File<&str, ()>, load::Error<&str>)>,
 ) -> anyhow::Error {
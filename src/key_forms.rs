@@ -0,0 +1,77 @@
+//! Keys merged by Unicode normalization (`--normalize-unicode`).
+//!
+//! With keys compared in NFC and without invisible characters (see
+//! [`crate::inference::unicode`]), a field's evidence remembers the other
+//! spellings it came with. The report lists them, the Rust models accept each
+//! as a `#[serde(alias)]` of the normalized key, and the schema carries them as
+//! the property's `x-aliases`.
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::inference::U;
+use crate::jpath::{JPath, Seg};
+use crate::overrides::Overrides;
+
+/// A record field some objects spelled differently.
+#[derive(Debug, Clone)]
+pub struct Merged {
+    /// The field, under its normalized key.
+    pub path: JPath,
+    /// The keys as written, other than the normalized one.
+    pub forms: Vec<String>,
+}
+
+/// Every record field of `u` merged from several spellings, in path order.
+pub fn survey(u: &U, ov: &Overrides) -> Vec<Merged> {
+    let mut out = Vec::new();
+    walk(u, &JPath::root(), ov, &mut out);
+    out.sort_by_cached_key(|m| m.path.to_string());
+    out
+}
+
+fn walk(u: &U, at: &JPath, ov: &Overrides, out: &mut Vec<Merged>) {
+    if ov.passthrough(at) {
+        return;
+    }
+    if let Some(arr) = &u.arr {
+        let tuple = ov.tuple_at(at).unwrap_or_else(|| crate::inference::decide_tuple(arr));
+        if tuple && !arr.cols.is_empty() {
+            for (i, c) in arr.cols.iter().enumerate() {
+                walk(c, &at.child(Seg::Index(i)), ov, out);
+            }
+        } else {
+            walk(&arr.item, &at.child(Seg::Items), ov, out);
+        }
+    }
+    let Some(obj) = &u.obj else { return };
+    match crate::inference::decide_map(obj) {
+        // the keys of a map are data
+        Some(value) => walk(&value, &at.child(Seg::Items), ov, out),
+        None => {
+            for (name, f) in &obj.fields {
+                let path = at.child(Seg::Field(name.clone()));
+                if !f.aliases.is_empty() {
+                    out.push(Merged { path: path.clone(), forms: f.aliases.iter().cloned().collect() });
+                }
+                walk(&f.ty, &path, ov, out);
+            }
+        }
+    }
+}
+
+/// Field path → its other spellings, for [`crate::codegen::Codegen::with_aliases`].
+pub fn aliases(merged: &[Merged]) -> BTreeMap<String, Vec<String>> {
+    merged.iter().map(|m| (m.path.to_string(), m.forms.clone())).collect()
+}
+
+/// Set `x-aliases` on the schema of every merged field.
+pub fn annotate(schema: &mut Value, aliases: &BTreeMap<String, Vec<String>>) {
+    for (path, forms) in aliases {
+        let Ok(path) = path.parse::<JPath>() else { continue };
+        if let Some(node) = crate::norm_ir::schema_at(schema, path.segs()) {
+            node["x-aliases"] = Value::from(forms.clone());
+        }
+    }
+}
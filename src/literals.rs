@@ -0,0 +1,163 @@
+//! Enum values remembered across runs (`gen --literals FILE`).
+//!
+//! Enum membership follows the corpus, so a value that happens to be missing
+//! from this week's data would drop out of the generated enum. The literal
+//! dictionary keeps every value each enum slot has ever held, with when it was
+//! first and last seen:
+//!
+//! ```text
+//! {
+//!   "$.status": {
+//!     "active":  { "first_seen": "2026-09-01T08:00:00Z", "last_seen": "2026-10-16T08:00:00Z" },
+//!     "paused":  { "first_seen": "2026-09-01T08:00:00Z", "last_seen": "2026-09-08T08:00:00Z" }
+//!   }
+//! }
+//! ```
+//!
+//! Each run adds the values it saw and keeps the remembered ones it didn't in
+//! the enum (after the observed values). A value only leaves the enum when it
+//! is deleted from the file; the report lists the ones not seen this run, so
+//! the decision shows up next to the other changes.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::jpath::{JPath, Seg};
+use crate::norm_ir::NTy;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Seen {
+    pub first_seen: String,
+    pub last_seen: String,
+}
+
+/// Enum path → value → when it was seen.
+pub type Dictionary = BTreeMap<String, BTreeMap<String, Seen>>;
+
+/// A value of an enum slot that this run saw for the first time, or didn't see.
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub path: String,
+    pub value: String,
+    /// `None` for a new value; else when it was last seen before this run.
+    pub last_seen: Option<String>,
+}
+
+/// The dictionary at `path`; empty if it doesn't exist yet.
+pub fn load(path: &std::path::Path) -> Result<Dictionary, String> {
+    if !path.exists() {
+        return Ok(Dictionary::new());
+    }
+    let src = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&src).map_err(|e| e.to_string())
+}
+
+/// Record the enum values of `n` in `dict` as seen `now`, and return the
+/// remembered values each enum is missing (path → values, for [`retain`]) along
+/// with what changed.
+pub fn merge(n: &NTy, dict: &mut Dictionary, now: &str) -> (BTreeMap<String, Vec<String>>, Vec<Change>) {
+    let mut enums = Vec::new();
+    enum_slots(n, &mut JPath::root(), &mut enums);
+    let mut retained: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut changes = Vec::new();
+    for (path, values) in enums {
+        let known = dict.entry(path.clone()).or_default();
+        for v in &values {
+            let seen = known.entry(v.clone()).or_insert_with(|| {
+                changes.push(Change { path: path.clone(), value: v.clone(), last_seen: None });
+                Seen { first_seen: now.to_string(), last_seen: now.to_string() }
+            });
+            seen.last_seen = now.to_string();
+        }
+        for (v, seen) in known.iter().filter(|(v, _)| !values.contains(v)) {
+            changes.push(Change { path: path.clone(), value: v.clone(), last_seen: Some(seen.last_seen.clone()) });
+            retained.entry(path.clone()).or_default().push(v.clone());
+        }
+    }
+    (retained, changes)
+}
+
+/// Append the `retained` values to the enums at their paths (with no
+/// observations, when the enum counts them).
+pub fn retain(n: &mut NTy, retained: &BTreeMap<String, Vec<String>>) {
+    if !retained.is_empty() {
+        retain_at(n, &mut JPath::root(), retained);
+    }
+}
+
+fn retain_at(n: &mut NTy, at: &mut JPath, retained: &BTreeMap<String, Vec<String>>) {
+    match n {
+        NTy::String { enum_, enum_counts, .. } if !enum_.is_empty() => {
+            let Some(extra) = retained.get(&at.to_string()) else { return };
+            for v in extra {
+                if enum_.contains(v) {
+                    continue;
+                }
+                if enum_counts.len() == enum_.len() {
+                    enum_counts.push(0);
+                }
+                enum_.push(v.clone());
+            }
+        }
+        NTy::Nullable(inner) | NTy::JsonString(inner) => retain_at(inner, at, retained),
+        NTy::OneOf(arms) => arms.iter_mut().for_each(|a| retain_at(a, at, retained)),
+        NTy::Tagged { variants, .. } => variants.iter_mut().for_each(|(_, v)| retain_at(v, at, retained)),
+        NTy::ArrayList { item, .. } | NTy::Map { value: item, .. } => {
+            at.0.push(Seg::Items);
+            retain_at(item, at, retained);
+            at.0.pop();
+        }
+        NTy::ArrayTuple { elems, .. } => {
+            for (i, e) in elems.iter_mut().enumerate() {
+                at.0.push(Seg::Index(i));
+                retain_at(e, at, retained);
+                at.0.pop();
+            }
+        }
+        NTy::Object { fields, .. } => {
+            for f in fields {
+                at.0.push(Seg::Field(f.name.clone()));
+                retain_at(&mut f.ty, at, retained);
+                at.0.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `(path, values)` of every string enum in `n` (union arms at one path pooled).
+fn enum_slots(n: &NTy, at: &mut JPath, out: &mut Vec<(String, Vec<String>)>) {
+    match n {
+        NTy::String { enum_, .. } if !enum_.is_empty() => {
+            let path = at.to_string();
+            match out.iter_mut().find(|(p, _)| *p == path) {
+                Some((_, values)) => values.extend(enum_.iter().filter(|v| !values.contains(v)).cloned().collect::<Vec<_>>()),
+                None => out.push((path, enum_.clone())),
+            }
+        }
+        NTy::Nullable(inner) | NTy::JsonString(inner) => enum_slots(inner, at, out),
+        NTy::OneOf(arms) => arms.iter().for_each(|a| enum_slots(a, at, out)),
+        NTy::Tagged { variants, .. } => variants.iter().for_each(|(_, v)| enum_slots(v, at, out)),
+        NTy::ArrayList { item, .. } | NTy::Map { value: item, .. } => {
+            at.0.push(Seg::Items);
+            enum_slots(item, at, out);
+            at.0.pop();
+        }
+        NTy::ArrayTuple { elems, .. } => {
+            for (i, e) in elems.iter().enumerate() {
+                at.0.push(Seg::Index(i));
+                enum_slots(e, at, out);
+                at.0.pop();
+            }
+        }
+        NTy::Object { fields, .. } => {
+            for f in fields {
+                at.0.push(Seg::Field(f.name.clone()));
+                enum_slots(&f.ty, at, out);
+                at.0.pop();
+            }
+        }
+        _ => {}
+    }
+}
@@ -1,10 +1,59 @@
 pub mod cli;
 pub mod codegen;
+pub mod columns;
+pub mod corpus;
+pub mod depth;
+pub mod descriptions;
+pub mod discriminator;
+pub mod distribution;
+pub mod enum_coverage;
+pub mod epoch;
+pub mod events;
+pub mod evidence_json;
+pub mod explain;
+pub mod formats;
+pub mod human;
 pub mod inference;
+pub mod ingest;
+pub mod inputs;
 pub mod ir;
+pub mod jpath;
 pub mod jq_exec;
+pub mod key_forms;
+pub mod literals;
+pub mod manifest;
+pub mod metrics;
 pub mod norm_ir;
+pub mod online;
+pub mod openapi;
+pub mod outliers;
+pub mod overrides;
 pub mod path_de;
+pub mod patterns;
+pub mod pii;
+pub mod plugin;
+pub mod portable;
+pub mod provenance;
+pub mod record_key;
+pub mod report;
+pub mod required;
+pub mod rewrite;
+pub mod rust_check;
+pub mod rust_crate;
+pub mod sampling;
+pub mod sandbox;
+pub mod scaffold;
+pub mod schema_check;
+pub mod score;
+pub mod sentinel;
+pub mod share;
+pub mod size;
+pub mod timeline;
+pub mod tuple_names;
+pub mod unify;
+pub mod unions;
+pub mod validate;
+pub mod width;
 
 use serde_json::{json, Value};
 
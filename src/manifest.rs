@@ -0,0 +1,404 @@
+//! Reproducibility manifest for generated artifacts.
+//!
+//! Every generated `.rs` file starts with one structured header line:
+//!
+//! ```text
+//! // json-osi: {"artifact":"rust","tool":"json-osi 0.1.0","policy":"sha256:…","inputs":"sha256:…","evidence":"sha256:…","options":{…},"body":"sha256:…"}
+//! ```
+//!
+//! - `policy`: hash of [`crate::inference::policy_fingerprint`]
+//! - `inputs`: hash of the input manifest (paths, content hashes, ingest settings)
+//! - `evidence`: id of the joined evidence (`gen --evidence FILE` saves it)
+//! - `body`: hash of everything after the header line, so edits are detectable
+//!
+//! `verify-generated` checks an artifact against a saved evidence file.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::codegen::RedactMode;
+use crate::epoch::EpochUnit;
+use crate::formats::Format;
+use crate::inference::str::Grex;
+use crate::inference::{ObserveOpts, U};
+use crate::inputs::Separator;
+use crate::overrides::Overrides;
+use crate::pii::Tag;
+use crate::sampling::Sampling;
+use crate::sentinel::SentinelModel;
+
+pub const HEADER_PREFIX: &str = "// json-osi: ";
+pub const EVIDENCE_FORMAT: &str = "json-osi-evidence/1";
+pub const CHECKPOINT_FORMAT: &str = "json-osi-checkpoint/1";
+
+pub fn sha256(bytes: &[u8]) -> String {
+    sha256_of(Sha256::new_with_prefix(bytes))
+}
+
+/// [`sha256`] of everything fed to `hasher` (for inputs hashed as they are read).
+pub fn sha256_of(hasher: Sha256) -> String {
+    let digest = hasher.finalize();
+    let mut s = String::with_capacity(7 + 64);
+    s.push_str("sha256:");
+    for b in digest {
+        s.push_str(&format!("{b:02x}"));
+    }
+    s
+}
+
+pub fn tool() -> String {
+    format!("json-osi {}", env!("CARGO_PKG_VERSION"))
+}
+
+pub fn policy_hash() -> String {
+    sha256(crate::inference::policy_fingerprint().as_bytes())
+}
+
+/// One input file as ingested.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputDigest {
+    pub path: String,
+    pub sha256: String,
+    pub docs: u64,
+}
+
+/// Settings that change what evidence the same inputs produce.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IngestSettings {
+    pub ndjson: bool,
+    pub jq_expr: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub separator: Option<Separator>,
+    /// `--json-strings`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub json_strings: bool,
+    /// `--coerce-numeric-strings`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub numeric_strings: bool,
+    /// `--empty-strings-as-null`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub empty_as_null: bool,
+    /// `--normalize-unicode`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub normalize_unicode: bool,
+    /// `--max-samples`, `--max-samples-per-input`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sampling: Option<Sampling>,
+    /// `--max-depth`; unlimited in older evidence.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_depth: Option<u32>,
+}
+
+impl IngestSettings {
+    pub fn observe_opts(&self) -> ObserveOpts {
+        ObserveOpts {
+            json_strings: self.json_strings,
+            numeric_strings: self.numeric_strings,
+            empty_as_null: self.empty_as_null,
+            normalize_unicode: self.normalize_unicode,
+            max_depth: self.max_depth,
+        }
+    }
+
+    /// How the inputs held their documents: `ndjson`, `rs` / `dashes` (`--separator`),
+    /// or `json` for one document per file.
+    pub fn encoding(&self) -> &'static str {
+        match (self.ndjson, self.separator) {
+            (true, _) => "ndjson",
+            (false, Some(sep)) => sep.as_str(),
+            (false, None) => "json",
+        }
+    }
+}
+
+pub fn manifest_hash(inputs: &[InputDigest], ingest: &IngestSettings) -> String {
+    let mut s = format!("ndjson={}\njq={}\n", ingest.ndjson, ingest.jq_expr.as_deref().unwrap_or(""));
+    // only when set, so hashes from before these options still match
+    if let Some(sep) = ingest.separator {
+        s.push_str(&format!("separator={}\n", sep.as_str()));
+    }
+    if ingest.json_strings {
+        s.push_str("json_strings=true\n");
+    }
+    if ingest.numeric_strings {
+        s.push_str("numeric_strings=true\n");
+    }
+    if ingest.empty_as_null {
+        s.push_str("empty_as_null=true\n");
+    }
+    if ingest.normalize_unicode {
+        s.push_str("normalize_unicode=true\n");
+    }
+    if let Some(sampling) = ingest.sampling {
+        s.push_str(&format!("sampling={}\n", sampling.describe()));
+    }
+    if let Some(depth) = ingest.max_depth {
+        s.push_str(&format!("max_depth={depth}\n"));
+    }
+    for i in inputs {
+        s.push_str(&format!("{}\t{}\t{}\n", i.sha256, i.docs, i.path));
+    }
+    sha256(s.as_bytes())
+}
+
+pub fn evidence_id(u: &U) -> String {
+    sha256(&serde_json::to_vec(u).expect("evidence serializes"))
+}
+
+/// Codegen options recorded so an artifact can be regenerated from its evidence.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenOptions {
+    pub root_type: String,
+    pub name_collisions: String,
+    /// Resolved key path (`--key auto` is recorded as whatever it picked).
+    pub key: Option<String>,
+    /// Depends on trackers that aren't part of the evidence, so such artifacts
+    /// can be integrity-checked but not regenerated.
+    pub group_nullables: bool,
+    /// `strict` or `small`; absent in headers written before profiles existed.
+    #[serde(default = "strict")]
+    pub codegen_profile: String,
+    /// `--overrides` in effect (they change normalization, so regeneration needs them).
+    #[serde(default, skip_serializing_if = "Overrides::is_empty")]
+    pub overrides: Overrides,
+    /// `--sentinels null|enum`: markers and where they were modeled (not part of the evidence).
+    #[serde(default, skip_serializing_if = "SentinelModel::is_empty")]
+    pub sentinels: SentinelModel,
+    /// `--ir-transform` program text (it rewrites the normalized IR, so regeneration needs it).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ir_transform: Option<String>,
+    /// Rewrite passes registered by an embedder (see [`crate::rewrite`]), in the order run.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rewrites: Vec<String>,
+    /// `--pii`: path → personal-data tag, for the `SCHEMA` table (not part of the evidence).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub pii: BTreeMap<String, Tag>,
+    /// `--formats`: path → declared string format the models check (not part of the evidence).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub formats: BTreeMap<String, Format>,
+    /// `--epoch-timestamps`: path → unit of integers read as timestamps (not part of the evidence).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub epochs: BTreeMap<String, EpochUnit>,
+    /// `--redact`: generate `redact()` methods blanking or hashing those fields.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redact: Option<RedactMode>,
+    /// `--rust-uuid`: UUID strings are `::uuid::Uuid`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub uuid: bool,
+    /// `--rust-bytes`: hex / base64 strings are decoded to bytes.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bytes: bool,
+    /// `--rust-decimal`: decimal-amount strings are `::rust_decimal::Decimal` newtypes.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub decimal: bool,
+    /// `--dictionary`: path → description, for the schema and the doc comments (not part
+    /// of the evidence).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub descriptions: BTreeMap<String, String>,
+    /// `--tuple-names`: tuple position → field name (not part of the evidence).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tuple_names: BTreeMap<String, String>,
+    /// `--normalize-unicode`: field path → the other spellings of its key, accepted as aliases.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, Vec<String>>,
+    /// `--grex` and its limits (they change normalization, so regeneration needs them).
+    #[serde(default, skip_serializing_if = "Grex::is_default")]
+    pub grex: Grex,
+    /// `--records-schema`: the root is a record of a stream; the models get `read_records`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub records: bool,
+    /// `--share-types`: types found at several paths are defined once.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub share_types: bool,
+    /// `--names`: type names pinned by the naming manifest (not part of the evidence).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub names: BTreeMap<String, String>,
+    /// `--literals`: path → remembered enum values this run didn't see, kept in the
+    /// enums (not part of the evidence).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub retained: BTreeMap<String, Vec<String>>,
+    /// `--min-arm-share`: path → union arms dropped from the evidence as too rare.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dropped_arms: BTreeMap<String, Vec<String>>,
+    /// `--required-threshold`: share of objects a field must be non-null in to be required.
+    #[serde(default = "all_objects", skip_serializing_if = "is_all_objects")]
+    pub required_threshold: f64,
+    /// `--int-enums`: integer slots of at most this many distinct values are enums (0: none).
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub int_enums: usize,
+    /// `--exact-ints`: integer and number arms of a slot were kept apart, not folded
+    /// into one `Number` (see [`crate::inference::COLLAPSE_INT_INTO_NUMBER`]).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub exact_ints: bool,
+    /// `--tightness`: constraints above this level were stripped after normalization.
+    #[serde(default = "max_tightness", skip_serializing_if = "is_max_tightness")]
+    pub tightness: u8,
+    /// `--evidence-counts`: fields and tuple positions carry their counts.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub evidence_counts: bool,
+    /// `--examples`: scalars carry a few of their values.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub examples: bool,
+}
+
+fn strict() -> String {
+    "strict".to_string()
+}
+
+fn all_objects() -> f64 {
+    crate::required::ALL
+}
+
+fn is_all_objects(t: &f64) -> bool {
+    *t == crate::required::ALL
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+fn max_tightness() -> u8 {
+    crate::norm_ir::MAX_TIGHTNESS
+}
+
+fn is_max_tightness(t: &u8) -> bool {
+    *t == crate::norm_ir::MAX_TIGHTNESS
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Header {
+    /// `rust` or `rust-config`.
+    pub artifact: String,
+    pub tool: String,
+    pub policy: String,
+    pub inputs: String,
+    pub evidence: String,
+    pub options: GenOptions,
+    pub body: String,
+}
+
+impl Header {
+    /// Prepend the header line to `body`, filling in `self.body`.
+    pub fn stamp(mut self, body: &str) -> String {
+        self.body = sha256(body.as_bytes());
+        format!("{HEADER_PREFIX}{}\n{body}", serde_json::to_string(&self).expect("header serializes"))
+    }
+
+    /// Split a stamped artifact into its header and body.
+    pub fn parse(src: &str) -> Result<(Header, &str), String> {
+        let (first, body) = src.split_once('\n').unwrap_or((src, ""));
+        let json = first
+            .strip_prefix(HEADER_PREFIX)
+            .ok_or_else(|| format!("no `{}` header on the first line", HEADER_PREFIX.trim_end()))?;
+        let header = serde_json::from_str(json).map_err(|e| format!("malformed header: {e}"))?;
+        Ok((header, body))
+    }
+}
+
+/// Saved evidence (`gen --evidence FILE`, conventionally `*.evd`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvidenceFile {
+    pub format: String,
+    pub tool: String,
+    pub policy: String,
+    pub ingest: IngestSettings,
+    pub inputs: Vec<InputDigest>,
+    /// `manifest_hash(inputs, ingest)`
+    pub manifest: String,
+    /// `evidence_id(evidence)`
+    pub id: String,
+    pub evidence: U,
+}
+
+/// A run interrupted while reading (Ctrl-C, SIGTERM), continued with `--resume`.
+/// Only whole files are recorded; one cut off mid-way is read again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub format: String,
+    pub tool: String,
+    pub policy: String,
+    pub ingest: IngestSettings,
+    /// Files fully observed, in input order.
+    pub done: Vec<DoneFile>,
+    /// Joined evidence of `done`.
+    pub evidence: U,
+}
+
+/// What a resumed run needs to report a checkpointed file as if it had read it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DoneFile {
+    pub path: String,
+    pub sha256: String,
+    pub docs: u64,
+    pub bytes: u64,
+    /// Path → kinds seen there (see [`crate::report::signature_of`]).
+    pub signature: BTreeMap<String, Vec<String>>,
+}
+
+impl Checkpoint {
+    /// Load `path`, rejecting checkpoints the current run can't continue.
+    pub fn load(path: &std::path::Path, ingest: &IngestSettings) -> Result<Self, String> {
+        let src = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let ckpt: Checkpoint = serde_json::from_str(&src).map_err(|e| format!("{}: {e}", path.display()))?;
+        if ckpt.format != CHECKPOINT_FORMAT {
+            return Err(format!("{}: expected {CHECKPOINT_FORMAT}, found {}", path.display(), ckpt.format));
+        }
+        if ckpt.policy != policy_hash() {
+            return Err(format!("{}: made with a different inference policy ({})", path.display(), ckpt.tool));
+        }
+        if manifest_hash(&[], &ckpt.ingest) != manifest_hash(&[], ingest) {
+            return Err(format!(
+                "{}: made with different ingest settings (ndjson={}, jq={:?}, separator={:?}, json_strings={}, numeric_strings={}, empty_as_null={}, normalize_unicode={}, sampling={}, max_depth={:?})",
+                path.display(), ckpt.ingest.ndjson, ckpt.ingest.jq_expr, ckpt.ingest.separator.map(Separator::as_str),
+                ckpt.ingest.json_strings, ckpt.ingest.numeric_strings, ckpt.ingest.empty_as_null,
+                ckpt.ingest.normalize_unicode, ckpt.ingest.sampling.map_or("none".to_string(), |s| s.describe()),
+                ckpt.ingest.max_depth,
+            ));
+        }
+        Ok(ckpt)
+    }
+}
+
+/// One line of `verify-generated` output.
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub what: &'static str,
+    /// `None` when the check could not be performed.
+    pub ok: Option<bool>,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(what: &'static str, ok: bool, detail: impl Into<String>) -> Self {
+        Check { what, ok: Some(ok), detail: detail.into() }
+    }
+
+    pub fn skipped(what: &'static str, detail: impl Into<String>) -> Self {
+        Check { what, ok: None, detail: detail.into() }
+    }
+}
+
+/// Everything that can be checked without regenerating.
+pub fn verify(header: &Header, body: &str, evd: &EvidenceFile) -> Vec<Check> {
+    let mut out = Vec::new();
+
+    let body_hash = sha256(body.as_bytes());
+    out.push(Check::new("body", body_hash == header.body, format!("artifact body {body_hash}")));
+
+    if evd.format != EVIDENCE_FORMAT {
+        out.push(Check::new("evidence format", false, format!("expected {EVIDENCE_FORMAT}, found {}", evd.format)));
+        return out;
+    }
+    let id = evidence_id(&evd.evidence);
+    out.push(Check::new("evidence integrity", id == evd.id, format!("evidence file content {id}")));
+    out.push(Check::new("evidence id", header.evidence == evd.id, format!("header {}, evidence file {}", header.evidence, evd.id)));
+
+    let manifest = manifest_hash(&evd.inputs, &evd.ingest);
+    out.push(Check::new("input manifest", manifest == evd.manifest && header.inputs == manifest, format!(
+        "header {}, evidence file {} ({} input(s))", header.inputs, manifest, evd.inputs.len()
+    )));
+    out.push(Check::new("policy", header.policy == evd.policy, format!("header {}, evidence file {}", header.policy, evd.policy)));
+    out
+}
@@ -0,0 +1,114 @@
+//! Size and shape of the generated Rust, per module.
+//!
+//! Outliers — a struct with a hundred fields, a tuple as wide as a table row,
+//! types nested a dozen levels deep — usually mean inference picked the wrong
+//! container (a map read as an object, a list read as a tuple) and are worth
+//! an override before the models are checked in.
+
+/// Struct fields above which a struct is flagged.
+pub const OUTLIER_FIELDS: usize = 64;
+/// Tuple arity above which a tuple is flagged.
+pub const OUTLIER_TUPLE: usize = 24;
+/// Union arms above which an untagged union is flagged.
+pub const OUTLIER_UNION_ARMS: usize = 4;
+/// Nesting depth (below the root) above which a type is flagged.
+pub const OUTLIER_DEPTH: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemKind {
+    Struct,
+    Tuple,
+    /// Scalar wrapper (bounds, pattern, URI checks).
+    Newtype,
+    /// Closed string enum.
+    Enum,
+    Union,
+}
+
+impl ItemKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ItemKind::Struct => "struct",
+            ItemKind::Tuple => "tuple",
+            ItemKind::Newtype => "newtype",
+            ItemKind::Enum => "enum",
+            ItemKind::Union => "union",
+        }
+    }
+}
+
+/// One named type the codegen emitted.
+#[derive(Debug, Clone)]
+pub struct Item {
+    pub name: String,
+    pub kind: ItemKind,
+    /// Fields, tuple slots, enum variants or union arms.
+    pub members: usize,
+    /// Walk path, root name first (`Root.data.Item`).
+    pub path: String,
+    pub depth: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CodeMetrics {
+    pub module: String,
+    pub lines: usize,
+    pub items: Vec<Item>,
+}
+
+impl CodeMetrics {
+    fn count(&self, kinds: &[ItemKind]) -> usize {
+        self.items.iter().filter(|i| kinds.contains(&i.kind)).count()
+    }
+
+    pub fn structs(&self) -> usize {
+        self.count(&[ItemKind::Struct, ItemKind::Tuple, ItemKind::Newtype])
+    }
+
+    pub fn enums(&self) -> usize {
+        self.count(&[ItemKind::Enum, ItemKind::Union])
+    }
+
+    /// Struct fields plus tuple slots.
+    pub fn fields(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|i| matches!(i.kind, ItemKind::Struct | ItemKind::Tuple))
+            .map(|i| i.members)
+            .sum()
+    }
+
+    pub fn deepest(&self) -> Option<&Item> {
+        self.items.iter().max_by_key(|i| i.depth)
+    }
+
+    pub fn largest_tuple(&self) -> Option<&Item> {
+        self.items.iter().filter(|i| i.kind == ItemKind::Tuple).max_by_key(|i| i.members)
+    }
+
+    /// Human-readable reasons, one per flagged item.
+    pub fn outliers(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        for i in &self.items {
+            let limit = match i.kind {
+                ItemKind::Struct => Some((OUTLIER_FIELDS, "fields")),
+                ItemKind::Tuple => Some((OUTLIER_TUPLE, "slots")),
+                ItemKind::Union => Some((OUTLIER_UNION_ARMS, "arms")),
+                ItemKind::Newtype | ItemKind::Enum => None,
+            };
+            if let Some((max, what)) = limit && i.members > max {
+                out.push(format!(
+                    "{} `{}` at {} has {} {what} (more than {max}); a map or list misread?",
+                    i.kind.as_str(), i.name, i.path, i.members
+                ));
+            }
+            if i.depth > OUTLIER_DEPTH {
+                out.push(format!(
+                    "{} `{}` at {} is nested {} levels deep (more than {OUTLIER_DEPTH})",
+                    i.kind.as_str(), i.name, i.path, i.depth
+                ));
+            }
+        }
+        out
+    }
+}
@@ -4,55 +4,211 @@
 //! Goal: build a compact, canonical tree from `inference::U` without descending into branches we’ll discard.
 //! Then adapt to `ir::Ty` for lowering/codegen.
 
-use crate::inference::U;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::inference::str::Grex;
+use crate::inference::{ObjC, StrFormat, U};
 use crate::ir;
+use crate::jpath::{JPath, Seg};
+use crate::overrides::Overrides;
 
 /// Canonical, compact shape after normalization policies are applied.
-#[derive(Debug, Clone)]
+/// Serializable so a run's result can be saved (`--ir-json`) and re-used by
+/// later commands (e.g. `unify`) without re-ingesting the corpus.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum NTy {
     Null,
     Bool,
-    Integer { min: Option<i64>, max: Option<i64> },
-    Number  { min: Option<f64>, max: Option<f64> },
+    /// `from_string`: some values were numeric strings (`--coerce-numeric-strings`),
+    /// so the string form is accepted too. `multiple_of`: a step every value
+    /// was a multiple of (see [`crate::inference::Step`]). `enum_`: every value,
+    /// ascending, when there are few enough (`--int-enums`). `examples` (as in
+    /// every scalar kind): a few values seen, kept with `gen --examples` (see
+    /// [`strip_examples`]); none next to an enum.
+    Integer {
+        min: Option<i128>,
+        max: Option<i128>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        from_string: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        multiple_of: Option<i128>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        enum_: Vec<i64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stats: Option<Stats>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        examples: Vec<serde_json::Value>,
+    },
+    Number {
+        min: Option<f64>,
+        max: Option<f64>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        from_string: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        multiple_of: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stats: Option<Stats>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        examples: Vec<serde_json::Value>,
+    },
+
+    /// Numbers, some written as strings spelling one (`4`, `"4.5"`), kept as
+    /// written (see [`crate::inference::ENABLE_STRING_OR_NUMBER`]); the bounds
+    /// are those of the JSON numbers.
+    StringOrNumber {
+        min: Option<f64>,
+        max: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stats: Option<Stats>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        examples: Vec<serde_json::Value>,
+    },
 
     /// Strings after policy:
-    /// - tiny enums kept in `enum_`
+    /// - tiny enums kept in `enum_`, most frequent first, with how often each
+    ///   value was seen in `enum_counts` (empty when unknown)
     /// - else possibly a grex pattern
     /// - `format_uri` passes the URI hint through
+    /// - `format`: a well-known syntax every value matched (then no pattern,
+    ///   unless it is `decimal`: see [`crate::inference::str::decimal_pattern`])
+    /// - `min_length` / `max_length`: in characters, unless it is an enum
     String {
         enum_: Vec<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        enum_counts: Vec<u64>,
         pattern: Option<String>,
         format_uri: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        format: Option<StrFormat>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_length: Option<u32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_length: Option<u32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stats: Option<Stats>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        examples: Vec<serde_json::Value>,
     },
 
     ArrayList {
         item: Box<NTy>,
         min_items: Option<u32>,
         max_items: Option<u32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stats: Option<Stats>,
     },
     ArrayTuple {
         elems: Vec<NTy>,   // exact arity after decision
         min_items: u32,    // last required index + 1 (pads required by value)
         max_items: u32,    // == elems.len()
+        /// Per position, out of the arrays seen (empty unless asked for, see [`Support`]).
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        support: Vec<Support>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stats: Option<Stats>,
     },
 
     Object {
         /// Stable order for deterministic downstream behavior (sorted by name).
         fields: Vec<NField>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stats: Option<Stats>,
     },
 
+    /// An object whose keys are ids rather than field names (see
+    /// [`crate::inference::decide_map`]): `key` describes the keys (a string
+    /// type), `value` every value. Paths reach the values through `[*]`.
+    Map {
+        key: Box<NTy>,
+        value: Box<NTy>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stats: Option<Stats>,
+    },
+
+    /// Objects whose `tag` field names their shape (see [`crate::inference::decide_tagged`]):
+    /// one `Object` per tag value, without the tag field. Paths look through it.
+    Tagged {
+        tag: String,
+        variants: Vec<(String, NTy)>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stats: Option<Stats>,
+    },
+
+    /// A string holding a JSON document of the inner type (`--json-strings`).
+    /// Paths look through it: the document's fields sit under the string's path.
+    JsonString(Box<NTy>),
+
     /// X ∪ null collapsed into `Nullable(X)`
     Nullable(Box<NTy>),
 
     /// Keep unions that cannot be simplified away.
     OneOf(Vec<NTy>),
+
+    /// Any JSON value: some nested past `--max-depth`, so none were looked into.
+    Any,
+
+    /// Any JSON value, kept as written: a `passthrough` path in `--overrides`,
+    /// whose evidence isn't normalized.
+    Raw,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NField {
     pub name: String,
     pub ty: NTy,
     pub required: bool, // present & non-null in all objects
+    /// The key is in every object, though maybe null (implied by `required`;
+    /// absent in older IR, which didn't tell missing keys from nulls).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub present: bool,
+    /// Out of the objects seen (`None` unless asked for, see [`Support`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub support: Option<Support>,
+}
+
+/// How much evidence backs a field or tuple position: of the `of` objects /
+/// arrays seen, how many had it, and how many with a non-null value. Kept
+/// with `gen --evidence-counts` (see [`strip_support`]), for `x-evidence` in
+/// the schema and doc comments on the Rust fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Support {
+    pub of: u64,
+    pub present: u64,
+    pub non_null: u64,
+}
+
+impl Support {
+    /// Doc comment line for a field.
+    pub fn describe(&self, what: &str) -> String {
+        if self.non_null == self.of {
+            format!("Non-null in all {} {what}.", self.of)
+        } else if self.present == self.of {
+            format!("In all {} {what}, non-null in {}.", self.of, self.non_null)
+        } else {
+            format!("In {} of {} {what}, non-null in {}.", self.present, self.of, self.non_null)
+        }
+    }
+}
+
+/// What a node was inferred from: how many values of its kind were seen at its
+/// path and, while every literal was kept (under the caps), how many distinct
+/// ones. `Null`, `Bool` and the wrappers carry none; a field's presence is its
+/// [`Support`]. Kept with `gen --evidence-counts` (see [`strip_support`]), in
+/// the IR and the lowered types, and as `x-stats` in the schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stats {
+    pub samples: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distinct: Option<u64>,
+}
+
+impl Stats {
+    /// `samples` values (none when unknown: zero in older evidence).
+    fn of(samples: u64, distinct: Option<usize>) -> Option<Stats> {
+        (samples > 0).then(|| Stats { samples, distinct: distinct.map(|d| d as u64) })
+    }
 }
 
 // -------------------- builder: U -> NTy (pure) --------------------
@@ -66,32 +222,84 @@ pub struct NField {
 /// Moves evidence out of `U` to avoid cloning large maps/vectors.
 /// Decides tuple-vs-list before descending; identical policies to `normalize_to_norm`.
 pub fn normalize_to_norm_consume(u: U) -> NTy {
+    normalize_at(Norm::DEFAULT, u, None)
+}
+
+/// Like [`normalize_to_norm_consume`], but array shapes forced in `ov` win over the evidence.
+pub fn normalize_with_overrides(u: U, ov: &Overrides) -> NTy {
+    normalize_with(u, ov, crate::required::ALL, 0, Grex::DEFAULT)
+}
+
+/// Like [`normalize_with_overrides`], with fields non-null in at least
+/// `required_threshold` of their objects required (see [`crate::required`]),
+/// integer slots of at most `int_enum_max` distinct values read as enums, and
+/// string patterns synthesized per `grex`.
+pub fn normalize_with(u: U, ov: &Overrides, required_threshold: f64, int_enum_max: usize, grex: Grex) -> NTy {
+    let opts = Norm { required: required_threshold, int_enum_max, grex };
+    if ov.is_empty() {
+        return normalize_at(opts, u, None);
+    }
+    normalize_at(opts, u, Some((ov, JPath::root())))
+}
+
+/// Normalization settings that aren't in the evidence.
+#[derive(Debug, Clone, Copy)]
+struct Norm {
+    /// Share of objects a field must be non-null in to be required.
+    required: f64,
+    /// Most distinct values of an integer enum (0: none).
+    int_enum_max: usize,
+    /// Regex synthesis for string slots.
+    grex: Grex,
+}
+
+impl Norm {
+    const DEFAULT: Norm = Norm { required: crate::required::ALL, int_enum_max: 0, grex: Grex::DEFAULT };
+}
+
+/// `at` is only tracked when there are overrides to look up.
+fn normalize_at(opts: Norm, mut u: U, at: Option<(&Overrides, JPath)>) -> NTy {
+    if at.as_ref().is_some_and(|(ov, p)| ov.passthrough(p)) {
+        return NTy::Raw;
+    }
     if u.is_exact_null() {
         return NTy::Null;
     }
+    if u.opaque {
+        return NTy::Any;
+    }
+    let child = |seg: Seg| at.as_ref().map(|(ov, p)| (*ov, p.child(seg)));
+    let counts = u.counts;
 
     let mut arms = Vec::<NTy>::new();
 
     // 1) Arrays first
     if let Some(arr) = u.arr {
         // decide cheaply from counts
-        let is_tuple = crate::inference::decide_tuple(&arr);
+        let is_tuple = at
+            .as_ref()
+            .and_then(|(ov, p)| ov.tuple_at(p))
+            .map(|t| t && !arr.cols.is_empty())
+            .unwrap_or_else(|| crate::inference::decide_tuple(&arr));
 
         // always normalize pooled list hypothesis (consume its Box<U>)
-        let item_norm = Box::new(normalize_to_norm_consume(*arr.item));
+        let item_norm = Box::new(normalize_at(opts, *arr.item, child(Seg::Items)));
+        let stats = Stats::of(arr.samples, None);
 
         if !is_tuple {
             arms.push(NTy::ArrayList {
                 item: item_norm,
                 min_items: Some(arr.len_min),
                 max_items: Some(arr.len_max),
+                stats,
             });
         } else {
             // consume cols vector
             let elems: Vec<NTy> = arr
                 .cols
                 .into_iter()
-                .map(normalize_to_norm_consume)
+                .enumerate()
+                .map(|(i, c)| normalize_at(opts, c, child(Seg::Index(i))))
                 .collect();
 
             let max_items = elems.len() as u32;
@@ -109,24 +317,66 @@ pub fn normalize_to_norm_consume(u: U) -> NTy {
                 })
             };
 
-            arms.push(NTy::ArrayTuple { elems, min_items, max_items });
+            let support = arr
+                .present
+                .iter()
+                .zip(&arr.non_null)
+                .map(|(&present, &non_null)| Support { of: arr.samples, present, non_null })
+                .collect();
+            arms.push(NTy::ArrayTuple { elems, min_items, max_items, support, stats });
         }
     }
 
-    // 2) Objects next
-    if let Some(obj) = u.obj {
-        // consume the BTreeMap by iterating it; push into Vec and sort
-        let mut fields: Vec<NField> = Vec::with_capacity(obj.fields.len());
-        for (name, field_c) in obj.fields {
-            let required = field_c.non_null_in == obj.seen_objects;
-            let ty = normalize_to_norm_consume(field_c.ty); // consume nested U
-            fields.push(NField { name, ty, required });
+    // 2) Objects next: maps keyed by ids, else records (or a union of them)
+    if let Some(value) = u.obj.as_ref().and_then(crate::inference::decide_map) {
+        let obj = u.obj.take().expect("decided above");
+        let stats = Stats::of(obj.seen_objects, None);
+        let key = match obj.wide {
+            Some(wide) => wide.key,
+            None => obj.fields.into_keys().fold(U::empty(), |acc, k| {
+                U::join_inner(&acc, &crate::inference::observe_value(&serde_json::Value::String(k)))
+            }),
+        };
+        arms.push(NTy::Map {
+            key: Box::new(normalize_at(opts, key, None)),
+            value: Box::new(normalize_at(opts, value, child(Seg::Items))),
+            stats,
+        });
+    }
+    if let Some(mut obj) = u.obj {
+        // tagged unions: one record per tag value, at the same paths
+        if let Some(tag) = crate::inference::decide_tagged(&obj).map(|(tag, _)| tag) {
+            let values = obj.variants.remove(tag).flatten().expect("decided above");
+            let variants = values
+                .into_iter()
+                .map(|(value, mut v)| {
+                    v.fields.remove(tag);
+                    (value, record(opts, v, &at))
+                })
+                .collect();
+            arms.push(NTy::Tagged { tag: tag.to_string(), variants, stats: Stats::of(obj.seen_objects, None) });
+        } else if let Some(records) = crate::inference::decide_shapes(&obj) {
+            // clearly different shapes: one record each, at the same paths
+            arms.extend(records.into_iter().map(|r| record(opts, r, &at)));
+        } else {
+            arms.push(record(opts, obj, &at));
         }
-        fields.sort_by(|a, b| a.name.cmp(&b.name));
-        arms.push(NTy::Object { fields });
     }
 
-    // 3) Numbers
+    // 3) Numbers (and strings that were all numbers, coerced into them)
+    let numeric = u.str_.as_mut().and_then(|s| s.numeric.take());
+    let from_string = numeric.is_some();
+    if let Some(numeric) = numeric {
+        u.num = Some(match &u.num {
+            Some(num) => crate::inference::NumC::join(num, &numeric),
+            None => numeric,
+        });
+        u.str_ = None;
+    }
+    // …or kept as written next to real numbers
+    let string_or_number = crate::inference::ENABLE_STRING_OR_NUMBER
+        && u.num.is_some()
+        && u.str_.as_ref().is_some_and(|s| s.spells_numbers && s.json.is_none());
     if let Some(num) = u.num {
         let integerish = (num.saw_int || num.saw_uint)
             && !num.saw_float
@@ -134,43 +384,112 @@ pub fn normalize_to_norm_consume(u: U) -> NTy {
             && num.max_f64.0.is_finite()
             && num.min_f64.0.fract() == 0.0
             && num.max_f64.0.fract() == 0.0;
+        // strings read as numbers count as numbers
+        let samples = counts.num + if from_string || string_or_number { counts.str_ } else { 0 };
+        let distinct = (!num.lits_f64.is_empty()).then_some(num.lits_f64.len());
+        // a step coarser than the values' own precision, over enough of them
+        // (an emptied literal set means more than it keeps)
+        let step = num.step.filter(|s| {
+            s.gcd > 1 && (num.lits_f64.is_empty() || num.lits_f64.len() >= crate::inference::STEP_MIN_VALUES)
+        });
 
-        if integerish {
+        let mut examples: Vec<serde_json::Value> = num.examples.iter().filter_map(|x| number_value(x.0)).collect();
+
+        if string_or_number {
+            // some of each way of writing them
+            let written = u.str_.as_ref().map_or(&[][..], |s| &s.examples[..]);
+            if !written.is_empty() {
+                examples.truncate(crate::inference::MAX_EXAMPLES - 1);
+            }
+            examples.extend(written.iter().map(|s| serde_json::Value::from(s.as_str())));
+            examples.truncate(crate::inference::MAX_EXAMPLES);
+            arms.push(NTy::StringOrNumber {
+                min: if num.min_f64.0.is_finite() { Some(num.min_f64.0) } else { None },
+                max: if num.max_f64.0.is_finite() { Some(num.max_f64.0) } else { None },
+                stats: Stats::of(samples, None),
+                examples,
+            });
+            u.str_ = None;
+        } else if integerish {
+            // exact where tracked; older evidence only has the rounded floats
+            let (min, max) = num.int_range.unwrap_or((num.min_f64.0 as i128, num.max_f64.0 as i128));
+            // a closed set of values is an enum (a step adds nothing to it)
+            let enum_ = num
+                .int_values()
+                .filter(|v| !from_string && v.len() <= opts.int_enum_max)
+                .unwrap_or_default();
+            let multiple_of = step
+                .filter(|s| s.scale == 0 && enum_.is_empty())
+                .and_then(|s| i128::try_from(s.gcd).ok());
+            let examples = if enum_.is_empty() { examples } else { Vec::new() };
             arms.push(NTy::Integer {
-                min: Some(num.min_f64.0 as i64),
-                max: Some(num.max_f64.0 as i64),
+                min: Some(min),
+                max: Some(max),
+                from_string,
+                multiple_of,
+                enum_,
+                stats: Stats::of(samples, distinct),
+                examples,
             });
         } else {
+            if !crate::inference::collapse_ints() && (num.saw_int || num.saw_uint) {
+                // the integers' own range isn't tracked next to floats; the
+                // slot's whole range bounds it
+                let (min, max) = (num.min_f64.0.ceil(), num.max_f64.0.floor());
+                arms.push(NTy::Integer {
+                    min: min.is_finite().then_some(min as i128),
+                    max: max.is_finite().then_some(max as i128),
+                    from_string,
+                    multiple_of: None,
+                    enum_: Vec::new(),
+                    // counted with the numbers, below
+                    stats: None,
+                    examples: Vec::new(),
+                });
+            }
             arms.push(NTy::Number {
                 min: if num.min_f64.0.is_finite() { Some(num.min_f64.0) } else { None },
                 max: if num.max_f64.0.is_finite() { Some(num.max_f64.0) } else { None },
+                from_string,
+                multiple_of: step.map(crate::inference::Step::value),
+                stats: Stats::of(samples, distinct),
+                examples,
             });
         }
     }
 
-    // 4) Strings
-    if let Some(mut str_c) = u.str_ {
+    // 4) Strings (holding JSON documents: their contents, under the same path)
+    if let Some(json) = u.str_.as_mut().and_then(|s| s.json.take()) {
+        arms.push(NTy::JsonString(Box::new(normalize_at(opts, *json, at.clone()))));
+    } else if let Some(mut str_c) = u.str_ {
+        let stats = Stats::of(counts.str_, (!str_c.lits.is_empty()).then_some(str_c.lits.len()));
         // Tiny-enum only if flag is on AND samples look human-ish within limits.
-        let tiny_enum = crate::inference::ENABLE_STRING_ENUMS
-            && str_c.lits.len() <= crate::inference::STRING_ENUM_MAX
-            && str_c.lits.iter().all(|s|
-                s.len() <= crate::inference::STRING_ENUM_MAX_LEN
-                && crate::inference::str::looks_humanish(s)
-            );
+        let tiny_enum = str_c.tiny_enum();
 
+        let mut enum_counts = Vec::new();
         let (enum_, pattern) = if tiny_enum && !str_c.lits.is_empty() {
-            // keep tiny enum
-            let mut v: ::std::vec::Vec<::std::string::String> = str_c.lits.into_iter().collect();
-            v.sort_unstable();
-            (v, None)
-        } else if !str_c.is_uri {
+            // keep tiny enum, most frequent first (by value where counts are unknown)
+            let mut v: ::std::vec::Vec<(::std::string::String, u64)> = str_c
+                .lits
+                .into_iter()
+                .map(|s| {
+                    let n = str_c.lit_counts.get(&s).copied().unwrap_or(0);
+                    (s, n)
+                })
+                .collect();
+            v.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            if str_c.lit_counts.len() == v.len() {
+                enum_counts = v.iter().map(|(_, n)| *n).collect();
+            }
+            (v.into_iter().map(|(s, _)| s).collect(), None)
+        } else if !str_c.is_uri && str_c.formats.is_empty() {
             // synthesize regex only if enabled; otherwise plain string
-            let rx = if crate::inference::ENABLE_GREX {
+            let rx = if opts.grex.enabled {
                 let key_now = crate::inference::str::grex_cache_key(&str_c.lits);
-                if str_c.grex_cache_key == Some(key_now) {
+                if str_c.grex_cache_key == Some(key_now) && opts.grex.is_default() {
                     str_c.pattern_synth.take()
                 } else {
-                    crate::inference::str::synth_regex_with_grex(&str_c.lits)
+                    crate::inference::str::synth_regex_with_grex(&str_c.lits, opts.grex)
                 }
             } else {
                 None
@@ -179,15 +498,26 @@ pub fn normalize_to_norm_consume(u: U) -> NTy {
             str_c.lits.clear();
             (Vec::new(), rx)
         } else {
-            // URI: plain string with format; drop atoms
+            // URI or known format: plain string with format; drop atoms (decimal
+            // amounts keep a pattern: `decimal` isn't a format validators know)
+            let pattern = (!str_c.is_uri && str_c.formats.first() == Some(&StrFormat::Decimal))
+                .then(|| crate::inference::str::decimal_pattern(&str_c.lits));
             str_c.lits.clear();
-            (Vec::new(), None)
+            (Vec::new(), pattern)
         };
 
+        let lengths = str_c.len_range.filter(|_| enum_.is_empty());
+        let examples = if enum_.is_empty() { str_c.examples.into_iter().map(serde_json::Value::from).collect() } else { Vec::new() };
         arms.push(NTy::String {
             enum_,
+            enum_counts,
             pattern,
             format_uri: str_c.is_uri,
+            format: str_c.formats.first().copied(),
+            min_length: lengths.and_then(|(lo, _)| u32::try_from(lo).ok()),
+            max_length: lengths.and_then(|(_, hi)| u32::try_from(hi).ok()),
+            stats,
+            examples,
         });
     }
 
@@ -211,6 +541,33 @@ pub fn normalize_to_norm_consume(u: U) -> NTy {
     }
 }
 
+/// The fields of `obj` read as one record, sorted by name.
+/// A record of `obj`'s fields.
+fn record(opts: Norm, obj: ObjC, at: &Option<(&Overrides, JPath)>) -> NTy {
+    let stats = Stats::of(obj.seen_objects, None);
+    NTy::Object { fields: record_fields(opts, obj, at), stats }
+}
+
+fn record_fields(opts: Norm, obj: ObjC, at: &Option<(&Overrides, JPath)>) -> Vec<NField> {
+    // consume the BTreeMap by iterating it; push into Vec and sort
+    let mut fields: Vec<NField> = Vec::with_capacity(obj.fields.len());
+    for (name, field_c) in obj.fields {
+        let required = crate::required::is_required(field_c.non_null_in, obj.seen_objects, opts.required);
+        let child = at.as_ref().map(|(ov, p)| (*ov, p.child(Seg::Field(name.clone()))));
+        let ty = normalize_at(opts, field_c.ty, child); // consume nested U
+        // required through the threshold: the rare nulls don't make it optional
+        let ty = match ty {
+            NTy::Nullable(inner) if required && field_c.non_null_in < obj.seen_objects => *inner,
+            ty => ty,
+        };
+        let present = required || field_c.present_in == obj.seen_objects;
+        let support = Some(Support { of: obj.seen_objects, present: field_c.present_in, non_null: field_c.non_null_in });
+        fields.push(NField { name, ty, required, present, support });
+    }
+    fields.sort_by(|a, b| a.name.cmp(&b.name));
+    fields
+}
+
 fn simplify_norm_unions(mut arms: Vec<NTy>) -> NTy {
     let mut had_null = false;
     arms.retain(|t| {
@@ -221,6 +578,9 @@ fn simplify_norm_unions(mut arms: Vec<NTy>) -> NTy {
             true
         }
     });
+    if crate::inference::collapse_ints() {
+        collapse_int_into_number(&mut arms);
+    }
     let core = match arms.len() {
         0 => NTy::Null,
         1 => arms.remove(0),
@@ -233,6 +593,206 @@ fn simplify_norm_unions(mut arms: Vec<NTy>) -> NTy {
     }
 }
 
+/// Fold an `Integer` arm into a `Number` arm next to it: every integer is a
+/// number, so the union only repeats itself (in schemas and as enum variants).
+fn collapse_int_into_number(arms: &mut Vec<NTy>) {
+    let Some(n) = arms.iter().position(|a| matches!(a, NTy::Number { .. })) else { return };
+    let Some(i) = arms.iter().position(|a| matches!(a, NTy::Integer { .. })) else { return };
+    let NTy::Integer { min: imin, max: imax, from_string: istr, multiple_of: istep, .. } = arms.remove(i) else {
+        unreachable!()
+    };
+    let n = if i < n { n - 1 } else { n };
+    let NTy::Number { min, max, from_string, multiple_of, .. } = &mut arms[n] else { unreachable!() };
+    let widen = |x: Option<f64>, y: Option<i128>, pick: fn(f64, f64) -> f64| Some(pick(x?, y? as f64));
+    *min = widen(*min, imin, f64::min);
+    *max = widen(*max, imax, f64::max);
+    // quoted integers stay accepted: the number arm reads strings if either did
+    *from_string |= istr;
+    // the number step holds for the integers only if it divides theirs
+    *multiple_of = multiple_of.filter(|&m| istep.is_some_and(|s| (s as f64 / m).fract() == 0.0));
+}
+
+// -------------------- tightness: constraint stripping --------------------
+
+/// The default `--tightness`: every observed constraint is emitted.
+pub const MAX_TIGHTNESS: u8 = 3;
+
+/// Drop the constraints above `tightness` so every emitter (schema, Rust, …)
+/// sees the same, looser IR. Each level keeps what the ones below keep:
+/// - 0: shapes only (kinds, fields, requiredness, tuple positions)
+/// - 1: + well-known string formats and encodings (`uuid`, `uri`, base64, …)
+/// - 2: + enums (string and integer) and patterns
+/// - 3: + numeric bounds, list and string lengths
+pub fn loosen(n: &mut NTy, tightness: u8) {
+    match n {
+        NTy::Null | NTy::Bool | NTy::Any | NTy::Raw => {}
+        NTy::Integer { min, max, multiple_of, enum_, .. } if tightness < 3 => {
+            (*min, *max, *multiple_of) = (None, None, None);
+            if tightness < 2 {
+                enum_.clear();
+            }
+        }
+        NTy::Number { min, max, multiple_of, .. } if tightness < 3 => (*min, *max, *multiple_of) = (None, None, None),
+        NTy::StringOrNumber { min, max, .. } if tightness < 3 => (*min, *max) = (None, None),
+        NTy::Integer { .. } | NTy::Number { .. } | NTy::StringOrNumber { .. } => {}
+        NTy::String { enum_, enum_counts, pattern, format_uri, format, min_length, max_length, .. } => {
+            if tightness < 3 {
+                (*min_length, *max_length) = (None, None);
+            }
+            if tightness < 2 {
+                enum_.clear();
+                enum_counts.clear();
+                *pattern = None;
+            }
+            if tightness < 1 {
+                *format_uri = false;
+                *format = None;
+            }
+        }
+        NTy::ArrayList { item, min_items, max_items, .. } => {
+            if tightness < 3 {
+                (*min_items, *max_items) = (None, None);
+            }
+            loosen(item, tightness);
+        }
+        NTy::ArrayTuple { elems, .. } => elems.iter_mut().for_each(|e| loosen(e, tightness)),
+        NTy::Tagged { variants, .. } => variants.iter_mut().for_each(|(_, v)| loosen(v, tightness)),
+        NTy::Object { fields, .. } => fields.iter_mut().for_each(|f| loosen(&mut f.ty, tightness)),
+        NTy::Map { key, value, .. } => {
+            loosen(key, tightness);
+            loosen(value, tightness);
+        }
+        NTy::JsonString(inner) | NTy::Nullable(inner) => loosen(inner, tightness),
+        NTy::OneOf(arms) => arms.iter_mut().for_each(|a| loosen(a, tightness)),
+    }
+}
+
+/// Drop the [`Support`] and [`Stats`] annotations (all but `gen --evidence-counts`
+/// runs), so outputs don't change with every document added to the corpus.
+pub fn strip_support(n: &mut NTy) {
+    if let Some(stats) = n.stats_mut() {
+        *stats = None;
+    }
+    match n {
+        NTy::ArrayTuple { elems, support, .. } => {
+            support.clear();
+            elems.iter_mut().for_each(strip_support);
+        }
+        NTy::Object { fields, .. } => fields.iter_mut().for_each(|f| {
+            f.support = None;
+            strip_support(&mut f.ty);
+        }),
+        NTy::ArrayList { item, .. } => strip_support(item),
+        NTy::Tagged { variants, .. } => variants.iter_mut().for_each(|(_, v)| strip_support(v)),
+        NTy::Map { key, value, .. } => {
+            strip_support(key);
+            strip_support(value);
+        }
+        NTy::JsonString(inner) | NTy::Nullable(inner) => strip_support(inner),
+        NTy::OneOf(arms) => arms.iter_mut().for_each(strip_support),
+        NTy::Null | NTy::Bool | NTy::Integer { .. } | NTy::Number { .. } | NTy::StringOrNumber { .. } | NTy::String { .. } | NTy::Any | NTy::Raw => {}
+    }
+}
+
+/// Drop the examples (all but `gen --examples` runs): they're data, and change
+/// as the corpus does.
+pub fn strip_examples(n: &mut NTy) {
+    match n {
+        NTy::Integer { examples, .. }
+        | NTy::Number { examples, .. }
+        | NTy::StringOrNumber { examples, .. }
+        | NTy::String { examples, .. } => examples.clear(),
+        NTy::ArrayTuple { elems, .. } => elems.iter_mut().for_each(strip_examples),
+        NTy::Object { fields, .. } => fields.iter_mut().for_each(|f| strip_examples(&mut f.ty)),
+        NTy::ArrayList { item, .. } => strip_examples(item),
+        NTy::Tagged { variants, .. } => variants.iter_mut().for_each(|(_, v)| strip_examples(v)),
+        NTy::Map { key, value, .. } => {
+            strip_examples(key);
+            strip_examples(value);
+        }
+        NTy::JsonString(inner) | NTy::Nullable(inner) => strip_examples(inner),
+        NTy::OneOf(arms) => arms.iter_mut().for_each(strip_examples),
+        NTy::Null | NTy::Bool | NTy::Any | NTy::Raw => {}
+    }
+}
+
+/// Path → the examples of the scalars there (union arms' together), for doc
+/// comments. Paths look through `Nullable`, `JsonString`, tagged variants and
+/// union arms, as in the schema; map keys are left out.
+pub fn examples(n: &NTy) -> BTreeMap<String, Vec<serde_json::Value>> {
+    fn walk(n: &NTy, at: &mut JPath, out: &mut BTreeMap<String, Vec<serde_json::Value>>) {
+        let mut below = |seg: Seg, n: &NTy, out: &mut BTreeMap<String, Vec<serde_json::Value>>| {
+            at.0.push(seg);
+            walk(n, at, out);
+            at.0.pop();
+        };
+        match n {
+            NTy::Integer { examples, .. }
+            | NTy::Number { examples, .. }
+            | NTy::StringOrNumber { examples, .. }
+            | NTy::String { examples, .. } => {
+                if !examples.is_empty() {
+                    out.entry(at.to_string()).or_default().extend(examples.iter().cloned());
+                }
+            }
+            NTy::ArrayTuple { elems, .. } => elems.iter().enumerate().for_each(|(i, e)| below(Seg::Index(i), e, out)),
+            NTy::Object { fields, .. } => fields.iter().for_each(|f| below(Seg::Field(f.name.clone()), &f.ty, out)),
+            NTy::ArrayList { item: inner, .. } | NTy::Map { value: inner, .. } => below(Seg::Items, inner, out),
+            NTy::Tagged { variants, .. } => variants.iter().for_each(|(_, v)| walk(v, at, out)),
+            NTy::JsonString(inner) | NTy::Nullable(inner) => walk(inner, at, out),
+            NTy::OneOf(arms) => arms.iter().for_each(|a| walk(a, at, out)),
+            NTy::Null | NTy::Bool | NTy::Any | NTy::Raw => {}
+        }
+    }
+    let mut out = BTreeMap::new();
+    walk(n, &mut JPath::root(), &mut out);
+    out
+}
+
+impl NTy {
+    /// The node's examples (see [`strip_examples`]), for the scalar kinds.
+    pub fn examples(&self) -> &[serde_json::Value] {
+        match self {
+            NTy::Integer { examples, .. }
+            | NTy::Number { examples, .. }
+            | NTy::StringOrNumber { examples, .. }
+            | NTy::String { examples, .. } => examples,
+            _ => &[],
+        }
+    }
+
+    /// The node's [`Stats`], for the kinds that have them.
+    pub fn stats(&self) -> Option<Stats> {
+        match self {
+            NTy::Integer { stats, .. }
+            | NTy::Number { stats, .. }
+            | NTy::StringOrNumber { stats, .. }
+            | NTy::String { stats, .. }
+            | NTy::ArrayList { stats, .. }
+            | NTy::ArrayTuple { stats, .. }
+            | NTy::Object { stats, .. }
+            | NTy::Map { stats, .. }
+            | NTy::Tagged { stats, .. } => *stats,
+            NTy::Null | NTy::Bool | NTy::JsonString(_) | NTy::Nullable(_) | NTy::OneOf(_) | NTy::Any | NTy::Raw => None,
+        }
+    }
+
+    fn stats_mut(&mut self) -> Option<&mut Option<Stats>> {
+        match self {
+            NTy::Integer { stats, .. }
+            | NTy::Number { stats, .. }
+            | NTy::StringOrNumber { stats, .. }
+            | NTy::String { stats, .. }
+            | NTy::ArrayList { stats, .. }
+            | NTy::ArrayTuple { stats, .. }
+            | NTy::Object { stats, .. }
+            | NTy::Map { stats, .. }
+            | NTy::Tagged { stats, .. } => Some(stats),
+            NTy::Null | NTy::Bool | NTy::JsonString(_) | NTy::Nullable(_) | NTy::OneOf(_) | NTy::Any | NTy::Raw => None,
+        }
+    }
+}
+
 // -------------------- adapter: NTy -> ir::Ty --------------------
 
 pub fn lower_from_norm(n: &NTy) -> ir::Ty {
@@ -240,37 +800,76 @@ pub fn lower_from_norm(n: &NTy) -> ir::Ty {
         NTy::Null => ir::Ty::Null,
         NTy::Bool => ir::Ty::Bool,
 
-        NTy::Integer { min, max } => ir::Ty::Integer { min: *min, max: *max },
-        NTy::Number  { min, max } => ir::Ty::Number  { min: *min, max: *max },
+        NTy::Integer { min, max, from_string, multiple_of, enum_, stats, .. } => ir::Ty::Integer {
+            min: *min,
+            max: *max,
+            from_string: *from_string,
+            multiple_of: *multiple_of,
+            enum_: enum_.clone(),
+            stats: *stats,
+        },
+        NTy::Number  { min, max, from_string, multiple_of, stats, .. } => ir::Ty::Number {
+            min: *min,
+            max: *max,
+            from_string: *from_string,
+            multiple_of: *multiple_of,
+            stats: *stats,
+        },
+        NTy::StringOrNumber { min, max, stats, .. } => ir::Ty::StringOrNumber { min: *min, max: *max, stats: *stats },
 
-        NTy::String { enum_, pattern, format_uri } => ir::Ty::String {
+        NTy::String { enum_, enum_counts, pattern, format_uri, format, min_length, max_length, stats, .. } => ir::Ty::String {
             enum_: enum_.clone(),
+            enum_counts: enum_counts.clone(),
             pattern: pattern.clone(),
             format_uri: *format_uri,
+            format: *format,
+            min_length: *min_length,
+            max_length: *max_length,
+            stats: *stats,
         },
 
-        NTy::ArrayList { item, min_items, max_items } => ir::Ty::ArrayList {
+        NTy::ArrayList { item, min_items, max_items, stats } => ir::Ty::ArrayList {
             item: Box::new(lower_from_norm(item)),
             min_items: *min_items,
             max_items: *max_items,
+            stats: *stats,
         },
 
-        NTy::ArrayTuple { elems, min_items, max_items } => ir::Ty::ArrayTuple {
+        NTy::ArrayTuple { elems, min_items, max_items, stats, .. } => ir::Ty::ArrayTuple {
             elems: elems.iter().map(lower_from_norm).collect(),
             min_items: *min_items,
             max_items: *max_items,
+            stats: *stats,
         },
 
-        NTy::Object { fields } => ir::Ty::Object {
+        NTy::Object { fields, stats } => ir::Ty::Object {
             fields: fields.iter().map(|f| ir::Field {
                 name: f.name.clone(),
                 ty: lower_from_norm(&f.ty),
                 required: f.required,
+                present: f.present || f.required,
+                support: f.support,
             }).collect(),
+            stats: *stats,
         },
 
+        NTy::Map { key, value, stats } => ir::Ty::Map {
+            key: Box::new(lower_from_norm(key)),
+            value: Box::new(lower_from_norm(value)),
+            stats: *stats,
+        },
+
+        NTy::Tagged { tag, variants, stats } => ir::Ty::Tagged {
+            tag: tag.clone(),
+            variants: variants.iter().map(|(value, v)| (value.clone(), lower_from_norm(v))).collect(),
+            stats: *stats,
+        },
+
+        NTy::JsonString(inner) => ir::Ty::JsonString(Box::new(lower_from_norm(inner))),
         NTy::Nullable(inner) => ir::Ty::Nullable(Box::new(lower_from_norm(inner))),
         NTy::OneOf(arms)     => ir::Ty::OneOf(arms.iter().map(lower_from_norm).collect()),
+        NTy::Any             => ir::Ty::Any,
+        NTy::Raw             => ir::Ty::Raw,
     }
 }
 
@@ -279,6 +878,27 @@ pub fn lower_from_norm(n: &NTy) -> ir::Ty {
 // JSON SCHEMA CG
 // ————————————————————————————————————————————————————————————————————————————
 
+/// JSON integer syntax, for integers also written as strings.
+pub const INTEGER_STRING_PATTERN: &str = "^-?(0|[1-9][0-9]*)$";
+/// JSON number syntax, for numbers also written as strings.
+pub const NUMBER_STRING_PATTERN: &str = "^-?(0|[1-9][0-9]*)(\\.[0-9]+)?([eE][+-]?[0-9]+)?$";
+
+/// `i` as a JSON number; past `i64` / `u64` it can only be approximated.
+fn int_value(i: i128) -> serde_json::Value {
+    i64::try_from(i)
+        .map(serde_json::Value::from)
+        .or_else(|_| u64::try_from(i).map(serde_json::Value::from))
+        .unwrap_or_else(|_| serde_json::Value::from(i as f64))
+}
+
+/// `x` as a JSON number, integral ones without a fraction (`None` if not finite).
+fn number_value(x: f64) -> Option<serde_json::Value> {
+    if x.fract() == 0.0 && x.abs() <= (1u64 << 53) as f64 {
+        return Some(int_value(x as i128));
+    }
+    serde_json::Number::from_f64(x).map(serde_json::Value::Number)
+}
+
 /// Build a JSON Schema (draft-ish) directly from the normalized IR.
 /// This mirrors your existing schema semantics but uses the compact NTy.
 pub fn schema_from_norm(n: &NTy) -> serde_json::Value {
@@ -306,38 +926,67 @@ pub fn schema_from_norm(n: &NTy) -> serde_json::Value {
         json!({ "oneOf": [inner, { "type": "null" }] })
     }
 
-    match n {
+    let mut schema = match n {
         NTy::Null => json!({ "type": "null" }),
         NTy::Bool => json!({ "type": "boolean" }),
 
-        NTy::Integer { min, max } => {
+        // bounds only constrain numbers; `pattern` only strings
+        NTy::Integer { min, max, from_string, multiple_of, enum_, .. } => {
             let mut o = json!({ "type": "integer" });
+            if let Some(m) = *min { o["minimum"] = int_value(m); }
+            if let Some(m) = *max { o["maximum"] = int_value(m); }
+            if let Some(m) = *multiple_of { o["multipleOf"] = int_value(m); }
+            if !enum_.is_empty() { o["enum"] = Value::from(enum_.clone()); }
+            if *from_string {
+                o["type"] = json!(["integer", "string"]);
+                o["pattern"] = Value::from(INTEGER_STRING_PATTERN);
+            }
+            o
+        }
+
+        NTy::Number { min, max, from_string, multiple_of, .. } => {
+            let mut o = json!({ "type": "number" });
             if let Some(m) = *min { o["minimum"] = Value::from(m); }
             if let Some(m) = *max { o["maximum"] = Value::from(m); }
+            if let Some(m) = *multiple_of { o["multipleOf"] = Value::from(m); }
+            if *from_string {
+                o["type"] = json!(["number", "string"]);
+                o["pattern"] = Value::from(NUMBER_STRING_PATTERN);
+            }
             o
         }
 
-        NTy::Number { min, max } => {
-            let mut o = json!({ "type": "number" });
+        NTy::StringOrNumber { min, max, .. } => {
+            let mut o = json!({ "type": ["string", "number"], "pattern": NUMBER_STRING_PATTERN });
             if let Some(m) = *min { o["minimum"] = Value::from(m); }
             if let Some(m) = *max { o["maximum"] = Value::from(m); }
             o
         }
 
-        NTy::String { enum_, pattern, format_uri } => {
+        NTy::String { enum_, enum_counts, pattern, format_uri, format, min_length, max_length, .. } => {
             let mut o = json!({ "type": "string" });
+            if let Some(n) = *min_length { o["minLength"] = Value::from(n); }
+            if let Some(n) = *max_length { o["maxLength"] = Value::from(n); }
             if !enum_.is_empty() {
                 o["enum"] = Value::Array(enum_.iter().cloned().map(Value::from).collect());
+                // how often each value was seen, most frequent first
+                if enum_counts.len() == enum_.len() {
+                    o["x-frequency"] = enum_.iter().cloned().zip(enum_counts.iter().map(|&n| Value::from(n))).collect();
+                }
             } else if let Some(rx) = pattern {
                 o["pattern"] = Value::from(rx.clone());
             }
             if *format_uri {
                 o["format"] = Value::from("uri");
+            } else if let Some(enc) = format.and_then(StrFormat::content_encoding) {
+                o["contentEncoding"] = Value::from(enc);
+            } else if let Some(f) = format {
+                o["format"] = Value::from(f.as_str());
             }
             o
         }
 
-        NTy::ArrayList { item, min_items, max_items } => {
+        NTy::ArrayList { item, min_items, max_items, .. } => {
             let mut o = json!({
                 "type": "array",
                 "items": schema_from_norm(item),
@@ -347,26 +996,69 @@ pub fn schema_from_norm(n: &NTy) -> serde_json::Value {
             o
         }
 
-        NTy::ArrayTuple { elems, min_items, max_items } => {
+        NTy::ArrayTuple { elems, min_items, max_items, support, .. } => {
+            let mut items = elems.iter().map(schema_from_norm).collect::<Vec<_>>();
+            for (item, s) in items.iter_mut().zip(support) {
+                item["x-evidence"] = json!(s);
+            }
             json!({
                 "type": "array",
-                "prefixItems": elems.iter().map(schema_from_norm).collect::<Vec<_>>(),
+                "prefixItems": items,
                 "minItems": *min_items,
                 "maxItems": *max_items
             })
         }
 
-        NTy::Object { fields } => {
+        NTy::Object { fields, .. } => {
             let props = fields.iter()
-                .map(|f| (f.name.clone(), schema_from_norm(&f.ty)))
+                .map(|f| {
+                    let mut s = schema_from_norm(&f.ty);
+                    if let Some(support) = f.support {
+                        s["x-evidence"] = json!(support);
+                    }
+                    (f.name.clone(), s)
+                })
                 .collect::<Vec<_>>();
+            // a key every object has is required, even where its value may be null
             let req = fields.iter()
-                .filter(|f| f.required)
+                .filter(|f| f.required || f.present)
                 .map(|f| f.name.clone())
                 .collect::<Vec<_>>();
             obj_of(props, req)
         }
 
+        // keys are only constrained when they have a known syntax
+        NTy::Map { key, value, .. } => {
+            let mut o = json!({
+                "type": "object",
+                "additionalProperties": schema_from_norm(value),
+            });
+            let names = schema_from_norm(key);
+            if names != json!({ "type": "string" }) {
+                o["propertyNames"] = names;
+            }
+            o
+        }
+
+        // each variant's object, with its tag pinned and required
+        NTy::Tagged { tag, variants, .. } => {
+            let arms = variants.iter().map(|(value, v)| {
+                let mut o = schema_from_norm(v);
+                o["properties"][tag] = json!({ "const": value });
+                let mut required = vec![Value::from(tag.clone())];
+                required.extend(o.get("required").and_then(Value::as_array).into_iter().flatten().cloned());
+                o["required"] = Value::Array(required);
+                o
+            });
+            json!({ "oneOf": arms.collect::<Vec<_>>() })
+        }
+
+        NTy::JsonString(inner) => json!({
+            "type": "string",
+            "contentMediaType": "application/json",
+            "contentSchema": schema_from_norm(inner),
+        }),
+
         NTy::Nullable(inner) => {
             let inner_schema = schema_from_norm(inner);
             // If the inner is exactly null (shouldn’t happen), return null;
@@ -383,7 +1075,166 @@ pub fn schema_from_norm(n: &NTy) -> serde_json::Value {
             // to keep behavior predictable. (Optional: collapse nested oneOfs.)
             json!({ "oneOf": arms.iter().map(schema_from_norm).collect::<Vec<_>>() })
         }
+
+        // the empty schema: `true` as an object, so annotations can go on it
+        NTy::Any | NTy::Raw => json!({}),
+    };
+    if let Some(stats) = n.stats() {
+        schema["x-stats"] = json!(stats);
+    }
+    if !n.examples().is_empty() {
+        schema["examples"] = json!(n.examples());
+    }
+    schema
+}
+
+/// The subschema at `segs`, looking through `oneOf` wrappers (nullables, unions)
+/// for the arm that has the next step.
+pub fn schema_at<'a>(schema: &'a mut serde_json::Value, segs: &[Seg]) -> Option<&'a mut serde_json::Value> {
+    let Some((seg, rest)) = segs.split_first() else {
+        return schema.is_object().then_some(schema);
+    };
+    let key = match seg {
+        Seg::Field(_) => "properties",
+        Seg::Items if schema.get("additionalProperties").is_some() => "additionalProperties",
+        Seg::Items => "items",
+        Seg::Index(_) => "prefixItems",
+    };
+    if schema.get(key).is_none() {
+        if schema.get("contentSchema").is_some() {
+            return schema_at(schema.get_mut("contentSchema")?, segs);
+        }
+        let arms = schema.get_mut("oneOf")?.as_array_mut()?;
+        // tagged unions: the variant that has the field
+        let has = |a: &serde_json::Value| match seg {
+            Seg::Field(k) => a.get(key).is_some_and(|p| p.get(k.as_str()).is_some()),
+            _ => a.get(key).is_some(),
+        };
+        let arm = arms.iter_mut().find(|a| has(a))?;
+        return schema_at(arm, segs);
+    }
+    let next = match seg {
+        Seg::Field(k) => schema.get_mut(key)?.get_mut(k.as_str())?,
+        Seg::Items => schema.get_mut(key)?,
+        Seg::Index(i) => schema.get_mut(key)?.get_mut(*i)?,
+    };
+    schema_at(next, rest)
+}
+
+/// The paths among `paths` that name no slot of `n`.
+pub fn unmatched<'a, V>(n: &NTy, paths: &'a BTreeMap<String, V>) -> Vec<&'a str> {
+    let mut schema = schema_from_norm(n);
+    paths
+        .keys()
+        .filter(|p| p.parse::<JPath>().map_or(true, |p| schema_at(&mut schema, p.segs()).is_none()))
+        .map(String::as_str)
+        .collect()
+}
+
+/// Set `title` to the generated type name on the subschema of every path in a
+/// naming manifest (see [`crate::codegen::Codegen::names`]), so schema-driven
+/// generators for other languages pick the same names. Entries for the extra types
+/// at a path (`PATH~2`, …) have no subschema of their own and are skipped.
+pub fn annotate_titles(schema: &mut serde_json::Value, names: &BTreeMap<String, String>) {
+    for (path, name) in names {
+        let Ok(path) = path.parse::<JPath>() else { continue };
+        let Some(node) = schema_at(schema, path.segs()) else { continue };
+        type_node(node)["title"] = serde_json::Value::from(name.as_str());
+    }
+}
+
+/// The subschema of the type a slot's schema `node` holds: the non-null arm of a
+/// nullable, the document inside a JSON string.
+pub fn type_node(node: &mut serde_json::Value) -> &mut serde_json::Value {
+    let node = match node.get("oneOf").and_then(|a| a.as_array()).map(|a| a.len()) {
+        Some(2) if node["oneOf"][1] == serde_json::json!({ "type": "null" }) => &mut node["oneOf"][0],
+        _ => node,
+    };
+    if node.get("contentSchema").is_some() { &mut node["contentSchema"] } else { node }
+}
+
+/// Rewrite every tuple (`prefixItems`) of `schema` as an object keyed by
+/// position (`"0"`, `"1"`, …), or by the name `names` gives the position (see
+/// [`crate::tuple_names`]), marked `"x-encoding": "positional-array"`: easier to
+/// read than `prefixItems`, though data is still written as arrays (the schema
+/// no longer validates it). Run it after the passes that locate nodes by path.
+pub fn tuples_as_objects(schema: &mut serde_json::Value, names: &BTreeMap<String, String>) {
+    // named tuples first, deepest first: converting one moves its positions out
+    // of `prefixItems`, where the paths of tuples nested inside it point
+    let mut named: BTreeMap<Vec<Seg>, BTreeMap<usize, &str>> = BTreeMap::new();
+    for (path, name) in names {
+        let Ok(path) = path.parse::<JPath>() else { continue };
+        let Some((Seg::Index(i), tuple)) = path.segs().split_last() else { continue };
+        named.entry(tuple.to_vec()).or_default().insert(*i, name.as_str());
+    }
+    let mut named: Vec<_> = named.into_iter().collect();
+    named.sort_by_key(|(tuple, _)| std::cmp::Reverse(tuple.len()));
+    for (tuple, names) in named {
+        if let Some(serde_json::Value::Object(o)) = schema_at(schema, &tuple).map(type_node) {
+            tuple_as_object(o, &names);
+        }
     }
+    positions_as_objects(schema);
+}
+
+fn positions_as_objects(schema: &mut serde_json::Value) {
+    match schema {
+        serde_json::Value::Array(xs) => xs.iter_mut().for_each(positions_as_objects),
+        serde_json::Value::Object(o) => {
+            o.values_mut().for_each(positions_as_objects);
+            tuple_as_object(o, &BTreeMap::new());
+        }
+        _ => {}
+    }
+}
+
+/// One tuple schema `o` as an object; positions missing from `names` (or whose
+/// name another position already took) are keyed by their index.
+fn tuple_as_object(o: &mut serde_json::Map<String, serde_json::Value>, names: &BTreeMap<usize, &str>) {
+    // (a schema, not a `properties` entry named so)
+    if !o.get("prefixItems").is_some_and(serde_json::Value::is_array) {
+        return;
+    }
+    let Some(serde_json::Value::Array(elems)) = o.remove("prefixItems") else { unreachable!() };
+    let min_items = o.remove("minItems").and_then(|m| m.as_u64()).unwrap_or(0) as usize;
+    o.remove("maxItems");
+    o.remove("items");
+    let mut props = serde_json::Map::new();
+    let mut required = Vec::new();
+    for (i, e) in elems.into_iter().enumerate() {
+        let key = match names.get(&i) {
+            Some(name) if !props.contains_key(*name) && name.parse::<usize>().is_err() => name.to_string(),
+            _ => i.to_string(),
+        };
+        if i < min_items {
+            required.push(key.clone());
+        }
+        props.insert(key, e);
+    }
+    o.insert("type".into(), serde_json::json!("object"));
+    o.insert("x-encoding".into(), serde_json::json!("positional-array"));
+    o.insert("properties".into(), props.into());
+    o.insert("required".into(), serde_json::json!(required));
+    o.insert("additionalProperties".into(), serde_json::json!(false));
+}
+
+/// Wrap the schema of one document as the record type of a stream: the record
+/// under `$defs/{name}` (which every record validates against, through `$ref`) and
+/// `x-stream` saying how records are delimited; definitions of the record's
+/// (see [`crate::share`]) move up beside it. Run it after every other pass.
+pub fn records_schema(mut record: serde_json::Value, name: &str, encoding: &str) -> serde_json::Value {
+    let at = format!("#/$defs/{name}");
+    let mut defs = match record.as_object_mut().and_then(|o| o.remove("$defs")) {
+        Some(serde_json::Value::Object(defs)) => defs,
+        _ => serde_json::Map::new(),
+    };
+    defs.insert(name.to_string(), record);
+    serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$ref": at,
+        "$defs": defs,
+        "x-stream": { "encoding": encoding, "record": at },
+    })
 }
 
 /// Convenience: normalize `U` → NTy → JSON Schema
@@ -401,3 +1252,57 @@ pub fn normalize_and_lower(u: U) -> ir::Ty {
     lower_from_norm(&n)
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn names(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(p, n)| (p.to_string(), n.to_string())).collect()
+    }
+
+    #[test]
+    fn tuples_become_objects_keyed_by_name_or_position() {
+        let mut schema = json!({
+            "type": "object",
+            "properties": {
+                "point": {
+                    "type": "array",
+                    "prefixItems": [{ "type": "number" }, { "type": "number" }, { "type": "string" }],
+                    "minItems": 2,
+                    "maxItems": 3,
+                    "items": false,
+                },
+            },
+        });
+        tuples_as_objects(&mut schema, &names(&[("$.point[0]", "x"), ("$.point[1]", "x"), ("$.point[2]", "7")]));
+        assert_eq!(schema["properties"]["point"], json!({
+            "type": "object",
+            "x-encoding": "positional-array",
+            "properties": { "x": { "type": "number" }, "1": { "type": "number" }, "2": { "type": "string" } },
+            "required": ["x", "1"],
+            "additionalProperties": false,
+        }));
+    }
+
+    #[test]
+    fn nested_and_nullable_tuples_find_their_names() {
+        let pair = |inner: serde_json::Value| json!({ "type": "array", "prefixItems": [{ "type": "integer" }, inner], "minItems": 2 });
+        let inner = pair(json!({ "type": "boolean" }));
+        let mut schema = json!({ "oneOf": [pair(inner), { "type": "null" }] });
+        tuples_as_objects(&mut schema, &names(&[("$[0]", "id"), ("$[1][1]", "ok")]));
+        let outer = &schema["oneOf"][0];
+        assert_eq!(outer["required"], json!(["id", "1"]));
+        assert_eq!(outer["properties"]["1"]["required"], json!(["0", "ok"]));
+        assert_eq!(outer["properties"]["1"]["x-encoding"], json!("positional-array"));
+    }
+
+    #[test]
+    fn a_property_named_prefix_items_is_left_alone() {
+        let mut schema = json!({ "type": "object", "properties": { "prefixItems": { "type": "string" } } });
+        let before = schema.clone();
+        tuples_as_objects(&mut schema, &BTreeMap::new());
+        assert_eq!(schema, before);
+    }
+}
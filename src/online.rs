@@ -0,0 +1,352 @@
+//! Incremental inference for long-running services.
+//!
+//! [`OnlineInferencer`] takes documents as they arrive and keeps the joined
+//! evidence; the normalized shape is recomputed in batches (or on demand) and
+//! handed out as an `Arc<NTy>`, so readers hold a snapshot without copying it
+//! or blocking later updates. Each snapshot that differs from the last bumps
+//! the generation counter; listeners only hear about *structural* changes
+//! (a field, kind, arm or tuple position appearing, a field becoming optional
+//! or nullable), not bounds, enums or patterns widening.
+//!
+//! Everything takes `&self`, so one inferencer can be shared (`Arc`) by every
+//! thread receiving payloads. Evidence lives in [`ShardedEvidence`]: each
+//! document is observed outside any lock and joined into whichever shard is
+//! free, and shards are only merged when a snapshot is taken.
+//!
+//! ```ignore
+//! let live = Arc::new(OnlineInferencer::new().with_batch(1_000));
+//! live.on_change(|c| eprintln!("payload shape changed after {} docs", c.docs));
+//! for doc in stream {
+//!     live.observe(&doc); // from any thread
+//! }
+//! let schema = schema_from_norm(&live.snapshot());
+//! ```
+
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use serde_json::Value;
+
+use crate::inference::{observe_value, U};
+use crate::norm_ir::{normalize_with_overrides, NTy};
+use crate::overrides::Overrides;
+
+/// Refresh after this many documents unless [`OnlineInferencer::with_batch`] says otherwise.
+pub const DEFAULT_BATCH: u64 = 1_000;
+
+/// Documents a shard buffers before joining them into its evidence.
+const FOLD_EVERY: u64 = 64;
+
+/// A panic mid-join leaves the tree joined or not, never torn, so a poisoned
+/// lock is still safe to use.
+fn lock<T>(m: &Mutex<T>) -> MutexGuard<'_, T> {
+    m.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// ---------------------------------- shards ---------------------------------- //
+
+#[derive(Default)]
+struct Shard {
+    u: U,
+    /// Recent documents, joined into `u` every [`FOLD_EVERY`] (joining small trees is cheap).
+    pending: U,
+    pending_docs: u64,
+}
+
+impl Shard {
+    fn add(&mut self, u: &U, docs: u64) {
+        self.pending = U::join(&self.pending, u);
+        self.pending_docs += docs;
+        if self.pending_docs >= FOLD_EVERY {
+            self.u = U::join(&self.u, &std::mem::take(&mut self.pending));
+            self.pending_docs = 0;
+        }
+    }
+}
+
+/// Evidence that many threads can add to at once: a set of independently locked
+/// accumulators, merged on read. A writer takes the first shard nobody holds,
+/// so writers only wait on each other when every shard is busy.
+pub struct ShardedEvidence {
+    shards: Vec<Mutex<Shard>>,
+    /// Where the next writer starts looking, to spread writers across shards.
+    next: AtomicUsize,
+    docs: AtomicU64,
+}
+
+impl Default for ShardedEvidence {
+    fn default() -> Self {
+        Self::new(std::thread::available_parallelism().map_or(4, |n| n.get()))
+    }
+}
+
+impl ShardedEvidence {
+    pub fn new(shards: usize) -> Self {
+        Self {
+            shards: (0..shards.max(1)).map(|_| Mutex::new(Shard::default())).collect(),
+            next: AtomicUsize::new(0),
+            docs: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the documents added so far, this one included.
+    pub fn observe(&self, v: &Value) -> u64 {
+        self.add(&observe_value(v), 1)
+    }
+
+    /// Join evidence already observed elsewhere (e.g. a thread-local batch of `docs` documents).
+    pub fn add(&self, u: &U, docs: u64) -> u64 {
+        let n = self.shards.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed) % n;
+        let free = (0..n).find_map(|i| self.shards[(start + i) % n].try_lock().ok());
+        let mut shard = free.unwrap_or_else(|| lock(&self.shards[start]));
+        shard.add(u, docs);
+        drop(shard);
+        self.docs.fetch_add(docs, Ordering::Relaxed) + docs
+    }
+
+    /// Documents added so far.
+    pub fn docs(&self) -> u64 {
+        self.docs.load(Ordering::Relaxed)
+    }
+
+    /// Every shard joined; each is locked only while it is read.
+    pub fn merged(&self) -> U {
+        self.shards.iter().fold(U::empty(), |acc, s| {
+            let s = lock(s);
+            U::join(&U::join(&acc, &s.u), &s.pending)
+        })
+    }
+}
+
+// --------------------------------- snapshots -------------------------------- //
+
+/// A structural change, as passed to [`OnlineInferencer::on_change`] listeners.
+#[derive(Debug, Clone)]
+pub struct Change {
+    /// Generation of `current`.
+    pub generation: u64,
+    /// Documents observed when the snapshot was taken.
+    pub docs: u64,
+    pub previous: Arc<NTy>,
+    pub current: Arc<NTy>,
+}
+
+type Listener = Box<dyn FnMut(&Change) + Send>;
+
+struct Snapshot {
+    current: Arc<NTy>,
+    /// Debug rendering of `current`, to tell whether a refresh changed anything.
+    full_key: String,
+    shape_key: String,
+}
+
+pub struct OnlineInferencer {
+    evidence: ShardedEvidence,
+    overrides: Overrides,
+    batch: u64,
+    /// Held for the whole of a refresh, so refreshes don't interleave.
+    snapshot: Mutex<Snapshot>,
+    /// Only the `Arc`, for cheap reads while a refresh is running.
+    current: Mutex<Arc<NTy>>,
+    generation: AtomicU64,
+    /// Documents arrived since `current` was computed.
+    stale: AtomicBool,
+    listeners: Mutex<Vec<Listener>>,
+}
+
+impl Default for OnlineInferencer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OnlineInferencer {
+    pub fn new() -> Self {
+        let current = Arc::new(NTy::Null);
+        Self {
+            evidence: ShardedEvidence::default(),
+            overrides: Overrides::default(),
+            batch: DEFAULT_BATCH,
+            snapshot: Mutex::new(Snapshot {
+                full_key: format!("{current:?}"),
+                shape_key: shape_key(&current),
+                current: Arc::clone(&current),
+            }),
+            current: Mutex::new(current),
+            generation: AtomicU64::new(0),
+            stale: AtomicBool::new(false),
+            listeners: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Array-shape decisions to apply on every refresh (as `gen --overrides`).
+    pub fn with_overrides(mut self, overrides: Overrides) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// Refresh the snapshot every `docs` documents; `0` refreshes only on
+    /// [`OnlineInferencer::snapshot`] / [`OnlineInferencer::refresh`].
+    pub fn with_batch(mut self, docs: u64) -> Self {
+        self.batch = docs;
+        self
+    }
+
+    /// Spread evidence over `n` shards (default: one per available core).
+    pub fn with_shards(mut self, n: usize) -> Self {
+        self.evidence = ShardedEvidence::new(n);
+        self
+    }
+
+    /// Call `f` after every refresh that changed the structure (on the refreshing thread).
+    pub fn on_change(&self, f: impl FnMut(&Change) + Send + 'static) {
+        lock(&self.listeners).push(Box::new(f));
+    }
+
+    pub fn observe(&self, v: &Value) {
+        let docs = self.evidence.observe(v);
+        self.stale.store(true, Ordering::Release);
+        if self.batch > 0 && docs.is_multiple_of(self.batch) {
+            self.refresh();
+        }
+    }
+
+    pub fn docs(&self) -> u64 {
+        self.evidence.docs()
+    }
+
+    /// Bumped whenever the snapshot changes, structurally or not.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// The snapshot as of the last refresh; no work, may lag behind `observe`.
+    pub fn current(&self) -> Arc<NTy> {
+        Arc::clone(&lock(&self.current))
+    }
+
+    /// The snapshot including every document observed so far.
+    pub fn snapshot(&self) -> Arc<NTy> {
+        self.refresh();
+        self.current()
+    }
+
+    /// The joined evidence so far (e.g. to save it like `gen --evidence`).
+    pub fn evidence(&self) -> U {
+        self.evidence.merged()
+    }
+
+    /// Re-normalize if documents arrived since the last refresh.
+    /// Returns whether the structure changed (listeners have been called).
+    pub fn refresh(&self) -> bool {
+        let mut snap = lock(&self.snapshot);
+        if !self.stale.swap(false, Ordering::AcqRel) {
+            return false;
+        }
+        let docs = self.evidence.docs();
+        let next = normalize_with_overrides(self.evidence.merged(), &self.overrides);
+        let full_key = format!("{next:?}");
+        if full_key == snap.full_key {
+            return false;
+        }
+        let next = Arc::new(next);
+        let previous = std::mem::replace(&mut snap.current, Arc::clone(&next));
+        *lock(&self.current) = Arc::clone(&next);
+        snap.full_key = full_key;
+        let generation = self.generation.fetch_add(1, Ordering::AcqRel) + 1;
+
+        let shape = shape_key(&next);
+        if shape == snap.shape_key {
+            return false;
+        }
+        snap.shape_key = shape;
+        let change = Change { generation, docs, previous, current: next };
+        for f in lock(&self.listeners).iter_mut() {
+            f(&change);
+        }
+        true
+    }
+}
+
+/// `n` without bounds, enums or patterns: kinds, fields, requiredness, tuple
+/// positions and nullability only.
+pub fn shape_key(n: &NTy) -> String {
+    fn walk(n: &NTy, out: &mut String) {
+        match n {
+            NTy::Null => out.push_str("null"),
+            NTy::Bool => out.push_str("bool"),
+            NTy::Integer { .. } => out.push_str("int"),
+            NTy::Number { .. } => out.push_str("num"),
+            NTy::StringOrNumber { .. } => out.push_str("num|str"),
+            NTy::String { .. } => out.push_str("str"),
+            NTy::ArrayList { item, .. } => {
+                out.push('[');
+                walk(item, out);
+                out.push(']');
+            }
+            NTy::ArrayTuple { elems, min_items, .. } => {
+                let _ = write!(out, "({min_items}:");
+                for (i, e) in elems.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    walk(e, out);
+                }
+                out.push(')');
+            }
+            NTy::Object { fields, .. } => {
+                out.push('{');
+                for (i, f) in fields.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    let mark = if f.required { "!" } else if f.present { "?" } else { "" };
+                    let _ = write!(out, "{:?}{mark}:", f.name);
+                    walk(&f.ty, out);
+                }
+                out.push('}');
+            }
+            NTy::Tagged { tag, variants, .. } => {
+                let _ = write!(out, "<{tag:?}:");
+                for (i, (value, v)) in variants.iter().enumerate() {
+                    if i > 0 {
+                        out.push('|');
+                    }
+                    let _ = write!(out, "{value:?}=");
+                    walk(v, out);
+                }
+                out.push('>');
+            }
+            NTy::Map { value, .. } => {
+                out.push_str("{*:");
+                walk(value, out);
+                out.push('}');
+            }
+            NTy::JsonString(inner) => {
+                out.push('$');
+                walk(inner, out);
+            }
+            NTy::Nullable(inner) => {
+                out.push('?');
+                walk(inner, out);
+            }
+            NTy::OneOf(arms) => {
+                out.push('<');
+                for (i, a) in arms.iter().enumerate() {
+                    if i > 0 {
+                        out.push('|');
+                    }
+                    walk(a, out);
+                }
+                out.push('>');
+            }
+            NTy::Any => out.push('*'),
+            NTy::Raw => out.push('~'),
+        }
+    }
+    let mut out = String::new();
+    walk(n, &mut out);
+    out
+}
@@ -0,0 +1,75 @@
+//! An OpenAPI operation skeleton for paired captures (`gen --pair … --openapi FILE`).
+//!
+//! With `--pair`, every document is `{"request": …, "response": …}`, so the
+//! root schema's two properties are one operation's request body and response.
+//! They go under `components.schemas` as `Request` and `Response` (OpenAPI 3.1
+//! takes JSON Schema 2020-12 as is), shared types (`--share-types`) beside
+//! them. Captures don't tell the path or the status, so those are placeholders.
+
+use serde_json::{Map, Value, json};
+
+pub const OPENAPI: &str = "3.1.0";
+
+/// The roles `--pair` accepts, in the order the root holds them.
+pub const ROLES: [&str; 2] = ["request", "response"];
+
+/// The operation document for the root `schema` of a `--pair` run over `docs`
+/// pairs; `None` if the root isn't the pair object (an `--ir-transform` changed it).
+pub fn skeleton(schema: &Value, title: &str, docs: u64) -> Option<Value> {
+    let props = schema.get("properties")?.as_object()?;
+    let (request, response) = (props.get("request")?, props.get("response")?);
+
+    let mut schemas = schema.get("$defs").and_then(Value::as_object).cloned().unwrap_or_default();
+    schemas.insert("Request".into(), request.clone());
+    schemas.insert("Response".into(), response.clone());
+    let mut schemas = Value::Object(schemas);
+    relink(&mut schemas);
+
+    let mut operation = Map::new();
+    operation.insert("operationId".into(), json!(operation_id(title)));
+    operation.insert(
+        "summary".into(),
+        json!(format!("Inferred from {docs} request/response pair(s); set the path and the status")),
+    );
+    // requests that never had a body (always null) take none
+    let bodiless = request.get("type") == Some(&json!("null"));
+    if !bodiless {
+        let required = schema["required"].as_array().is_some_and(|r| r.contains(&json!("request")));
+        operation.insert("requestBody".into(), json!({ "required": required, "content": content("Request") }));
+    }
+    operation.insert("responses".into(), json!({ "200": { "description": "OK", "content": content("Response") } }));
+    let method = if bodiless { "get" } else { "post" };
+
+    Some(json!({
+        "openapi": OPENAPI,
+        "info": { "title": title, "version": "0.1.0" },
+        "paths": { "/": { method: operation } },
+        "components": { "schemas": schemas },
+    }))
+}
+
+fn content(name: &str) -> Value {
+    json!({ "application/json": { "schema": { "$ref": format!("#/components/schemas/{name}") } } })
+}
+
+/// `Root` → `root`, as an `operationId`.
+fn operation_id(title: &str) -> String {
+    let mut chars = title.chars();
+    chars.next().map(|c| c.to_lowercase().chain(chars).collect()).unwrap_or_default()
+}
+
+/// Point the `$ref`s into `$defs` at the same names under `components.schemas`.
+fn relink(v: &mut Value) {
+    match v {
+        Value::Object(m) => {
+            if let Some(Value::String(r)) = m.get_mut("$ref")
+                && let Some(name) = r.strip_prefix("#/$defs/")
+            {
+                *r = format!("#/components/schemas/{name}");
+            }
+            m.values_mut().for_each(relink);
+        }
+        Value::Array(xs) => xs.iter_mut().for_each(relink),
+        _ => {}
+    }
+}
@@ -0,0 +1,150 @@
+//! Union arms too rare to trust (`--min-arm-share`).
+//!
+//! One malformed value in a million widens its slot for good: a field of
+//! integers turns into `oneOf [string, integer]`. With a share threshold, the
+//! arms holding fewer than that share of a slot's non-null values are dropped
+//! from the evidence before normalization, and the report lists each one with
+//! where it was and how often it occurred. The slot's most frequent arm always
+//! stays, and slots an override marks as `union` are left alone.
+
+use std::collections::BTreeMap;
+
+use crate::inference::{ObjC, U};
+use crate::jpath::{JPath, Seg};
+use crate::overrides::{Decision, Overrides};
+
+/// An arm dropped for being rare.
+#[derive(Debug, Clone)]
+pub struct Outlier {
+    pub path: JPath,
+    /// `boolean`, `number`, `string`, `array` or `object`.
+    pub kind: &'static str,
+    pub count: u64,
+    /// Non-null values at the path.
+    pub total: u64,
+}
+
+/// Every arm of `u` holding fewer than `min_share` of its slot's non-null
+/// values, in path order. Slots with arms from older evidence (uncounted) are
+/// skipped.
+pub fn find(u: &U, min_share: f64, ov: &Overrides) -> Vec<Outlier> {
+    let mut out = Vec::new();
+    walk(u, &JPath::root(), min_share, ov, &mut out);
+    out
+}
+
+fn walk(u: &U, at: &JPath, min_share: f64, ov: &Overrides, out: &mut Vec<Outlier>) {
+    if ov.passthrough(at) {
+        return;
+    }
+    let arms = arms(u);
+    let total: u64 = arms.iter().map(|(_, n)| n).sum();
+    let mut dropped = Vec::new();
+    let counted = arms.iter().all(|(_, n)| *n > 0);
+    if arms.len() > 1 && counted && ov.get(at) != Some(Decision::Union) {
+        let top = arms.iter().map(|(_, n)| *n).max().unwrap_or(0);
+        let mut kept_top = false;
+        for &(kind, count) in &arms {
+            if count == top && !kept_top {
+                kept_top = true;
+                continue;
+            }
+            if (count as f64) < min_share * total as f64 {
+                out.push(Outlier { path: at.clone(), kind, count, total });
+                dropped.push(kind);
+            }
+        }
+    }
+
+    if let Some(arr) = u.arr.as_ref().filter(|_| !dropped.contains(&"array")) {
+        let tuple = ov.tuple_at(at).unwrap_or_else(|| crate::inference::decide_tuple(arr));
+        if tuple && !arr.cols.is_empty() {
+            for (i, c) in arr.cols.iter().enumerate() {
+                walk(c, &at.child(Seg::Index(i)), min_share, ov, out);
+            }
+        } else {
+            walk(&arr.item, &at.child(Seg::Items), min_share, ov, out);
+        }
+    }
+    if let Some(obj) = u.obj.as_ref().filter(|_| !dropped.contains(&"object")) {
+        for (name, f) in &obj.fields {
+            walk(&f.ty, &at.child(Seg::Field(name.clone())), min_share, ov, out);
+        }
+    }
+}
+
+/// The non-null arms of `u` with how many values each held.
+fn arms(u: &U) -> Vec<(&'static str, u64)> {
+    let c = u.counts;
+    [
+        ("boolean", u.has_bool, c.bool),
+        ("number", u.num.is_some(), c.num),
+        ("string", u.str_.is_some(), c.str_),
+        ("array", u.arr.is_some(), c.arr),
+        ("object", u.obj.is_some(), c.obj),
+    ]
+    .into_iter()
+    .filter(|(_, present, _)| *present)
+    .map(|(kind, _, n)| (kind, n))
+    .collect()
+}
+
+/// Path → dropped kinds, as recorded in artifact headers for regeneration.
+pub fn rules(found: &[Outlier]) -> BTreeMap<String, Vec<String>> {
+    let mut out: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for o in found {
+        out.entry(o.path.to_string()).or_default().push(o.kind.to_string());
+    }
+    out
+}
+
+/// Remove the arms named by `rules` from the evidence.
+pub fn drop(u: &mut U, rules: &BTreeMap<String, Vec<String>>) {
+    for (path, kinds) in rules {
+        let Ok(path) = path.parse::<JPath>() else { continue };
+        drop_at(u, path.segs(), kinds);
+    }
+}
+
+fn drop_at(u: &mut U, segs: &[Seg], kinds: &[String]) {
+    let Some((seg, rest)) = segs.split_first() else {
+        for kind in kinds {
+            match kind.as_str() {
+                "boolean" => (u.has_bool, u.counts.bool) = (false, 0),
+                "number" => (u.num, u.counts.num) = (None, 0),
+                "string" => (u.str_, u.counts.str_) = (None, 0),
+                "array" => (u.arr, u.counts.arr) = (None, 0),
+                "object" => (u.obj, u.counts.obj) = (None, 0),
+                _ => {}
+            }
+        }
+        return;
+    };
+    match seg {
+        Seg::Field(k) => {
+            if let Some(obj) = u.obj.as_mut() {
+                drop_in_object(obj, k, rest, kinds);
+            }
+        }
+        Seg::Index(i) => {
+            if let Some(c) = u.arr.as_mut().and_then(|a| a.cols.get_mut(*i)) {
+                drop_at(c, rest, kinds);
+            }
+        }
+        Seg::Items => {
+            if let Some(arr) = u.arr.as_mut() {
+                drop_at(&mut arr.item, rest, kinds);
+            }
+        }
+    }
+}
+
+/// The field `k` of `obj`, and of the per-tag copies of it kept for tagged unions.
+fn drop_in_object(obj: &mut ObjC, k: &str, rest: &[Seg], kinds: &[String]) {
+    if let Some(f) = obj.fields.get_mut(k) {
+        drop_at(&mut f.ty, rest, kinds);
+    }
+    for variant in obj.variants.values_mut().flatten().flat_map(|m| m.values_mut()) {
+        drop_in_object(variant, k, rest, kinds);
+    }
+}
@@ -0,0 +1,268 @@
+//! Per-path answers to ambiguous inference decisions (`--overrides FILE`),
+//! and the checks behind `--strict-inference`.
+//!
+//! The overrides file is one JSON object mapping paths to decisions:
+//!
+//! ```json
+//! { "$.results": "list", "$.bbox": "tuple", "$.value": "union", "$.rare": "accept" }
+//! ```
+//!
+//! - `list` / `tuple`: force the array shape at that path (applies with or without `--strict-inference`)
+//! - `union`: mixed kinds at that path are intended
+//! - `accept`: take inference's guess at that path as is
+//! - `passthrough`: don't interpret the values at that path (tracking blobs,
+//!   debug payloads): normalization skips the subtree, the schema takes any
+//!   value there, and the Rust models keep it unparsed (`Box<RawValue>`)
+//!
+//! Under `--strict-inference`, every ambiguous decision without an override is an error.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::inference::U;
+use crate::jpath::{JPath, Seg};
+
+/// Fewer observations than this and a decision counts as a guess.
+pub const STRICT_MIN_SAMPLES: u64 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Decision {
+    List,
+    Tuple,
+    Union,
+    Accept,
+    Passthrough,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Overrides(pub BTreeMap<String, Decision>);
+
+impl Overrides {
+    pub fn load(path: &std::path::Path) -> Result<Self, String> {
+        let src = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let ov: Overrides = serde_json::from_str(&src).map_err(|e| format!("{}: {e}", path.display()))?;
+        for p in ov.0.keys() {
+            p.parse::<JPath>().map_err(|e| format!("{}: {e}", path.display()))?;
+        }
+        Ok(ov)
+    }
+
+    /// Load `path`, or start empty if it doesn't exist yet (`--interactive` creates it).
+    pub fn load_or_default(path: &std::path::Path) -> Result<Self, String> {
+        if path.exists() { Self::load(path) } else { Ok(Self::default()) }
+    }
+
+    pub fn save(&self, path: &std::path::Path) -> std::io::Result<()> {
+        let mut src = serde_json::to_string_pretty(self).expect("overrides serialize");
+        src.push('\n');
+        std::fs::write(path, src)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn get(&self, path: &JPath) -> Option<Decision> {
+        if self.0.is_empty() {
+            return None;
+        }
+        self.0.get(&path.to_string()).copied()
+    }
+
+    /// Forced array shape at `path`: `Some(true)` tuple, `Some(false)` list.
+    pub fn tuple_at(&self, path: &JPath) -> Option<bool> {
+        match self.get(path)? {
+            Decision::Tuple => Some(true),
+            Decision::List => Some(false),
+            Decision::Union | Decision::Accept | Decision::Passthrough => None,
+        }
+    }
+
+    /// The values at `path` are passed through uninterpreted.
+    pub fn passthrough(&self, path: &JPath) -> bool {
+        self.get(path) == Some(Decision::Passthrough)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmbiguityKind {
+    /// Lengths vary, positions hold different kinds, and nothing proves a tuple.
+    ArrayShape,
+    /// Several kinds at one path merged into a union.
+    MixedKinds,
+    /// Too few observations to trust the decision.
+    LowSamples,
+}
+
+#[derive(Debug, Clone)]
+pub struct Ambiguity {
+    pub path: JPath,
+    pub kind: AmbiguityKind,
+    pub detail: String,
+}
+
+impl Ambiguity {
+    /// Short description for prompts.
+    pub fn question(&self) -> &'static str {
+        match self.kind {
+            AmbiguityKind::ArrayShape => "list or tuple?",
+            AmbiguityKind::MixedKinds => "mixed kinds",
+            AmbiguityKind::LowSamples => "too few samples",
+        }
+    }
+
+    /// Decisions that resolve this ambiguity in the overrides file.
+    pub fn choices(&self) -> &'static [&'static str] {
+        match self.kind {
+            AmbiguityKind::ArrayShape => &["list", "tuple"],
+            AmbiguityKind::MixedKinds => &["union"],
+            AmbiguityKind::LowSamples => &["accept"],
+        }
+    }
+}
+
+impl fmt::Display for Ambiguity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let what = self.question();
+        let fixes: Vec<String> = self.choices()
+            .iter()
+            .map(|c| format!("\"{}\": \"{c}\"", self.path.to_string().replace('"', "\\\"")))
+            .collect();
+        write!(f, "{}: {what}: {}\n  resolve with {} in --overrides", self.path, self.detail, fixes.join(" or "))
+    }
+}
+
+fn kinds(u: &U) -> BTreeSet<&'static str> {
+    let mut out = BTreeSet::new();
+    if u.has_bool { out.insert("boolean"); }
+    if u.num.is_some() { out.insert("number"); }
+    if u.str_.is_some() { out.insert("string"); }
+    if u.arr.is_some() { out.insert("array"); }
+    if u.obj.is_some() { out.insert("object"); }
+    out
+}
+
+/// Every unresolved ambiguity in the evidence, in path order.
+/// `docs` is the number of root documents.
+pub fn ambiguities(u: &U, docs: u64, ov: &Overrides) -> Vec<Ambiguity> {
+    let mut out = Vec::new();
+    if docs < STRICT_MIN_SAMPLES {
+        out.push(Ambiguity {
+            path: JPath::root(),
+            kind: AmbiguityKind::LowSamples,
+            detail: format!("only {docs} document(s)"),
+        });
+    }
+    walk(u, &JPath::root(), docs >= STRICT_MIN_SAMPLES, ov, &mut out);
+    out.retain(|a| ov.get(&a.path).is_none_or(|d| d != Decision::Accept && !accepts(a.kind, d)));
+    out
+}
+
+fn accepts(kind: AmbiguityKind, d: Decision) -> bool {
+    matches!(
+        (kind, d),
+        (AmbiguityKind::ArrayShape, Decision::List | Decision::Tuple)
+            | (AmbiguityKind::MixedKinds, Decision::Union)
+            | (_, Decision::Passthrough)
+    )
+}
+
+fn walk(u: &U, at: &JPath, check_samples: bool, ov: &Overrides, out: &mut Vec<Ambiguity>) {
+    if ov.passthrough(at) {
+        return;
+    }
+    let ks = kinds(u);
+    if ks.len() > 1 {
+        out.push(Ambiguity {
+            path: at.clone(),
+            kind: AmbiguityKind::MixedKinds,
+            detail: ks.into_iter().collect::<Vec<_>>().join(" | "),
+        });
+    }
+
+    if let Some(arr) = &u.arr {
+        let proven = crate::inference::decide_tuple(arr);
+        let tuple = ov.tuple_at(at).unwrap_or(proven);
+        if !proven && arr.cols.len() >= 2 {
+            // position-dependent kinds (null-only positions say nothing)
+            let sigs: BTreeSet<BTreeSet<&str>> = arr.cols
+                .iter()
+                .map(kinds)
+                .filter(|k| !k.is_empty())
+                .collect();
+            if sigs.len() > 1 {
+                let by_pos: Vec<String> = arr.cols
+                    .iter()
+                    .enumerate()
+                    .map(|(i, c)| format!("{i}: {}", kinds(c).into_iter().collect::<Vec<_>>().join("|")))
+                    .collect();
+                out.push(Ambiguity {
+                    path: at.clone(),
+                    kind: AmbiguityKind::ArrayShape,
+                    detail: format!(
+                        "{} array(s) of length {}..={}, no tuple proof, positions differ ({})",
+                        arr.samples, arr.len_min, arr.len_max, by_pos.join(", ")
+                    ),
+                });
+            }
+        }
+        if tuple && !arr.cols.is_empty() {
+            for (i, c) in arr.cols.iter().enumerate() {
+                walk(c, &at.child(Seg::Index(i)), check_samples, ov, out);
+            }
+        } else {
+            walk(&arr.item, &at.child(Seg::Items), check_samples, ov, out);
+        }
+    }
+
+    if let Some(obj) = &u.obj {
+        for (name, f) in &obj.fields {
+            let child = at.child(Seg::Field(name.clone()));
+            if check_samples && f.present_in < STRICT_MIN_SAMPLES {
+                out.push(Ambiguity {
+                    path: child.clone(),
+                    kind: AmbiguityKind::LowSamples,
+                    detail: format!("seen in {} of {} object(s)", f.present_in, obj.seen_objects),
+                });
+            }
+            walk(&f.ty, &child, check_samples, ov, out);
+        }
+    }
+}
+
+/// The evidence recorded at `path` (following tuple positions and list items as written).
+pub fn evidence_at<'a>(u: &'a U, path: &JPath) -> Option<&'a U> {
+    let mut cur = u;
+    for seg in path.segs() {
+        cur = match seg {
+            Seg::Field(k) => &cur.obj.as_ref()?.fields.get(k)?.ty,
+            Seg::Index(i) => cur.arr.as_ref()?.cols.get(*i)?,
+            Seg::Items => &cur.arr.as_ref()?.item,
+        };
+    }
+    Some(cur)
+}
+
+/// A few observed values of `u`, JSON-ish; literals are only kept up to the inference caps.
+pub fn examples(u: &U, max: usize) -> Vec<String> {
+    let mut out = Vec::new();
+    if let Some(s) = &u.str_ {
+        out.extend(s.lits.iter().take(max).map(|x| serde_json::Value::from(x.as_str()).to_string()));
+    }
+    if let Some(n) = &u.num {
+        out.extend(n.lits_f64.iter().take(max).map(|x| x.0.to_string()));
+        if n.lits_f64.is_empty() {
+            out.push(format!("{}..={}", n.min_f64.0, n.max_f64.0));
+        }
+    }
+    if u.has_bool { out.push("true|false".into()); }
+    if let Some(a) = &u.arr { out.push(format!("[…{}..={} items]", a.len_min, a.len_max)); }
+    if let Some(o) = &u.obj { out.push(format!("{{…{} field(s)}}", o.fields.len())); }
+    if u.nullable { out.push("null".into()); }
+    out.truncate(max);
+    out
+}
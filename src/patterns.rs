@@ -0,0 +1,61 @@
+//! String slots whose pattern synthesis was skipped, for the report.
+//!
+//! grex's time grows steeply with the literals it is given, so one hot column
+//! could stall normalization. It isn't run on too many distinct literals or
+//! characters, and a run past the time budget is abandoned (see
+//! [`crate::inference::str::try_synth_regex_with_grex`]); those slots become
+//! plain strings. The survey runs synthesis ahead of normalization, which then
+//! reuses each outcome, and lists the slots that fell back.
+
+use crate::inference::U;
+use crate::inference::str::{Grex, GrexSkip};
+use crate::jpath::{JPath, Seg};
+use crate::overrides::Overrides;
+
+/// One string slot without a pattern, and why.
+#[derive(Debug, Clone)]
+pub struct Skipped {
+    pub path: JPath,
+    /// Distinct literals the slot kept.
+    pub distinct: usize,
+    pub reason: GrexSkip,
+}
+
+/// The string slots of `u` whose literals grex wasn't run on (to the end), in path order.
+pub fn survey(u: &U, ov: &Overrides, grex: Grex) -> Vec<Skipped> {
+    let mut out = Vec::new();
+    if grex.enabled {
+        walk(u, &JPath::root(), ov, grex, &mut out);
+    }
+    out
+}
+
+fn walk(u: &U, at: &JPath, ov: &Overrides, grex: Grex, out: &mut Vec<Skipped>) {
+    if ov.passthrough(at) {
+        return;
+    }
+    if let Some(str_c) = u.str_.as_ref().filter(|s| s.wants_pattern(grex))
+        && let Err(reason) = crate::inference::str::try_synth_regex_with_grex(&str_c.lits, grex)
+    {
+        out.push(Skipped { path: at.clone(), distinct: str_c.lits.len(), reason });
+    }
+    if let Some(arr) = &u.arr {
+        let tuple = ov.tuple_at(at).unwrap_or_else(|| crate::inference::decide_tuple(arr));
+        if tuple && !arr.cols.is_empty() {
+            for (i, c) in arr.cols.iter().enumerate() {
+                walk(c, &at.child(Seg::Index(i)), ov, grex, out);
+            }
+        } else {
+            walk(&arr.item, &at.child(Seg::Items), ov, grex, out);
+        }
+    }
+    let Some(obj) = &u.obj else { return };
+    match crate::inference::decide_map(obj) {
+        Some(value) => walk(&value, &at.child(Seg::Items), ov, grex, out),
+        None => {
+            for (name, f) in &obj.fields {
+                walk(&f.ty, &at.child(Seg::Field(name.clone())), ov, grex, out);
+            }
+        }
+    }
+}
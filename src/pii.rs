@@ -0,0 +1,304 @@
+//! Personal-data classification (`--pii`): fields that look like they hold
+//! emails, phone numbers, people's names or GPS coordinates.
+//!
+//! Values decide emails and phone numbers (most strings at the slot must look
+//! like one); field names decide names and coordinates, checked against the
+//! values (strings for names, in-range numbers for coordinates). Each tag has a
+//! sensitivity level and lands in the report, as `x-pii` in the JSON Schema and
+//! in the Rust `SCHEMA` table.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::distribution::{paths_where, MAX_POSITIONS, MAX_SLOTS};
+use crate::ir::Ty;
+use crate::jpath::{JPath, Seg};
+
+/// Share of a slot's strings that must look like an email / phone number.
+const MIN_SHARE: f64 = 0.8;
+
+/// Field names (lowercased, without `_` / `-`) that hold a person's name.
+const PERSON_NAMES: &[&str] = &[
+    "firstname", "lastname", "fullname", "surname", "givenname", "familyname",
+    "middlename", "maidenname", "displayname", "realname", "contactname",
+];
+/// Field names that often, but not always, hold a person's name or handle.
+const HANDLES: &[&str] = &["name", "username", "nickname", "screenname", "handle", "login", "author"];
+const PHONES: &[&str] = &["phone", "phonenumber", "mobile", "cell", "tel", "telephone", "fax"];
+const LATITUDES: &[&str] = &["lat", "latitude"];
+const LONGITUDES: &[&str] = &["lng", "lon", "long", "longitude"];
+/// Arrays of `[lng, lat]` / `[lat, lng]`.
+const POINTS: &[&str] = &["coordinates", "coords", "latlng", "lnglat", "latlon", "lonlat", "geo", "location", "position", "point"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PiiKind {
+    Email,
+    Phone,
+    Name,
+    Location,
+}
+
+impl PiiKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PiiKind::Email => "email",
+            PiiKind::Phone => "phone",
+            PiiKind::Name => "name",
+            PiiKind::Location => "location",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sensitivity {
+    /// Identifies someone only with other data (handles, generic `name` fields).
+    Low,
+    /// Identifies someone, but is rarely secret (a person's name).
+    Medium,
+    /// Reaches or locates someone directly (email, phone, coordinates).
+    High,
+}
+
+impl Sensitivity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Sensitivity::Low => "low",
+            Sensitivity::Medium => "medium",
+            Sensitivity::High => "high",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tag {
+    pub kind: PiiKind,
+    pub sensitivity: Sensitivity,
+}
+
+/// One classified field.
+#[derive(Debug, Clone)]
+pub struct Pii {
+    pub path: JPath,
+    pub tag: Tag,
+    /// Values that matched, of those considered (strings, or numbers for coordinates).
+    pub matched: u64,
+    pub total: u64,
+    /// Tagged for its field name rather than its values' shape.
+    pub by_name: bool,
+}
+
+// --------------------------------- tracking --------------------------------- //
+
+#[derive(Debug, Clone, Default)]
+struct Slot {
+    strings: u64,
+    emails: u64,
+    /// Phone-shaped with a `+` or separators, and bare 7–15 digit strings.
+    phones: u64,
+    digits: u64,
+    numbers: u64,
+    fractional: u64,
+    /// Numbers within ±90 / ±180.
+    in_lat: u64,
+    in_lng: u64,
+}
+
+fn is_email(s: &str) -> bool {
+    let Some((local, domain)) = s.split_once('@') else { return false };
+    let labels: Vec<&str> = domain.split('.').collect();
+    !local.is_empty()
+        && !s.chars().any(char::is_whitespace)
+        && labels.len() >= 2
+        && labels.iter().all(|l| !l.is_empty() && l.chars().all(|c| c.is_alphanumeric() || c == '-'))
+        && labels.last().is_some_and(|tld| tld.len() >= 2 && tld.chars().all(char::is_alphabetic))
+}
+
+/// `Some(true)` for formatted numbers (`+1 555-0100`, `(555) 010 0100`),
+/// `Some(false)` for bare digit strings of phone length.
+fn phone_shape(s: &str) -> Option<bool> {
+    let t = s.trim();
+    let digits = t.chars().filter(char::is_ascii_digit).count();
+    if !(7..=15).contains(&digits) || !t.chars().all(|c| c.is_ascii_digit() || " +-().".contains(c)) {
+        return None;
+    }
+    if t[1..].contains('+') || (t.contains('.') && t.matches('.').count() < 2) {
+        return None;
+    }
+    // ISO dates (`2024-01-02`) have the digits and dashes of a phone number
+    let b = t.as_bytes();
+    if b.len() == 10 && b[4] == b'-' && b[7] == b'-' {
+        return None;
+    }
+    Some(t.starts_with('+') || t.contains([' ', '-', '(', '.']))
+}
+
+/// Values per string / number slot (`[*]` pools list items; the first tuple
+/// positions are also tracked on their own).
+#[derive(Debug, Clone, Default)]
+pub struct PiiStats {
+    slots: BTreeMap<JPath, Slot>,
+}
+
+impl PiiStats {
+    pub fn observe(&mut self, v: &Value) {
+        self.walk(v, &mut JPath::root());
+    }
+
+    fn walk(&mut self, v: &Value, at: &mut JPath) {
+        match v {
+            Value::String(_) | Value::Number(_) => {
+                if !self.slots.contains_key(at) {
+                    if self.slots.len() >= MAX_SLOTS {
+                        return;
+                    }
+                    self.slots.insert(at.clone(), Slot::default());
+                }
+                let slot = self.slots.get_mut(at).expect("inserted above");
+                if let Value::String(s) = v {
+                    slot.strings += 1;
+                    slot.emails += is_email(s) as u64;
+                    match phone_shape(s) {
+                        Some(true) => slot.phones += 1,
+                        Some(false) => slot.digits += 1,
+                        None => {}
+                    }
+                } else if let Some(x) = v.as_f64() {
+                    slot.numbers += 1;
+                    slot.fractional += (x.fract() != 0.0) as u64;
+                    slot.in_lat += (x.abs() <= 90.0) as u64;
+                    slot.in_lng += (x.abs() <= 180.0) as u64;
+                }
+            }
+            Value::Array(xs) => {
+                for (i, x) in xs.iter().enumerate() {
+                    at.0.push(Seg::Items);
+                    self.walk(x, at);
+                    at.0.pop();
+                    if i < MAX_POSITIONS {
+                        at.0.push(Seg::Index(i));
+                        self.walk(x, at);
+                        at.0.pop();
+                    }
+                }
+            }
+            Value::Object(m) => {
+                for (k, x) in m {
+                    at.0.push(Seg::Field(k.clone()));
+                    self.walk(x, at);
+                    at.0.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn join(mut a: Self, b: Self) -> Self {
+        for (p, sb) in b.slots {
+            if !a.slots.contains_key(&p) && a.slots.len() >= MAX_SLOTS {
+                continue;
+            }
+            let sa = a.slots.entry(p).or_default();
+            sa.strings += sb.strings;
+            sa.emails += sb.emails;
+            sa.phones += sb.phones;
+            sa.digits += sb.digits;
+            sa.numbers += sb.numbers;
+            sa.fractional += sb.fractional;
+            sa.in_lat += sb.in_lat;
+            sa.in_lng += sb.in_lng;
+        }
+        a
+    }
+}
+
+// -------------------------------- classifying ------------------------------- //
+
+/// `firstName` / `first_name` / `First-Name` → `firstname`.
+fn squash(name: &str) -> String {
+    name.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+fn classify(path: &JPath, slot: &Slot) -> Option<Pii> {
+    let segs = path.segs();
+    // the field a value belongs to, and whether it sits inside an array there
+    let (name, in_array) = match segs.iter().rposition(|s| matches!(s, Seg::Field(_))) {
+        Some(i) => match &segs[i] {
+            Seg::Field(k) => (squash(k), i + 1 < segs.len()),
+            _ => unreachable!(),
+        },
+        None => (String::new(), false),
+    };
+    let named = |words: &[&str]| words.contains(&name.as_str());
+    let pii = |kind, sensitivity, matched, total, by_name| {
+        Some(Pii { path: path.clone(), tag: Tag { kind, sensitivity }, matched, total, by_name })
+    };
+    let most = |n: u64, of: u64| of > 0 && n as f64 >= MIN_SHARE * of as f64;
+
+    if most(slot.emails, slot.strings) {
+        return pii(PiiKind::Email, Sensitivity::High, slot.emails, slot.strings, false);
+    }
+    if most(slot.phones, slot.strings) {
+        return pii(PiiKind::Phone, Sensitivity::High, slot.phones, slot.strings, false);
+    }
+    if named(PHONES) && most(slot.phones + slot.digits, slot.strings) {
+        return pii(PiiKind::Phone, Sensitivity::High, slot.phones + slot.digits, slot.strings, true);
+    }
+    if slot.strings > 0 && slot.numbers == 0 && !in_array {
+        if named(PERSON_NAMES) {
+            return pii(PiiKind::Name, Sensitivity::Medium, slot.strings, slot.strings, true);
+        }
+        if named(HANDLES) {
+            return pii(PiiKind::Name, Sensitivity::Low, slot.strings, slot.strings, true);
+        }
+    }
+    if slot.numbers > 0 && slot.strings == 0 {
+        let lat = named(LATITUDES) && !in_array && slot.in_lat == slot.numbers;
+        let lng = named(LONGITUDES) && !in_array && slot.in_lng == slot.numbers;
+        // bare integer pairs are more likely sizes or ranges than coordinates
+        let point = named(POINTS) && in_array && slot.in_lng == slot.numbers && slot.fractional > 0;
+        if lat || lng || point {
+            return pii(PiiKind::Location, Sensitivity::High, slot.numbers, slot.numbers, true);
+        }
+    }
+    None
+}
+
+/// Classified fields among the string and number paths of `ty`, in path order.
+pub fn detect(stats: PiiStats, ty: &Ty) -> Vec<Pii> {
+    let leaves = paths_where(ty, |t| matches!(t, Ty::String { .. } | Ty::Integer { .. } | Ty::Number { .. } | Ty::StringOrNumber { .. }));
+    leaves
+        .iter()
+        .filter_map(|p| stats.slots.get(p).and_then(|slot| classify(p, slot)))
+        .collect()
+}
+
+/// Path → tag, as recorded in artifact headers for the Rust `SCHEMA` table.
+pub fn tags(found: &[Pii]) -> BTreeMap<String, Tag> {
+    found.iter().map(|p| (p.path.to_string(), p.tag)).collect()
+}
+
+/// Add `x-pii: { kind, sensitivity }` to the schema of every classified field.
+pub fn annotate(schema: &mut Value, found: &[Pii]) {
+    for p in found {
+        tag(schema, &p.path, p.tag);
+    }
+}
+
+/// [`annotate`] from the tags recorded in an artifact header.
+pub fn annotate_tags(schema: &mut Value, tags: &BTreeMap<String, Tag>) {
+    for (path, t) in tags {
+        if let Ok(path) = path.parse::<JPath>() {
+            tag(schema, &path, *t);
+        }
+    }
+}
+
+fn tag(schema: &mut Value, path: &JPath, tag: Tag) {
+    if let Some(node) = crate::norm_ir::schema_at(schema, path.segs()) {
+        node["x-pii"] = json!({ "kind": tag.kind, "sensitivity": tag.sensitivity });
+    }
+}
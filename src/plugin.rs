@@ -0,0 +1,98 @@
+//! Plugins (`gen --plugin MODULE.wasm`): organization-specific lint rules and
+//! emitters that don't belong upstream.
+//!
+//! Plugins run once, after inference, on the finished IR: they can't see values
+//! during observation or change the inferred types. Custom string recognizers
+//! are `--formats` rules, not plugins.
+//!
+//! A plugin is a WASI command module. It reads one JSON request on stdin and
+//! writes one JSON response on stdout. No runtime is embedded: each plugin runs
+//! as `<runtime> run <module>` (`--plugin-runtime`, `wasmtime` by default), so
+//! the module gets no access beyond stdio unless the runtime grants it.
+//!
+//! Request (`abi` is [`ABI`]; new fields may be added, existing ones keep their meaning):
+//! - `ir`: the normalized IR, as written by `--ir-json`
+//! - `docs`: documents observed; `sources`: input paths in order
+//! - `samples`: the first documents in input order (up to [`MAX_SAMPLES`])
+//!
+//! Response (every field optional; unknown fields are ignored):
+//! - `warnings`: `[{ "path": "$.x", "message": "…" }]`, logged and added to the report
+//! - `outputs`: `[{ "name": "rel/path.ext", "contents": "…" }]`, written under `--plugin-out`
+
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::norm_ir::NTy;
+
+pub const ABI: &str = "json-osi-plugin/1";
+
+/// Sample documents passed to plugins.
+pub const MAX_SAMPLES: usize = 100;
+
+#[derive(Debug, Serialize)]
+pub struct Request<'a> {
+    pub abi: &'static str,
+    pub ir: &'a NTy,
+    pub docs: u64,
+    pub sources: Vec<&'a str>,
+    pub samples: &'a [Value],
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Response {
+    #[serde(default)]
+    pub warnings: Vec<Finding>,
+    #[serde(default)]
+    pub outputs: Vec<Output>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Finding {
+    #[serde(default)]
+    pub path: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Output {
+    pub name: String,
+    pub contents: String,
+}
+
+/// Run `module` on `request` and read its response.
+pub fn run(runtime: &str, module: &Path, request: &Request) -> Result<Response, String> {
+    crate::sandbox::request(crate::sandbox::Effect::Process(&format!("{runtime} run {}", module.display())))?;
+    let body = serde_json::to_vec(request).expect("plugin request serializes");
+    let mut child = Command::new(runtime)
+        .arg("run")
+        .arg(module)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| format!("failed to start `{runtime}` (see --plugin-runtime): {e}"))?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    // write from another thread: a plugin may answer before reading everything
+    let writer = std::thread::spawn(move || stdin.write_all(&body));
+    let out = child.wait_with_output().map_err(|e| e.to_string())?;
+    // a plugin that exits without reading its input is fine
+    let _ = writer.join();
+    if !out.status.success() {
+        return Err(format!("exited with {}", out.status));
+    }
+    serde_json::from_slice(&out.stdout).map_err(|e| format!("response is not valid JSON: {e}"))
+}
+
+/// Where output `name` goes under `dir`; names that would leave `dir` are refused.
+pub fn output_path(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let rel = Path::new(name);
+    let plain = rel.components().all(|c| matches!(c, Component::Normal(_)));
+    if name.is_empty() || !plain {
+        return Err(format!("output name {name:?} must be a relative path without `..`"));
+    }
+    Ok(dir.join(rel))
+}
@@ -0,0 +1,133 @@
+//! Outputs that read the same whichever platform wrote them.
+//!
+//! Generated files get committed, so one made on Windows must not differ from
+//! one made on Linux. Every file the tool generates is written through
+//! [`write`], with the line breaks `--newline` asks for (LF unless told
+//! otherwise), and artifacts read back go through [`lf`] first, so a checkout
+//! that converted them still verifies. Input paths recorded in manifests,
+//! evidence and reports go through [`path_str`]: relative to the working
+//! directory where they can be, with `/` between components.
+
+use std::borrow::Cow;
+use std::io;
+use std::path::{Component, Path};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Line breaks in generated files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Newline {
+    Lf,
+    Crlf,
+    /// CRLF on Windows, LF elsewhere.
+    Native,
+}
+
+impl Newline {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Newline::Lf => "\n",
+            Newline::Crlf => "\r\n",
+            Newline::Native if cfg!(windows) => "\r\n",
+            Newline::Native => "\n",
+        }
+    }
+}
+
+static CRLF: AtomicBool = AtomicBool::new(false);
+
+/// `--newline`, once at startup.
+pub fn configure(newline: Newline) {
+    CRLF.store(newline.as_str() == "\r\n", Ordering::Relaxed);
+}
+
+/// `contents` with every line break, LF or CRLF, read as LF.
+pub fn lf(contents: &str) -> Cow<'_, str> {
+    if contents.contains("\r\n") { Cow::Owned(contents.replace("\r\n", "\n")) } else { Cow::Borrowed(contents) }
+}
+
+/// `contents` with the configured line breaks.
+pub fn newlines(contents: &str) -> Cow<'_, str> {
+    if CRLF.load(Ordering::Relaxed) { Cow::Owned(lf(contents).replace('\n', "\r\n")) } else { lf(contents) }
+}
+
+/// Write the generated file `path`, with the configured line breaks.
+pub fn write(path: &Path, contents: &str) -> io::Result<()> {
+    std::fs::write(path, newlines(contents).as_bytes())
+}
+
+/// `path` as recorded in manifests and reports: relative to the working
+/// directory when below it, `/`-separated, without `.` components. Equal on
+/// every platform for the same files under the same directory.
+pub fn path_str(path: &Path) -> String {
+    let cwd = std::env::current_dir().ok();
+    let rel = cwd
+        .as_deref()
+        .filter(|_| path.is_absolute())
+        .and_then(|cwd| path.strip_prefix(cwd).ok())
+        .filter(|rel| !rel.as_os_str().is_empty())
+        .unwrap_or(path);
+    let mut out = String::new();
+    for c in rel.components() {
+        match c {
+            Component::Prefix(p) => out.push_str(&p.as_os_str().to_string_lossy()),
+            Component::RootDir => out.push('/'),
+            Component::CurDir => {}
+            Component::ParentDir | Component::Normal(_) => {
+                if !out.is_empty() && !out.ends_with('/') {
+                    out.push('/');
+                }
+                out.push_str(&c.as_os_str().to_string_lossy());
+            }
+        }
+    }
+    if out.is_empty() {
+        out.push('.');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_str_is_relative_to_the_working_directory() {
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(path_str(&cwd.join("data").join("a.json")), "data/a.json");
+        assert_eq!(path_str(&cwd), cwd.to_string_lossy().replace('\\', "/"));
+        assert_eq!(path_str(Path::new("./data/./a.json")), "data/a.json");
+        assert_eq!(path_str(Path::new("../data/a.json")), "../data/a.json");
+        assert_eq!(path_str(Path::new("data/../a.json")), "data/../a.json");
+        assert_eq!(path_str(Path::new(".")), ".");
+        assert_eq!(path_str(Path::new("-")), "-");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn path_str_keeps_absolute_paths_outside_the_working_directory() {
+        assert_eq!(path_str(Path::new("/nonexistent/data/a.json")), "/nonexistent/data/a.json");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn path_str_uses_forward_slashes_on_windows() {
+        assert_eq!(path_str(Path::new(r"data\sub\a.json")), "data/sub/a.json");
+        assert_eq!(path_str(Path::new(r"..\data\a.json")), "../data/a.json");
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(path_str(&cwd.join(r"data\a.json")), "data/a.json");
+        let drive = if cwd.to_string_lossy().starts_with("Z:") { "Y:" } else { "Z:" };
+        assert_eq!(path_str(Path::new(&format!(r"{drive}\data\a.json"))), format!("{drive}/data/a.json"));
+    }
+
+    #[test]
+    fn newlines_follow_the_configured_line_break() {
+        assert_eq!(lf("a\r\nb\nc\r\n"), "a\nb\nc\n");
+        assert!(matches!(lf("a\nb\n"), Cow::Borrowed(_)));
+        // the only test that configures CRLF; back to LF before returning
+        configure(Newline::Crlf);
+        assert_eq!(newlines("a\nb\r\nc"), "a\r\nb\r\nc");
+        configure(Newline::Lf);
+        assert_eq!(newlines("a\r\nb\nc"), "a\nb\nc");
+        assert_eq!(Newline::Native.as_str(), if cfg!(windows) { "\r\n" } else { "\n" });
+    }
+}
@@ -0,0 +1,90 @@
+//! Where each kind of value first showed up (`--provenance`).
+//!
+//! A field typed `oneOf [string, integer]` after reading a million documents
+//! says nothing about which document held the string. With provenance on,
+//! every document's evidence is flattened to `path → kinds` (as for the
+//! per-file report) and the first document, in input order, to bring each kind
+//! to each path is kept: its file, and its line for NDJSON or separated inputs.
+//! The slots holding several kinds are listed at the end of `--ir-debug`; all
+//! of them go into the `--provenance` file.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use serde::Serialize;
+
+use crate::inference::U;
+
+/// `format` of the `--provenance` file.
+pub const FORMAT: &str = "json-osi-provenance/1";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Origin {
+    pub file: String,
+    /// Line the document starts at; `None` for a file holding one document.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u64>,
+}
+
+impl std::fmt::Display for Origin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{line}", self.file),
+            None => f.write_str(&self.file),
+        }
+    }
+}
+
+/// Path → kind → the first document that had it there.
+#[derive(Debug, Clone, Default)]
+pub struct Provenance(BTreeMap<String, BTreeMap<&'static str, Origin>>);
+
+impl Provenance {
+    /// Every kind in the evidence `u` of one document, from `origin`.
+    pub fn of(u: &U, origin: &Origin) -> Self {
+        let sig = crate::report::signature_of(u);
+        Provenance(
+            sig.into_iter()
+                .map(|(path, kinds)| (path, kinds.into_iter().map(|k| (k, origin.clone())).collect()))
+                .collect(),
+        )
+    }
+
+    /// `a` comes before `b` in input order, so its origins win.
+    pub fn join(mut a: Self, b: Self) -> Self {
+        for (path, kinds) in b.0 {
+            let seen = a.0.entry(path).or_default();
+            for (kind, origin) in kinds {
+                seen.entry(kind).or_insert(origin);
+            }
+        }
+        a
+    }
+
+    /// The paths holding more than one kind, with where each came from.
+    pub fn mixed(&self) -> impl Iterator<Item = (&String, &BTreeMap<&'static str, Origin>)> {
+        self.0.iter().filter(|(_, kinds)| kinds.len() > 1)
+    }
+
+    /// Comment lines closing `--ir-debug`.
+    pub fn render(&self) -> String {
+        let mut s = String::from("\n// provenance: first document with each kind, for slots holding several\n");
+        for (path, kinds) in self.mixed() {
+            let _ = writeln!(s, "// {path}");
+            for (kind, origin) in kinds {
+                let _ = writeln!(s, "//   {kind:<8} {origin}");
+            }
+        }
+        s
+    }
+
+    /// The `--provenance` file.
+    pub fn to_json(&self, docs: u64) -> serde_json::Value {
+        let paths: Vec<_> = self
+            .0
+            .iter()
+            .map(|(path, kinds)| serde_json::json!({ "path": path, "mixed": kinds.len() > 1, "kinds": kinds }))
+            .collect();
+        serde_json::json!({ "format": FORMAT, "docs": docs, "paths": paths })
+    }
+}
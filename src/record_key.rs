@@ -0,0 +1,86 @@
+//! Record-key auto-detection (`--key auto`).
+//!
+//! Tracks, exactly, the distinct values of every top-level string slot (object
+//! field or tuple position of the document root). A slot qualifies as the key
+//! when it is a string in every document and no value repeats. A slot's set is
+//! dropped at its first repeat, so only slots still in the running hold values.
+
+use std::collections::{BTreeMap, HashSet};
+
+use serde_json::Value;
+
+use crate::jpath::{JPath, Seg};
+
+#[derive(Debug, Default, Clone)]
+pub struct KeySniffer {
+    docs: u64,
+    slots: BTreeMap<JPath, Slot>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Slot {
+    strings: u64,
+    /// A value repeated; `distinct` has been dropped and stays empty.
+    repeated: bool,
+    distinct: HashSet<String>,
+}
+
+impl Slot {
+    fn disqualify(&mut self) {
+        self.repeated = true;
+        self.distinct = HashSet::new();
+    }
+}
+
+impl KeySniffer {
+    pub fn observe(&mut self, v: &Value) {
+        self.docs += 1;
+        let mut see = |seg: Seg, x: &Value| {
+            if let Value::String(s) = x {
+                let slot = self.slots.entry(JPath(vec![seg])).or_default();
+                slot.strings += 1;
+                if !slot.repeated && !slot.distinct.insert(s.clone()) {
+                    slot.disqualify();
+                }
+            }
+        };
+        match v {
+            Value::Object(m) => m.iter().for_each(|(k, x)| see(Seg::Field(k.clone()), x)),
+            Value::Array(xs) => xs.iter().enumerate().for_each(|(i, x)| see(Seg::Index(i), x)),
+            _ => {}
+        }
+    }
+
+    pub fn join(mut a: Self, b: Self) -> Self {
+        a.docs += b.docs;
+        for (p, sb) in b.slots {
+            let sa = a.slots.entry(p).or_default();
+            sa.strings += sb.strings;
+            if sa.repeated || sb.repeated {
+                sa.disqualify();
+                continue;
+            }
+            let want = sa.distinct.len() + sb.distinct.len();
+            sa.distinct.extend(sb.distinct);
+            if sa.distinct.len() < want {
+                sa.disqualify();
+            }
+        }
+        a
+    }
+
+    /// Slots that are a unique string in every document, `id`-like names first.
+    pub fn candidates(&self) -> Vec<JPath> {
+        let mut out: Vec<JPath> = self.slots
+            .iter()
+            .filter(|(_, s)| self.docs >= 2 && s.strings == self.docs && !s.repeated && s.distinct.len() as u64 == self.docs)
+            .map(|(p, _)| p.clone())
+            .collect();
+        let id_like = |p: &JPath| match p.segs().last() {
+            Some(Seg::Field(n)) => n.to_ascii_lowercase().ends_with("id"),
+            _ => false,
+        };
+        out.sort_by_key(|p| !id_like(p));
+        out
+    }
+}
@@ -0,0 +1,801 @@
+//! Human-readable run report (Markdown).
+//!
+//! The pipeline fills sections in as evidence is gathered; rendering happens
+//! once at the end so every emitter sees the same numbers.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+
+use serde_json::json;
+
+use crate::columns::ColumnSummary;
+use crate::discriminator::Discriminator;
+use crate::distribution::NumericSummary;
+use crate::epoch::{EpochMatch, EpochUnit};
+use crate::formats::FormatMatch;
+use crate::human::NumberFormat;
+use crate::inference::U;
+use crate::manifest::{IngestSettings, InputDigest};
+use crate::literals::Change;
+use crate::metrics::CodeMetrics;
+use crate::outliers::Outlier;
+use crate::required::Presence;
+use crate::overrides::Overrides;
+use crate::pii::Pii;
+use crate::plugin::Finding;
+use crate::sentinel::{Sentinel, SentinelPolicy};
+use crate::size::SizeSummary;
+use crate::timeline::TimelineSummary;
+use crate::validate::Revalidation;
+use crate::jpath::{JPath, Seg};
+
+/// Everything we know about a run that isn't part of the schema itself.
+#[derive(Debug, Default, Clone)]
+pub struct Report {
+    /// Per-source-file contribution summaries, in input order.
+    pub sources: Vec<SourceStats>,
+    /// Top-level string slots unique across all documents (`--key auto`), best first.
+    pub key_candidates: Vec<String>,
+    /// Root-record column uniqueness and dependencies (`--column-stats`).
+    pub columns: Option<ColumnSummary>,
+    /// Quantiles, histograms and sentinel candidates of numeric slots (`--histograms`).
+    pub numbers: Option<Vec<NumericSummary>>,
+    /// Marker values standing in for "missing" (`--sentinels`), and how they were modeled.
+    pub sentinels: Option<Vec<Sentinel>>,
+    pub sentinel_policy: SentinelPolicy,
+    /// Slots that held `""`, and whether it looks like null there (see
+    /// [`crate::sentinel::empty_strings`]).
+    pub empty_strings: Vec<(Sentinel, bool)>,
+    /// Union arms dropped from the evidence as too rare (`--min-arm-share`).
+    pub outliers: Vec<Outlier>,
+    /// Fields required through `--required-threshold`, or that barely missed it.
+    pub required: Vec<Presence>,
+    /// Paths that normalized to a union, and where null showed up in place of a value.
+    pub unions: Vec<crate::unions::Union>,
+    pub unexpected_nulls: Vec<crate::unions::Nulls>,
+    /// String slots left without a pattern because grex was not run, or not to the end.
+    pub patterns_skipped: Vec<crate::patterns::Skipped>,
+    /// Slots nested past `--max-depth`, typed as any JSON.
+    pub too_deep: Vec<JPath>,
+    /// The object slots with the most distinct keys, and how each was typed.
+    pub widths: Vec<crate::width::Width>,
+    /// Fields merged from keys spelled differently (`--normalize-unicode`).
+    pub merged_keys: Vec<crate::key_forms::Merged>,
+    /// Integer slots whose values read as Unix timestamps, and whether the
+    /// models type them so (`--epoch-timestamps`).
+    pub epochs: Vec<EpochMatch>,
+    pub epochs_typed: bool,
+    /// Enum values new in this run or kept from earlier ones (`--literals`).
+    pub literals: Option<Vec<Change>>,
+    /// Fields that look like personal data (`--pii`).
+    pub pii: Option<Vec<Pii>>,
+    /// String slots matching a declared format (`--formats`).
+    pub formats: Option<Vec<FormatMatch>>,
+    /// Root columns that are null in exactly the same documents (`--column-stats`, `--group-nullables`).
+    pub nullable_groups: Option<Vec<Vec<String>>>,
+    /// When paths appeared and stopped appearing, by document timestamp (`--timestamp`).
+    pub timeline: Option<TimelineSummary>,
+    /// Typical and worst-case record sizes (`--size-estimate`).
+    pub sizes: Option<SizeSummary>,
+    /// Root field whose value decides which other fields appear (`--schema-conditionals`).
+    pub discriminator: Option<Discriminator>,
+    pub ingest: IngestSettings,
+    /// Hash of the input manifest (see [`crate::manifest::manifest_hash`]).
+    pub manifest: String,
+    /// Id of the joined evidence (see [`crate::manifest::evidence_id`]).
+    pub evidence_id: String,
+    /// First documents in input order (`--rust-crate` test fixtures).
+    pub samples: Vec<serde_json::Value>,
+    /// Generated Rust modules, once emitted.
+    pub code: Vec<CodeMetrics>,
+    /// Samples checked against the generated types (`--revalidate`).
+    pub revalidation: Option<Revalidation>,
+    /// `--overrides` applied during normalization.
+    pub overrides: Overrides,
+    /// How counts and sizes are printed (`--number-format`).
+    pub number_format: NumberFormat,
+    /// Warnings returned by `--plugin` modules, with the module's file name.
+    pub plugin_warnings: Vec<(String, Finding)>,
+    /// Documents skipped because they could not be read.
+    pub parse_failed: ParseFailures,
+    /// Documents `--jq-expr` yielded nothing for, skipped.
+    pub jq_empty: u64,
+    /// First document to bring each kind to each path (`--provenance`).
+    pub provenance: Option<crate::provenance::Provenance>,
+}
+
+/// Failures listed by location in the report; the rest are only counted.
+pub const MAX_PARSE_FAILURES_LISTED: usize = 20;
+
+/// A problem with one document, found by a worker while reading in parallel.
+///
+/// Workers only collect these; they are logged once reading is done, sorted by
+/// `(file, line, path)`, so two runs over the same inputs log the same lines.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Diagnostic {
+    pub file: String,
+    /// 1-based line of the document, for line- or record-separated inputs.
+    pub line: Option<u64>,
+    /// JSON path inside the document, when the problem is local to one value.
+    pub path: Option<String>,
+    pub message: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    /// `file[:line][: path]: message`, on one line.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.file)?;
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, ": {path}")?;
+        }
+        write!(f, ": {}", self.message.replace('\n', " "))
+    }
+}
+
+/// Documents that contributed no evidence because they could not be read:
+/// malformed or truncated JSON, a failing `--jq-expr`, or jq output that isn't JSON.
+/// The run goes on without them; these say how much of the corpus that was.
+#[derive(Debug, Default, Clone)]
+pub struct ParseFailures {
+    /// Failed documents per source file.
+    pub by_file: BTreeMap<String, u64>,
+    /// The first [`MAX_PARSE_FAILURES_LISTED`] in [`Diagnostic`] order, whatever
+    /// order the workers finished in.
+    pub first: Vec<Diagnostic>,
+}
+
+impl ParseFailures {
+    pub fn one(failure: Diagnostic) -> Self {
+        ParseFailures { by_file: [(failure.file.clone(), 1)].into(), first: vec![failure] }
+    }
+
+    pub fn join(mut a: Self, b: Self) -> Self {
+        for (file, n) in b.by_file {
+            *a.by_file.entry(file).or_default() += n;
+        }
+        a.first.extend(b.first);
+        a.first.sort();
+        a.first.truncate(MAX_PARSE_FAILURES_LISTED);
+        a
+    }
+
+    pub fn count(&self) -> u64 {
+        self.by_file.values().sum()
+    }
+}
+
+/// What a single source file contributed to the merged evidence.
+///
+/// "New" and "widened" are relative to the files that came *before* it in
+/// input order, so the first file to introduce a union arm is the one blamed.
+#[derive(Debug, Default, Clone)]
+pub struct SourceStats {
+    pub path: String,
+    /// Content hash of the file as read.
+    pub sha256: String,
+    /// Documents observed (after the jq filter, if any).
+    pub docs: u64,
+    /// Size of the file as read.
+    pub bytes: u64,
+    /// Distinct paths seen in this file.
+    pub paths: usize,
+    /// Paths no earlier file had.
+    pub new_fields: Vec<String>,
+    /// `(path, kind)` for paths that existed before but gained a new kind here.
+    pub widened: Vec<(String, &'static str)>,
+}
+
+/// Flattened `path → kinds` view of an evidence tree.
+pub type Signature = BTreeMap<String, BTreeSet<&'static str>>;
+
+/// Kinds a [`Signature`] can hold, for reading one back from a checkpoint.
+pub const KINDS: [&str; 7] = ["null", "bool", "integer", "number", "string", "array", "object"];
+
+/// Walk `U` and record which kinds were observed at which path.
+/// Arrays contribute their pooled item under `[*]`; tuple columns are not
+/// split out since the list hypothesis already covers every element.
+pub fn signature_of(u: &U) -> Signature {
+    fn walk(u: &U, path: &mut JPath, out: &mut Signature) {
+        let mut kinds = BTreeSet::new();
+        if u.nullable { kinds.insert("null"); }
+        if u.has_bool { kinds.insert("bool"); }
+        if let Some(num) = &u.num {
+            kinds.insert(if num.saw_float { "number" } else { "integer" });
+        }
+        if u.str_.is_some() { kinds.insert("string"); }
+        if u.arr.is_some() { kinds.insert("array"); }
+        if u.obj.is_some() { kinds.insert("object"); }
+        out.entry(path.to_string()).or_default().extend(kinds);
+
+        if let Some(arr) = &u.arr {
+            path.0.push(Seg::Items);
+            walk(&arr.item, path, out);
+            path.0.pop();
+        }
+        if let Some(obj) = &u.obj {
+            for (name, field) in &obj.fields {
+                path.0.push(Seg::Field(name.clone()));
+                walk(&field.ty, path, out);
+                path.0.pop();
+            }
+        }
+    }
+
+    let mut out = Signature::new();
+    if !u.is_bottom() {
+        walk(u, &mut JPath::root(), &mut out);
+    }
+    out
+}
+
+/// Attribute new paths and widened kinds to files in input order.
+pub fn stratify<I>(files: I) -> Vec<SourceStats>
+where
+    I: IntoIterator<Item = (String, u64, u64, Signature, String)>,
+{
+    let mut seen = Signature::new();
+    let mut out = Vec::new();
+    for (path, docs, bytes, sig, sha256) in files {
+        let mut stats = SourceStats { path, sha256, docs, bytes, paths: sig.len(), ..SourceStats::default() };
+        for (p, kinds) in sig {
+            match seen.get_mut(&p) {
+                None => {
+                    stats.new_fields.push(p.clone());
+                    seen.insert(p, kinds);
+                }
+                Some(prev) => {
+                    for k in kinds {
+                        if prev.insert(k) {
+                            stats.widened.push((p.clone(), k));
+                        }
+                    }
+                }
+            }
+        }
+        out.push(stats);
+    }
+    out
+}
+
+impl Report {
+    /// The input manifest, in input order.
+    pub fn inputs(&self) -> Vec<InputDigest> {
+        self.sources
+            .iter()
+            .map(|s| InputDigest { path: s.path.clone(), sha256: s.sha256.clone(), docs: s.docs })
+            .collect()
+    }
+
+    /// Documents observed across all sources.
+    pub fn docs(&self) -> u64 {
+        self.sources.iter().map(|s| s.docs).sum()
+    }
+
+    /// Bytes read across all sources.
+    pub fn bytes(&self) -> u64 {
+        self.sources.iter().map(|s| s.bytes).sum()
+    }
+
+    /// `--summary`: the run at a glance, for scripts; `written` is `(output, path)` of
+    /// every file the run wrote.
+    pub fn summary(&self, written: &[(String, String)]) -> serde_json::Value {
+        json!({
+            "tool": crate::manifest::tool(),
+            "files": self.sources.len(),
+            "docs": self.docs(),
+            "bytes": self.bytes(),
+            "manifest": self.manifest,
+            "evidence": self.evidence_id,
+            "key_candidates": self.key_candidates,
+            "code": self.code.iter().map(|c| json!({ "module": c.module, "lines": c.lines, "types": c.items.len() })).collect::<Vec<_>>(),
+            "revalidation": self.revalidation.as_ref().map(|r| json!({
+                "checked": r.checked,
+                "failed": r.failed,
+                "schema_failed": r.schema.as_ref().map(|c| c.failed),
+            })),
+            "plugin_warnings": self.plugin_warnings.len(),
+            "parse_failed": self.parse_failed.count(),
+            "jq_empty": self.jq_empty,
+            "outputs": written.iter().map(|(output, path)| json!({ "output": output, "path": path })).collect::<Vec<_>>(),
+        })
+    }
+
+    pub fn render_markdown(&self) -> String {
+        let n = |x: u64| self.number_format.count(x);
+        let mut s = String::new();
+        s.push_str("# json-osi report\n\n");
+
+        let _ = writeln!(s, "## Sources ({})\n", self.sources.len());
+        let _ = writeln!(s, "{} docs, {} read.\n", n(self.docs()), self.number_format.bytes(self.bytes()));
+        if self.jq_empty > 0 {
+            let _ = writeln!(s, "{} input document(s) had no `--jq-expr` output and contributed nothing.\n", n(self.jq_empty));
+        }
+        s.push_str("| file | docs | size | paths | new fields | widened |\n");
+        s.push_str("| --- | ---: | ---: | ---: | ---: | ---: |\n");
+        for src in &self.sources {
+            let _ = writeln!(
+                s,
+                "| `{}` | {} | {} | {} | {} | {} |",
+                src.path, n(src.docs), self.number_format.bytes(src.bytes), n(src.paths as u64), src.new_fields.len(), src.widened.len()
+            );
+        }
+        s.push('\n');
+
+        // Only files after the first can widen anything; list those explicitly
+        // since they are the usual suspects for an unexpected union arm.
+        for src in self.sources.iter().filter(|s| !s.widened.is_empty()) {
+            let _ = writeln!(s, "### `{}` widened\n", src.path);
+            for (p, k) in &src.widened {
+                let _ = writeln!(s, "- `{p}` +{k}");
+            }
+            s.push('\n');
+        }
+
+        let failed = self.parse_failed.count();
+        if failed > 0 {
+            let total = self.docs() + failed;
+            let _ = writeln!(s, "## Unreadable documents ({} of {}, {:.2}%)\n", n(failed), n(total), 100.0 * failed as f64 / total as f64);
+            s.push_str("Skipped: they contribute nothing to the schema.\n\n");
+            s.push_str("| file | failed |\n");
+            s.push_str("| --- | ---: |\n");
+            for (file, k) in &self.parse_failed.by_file {
+                let _ = writeln!(s, "| `{file}` | {} |", n(*k));
+            }
+            s.push('\n');
+            s.push_str("| at | error |\n");
+            s.push_str("| --- | --- |\n");
+            for d in &self.parse_failed.first {
+                let at = d.line.map_or_else(|| d.file.clone(), |line| format!("{}:{line}", d.file));
+                let _ = writeln!(s, "| `{at}` | {} |", d.message.replace('|', "\\|").replace('\n', " "));
+            }
+            if failed > self.parse_failed.first.len() as u64 {
+                let _ = writeln!(s, "| … | {} more |", n(failed - self.parse_failed.first.len() as u64));
+            }
+            s.push('\n');
+        }
+
+        if !self.outliers.is_empty() {
+            s.push_str("## Dropped union arms\n\n");
+            s.push_str("Too rare to shape the schema (`--min-arm-share`): the models reject these values.\n\n");
+            s.push_str("| path | kind | count | share |\n");
+            s.push_str("| --- | --- | ---: | ---: |\n");
+            for x in &self.outliers {
+                let share = x.count as f64 * 100.0 / x.total.max(1) as f64;
+                let _ = writeln!(s, "| `{}` | {} | {} of {} | {:.3}% |", x.path, x.kind, n(x.count), n(x.total), share);
+            }
+            s.push('\n');
+        }
+
+        if !self.required.is_empty() {
+            s.push_str("## Required fields\n\n");
+            s.push_str("Fields short of non-null in every object, close to `--required-threshold`.\n\n");
+            s.push_str("| path | non-null | share | required |\n");
+            s.push_str("| --- | ---: | ---: | --- |\n");
+            for x in &self.required {
+                let required = if x.required { "yes" } else { "no, barely" };
+                let _ = writeln!(s, "| `{}` | {} of {} | {:.3}% | {required} |", x.path, n(x.non_null), n(x.objects), 100.0 * x.share());
+            }
+            s.push('\n');
+        }
+
+        if !self.unions.is_empty() {
+            s.push_str("## Unions\n\n");
+            s.push_str("Paths holding more than one kind of value: settle them with `--overrides`, or filter them with `--jq-expr`.\n\n");
+            s.push_str("| path | kinds |\n");
+            s.push_str("| --- | --- |\n");
+            for u in &self.unions {
+                let mut arms: Vec<String> = u
+                    .arms
+                    .iter()
+                    .map(|(kind, count)| count.map_or_else(|| kind.to_string(), |c| format!("{kind} ({})", n(c))))
+                    .collect();
+                if u.nullable {
+                    arms.push("null".to_string());
+                }
+                let _ = writeln!(s, "| `{}` | {} |", u.path, arms.join(", "));
+            }
+            s.push('\n');
+        }
+
+        if !self.unexpected_nulls.is_empty() {
+            s.push_str("## Unexpected nulls\n\n");
+            s.push_str("Null in place of a list item, a tuple position or a field every object has: the models accept it there.\n\n");
+            s.push_str("| path | kind | nulls |\n");
+            s.push_str("| --- | --- | ---: |\n");
+            for x in &self.unexpected_nulls {
+                let nulls = x.nulls.map_or_else(|| "?".to_string(), |(k, of)| format!("{} of {}", n(k), n(of)));
+                let _ = writeln!(s, "| `{}` | {} | {nulls} |", x.path, x.kind);
+            }
+            s.push('\n');
+        }
+
+        if !self.patterns_skipped.is_empty() {
+            s.push_str("## Pattern synthesis skipped\n\n");
+            s.push_str("Too much for grex within its limits (`--grex-time-budget`): these slots are plain strings.\n\n");
+            s.push_str("| path | distinct | reason |\n");
+            s.push_str("| --- | ---: | --- |\n");
+            for x in &self.patterns_skipped {
+                let _ = writeln!(s, "| `{}` | {} | {} |", x.path, n(x.distinct as u64), x.reason.describe());
+            }
+            s.push('\n');
+        }
+
+        if !self.too_deep.is_empty() {
+            s.push_str("## Nested past --max-depth\n\n");
+            s.push_str("Not looked into: these slots take any JSON (`serde_json::Value`).\n\n");
+            for p in &self.too_deep {
+                let _ = writeln!(s, "- `{p}`");
+            }
+            s.push('\n');
+        }
+
+        if !self.widths.is_empty() {
+            s.push_str("## Key cardinality\n\n");
+            let _ = writeln!(
+                s,
+                "Object slots with at least {} distinct keys, widest first; past `max_object_fields` ({}) a slot is a map.\n",
+                crate::width::MIN_LISTED_KEYS,
+                crate::inference::caps::max_object_fields(),
+            );
+            s.push_str("| path | keys | objects | keys per object | typed as |\n");
+            s.push_str("| --- | ---: | ---: | ---: | --- |\n");
+            for w in &self.widths {
+                let _ = writeln!(s, "| `{}` | {} | {} | {:.1} | {} |", w.path, n(w.keys as u64), n(w.objects), w.mean, w.lowered.as_str());
+            }
+            s.push('\n');
+        }
+
+        if !self.merged_keys.is_empty() {
+            s.push_str("## Merged keys\n\n");
+            s.push_str("Keys equal in Unicode NFC once invisible characters are dropped (`--normalize-unicode`); the models accept every spelling.\n\n");
+            s.push_str("| field | also written |\n");
+            s.push_str("| --- | --- |\n");
+            for m in &self.merged_keys {
+                let forms: Vec<String> = m.forms.iter().map(|f| format!("`{}`", f.escape_default())).collect();
+                let _ = writeln!(s, "| `{}` | {} |", m.path, forms.join(", "));
+            }
+            s.push('\n');
+        }
+
+        if !self.key_candidates.is_empty() {
+            s.push_str("## Record key candidates\n\n");
+            for p in &self.key_candidates {
+                let _ = writeln!(s, "- `{p}`");
+            }
+            s.push('\n');
+        }
+
+        if let Some(cols) = &self.columns {
+            let _ = writeln!(s, "## Columns ({} docs, approximate)\n", n(cols.docs));
+            s.push_str("| column | non-null | distinct | unique |\n");
+            s.push_str("| --- | ---: | ---: | :---: |\n");
+            for c in &cols.columns {
+                let _ = writeln!(
+                    s,
+                    "| `{}` | {} | ~{} | {} |",
+                    c.path, n(c.non_null), n(c.distinct), if c.unique { "yes" } else { "" }
+                );
+            }
+            s.push('\n');
+
+            s.push_str("### Functional dependencies\n\n");
+            if cols.fd_skipped {
+                let _ = writeln!(
+                    s,
+                    "_Skipped: some documents have more than {} non-null columns._\n",
+                    crate::columns::MAX_FD_COLUMNS
+                );
+            } else if cols.dependencies.is_empty() {
+                s.push_str("_None found._\n\n");
+            } else {
+                for (a, b) in &cols.dependencies {
+                    let _ = writeln!(s, "- `{a}` → `{b}`");
+                }
+                s.push('\n');
+            }
+        }
+
+        if let Some(nums) = &self.numbers {
+            s.push_str("## Numeric distributions (approximate)\n\n");
+            if nums.is_empty() {
+                s.push_str("_No numeric slots._\n\n");
+            } else {
+                s.push_str("| path | count | min | p5 | median | p95 | max | histogram |\n");
+                s.push_str("| --- | ---: | ---: | ---: | ---: | ---: | ---: | --- |\n");
+                let g = |x: f64| format!("{}", (x * 1e6).round() / 1e6);
+                for x in nums {
+                    let _ = writeln!(
+                        s,
+                        "| `{}` | {} | {} | {} | {} | {} | {} | `{}` |",
+                        x.path, n(x.count), g(x.min), g(x.p5), g(x.median), g(x.p95), g(x.max), x.sparkline
+                    );
+                }
+                s.push('\n');
+                let flagged: Vec<_> = nums.iter().filter(|x| !x.sentinels.is_empty()).collect();
+                if !flagged.is_empty() {
+                    s.push_str("### Sentinel candidates\n\n");
+                    s.push_str("_Values far from the rest of their slot; consider an enum arm or a nullable marker._\n\n");
+                    for x in flagged {
+                        for (v, c) in &x.sentinels {
+                            let _ = writeln!(s, "- `{}`: `{v}` in {} of {} values", x.path, n(*c), n(x.count));
+                        }
+                    }
+                    s.push('\n');
+                }
+            }
+        }
+
+        if let Some(found) = &self.sentinels {
+            s.push_str("## Sentinel values\n\n");
+            if found.is_empty() {
+                s.push_str("_None found._\n\n");
+            } else {
+                let modeled = match self.sentinel_policy {
+                    SentinelPolicy::Keep => "kept (`--sentinels null` or `enum` to model)",
+                    SentinelPolicy::Null => "`None`",
+                    SentinelPolicy::Enum => "enum variant",
+                };
+                s.push_str("| path | value | count | share | modeled as |\n");
+                s.push_str("| --- | --- | ---: | ---: | --- |\n");
+                for x in found {
+                    let share = x.count as f64 * 100.0 / x.total.max(1) as f64;
+                    let _ = writeln!(
+                        s,
+                        "| `{}` | `{}` | {} of {} | {:.1}% | {modeled} |",
+                        x.path, x.mark, n(x.count), n(x.total), share
+                    );
+                }
+                s.push('\n');
+            }
+        }
+
+        let likely: Vec<&Sentinel> = self.empty_strings.iter().filter(|(_, likely)| *likely).map(|(x, _)| x).collect();
+        if !likely.is_empty() {
+            s.push_str("## Empty strings as null\n\n");
+            s.push_str("`\"\"` next to real values, in objects whose other fields hold actual nulls.\n\n");
+            let read_as = if self.ingest.empty_as_null { "`None`" } else { "a string (`--empty-strings-as-null` for `None`)" };
+            s.push_str("| path | count | share | read as |\n");
+            s.push_str("| --- | ---: | ---: | --- |\n");
+            for x in likely {
+                let share = x.count as f64 * 100.0 / x.total.max(1) as f64;
+                let _ = writeln!(s, "| `{}` | {} of {} | {:.1}% | {read_as} |", x.path, n(x.count), n(x.total), share);
+            }
+            s.push('\n');
+        }
+
+        if let Some(changes) = &self.literals {
+            s.push_str("## Enum values\n\n");
+            if changes.is_empty() {
+                s.push_str("_Every enum holds exactly the values remembered in the literal dictionary._\n\n");
+            } else {
+                s.push_str("Values missing from this run stay in their enum until deleted from the literal dictionary.\n\n");
+                s.push_str("| path | value | change |\n");
+                s.push_str("| --- | --- | --- |\n");
+                for c in changes {
+                    let change = match &c.last_seen {
+                        None => "new".to_string(),
+                        Some(last) => format!("not seen since {last}, kept"),
+                    };
+                    let _ = writeln!(s, "| `{}` | `{:?}` | {change} |", c.path, c.value);
+                }
+                s.push('\n');
+            }
+        }
+
+        if !self.epochs.is_empty() {
+            s.push_str("## Timestamps\n\n");
+            s.push_str("Integers between 2000 and 2100 when read as seconds or milliseconds since 1970.\n\n");
+            let read_as = if self.epochs_typed { "`DateTime<Utc>`" } else { "an integer (`--epoch-timestamps` for `DateTime<Utc>`)" };
+            s.push_str("| path | unit | earliest | latest | read as |\n");
+            s.push_str("| --- | --- | --- | --- | --- |\n");
+            for x in &self.epochs {
+                let (unit, fmt) = match x.unit {
+                    EpochUnit::Seconds => ("seconds", "%Y-%m-%d %H:%M:%S"),
+                    EpochUnit::Millis => ("milliseconds", "%Y-%m-%d %H:%M:%S%.3f"),
+                };
+                let time = |v: i128| x.unit.time(v).map(|t| t.format(fmt).to_string()).unwrap_or_default();
+                let _ = writeln!(s, "| `{}` | {unit} | {} | {} | {read_as} |", x.path, time(x.min), time(x.max));
+            }
+            s.push('\n');
+        }
+
+        if let Some(found) = &self.pii {
+            s.push_str("## Personal data\n\n");
+            if found.is_empty() {
+                s.push_str("_None found._\n\n");
+            } else {
+                s.push_str("| path | kind | sensitivity | evidence |\n");
+                s.push_str("| --- | --- | --- | --- |\n");
+                for x in found {
+                    let evidence = if x.by_name {
+                        format!("field name, {} value(s)", n(x.total))
+                    } else {
+                        format!("{} of {} value(s)", n(x.matched), n(x.total))
+                    };
+                    let _ = writeln!(s, "| `{}` | {} | {} | {evidence} |", x.path, x.tag.kind.as_str(), x.tag.sensitivity.as_str());
+                }
+                s.push('\n');
+            }
+        }
+
+        if let Some(found) = &self.formats {
+            s.push_str("## Formats\n\n");
+            if found.is_empty() {
+                s.push_str("_No string slot matched a declared format throughout._\n\n");
+            } else {
+                s.push_str("| path | format | values | also matched |\n");
+                s.push_str("| --- | --- | ---: | --- |\n");
+                for x in found {
+                    let also: Vec<String> = x.also.iter().map(|a| format!("`{a}`")).collect();
+                    let _ = writeln!(s, "| `{}` | `{}` | {} | {} |", x.path, x.format.name, n(x.values), also.join(", "));
+                }
+                s.push('\n');
+            }
+        }
+
+        if let Some(groups) = &self.nullable_groups {
+            s.push_str("## Nullable groups\n\n");
+            if groups.is_empty() {
+                s.push_str("_None found._\n\n");
+            }
+            for g in groups {
+                let cols: Vec<String> = g.iter().map(|p| format!("`{p}`")).collect();
+                let _ = writeln!(s, "- {}", cols.join(", "));
+            }
+            if !groups.is_empty() {
+                s.push('\n');
+            }
+        }
+
+        if let Some(tl) = &self.timeline {
+            let _ = writeln!(s, "## Timeline (`{}`, per {})\n", tl.path, tl.window.as_str());
+            if tl.untimed > 0 {
+                let _ = writeln!(s, "_{} document(s) without a readable timestamp are left out._\n", n(tl.untimed));
+            }
+            if tl.buckets.is_empty() {
+                s.push_str("_No timestamped documents._\n\n");
+            } else {
+                s.push_str("| window | docs | paths | appeared | gone |\n");
+                s.push_str("| --- | ---: | ---: | ---: | ---: |\n");
+                for b in &tl.buckets {
+                    let _ = writeln!(s, "| {} | {} | {} | {} | {} |", b.label, n(b.docs), n(b.paths as u64), b.appeared.len(), b.gone.len());
+                }
+                s.push('\n');
+                let changed: Vec<_> = tl.buckets.iter().filter(|b| !b.appeared.is_empty() || !b.gone.is_empty()).collect();
+                if !changed.is_empty() {
+                    s.push_str("### Changes\n\n");
+                    for b in changed {
+                        let _ = writeln!(s, "- **{}**", b.label);
+                        for p in &b.appeared {
+                            let _ = writeln!(s, "  - added `{p}`");
+                        }
+                        for p in &b.gone {
+                            let _ = writeln!(s, "  - stopped `{p}`");
+                        }
+                    }
+                    s.push('\n');
+                }
+                if !tl.lifespans.is_empty() {
+                    s.push_str("### Paths not present throughout\n\n");
+                    s.push_str("| path | first seen | last seen | docs |\n");
+                    s.push_str("| --- | --- | --- | ---: |\n");
+                    for l in &tl.lifespans {
+                        let _ = writeln!(s, "| `{}` | {} | {} | {} |", l.path, l.first, l.last, n(l.docs));
+                    }
+                    s.push('\n');
+                }
+            }
+        }
+
+        if let Some(sz) = &self.sizes {
+            let b = |x: f64| self.number_format.bytes(x.round() as u64);
+            let _ = writeln!(s, "## Record size (estimated from {} docs)\n", n(sz.docs));
+            if sz.partial {
+                let _ = writeln!(s, "_Only the first {} paths were tracked, so these are underestimates._\n", crate::size::MAX_SLOTS);
+            }
+            s.push_str("| | typical | worst case |\n");
+            s.push_str("| --- | ---: | ---: |\n");
+            let _ = writeln!(s, "| serialized JSON | {} | {} |", b(sz.record.json), b(sz.record.json_max));
+            let _ = writeln!(s, "| Rust models (approximate) | {} | {} |", b(sz.record.mem), b(sz.record.mem_max));
+            let _ = writeln!(
+                s,
+                "| per million records (JSON) | {} | {} |",
+                b(sz.record.json * 1e6),
+                b(sz.record.json_max * 1e6)
+            );
+            s.push('\n');
+            if !sz.fields.is_empty() {
+                s.push_str("### Largest fields\n\n");
+                s.push_str("| field | typical | worst case |\n");
+                s.push_str("| --- | ---: | ---: |\n");
+                for f in sz.fields.iter().take(10) {
+                    let _ = writeln!(s, "| `{}` | {} | {} |", f.path, b(f.json), b(f.json_max));
+                }
+                s.push('\n');
+            }
+        }
+
+        if let Some(d) = &self.discriminator {
+            let _ = writeln!(s, "## Discriminator `{}`\n", d.field);
+            s.push_str("| value | docs | requires | never has | always null |\n");
+            s.push_str("| --- | ---: | --- | --- | --- |\n");
+            let list = |xs: &[String]| xs.iter().map(|x| format!("`{x}`")).collect::<Vec<_>>().join(", ");
+            for v in &d.variants {
+                let _ = writeln!(
+                    s,
+                    "| `{}` | {} | {} | {} | {} |",
+                    v.value, n(v.docs), list(&v.required), list(&v.absent), list(&v.null)
+                );
+            }
+            s.push('\n');
+        }
+
+        if !self.code.is_empty() {
+            s.push_str("## Generated code\n\n");
+            s.push_str("| module | lines | structs | enums | fields | deepest | largest tuple |\n");
+            s.push_str("| --- | ---: | ---: | ---: | ---: | --- | --- |\n");
+            for m in &self.code {
+                let deepest = m.deepest().map(|i| format!("{} (`{}`)", i.depth, i.name)).unwrap_or_default();
+                let tuple = m.largest_tuple().map(|i| format!("{} (`{}`)", i.members, i.name)).unwrap_or_default();
+                let _ = writeln!(
+                    s,
+                    "| `{}` | ~{} | {} | {} | {} | {} | {} |",
+                    m.module, m.lines, m.structs(), m.enums(), m.fields(), deepest, tuple
+                );
+            }
+            s.push('\n');
+            let outliers: Vec<(String, String)> = self.code
+                .iter()
+                .flat_map(|m| m.outliers().into_iter().map(|o| (m.module.clone(), o)))
+                .collect();
+            if !outliers.is_empty() {
+                s.push_str("### Outliers\n\n");
+                for (module, o) in &outliers {
+                    let _ = writeln!(s, "- `{module}`: {o}");
+                }
+                s.push('\n');
+            }
+        }
+
+        if !self.plugin_warnings.is_empty() {
+            s.push_str("## Plugin warnings\n\n");
+            s.push_str("| plugin | path | message |\n");
+            s.push_str("| --- | --- | --- |\n");
+            for (plugin, w) in &self.plugin_warnings {
+                let path = w.path.as_ref().map(|p| format!("`{p}`")).unwrap_or_default();
+                let _ = writeln!(s, "| `{plugin}` | {path} | {} |", w.message.replace('|', "\\|").replace('\n', " "));
+            }
+            s.push('\n');
+        }
+
+        if let Some(rv) = &self.revalidation {
+            let _ = writeln!(s, "## Revalidation ({} of {} samples fail)\n", rv.failed, rv.checked);
+            if rv.suggestions.is_empty() {
+                s.push_str("_Every sample passes the generated types._\n\n");
+            } else {
+                s.push_str("| path | suggested relaxation | docs | first sample |\n");
+                s.push_str("| --- | --- | ---: | ---: |\n");
+                for sug in &rv.suggestions {
+                    let _ = writeln!(s, "| `{}` | {} | {} | {} |", sug.path, sug.relax, sug.docs, sug.first + 1);
+                }
+                s.push('\n');
+            }
+            if let Some(c) = &rv.schema {
+                let _ = writeln!(s, "### Against the schema ({} of {} samples fail)\n", c.failed, c.docs);
+                if !c.problems.is_empty() {
+                    s.push_str("| path | keyword | first failure | docs | first sample |\n");
+                    s.push_str("| --- | --- | --- | ---: | ---: |\n");
+                    for p in &c.problems {
+                        let message = p.message.replace('|', "\\|");
+                        let _ = writeln!(s, "| `{}` | `{}` | {message} | {} | {} |", p.path, p.keyword, p.docs, p.first + 1);
+                    }
+                    s.push('\n');
+                }
+            }
+        }
+        s
+    }
+}
@@ -0,0 +1,78 @@
+//! Presence threshold for required fields (`--required-threshold`).
+//!
+//! A field is required when it is present and non-null in every object of its
+//! slot, so one null among millions of objects makes it optional. With a
+//! threshold below 1, a field non-null in at least that share of the objects is
+//! required too (and its type loses the null the rare objects had). The report
+//! lists the fields the threshold decided, and those that barely missed it.
+
+use crate::inference::U;
+use crate::jpath::{JPath, Seg};
+use crate::overrides::Overrides;
+
+/// Required only when non-null in every object.
+pub const ALL: f64 = 1.0;
+
+/// Whether a field non-null in `non_null` of `objects` objects is required.
+pub fn is_required(non_null: u64, objects: u64, threshold: f64) -> bool {
+    non_null == objects || (threshold < ALL && non_null as f64 >= threshold * objects as f64)
+}
+
+/// A field whose presence is close to the threshold.
+#[derive(Debug, Clone)]
+pub struct Presence {
+    pub path: JPath,
+    pub non_null: u64,
+    pub objects: u64,
+    /// Required through the threshold; else it barely missed it.
+    pub required: bool,
+}
+
+impl Presence {
+    pub fn share(&self) -> f64 {
+        self.non_null as f64 / self.objects.max(1) as f64
+    }
+}
+
+/// The fields of `u` required only through `threshold`, and those that missed
+/// it by at most as much again (non-null in at least `2 * threshold - 1` of the
+/// objects), in path order.
+pub fn near(u: &U, threshold: f64, ov: &Overrides) -> Vec<Presence> {
+    let mut out = Vec::new();
+    walk(u, &JPath::root(), threshold, ov, &mut out);
+    out
+}
+
+fn walk(u: &U, at: &JPath, threshold: f64, ov: &Overrides, out: &mut Vec<Presence>) {
+    if ov.passthrough(at) {
+        return;
+    }
+    if let Some(arr) = &u.arr {
+        let tuple = ov.tuple_at(at).unwrap_or_else(|| crate::inference::decide_tuple(arr));
+        if tuple && !arr.cols.is_empty() {
+            for (i, c) in arr.cols.iter().enumerate() {
+                walk(c, &at.child(Seg::Index(i)), threshold, ov, out);
+            }
+        } else {
+            walk(&arr.item, &at.child(Seg::Items), threshold, ov, out);
+        }
+    }
+    if let Some(obj) = &u.obj {
+        // maps have no required keys
+        if let Some(value) = crate::inference::decide_map(obj) {
+            return walk(&value, &at.child(Seg::Items), threshold, ov, out);
+        }
+        for (name, f) in &obj.fields {
+            let path = at.child(Seg::Field(name.clone()));
+            let (non_null, objects) = (f.non_null_in, obj.seen_objects);
+            if non_null < objects {
+                let share = non_null as f64 / objects as f64;
+                let required = is_required(non_null, objects, threshold);
+                if required || share >= 2.0 * threshold - 1.0 {
+                    out.push(Presence { path: path.clone(), non_null, objects, required });
+                }
+            }
+            walk(&f.ty, &path, threshold, ov, out);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! Rewrite passes over the normalized IR, for embedders.
+//!
+//! The normalizer's simplifications are fixed; a caller with its own (collapse
+//! every `{ "amount", "currency" }` record into a money string, drop a vendor's
+//! padding fields) implements [`Pass`] rather than forking it. A pass is offered
+//! every node with its path and may return a replacement. [`register`] adds one
+//! to those `gen`, `verify-generated` and `redecide` run between normalization
+//! (after `--tightness` and `--ir-transform`) and lowering, in order.
+//!
+//! The names of the registered passes are kept in the artifact header, so
+//! `verify-generated` only regenerates under the same passes.
+
+use std::sync::Mutex;
+
+use crate::jpath::{JPath, Seg};
+use crate::norm_ir::NTy;
+
+/// A rewrite of the normalized IR.
+pub trait Pass: Send {
+    /// A short name, for logs and the artifact header.
+    fn name(&self) -> &str;
+
+    /// The replacement for `n`, found at `path`, or `None` to keep it. Paths
+    /// look through `Nullable`, `JsonString`, tagged variants and union arms,
+    /// as in the schema; a map's key type is offered at the map's own path.
+    fn rewrite(&mut self, path: &JPath, n: &NTy) -> Option<NTy>;
+}
+
+/// A [`Pass`] from a closure (see [`from_fn`]).
+pub struct FnPass<F> {
+    name: String,
+    f: F,
+}
+
+impl<F: FnMut(&JPath, &NTy) -> Option<NTy> + Send> Pass for FnPass<F> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn rewrite(&mut self, path: &JPath, n: &NTy) -> Option<NTy> {
+        (self.f)(path, n)
+    }
+}
+
+/// A pass named `name` that calls `f`.
+pub fn from_fn<F: FnMut(&JPath, &NTy) -> Option<NTy> + Send>(name: &str, f: F) -> FnPass<F> {
+    FnPass { name: name.to_string(), f }
+}
+
+/// Run `pass` over `n`, bottom-up: a node is offered after its children were,
+/// so it sees them rewritten. A replacement isn't offered again, nor are its
+/// children. Returns how many nodes were replaced.
+pub fn apply(n: &mut NTy, pass: &mut dyn Pass) -> usize {
+    walk(n, &mut JPath::root(), pass)
+}
+
+fn walk(n: &mut NTy, at: &mut JPath, pass: &mut dyn Pass) -> usize {
+    let mut count = match n {
+        NTy::Null
+        | NTy::Bool
+        | NTy::Integer { .. }
+        | NTy::Number { .. }
+        | NTy::StringOrNumber { .. }
+        | NTy::String { .. }
+        | NTy::Any
+        | NTy::Raw => 0,
+        NTy::ArrayList { item, .. } => below(Seg::Items, item, at, pass),
+        NTy::ArrayTuple { elems, .. } => {
+            elems.iter_mut().enumerate().map(|(i, e)| below(Seg::Index(i), e, at, pass)).sum()
+        }
+        NTy::Object { fields, .. } => fields.iter_mut().map(|f| below(Seg::Field(f.name.clone()), &mut f.ty, at, pass)).sum(),
+        NTy::Map { key, value, .. } => walk(key, at, pass) + below(Seg::Items, value, at, pass),
+        NTy::Tagged { variants, .. } => variants.iter_mut().map(|(_, v)| walk(v, at, pass)).sum(),
+        NTy::JsonString(inner) | NTy::Nullable(inner) => walk(inner, at, pass),
+        NTy::OneOf(arms) => arms.iter_mut().map(|a| walk(a, at, pass)).sum(),
+    };
+    if let Some(replacement) = pass.rewrite(at, n) {
+        *n = replacement;
+        count += 1;
+    }
+    count
+}
+
+fn below(seg: Seg, n: &mut NTy, at: &mut JPath, pass: &mut dyn Pass) -> usize {
+    at.0.push(seg);
+    let count = walk(n, at, pass);
+    at.0.pop();
+    count
+}
+
+static REGISTERED: Mutex<Vec<Box<dyn Pass>>> = Mutex::new(Vec::new());
+
+/// Add `pass` to those run after normalization, after the ones registered before.
+pub fn register(pass: impl Pass + 'static) {
+    REGISTERED.lock().unwrap_or_else(|e| e.into_inner()).push(Box::new(pass));
+}
+
+/// Names of the registered passes, in order.
+pub fn registered() -> Vec<String> {
+    REGISTERED.lock().unwrap_or_else(|e| e.into_inner()).iter().map(|p| p.name().to_string()).collect()
+}
+
+/// Run the registered passes over `n`, in order. Returns each pass's name and
+/// how many nodes it replaced.
+pub fn run_registered(n: &mut NTy) -> Vec<(String, usize)> {
+    let mut passes = REGISTERED.lock().unwrap_or_else(|e| e.into_inner());
+    passes.iter_mut().map(|p| (p.name().to_string(), apply(n, p.as_mut()))).collect()
+}
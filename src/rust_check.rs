@@ -0,0 +1,222 @@
+//! A self-check of generated Rust before it is written.
+//!
+//! A name codegen failed to sanitize (a keyword, a clash) makes rustc fail
+//! somewhere in a file of thousands of lines, far from the JSON that caused it.
+//! [`check`] parses the source with `syn` and looks for what codegen can get wrong:
+//! - anything `syn` rejects: unterminated literals, unbalanced delimiters,
+//!   keywords used as names (`r#` names pass), …
+//! - two items of one namespace with the same name at module level
+//! - two fields of a struct, or two variants of an enum, with the same name
+//!
+//! Parsing isn't type checking, so the code it passes can still be rejected by
+//! rustc; but each problem it finds names the item (and member) it is in, which
+//! [`crate::codegen::Codegen::origin`] traces back to a JSON path.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use syn::ext::IdentExt;
+use syn::{Fields, Ident, Item};
+
+/// Something in the source rustc would reject.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// 1-based.
+    pub line: usize,
+    /// The module-level item it is in (for an `impl`, the type implemented).
+    pub item: Option<String>,
+    /// The field or variant, when it is about one.
+    pub member: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}", self.line)?;
+        if let Some(item) = &self.item {
+            write!(f, ", in `{item}`")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// The problems in `src`, in source order. A syntax error is the only problem
+/// reported: nothing after it can be trusted.
+pub fn check(src: &str) -> Vec<Problem> {
+    // lexing first: its errors say little, but where they are is enough
+    let tokens = match src.parse::<proc_macro2::TokenStream>() {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            let message = "unterminated literal or comment, or unbalanced delimiter";
+            return vec![syntax_error(src, &syn::Error::new(e.span(), message))];
+        }
+    };
+    let file = match syn::parse2::<syn::File>(tokens) {
+        Ok(file) => file,
+        Err(e) => return vec![syntax_error(src, &e)],
+    };
+    let mut out = Vec::new();
+    let mut types: BTreeMap<String, usize> = BTreeMap::new();
+    let mut values: BTreeMap<String, usize> = BTreeMap::new();
+    for item in &file.items {
+        let (name, is_type) = match item {
+            Item::Struct(s) => (&s.ident, true),
+            Item::Enum(e) => (&e.ident, true),
+            Item::Union(u) => (&u.ident, true),
+            Item::Trait(t) => (&t.ident, true),
+            Item::Type(t) => (&t.ident, true),
+            Item::Mod(m) => (&m.ident, true),
+            Item::Fn(f) => (&f.sig.ident, false),
+            Item::Const(c) => (&c.ident, false),
+            Item::Static(s) => (&s.ident, false),
+            _ => continue,
+        };
+        let item_name = name.unraw().to_string();
+        let problem = |line: usize, member: Option<&Ident>, message: String| Problem {
+            line,
+            item: Some(item_name.clone()),
+            member: member.map(|m| m.unraw().to_string()),
+            message,
+        };
+        if let Some(kw) = keyword(name) {
+            out.push(problem(line_of(name), None, format!("keyword `{kw}` as the name of an item")));
+        } else if let Some(first) = (if is_type { &mut types } else { &mut values }).insert(item_name.clone(), line_of(name)) {
+            out.push(problem(line_of(name), None, format!("`{item_name}` is defined twice (first on line {first})")));
+        }
+        let members: Vec<&Ident> = match item {
+            Item::Struct(s) => match &s.fields {
+                Fields::Named(named) => named.named.iter().filter_map(|f| f.ident.as_ref()).collect(),
+                _ => Vec::new(),
+            },
+            Item::Union(u) => u.fields.named.iter().filter_map(|f| f.ident.as_ref()).collect(),
+            Item::Enum(e) => e.variants.iter().map(|v| &v.ident).collect(),
+            _ => Vec::new(),
+        };
+        let what = if matches!(item, Item::Enum(_)) { "variant" } else { "field" };
+        let mut seen: BTreeMap<String, usize> = BTreeMap::new();
+        for m in members {
+            let name = m.unraw().to_string();
+            if let Some(kw) = keyword(m) {
+                out.push(problem(line_of(m), Some(m), format!("keyword `{kw}` as a {what} name")));
+            } else if let Some(first) = seen.insert(name.clone(), line_of(m)) {
+                out.push(problem(line_of(m), Some(m), format!("duplicate {what} `{name}` (first on line {first})")));
+            }
+        }
+    }
+    out.sort_by_key(|p| p.line);
+    out
+}
+
+fn line_of(ident: &Ident) -> usize {
+    ident.span().start().line
+}
+
+/// Keywords `syn` still takes as names (reserved in later editions), unless written `r#…`.
+fn keyword(ident: &Ident) -> Option<String> {
+    let s = ident.to_string();
+    matches!(s.as_str(), "gen").then_some(s)
+}
+
+/// `e` as a problem, placed in the item (and member) around its line.
+fn syntax_error(src: &str, e: &syn::Error) -> Problem {
+    let line = e.span().start().line.max(1);
+    Problem {
+        line,
+        item: item_around(src, line),
+        member: src.lines().nth(line - 1).and_then(member_on),
+        message: e.to_string(),
+    }
+}
+
+/// The module-level item whose header is the last one at or above `line`.
+/// Codegen writes items unindented, so a header is a line starting with an item
+/// keyword; for an `impl`, the type is the last name before the body.
+fn item_around(src: &str, line: usize) -> Option<String> {
+    let above: Vec<&str> = src.lines().take(line).collect();
+    above.into_iter().rev().filter(|l| !l.starts_with(char::is_whitespace)).find_map(|l| {
+        let words = || l.split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '#')).filter(|w| !w.is_empty());
+        let mut it = words().skip_while(|w| *w == "pub" || *w == "crate");
+        match it.next()? {
+            "struct" | "enum" | "union" | "trait" | "type" | "mod" | "fn" | "const" | "static" => {
+                it.next().map(|name| name.trim_start_matches("r#").to_string())
+            }
+            "impl" => {
+                let head = l.split('{').next().unwrap_or(l);
+                head.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .rfind(|w| !w.is_empty() && !matches!(*w, "impl" | "for" | "where"))
+                    .map(str::to_string)
+            }
+            _ => None,
+        }
+    })
+}
+
+/// The field on a `name: Type,` line, or the variant on a `Name,` / `Name(…)` line.
+fn member_on(line: &str) -> Option<String> {
+    let line = line.trim_start();
+    let line = line.strip_prefix("pub ").unwrap_or(line);
+    let name: String = line.trim_start_matches("r#").chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect();
+    let rest = line.trim_start_matches("r#")[name.len()..].trim_start();
+    let member = (rest.starts_with(':') && !rest.starts_with("::")) || rest.starts_with(',') || rest.starts_with('(') || rest.starts_with('{');
+    (!name.is_empty() && member).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clean_code_and_raw_names_pass() {
+        let src = "pub struct Card {\n    pub r#type: String,\n    pub id: u64,\n}\n\npub enum Kind {\n    A,\n    B(u8),\n}\n\nimpl Card {\n    fn r#gen(&self) {}\n}\n";
+        assert_eq!(check(src), vec![]);
+    }
+
+    #[test]
+    fn keyword_field_names_the_item_and_member() {
+        let src = "pub struct Card {\n    pub id: u64,\n    pub type: String,\n}\n";
+        let problems = check(src);
+        assert_eq!(problems.len(), 1, "{problems:?}");
+        assert_eq!(problems[0].line, 3);
+        assert_eq!(problems[0].item.as_deref(), Some("Card"));
+        assert_eq!(problems[0].member.as_deref(), Some("type"));
+    }
+
+    #[test]
+    fn gen_is_reported_though_syn_takes_it() {
+        let src = "pub enum Kind {\n    gen,\n}\n\npub fn gen() {}\n";
+        let problems = check(src);
+        assert_eq!(problems.iter().map(|p| p.line).collect::<Vec<_>>(), vec![2, 5]);
+        assert_eq!(problems[0].member.as_deref(), Some("gen"));
+        assert_eq!(problems[1].item.as_deref(), Some("gen"));
+        assert_eq!(problems[1].member, None);
+    }
+
+    #[test]
+    fn duplicates_in_one_namespace() {
+        let src = "pub struct A {\n    x: u8,\n    r#x: u8,\n}\n\npub enum A {}\n\npub fn A() {}\n";
+        let problems = check(src);
+        assert_eq!(problems.len(), 2, "{problems:?}");
+        assert_eq!((problems[0].line, problems[0].member.as_deref()), (3, Some("x")));
+        assert!(problems[0].message.contains("first on line 2"), "{}", problems[0].message);
+        // the fn is a value, so only the enum clashes
+        assert_eq!((problems[1].line, problems[1].item.as_deref()), (6, Some("A")));
+    }
+
+    #[test]
+    fn lex_errors_are_placed_in_their_item() {
+        let src = "pub struct A {\n    x: u8,\n}\n\npub struct B {\n    y: u8,\n    z: [u8; 2,\n}\n";
+        let problems = check(src);
+        assert_eq!(problems.len(), 1, "{problems:?}");
+        assert!(problems[0].line >= 5, "{problems:?}");
+        assert_eq!(problems[0].item.as_deref(), Some("B"));
+    }
+
+    #[test]
+    fn item_around_skips_indented_headers_and_reads_impls() {
+        let src = "impl Deserialize for Card {\n    fn visit() {\n        x\n    }\n}\n";
+        assert_eq!(item_around(src, 3), Some("Card".to_string()));
+        assert_eq!(member_on("    pub r#type: String,"), Some("type".to_string()));
+        assert_eq!(member_on("    Variant(u8),"), Some("Variant".to_string()));
+        assert_eq!(member_on("    let x = a::b;"), None);
+    }
+}
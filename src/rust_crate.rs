@@ -0,0 +1,144 @@
+//! Standalone cargo crate around the generated models (`gen --rust-crate DIR`).
+//!
+//! Layout:
+//!   Cargo.toml                 dependencies the models actually use, minimum versions pinned
+//!   src/lib.rs                 re-exports the models
+//!   src/models.rs              `--rust` output (stamped)
+//!   src/generated_config.rs    `--rust-config` output (stamped)
+//!   src/contract_tests.rs      `--contract-tests`: unit tests pinning the types' shapes
+//!   tests/samples.rs           every captured sample document must deserialize
+//!   tests/samples.ndjson
+
+use std::io;
+use std::path::Path;
+
+use serde_json::Value;
+
+/// Sample documents captured for `tests/samples.ndjson` (first ones in input order).
+pub const MAX_SAMPLES: usize = 32;
+
+// Minimum versions; caret requirements so the crate unifies with the consumer's graph.
+const SERDE: &str = "1.0.200";
+const SERDE_JSON: &str = "1.0.116";
+const REGEX: &str = "1.10";
+const ONCE_CELL: &str = "1.19";
+const UUID: &str = "1.8";
+const RUST_DECIMAL: &str = "1.33";
+const CHRONO: &str = "0.4.35";
+const SERDE_WITH: &str = "3.4";
+
+pub struct CrateSources<'a> {
+    /// Stamped models source.
+    pub models: &'a str,
+    /// Stamped `generated_config.rs` source.
+    pub config: &'a str,
+    /// Name requested for the root type (`--root-type`).
+    pub root_type: &'a str,
+    /// Rust type the root resolved to (differs from `root_type` for lists, unions, …).
+    pub record: &'a str,
+    pub samples: &'a [Value],
+    /// `contract_tests.rs`, with `--contract-tests`.
+    pub contract_tests: Option<&'a str>,
+}
+
+/// `my models` / `payload_models` → `payload-models`.
+pub(crate) fn package_name(dir: &Path) -> String {
+    let raw = dir
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut out = String::new();
+    for c in raw.chars() {
+        if c.is_ascii_alphanumeric() {
+            out.push(c.to_ascii_lowercase());
+        } else if !out.ends_with('-') {
+            out.push('-');
+        }
+    }
+    let out = out.trim_matches('-').to_string();
+    if out.is_empty() || out.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("models-{out}").trim_end_matches('-').to_string()
+    } else {
+        out
+    }
+}
+
+/// `[dependencies]` lines for what `models` uses.
+pub(crate) fn dependencies(models: &str) -> String {
+    let mut deps = format!("serde = {{ version = \"{SERDE}\", features = [\"derive\"] }}\n");
+    if models.contains("::serde_json::value::RawValue") {
+        deps.push_str(&format!("serde_json = {{ version = \"{SERDE_JSON}\", features = [\"raw_value\"] }}\n"));
+    } else {
+        deps.push_str(&format!("serde_json = \"{SERDE_JSON}\"\n"));
+    }
+    if models.contains("::regex::") {
+        deps.push_str(&format!("regex = \"{REGEX}\"\n"));
+    }
+    if models.contains("::once_cell::") {
+        deps.push_str(&format!("once_cell = \"{ONCE_CELL}\"\n"));
+    }
+    if models.contains("::uuid::") {
+        deps.push_str(&format!("uuid = {{ version = \"{UUID}\", features = [\"serde\"] }}\n"));
+    }
+    if models.contains("::rust_decimal::") {
+        deps.push_str(&format!("rust_decimal = \"{RUST_DECIMAL}\"\n"));
+    }
+    if models.contains("::serde_with::") {
+        deps.push_str(&format!("chrono = \"{CHRONO}\"\n"));
+        deps.push_str(&format!("serde_with = {{ version = \"{SERDE_WITH}\", features = [\"chrono_0_4\"] }}\n"));
+    }
+    deps
+}
+
+pub fn write_crate(dir: &Path, src: &CrateSources) -> io::Result<()> {
+    let package = package_name(dir);
+    let lib = package.replace('-', "_");
+    let deps = dependencies(src.models);
+    let cargo_toml = format!(
+        "# AUTOGENERATED by {tool}\n[package]\nname = \"{package}\"\nversion = \"0.1.0\"\nedition = \"2021\"\npublish = false\n\n[dependencies]\n{deps}",
+        tool = crate::manifest::tool(),
+    );
+
+    let mut lib_rs = String::from(
+        "// AUTOGENERATED: generated models crate\n#![allow(dead_code, non_camel_case_types, clippy::all)]\n\npub mod generated_config;\npub mod models;\n\npub use models::*;\n",
+    );
+    if src.record != src.root_type {
+        lib_rs.push_str(&format!("\npub type {} = {};\n", src.root_type, src.record));
+    }
+    if src.contract_tests.is_some() {
+        lib_rs.push_str("\n#[cfg(test)]\nmod contract_tests;\n");
+    }
+
+    let test_rs = format!(
+r#"// AUTOGENERATED: every sample document captured at generation time must deserialize.
+
+#[test]
+fn samples_deserialize() {{
+    for (i, line) in include_str!("samples.ndjson").lines().enumerate() {{
+        if let Err(e) = ::serde_json::from_str::<{lib}::{root}>(line) {{
+            panic!("sample {{}}: {{}}\n{{}}", i + 1, e, line);
+        }}
+    }}
+}}
+"#,
+        root = src.root_type,
+    );
+    let mut samples = String::new();
+    for v in src.samples {
+        samples.push_str(&serde_json::to_string(v).expect("sample serializes"));
+        samples.push('\n');
+    }
+
+    std::fs::create_dir_all(dir.join("src"))?;
+    std::fs::create_dir_all(dir.join("tests"))?;
+    crate::portable::write(&dir.join("Cargo.toml"), &cargo_toml)?;
+    crate::portable::write(&dir.join("src/lib.rs"), &lib_rs)?;
+    crate::portable::write(&dir.join("src/models.rs"), src.models)?;
+    crate::portable::write(&dir.join("src/generated_config.rs"), src.config)?;
+    if let Some(tests) = src.contract_tests {
+        crate::portable::write(&dir.join("src/contract_tests.rs"), tests)?;
+    }
+    crate::portable::write(&dir.join("tests/samples.rs"), &test_rs)?;
+    crate::portable::write(&dir.join("tests/samples.ndjson"), &samples)?;
+    Ok(())
+}
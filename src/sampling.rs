@@ -0,0 +1,160 @@
+//! Reading a sample of the documents (`--max-samples`, `--max-samples-per-input`).
+//!
+//! Inference on a huge corpus usually settles long before the last document.
+//! [`Sampling::select`] picks which documents to observe, given how many each
+//! input holds: at most `per_input` of each input's, then at most `max` of
+//! those over all inputs.
+//!
+//! - `reservoir`: a uniform sample. Each document gets a priority hashed from
+//!   the seed, its input's path and its position there, and the lowest ones are
+//!   kept (bottom-k sampling, the reservoir sampling of a stream read twice).
+//! - `stride`: every n-th document, starting at an offset picked by the seed;
+//!   cheaper to reason about, but in step with any periodic structure.
+//!
+//! Either way the same seed, inputs and limits pick the same documents, so the
+//! evidence (and what is generated from it) is reproducible.
+
+use std::collections::BinaryHeap;
+
+use serde::{Deserialize, Serialize};
+
+/// How documents are picked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Method {
+    Reservoir,
+    Stride,
+}
+
+impl Method {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Method::Reservoir => "reservoir",
+            Method::Stride => "stride",
+        }
+    }
+}
+
+/// The sampling settings of a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sampling {
+    pub method: Method,
+    pub seed: u64,
+    /// Documents over all inputs (`--max-samples`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<u64>,
+    /// Documents per input (`--max-samples-per-input`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub per_input: Option<u64>,
+}
+
+impl Sampling {
+    /// `reservoir max=1000 per_input=100 seed=7`, for the manifest and messages.
+    pub fn describe(&self) -> String {
+        let mut s = self.method.as_str().to_string();
+        if let Some(max) = self.max {
+            s.push_str(&format!(" max={max}"));
+        }
+        if let Some(per_input) = self.per_input {
+            s.push_str(&format!(" per_input={per_input}"));
+        }
+        s.push_str(&format!(" seed={}", self.seed));
+        s
+    }
+
+    /// Which documents to observe of each input, given its path and how many
+    /// documents it holds: their positions (ascending), or `None` to read it all.
+    pub fn select(&self, inputs: &[(String, u64)]) -> Vec<Option<Vec<u64>>> {
+        let picked: Vec<Option<Vec<u64>>> = inputs
+            .iter()
+            .map(|(path, n)| self.per_input.filter(|k| k < n).map(|k| self.pick(path, *n, k)))
+            .collect();
+        let Some(max) = self.max else {
+            return picked;
+        };
+        let kept = |i: usize| -> Box<dyn Iterator<Item = u64> + '_> {
+            match &picked[i] {
+                Some(docs) => Box::new(docs.iter().copied()),
+                None => Box::new(0..inputs[i].1),
+            }
+        };
+        let total: u64 = (0..inputs.len()).map(|i| picked[i].as_ref().map_or(inputs[i].1, |d| d.len() as u64)).sum();
+        if total <= max {
+            return picked;
+        }
+        let mut out: Vec<Vec<u64>> = vec![Vec::new(); inputs.len()];
+        match self.method {
+            Method::Reservoir => {
+                // the `max` lowest priorities, largest on top
+                let mut heap: BinaryHeap<(u64, usize, u64)> = BinaryHeap::with_capacity(max as usize + 1);
+                for (i, (path, _)) in inputs.iter().enumerate() {
+                    let key = path_key(path);
+                    for doc in kept(i) {
+                        heap.push((priority(self.seed, key, doc), i, doc));
+                        if heap.len() as u64 > max {
+                            heap.pop();
+                        }
+                    }
+                }
+                for (_, i, doc) in heap {
+                    out[i].push(doc);
+                }
+                out.iter_mut().for_each(|docs| docs.sort_unstable());
+            }
+            Method::Stride => {
+                let (step, offset) = stride(self.seed, total, max);
+                let mut at = 0u64;
+                for (i, docs) in out.iter_mut().enumerate() {
+                    for doc in kept(i) {
+                        if at >= offset && (at - offset).is_multiple_of(step) && (at - offset) / step < max {
+                            docs.push(doc);
+                        }
+                        at += 1;
+                    }
+                }
+            }
+        }
+        out.into_iter().map(Some).collect()
+    }
+
+    /// `k` of the `n` documents of the input at `path` (`k < n`).
+    fn pick(&self, path: &str, n: u64, k: u64) -> Vec<u64> {
+        match self.method {
+            Method::Reservoir => {
+                let key = path_key(path);
+                let mut docs: Vec<(u64, u64)> = (0..n).map(|doc| (priority(self.seed, key, doc), doc)).collect();
+                docs.select_nth_unstable(k as usize);
+                let mut docs: Vec<u64> = docs[..k as usize].iter().map(|&(_, doc)| doc).collect();
+                docs.sort_unstable();
+                docs
+            }
+            Method::Stride => {
+                let (step, offset) = stride(self.seed, n, k);
+                (0..k).map(|i| offset + i * step).collect()
+            }
+        }
+    }
+}
+
+/// Step and first position taking exactly `k` of `n` (`k < n`) evenly.
+fn stride(seed: u64, n: u64, k: u64) -> (u64, u64) {
+    let step = n / k;
+    (step, mix(seed) % step)
+}
+
+/// FNV-1a of `path`.
+fn path_key(path: &str) -> u64 {
+    path.bytes().fold(0xCBF2_9CE4_8422_2325, |h, b| (h ^ b as u64).wrapping_mul(0x0000_0100_0000_01B3))
+}
+
+fn priority(seed: u64, path: u64, doc: u64) -> u64 {
+    mix(mix(seed ^ path).wrapping_add(doc))
+}
+
+/// The SplitMix64 finalizer.
+fn mix(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
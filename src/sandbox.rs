@@ -0,0 +1,75 @@
+//! What a run may do besides reading its inputs and writing its outputs.
+//!
+//! Inference never opens a connection or starts a process, and jq programs
+//! (`--jq-expr`, `--ir-transform`) run without the builtins that reach outside
+//! the document: [`JQ_DENIED`]. The one exception today is `gen --plugin`,
+//! which starts the plugin runtime. Every such effect asks [`request`] first:
+//! under `--offline` it is refused, and `--sandbox-audit` logs each one and
+//! sums them up when the run ends. Features that fetch URLs must ask too.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// jq builtins left out of the engine: they read the environment or the
+/// clock, exit the process, or write to the log.
+pub const JQ_DENIED: [&str; 6] = ["env", "now", "halt", "halt_error", "debug", "stderr"];
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+static AUDIT: AtomicBool = AtomicBool::new(false);
+static USED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// An effect beyond the inputs and outputs.
+#[derive(Debug, Clone, Copy)]
+pub enum Effect<'a> {
+    /// Start a program (its command line).
+    Process(&'a str),
+    /// Fetch a URL.
+    #[allow(dead_code)] // nothing fetches URLs yet
+    Network(&'a str),
+}
+
+impl std::fmt::Display for Effect<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Effect::Process(cmd) => write!(f, "start `{cmd}`"),
+            Effect::Network(url) => write!(f, "fetch {url}"),
+        }
+    }
+}
+
+/// `--offline` and `--sandbox-audit`, once at startup.
+pub fn configure(offline: bool, audit: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+    AUDIT.store(audit, Ordering::Relaxed);
+}
+
+pub fn offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+/// Ask to perform `effect`: an error under `--offline`.
+pub fn request(effect: Effect) -> Result<(), String> {
+    let offline = offline();
+    if AUDIT.load(Ordering::Relaxed) {
+        let verdict = if offline { "refused" } else { "allowed" };
+        crate::cli::log_info(format!("sandbox: {effect}: {verdict}"));
+        USED.lock().unwrap().push(format!("{effect} ({verdict})"));
+    }
+    if offline {
+        return Err(format!("--offline: refusing to {effect}"));
+    }
+    Ok(())
+}
+
+/// The closing line of `--sandbox-audit`; `None` when not auditing.
+pub fn summary() -> Option<String> {
+    if !AUDIT.load(Ordering::Relaxed) {
+        return None;
+    }
+    let used = USED.lock().unwrap();
+    Some(if used.is_empty() {
+        format!("sandbox audit: no network access, no processes started; jq without {}", JQ_DENIED.join(", "))
+    } else {
+        format!("sandbox audit: {} effect(s) beyond inputs and outputs: {}", used.len(), used.join("; "))
+    })
+}
@@ -0,0 +1,81 @@
+//! Runnable example project around models generated from an IR (`scaffold`).
+//!
+//! Layout:
+//!   Cargo.toml         dependencies the models use, plus `serde_path_to_error`
+//!   src/main.rs        parses the file named on the command line (default `sample.json`),
+//!                      reporting errors with the JSON path they occurred at
+//!   src/models.rs      the generated models
+//!   sample.json        the captured payload; the test in `main.rs` parses it
+
+use std::io;
+use std::path::Path;
+
+// Minimum version; caret requirement, as for the other dependencies.
+const SERDE_PATH_TO_ERROR: &str = "0.1.20";
+
+pub struct ScaffoldSources<'a> {
+    pub models: &'a str,
+    /// Rust type the root resolved to.
+    pub record: &'a str,
+    /// The sample document, as read.
+    pub sample: &'a str,
+}
+
+pub fn write_project(dir: &Path, src: &ScaffoldSources) -> io::Result<()> {
+    let cargo_toml = format!(
+        "# AUTOGENERATED by {tool}\n[package]\nname = \"{package}\"\nversion = \"0.1.0\"\nedition = \"2021\"\npublish = false\n\n[dependencies]\n{deps}serde_path_to_error = \"{SERDE_PATH_TO_ERROR}\"\n",
+        tool = crate::manifest::tool(),
+        package = crate::rust_crate::package_name(dir),
+        deps = crate::rust_crate::dependencies(src.models),
+    );
+
+    let main_rs = format!(
+r#"// AUTOGENERATED by json-osi scaffold: parse a JSON document with the generated models.
+//
+//     cargo run -- path/to/payload.json
+//     cargo test
+
+#[allow(dead_code, non_camel_case_types, clippy::all)]
+mod models;
+
+/// The document in `src`, or where and why it doesn't fit the models.
+fn parse(src: &str) -> Result<models::{record}, String> {{
+    let de = &mut ::serde_json::Deserializer::from_str(src);
+    ::serde_path_to_error::deserialize(de).map_err(|e| format!("at {{}}: {{}}", e.path(), e.inner()))
+}}
+
+fn main() {{
+    let path = std::env::args().nth(1).unwrap_or_else(|| "sample.json".to_string());
+    let src = std::fs::read_to_string(&path).unwrap_or_else(|e| {{
+        eprintln!("error: {{path}}: {{e}}");
+        std::process::exit(2);
+    }});
+    match parse(&src) {{
+        Ok(doc) => println!("{{doc:#?}}"),
+        Err(e) => {{
+            eprintln!("error: {{path}}: {{e}}");
+            std::process::exit(1);
+        }}
+    }}
+}}
+
+#[cfg(test)]
+mod tests {{
+    #[test]
+    fn sample_parses() {{
+        if let Err(e) = super::parse(include_str!("../sample.json")) {{
+            panic!("sample.json: {{e}}");
+        }}
+    }}
+}}
+"#,
+        record = src.record,
+    );
+
+    std::fs::create_dir_all(dir.join("src"))?;
+    crate::portable::write(&dir.join("Cargo.toml"), &cargo_toml)?;
+    crate::portable::write(&dir.join("src/main.rs"), &main_rs)?;
+    crate::portable::write(&dir.join("src/models.rs"), src.models)?;
+    crate::portable::write(&dir.join("sample.json"), src.sample)?;
+    Ok(())
+}
@@ -0,0 +1,562 @@
+//! Documents checked against a JSON Schema as json-osi writes it (`validate
+//! --mode documents`, and the schema half of `gen --revalidate`).
+//!
+//! Validator crates each read the drafts a little differently, and none knows
+//! json-osi's own conventions (tuples written as objects, `x-encoding`). This
+//! one implements exactly the keywords the emitter produces: `type`, `enum`,
+//! `const`, numeric bounds and `multipleOf`, string lengths, `pattern`, the
+//! inferred `format`s, `items` / `prefixItems` and their bounds, `properties`,
+//! `required`, `additionalProperties`, `propertyNames`, `oneOf` / `anyOf` /
+//! `allOf`, `if` / `then` / `else`, local `$ref`s into `$defs`, and JSON held in
+//! strings (`contentSchema`). Annotations (`title`, `description`, `x-…`) are
+//! ignored; a schema using any other keyword is refused up front, so a check
+//! never passes for lack of understanding.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write as _;
+
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+
+use crate::ir::StrFormat;
+use crate::jpath::{JPath, Seg};
+
+/// Keywords that constrain values.
+const ASSERTIONS: [&str; 28] = [
+    "type", "enum", "const", "minimum", "maximum", "exclusiveMinimum", "exclusiveMaximum", "multipleOf",
+    "minLength", "maxLength", "pattern", "format", "items", "prefixItems", "minItems", "maxItems", "properties",
+    "required", "additionalProperties", "propertyNames", "oneOf", "anyOf", "allOf", "if", "then", "else", "$ref",
+    "contentSchema",
+];
+
+/// Keywords that only describe values (as do all `x-` ones).
+const ANNOTATIONS: [&str; 13] = [
+    "$schema", "$id", "$defs", "$comment", "title", "description", "default", "examples", "deprecated",
+    "readOnly", "writeOnly", "contentMediaType", "contentEncoding",
+];
+
+/// Problems listed per check; the rest are only counted.
+pub const MAX_LISTED: usize = 50;
+
+/// One failed keyword.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// Where in the document (list items and map values as `[*]`).
+    pub path: JPath,
+    pub keyword: &'static str,
+    pub message: String,
+}
+
+/// A checker for one schema.
+#[derive(Debug)]
+pub struct Validator<'s> {
+    root: &'s Value,
+    patterns: HashMap<&'s str, Regex>,
+}
+
+impl<'s> Validator<'s> {
+    /// Refuses schemas with keywords outside the emitted subset, patterns that
+    /// don't compile, and `$ref`s that aren't local JSON pointers into `schema`.
+    pub fn new(schema: &'s Value) -> Result<Self, String> {
+        let mut v = Validator { root: schema, patterns: HashMap::new() };
+        v.prepare(schema, "#")?;
+        Ok(v)
+    }
+
+    fn prepare(&mut self, schema: &'s Value, at: &str) -> Result<(), String> {
+        let s = match schema {
+            Value::Bool(_) => return Ok(()),
+            Value::Object(s) => s,
+            _ => return Err(format!("{at}: a schema is an object or a boolean")),
+        };
+        for (k, sub) in s {
+            let here = format!("{at}/{k}");
+            match k.as_str() {
+                "properties" | "$defs" => {
+                    for (name, sub) in sub.as_object().ok_or_else(|| format!("{here}: expected an object"))? {
+                        self.prepare(sub, &format!("{here}/{name}"))?;
+                    }
+                }
+                "prefixItems" | "oneOf" | "anyOf" | "allOf" => {
+                    for (i, sub) in sub.as_array().ok_or_else(|| format!("{here}: expected an array"))?.iter().enumerate() {
+                        self.prepare(sub, &format!("{here}/{i}"))?;
+                    }
+                }
+                "items" | "additionalProperties" | "propertyNames" | "contentSchema" | "if" | "then" | "else" => {
+                    self.prepare(sub, &here)?;
+                }
+                "pattern" => {
+                    let src = sub.as_str().ok_or_else(|| format!("{here}: expected a string"))?;
+                    let rx = Regex::new(src).map_err(|e| format!("{here}: {e}"))?;
+                    self.patterns.insert(src, rx);
+                }
+                "$ref" => {
+                    let target = sub.as_str().ok_or_else(|| format!("{here}: expected a string"))?;
+                    if self.resolve(target).is_none() {
+                        return Err(format!("{here}: `{target}` is not a local reference into this schema"));
+                    }
+                }
+                k if ASSERTIONS.contains(&k) || ANNOTATIONS.contains(&k) || k.starts_with("x-") => {}
+                k => return Err(format!("{at}: unsupported keyword `{k}`")),
+            }
+        }
+        Ok(())
+    }
+
+    /// The subschema a local `$ref` (`#`, `#/$defs/Name`, …) points to.
+    fn resolve(&self, target: &str) -> Option<&'s Value> {
+        let pointer = target.strip_prefix('#')?;
+        let decoded = pointer.split('/').map(percent_decode).collect::<Vec<_>>().join("/");
+        self.root.pointer(&decoded)
+    }
+
+    /// Every keyword `doc` fails, in document order.
+    pub fn validate(&self, doc: &Value) -> Vec<Violation> {
+        let mut out = Vec::new();
+        self.check(doc, self.root, &mut JPath::root(), &mut out);
+        out
+    }
+
+    fn check(&self, v: &Value, schema: &'s Value, at: &mut JPath, out: &mut Vec<Violation>) {
+        let fail = |out: &mut Vec<Violation>, at: &JPath, keyword: &'static str, message: String| {
+            out.push(Violation { path: at.clone(), keyword, message });
+        };
+        let s = match schema {
+            Value::Bool(true) => return,
+            Value::Object(s) => s,
+            _ => return fail(out, at, "false", "no value is allowed here".into()),
+        };
+        if let Some(target) = s.get("$ref").and_then(Value::as_str) {
+            // checked by `new`
+            let sub = self.resolve(target).expect("local $ref");
+            self.check(v, sub, at, out);
+        }
+        // a tuple written as an object keyed by position (`--schema-tuples objects`)
+        let positional = is_positional(s) && v.is_array();
+        if let Some(types) = s.get("type")
+            && !positional
+            && !admits(types, v)
+        {
+            return fail(out, at, "type", format!("expected {}, got {}", type_list(types), describe(v)));
+        }
+        if let Some(values) = s.get("enum").and_then(Value::as_array)
+            && !values.iter().any(|e| same(e, v))
+        {
+            fail(out, at, "enum", format!("{} is not one of the {} value(s)", brief(v), values.len()));
+        }
+        if let Some(c) = s.get("const")
+            && !same(c, v)
+        {
+            fail(out, at, "const", format!("expected {}, got {}", brief(c), brief(v)));
+        }
+
+        match v {
+            Value::Number(x) => self.check_number(x, s, at, out),
+            Value::String(x) => self.check_string(x, s, at, out),
+            Value::Array(xs) if positional => {
+                let keyed: Map<String, Value> = xs.iter().enumerate().map(|(i, x)| (i.to_string(), x.clone())).collect();
+                self.check_object(&keyed, s, at, out);
+            }
+            Value::Array(xs) => self.check_array(xs, s, at, out),
+            Value::Object(m) => self.check_object(m, s, at, out),
+            Value::Null | Value::Bool(_) => {}
+        }
+
+        if let Some(arms) = s.get("allOf").and_then(Value::as_array) {
+            for arm in arms {
+                self.check(v, arm, at, out);
+            }
+        }
+        if let Some(arms) = s.get("anyOf").and_then(Value::as_array) {
+            self.check_arms(v, arms, "anyOf", at, out);
+        }
+        if let Some(arms) = s.get("oneOf").and_then(Value::as_array) {
+            self.check_arms(v, arms, "oneOf", at, out);
+        }
+        if let Some(cond) = s.get("if") {
+            let mut scratch = Vec::new();
+            self.check(v, cond, at, &mut scratch);
+            let branch = if scratch.is_empty() { s.get("then") } else { s.get("else") };
+            if let Some(branch) = branch {
+                self.check(v, branch, at, out);
+            }
+        }
+    }
+
+    /// `anyOf` takes one or more matching arms, `oneOf` exactly one. When none
+    /// matches, the failures of the closest arm meant for the value's kind are
+    /// the ones reported.
+    fn check_arms(&self, v: &Value, arms: &'s [Value], keyword: &'static str, at: &mut JPath, out: &mut Vec<Violation>) {
+        let mut matched = 0;
+        let mut closest: Option<Vec<Violation>> = None;
+        for arm in arms {
+            let mut errs = Vec::new();
+            self.check(v, arm, at, &mut errs);
+            if errs.is_empty() {
+                matched += 1;
+            } else if self.meant_for(arm, v) && closest.as_ref().is_none_or(|c| errs.len() < c.len()) {
+                closest = Some(errs);
+            }
+        }
+        match (matched, closest) {
+            (0, Some(errs)) => out.extend(errs),
+            (0, None) => out.push(Violation {
+                path: at.clone(),
+                keyword,
+                message: format!("no alternative takes {}", describe(v)),
+            }),
+            (n, _) if n > 1 && keyword == "oneOf" => out.push(Violation {
+                path: at.clone(),
+                keyword,
+                message: format!("{} matches {n} alternatives, not one", describe(v)),
+            }),
+            _ => {}
+        }
+    }
+
+    /// Whether `schema` is meant for values of `v`'s kind at all.
+    fn meant_for(&self, schema: &'s Value, v: &Value) -> bool {
+        let Some(s) = schema.as_object() else { return schema == &Value::Bool(true) };
+        if let Some(sub) = s.get("$ref").and_then(Value::as_str).and_then(|t| self.resolve(t))
+            && !self.meant_for(sub, v)
+        {
+            return false;
+        }
+        if s.get("type").is_some_and(|t| !admits(t, v)) && !(is_positional(s) && v.is_array()) {
+            return false;
+        }
+        ["oneOf", "anyOf"].iter().all(|k| match s.get(*k).and_then(Value::as_array) {
+            Some(arms) => arms.iter().any(|a| self.meant_for(a, v)),
+            None => true,
+        })
+    }
+
+    fn check_number(&self, x: &Number, s: &Map<String, Value>, at: &JPath, out: &mut Vec<Violation>) {
+        let mut bound = |keyword: &'static str, ok: fn(Ordering) -> bool, what: &str| {
+            if let Some(b) = s.get(keyword).and_then(Value::as_number)
+                && compare(x, b).is_some_and(|o| !ok(o))
+            {
+                out.push(Violation { path: at.clone(), keyword, message: format!("{x} is {what} {b}") });
+            }
+        };
+        bound("minimum", |o| o != Ordering::Less, "less than");
+        bound("maximum", |o| o != Ordering::Greater, "greater than");
+        bound("exclusiveMinimum", |o| o == Ordering::Greater, "not greater than");
+        bound("exclusiveMaximum", |o| o == Ordering::Less, "not less than");
+        if let Some(m) = s.get("multipleOf").and_then(Value::as_number)
+            && !is_multiple(x, m)
+        {
+            out.push(Violation { path: at.clone(), keyword: "multipleOf", message: format!("{x} is not a multiple of {m}") });
+        }
+    }
+
+    fn check_string(&self, x: &str, s: &Map<String, Value>, at: &mut JPath, out: &mut Vec<Violation>) {
+        let fail = |out: &mut Vec<Violation>, keyword: &'static str, message: String| {
+            out.push(Violation { path: at.clone(), keyword, message });
+        };
+        let len = x.chars().count();
+        if let Some(n) = s.get("minLength").and_then(Value::as_u64)
+            && (len as u64) < n
+        {
+            fail(out, "minLength", format!("{len} character(s), fewer than {n}"));
+        }
+        if let Some(n) = s.get("maxLength").and_then(Value::as_u64)
+            && len as u64 > n
+        {
+            fail(out, "maxLength", format!("{len} character(s), more than {n}"));
+        }
+        if let Some(src) = s.get("pattern").and_then(Value::as_str)
+            && !self.patterns[src].is_match(x)
+        {
+            fail(out, "pattern", format!("{} doesn't match /{src}/", brief(&Value::from(x))));
+        }
+        if let Some(f) = s.get("format").and_then(Value::as_str)
+            && !format_matches(f, x)
+        {
+            fail(out, "format", format!("{} is not a {f}", brief(&Value::from(x))));
+        }
+        if let Some(inner) = s.get("contentSchema")
+            && s.get("contentMediaType").and_then(Value::as_str) == Some("application/json")
+        {
+            // the document's paths are the string's own (see `NTy::JsonString`)
+            match serde_json::from_str::<Value>(x) {
+                Ok(doc) => self.check(&doc, inner, at, out),
+                Err(e) => fail(out, "contentMediaType", format!("not a JSON document: {e}")),
+            }
+        }
+    }
+
+    fn check_array(&self, xs: &[Value], s: &'s Map<String, Value>, at: &mut JPath, out: &mut Vec<Violation>) {
+        if let Some(n) = s.get("minItems").and_then(Value::as_u64)
+            && (xs.len() as u64) < n
+        {
+            out.push(Violation { path: at.clone(), keyword: "minItems", message: format!("{} item(s), fewer than {n}", xs.len()) });
+        }
+        if let Some(n) = s.get("maxItems").and_then(Value::as_u64)
+            && xs.len() as u64 > n
+        {
+            out.push(Violation { path: at.clone(), keyword: "maxItems", message: format!("{} item(s), more than {n}", xs.len()) });
+        }
+        let prefix = s.get("prefixItems").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+        for (i, (x, elem)) in xs.iter().zip(prefix).enumerate() {
+            at.0.push(Seg::Index(i));
+            self.check(x, elem, at, out);
+            at.0.pop();
+        }
+        if let Some(items) = s.get("items") {
+            for (i, x) in xs.iter().enumerate().skip(prefix.len()) {
+                at.0.push(if prefix.is_empty() { Seg::Items } else { Seg::Index(i) });
+                self.check(x, items, at, out);
+                at.0.pop();
+            }
+        }
+    }
+
+    fn check_object(&self, m: &Map<String, Value>, s: &'s Map<String, Value>, at: &mut JPath, out: &mut Vec<Violation>) {
+        let positional = is_positional(s);
+        let seg = |k: &str| match k.parse() {
+            Ok(i) if positional => Seg::Index(i),
+            _ => Seg::Field(k.to_string()),
+        };
+        for k in s.get("required").and_then(Value::as_array).into_iter().flatten().filter_map(Value::as_str) {
+            if !m.contains_key(k) {
+                let what = if positional { format!("position {k}") } else { format!("field `{k}`") };
+                out.push(Violation { path: at.clone(), keyword: "required", message: format!("{what} is missing") });
+            }
+        }
+        let props = s.get("properties").and_then(Value::as_object);
+        for (k, x) in m {
+            match props.and_then(|p| p.get(k)) {
+                Some(sub) => {
+                    at.0.push(seg(k));
+                    self.check(x, sub, at, out);
+                    at.0.pop();
+                }
+                None => match s.get("additionalProperties") {
+                    Some(Value::Bool(false)) => {
+                        let what = if positional { format!("position {k}") } else { format!("field `{k}`") };
+                        out.push(Violation { path: at.clone(), keyword: "additionalProperties", message: format!("{what} is not allowed") });
+                    }
+                    Some(sub) => {
+                        at.0.push(Seg::Items);
+                        self.check(x, sub, at, out);
+                        at.0.pop();
+                    }
+                    None => {}
+                },
+            }
+            // keys are checked as strings at the object's own path
+            if let Some(names) = s.get("propertyNames") {
+                self.check(&Value::from(k.as_str()), names, at, out);
+            }
+        }
+    }
+}
+
+fn is_positional(s: &Map<String, Value>) -> bool {
+    s.get("x-encoding").and_then(Value::as_str) == Some("positional-array")
+}
+
+/// `%xx` escapes of a JSON pointer token in a URI fragment.
+fn percent_decode(token: &str) -> String {
+    let bytes = token.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(std::str::from_utf8(h).ok()?, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Whether a `type` keyword (one name or several) takes `v`.
+fn admits(types: &Value, v: &Value) -> bool {
+    let one = |t: &Value| match t.as_str() {
+        Some("integer") => v.as_number().is_some_and(is_integral),
+        Some(t) => kind_of(v) == t || (t == "number" && v.is_number()),
+        None => false,
+    };
+    match types {
+        Value::Array(ts) => ts.iter().any(one),
+        t => one(t),
+    }
+}
+
+fn type_list(types: &Value) -> String {
+    match types {
+        Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect::<Vec<_>>().join(" or "),
+        t => t.as_str().unwrap_or("?").to_string(),
+    }
+}
+
+fn kind_of(v: &Value) -> &'static str {
+    match v {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if is_integral(n) => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// `1.0` is an integer too.
+fn is_integral(n: &Number) -> bool {
+    n.is_i64() || n.is_u64() || n.as_f64().is_some_and(|x| x.fract() == 0.0)
+}
+
+fn exact(n: &Number) -> Option<i128> {
+    n.as_i64().map(i128::from).or_else(|| n.as_u64().map(i128::from))
+}
+
+/// Numbers compare by value, exactly when both are integers.
+fn compare(a: &Number, b: &Number) -> Option<Ordering> {
+    match (exact(a), exact(b)) {
+        (Some(a), Some(b)) => Some(a.cmp(&b)),
+        _ => a.as_f64()?.partial_cmp(&b.as_f64()?),
+    }
+}
+
+fn is_multiple(x: &Number, m: &Number) -> bool {
+    match (exact(x), exact(m)) {
+        (_, Some(0)) => true,
+        (Some(x), Some(m)) => x % m == 0,
+        _ => {
+            let (Some(x), Some(m)) = (x.as_f64(), m.as_f64()) else { return true };
+            let q = x / m;
+            // decimal steps (`0.01`) are inexact in binary
+            (q - q.round()).abs() <= 1e-9 * q.abs().max(1.0)
+        }
+    }
+}
+
+/// JSON equality, with numbers compared by value (`1` is `1.0`).
+fn same(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => compare(x, y) == Some(Ordering::Equal),
+        (Value::Array(xs), Value::Array(ys)) => xs.len() == ys.len() && xs.iter().zip(ys).all(|(x, y)| same(x, y)),
+        (Value::Object(x), Value::Object(y)) => {
+            x.len() == y.len() && x.iter().all(|(k, v)| y.get(k).is_some_and(|w| same(v, w)))
+        }
+        _ => a == b,
+    }
+}
+
+/// The inferred formats are checked as the generated models check them;
+/// others (`--formats`, `unix-time`, …) only describe.
+fn format_matches(format: &str, s: &str) -> bool {
+    match format {
+        // same schemes as the generated `Uri` check (see `crate::validate`)
+        "uri" => ["http://", "https://", "mailto:", "tel:"].iter().any(|p| s.starts_with(p)),
+        f => StrFormat::ALL.iter().find(|k| k.as_str() == f).is_none_or(|k| k.matches(s)),
+    }
+}
+
+/// `v` in a message: its kind and, for scalars, the value (long strings cut).
+fn describe(v: &Value) -> String {
+    match v {
+        Value::Array(_) | Value::Object(_) => format!("an {}", kind_of(v)),
+        _ => format!("{} {}", kind_of(v), brief(v)),
+    }
+}
+
+fn brief(v: &Value) -> String {
+    const MAX: usize = 40;
+    match v {
+        Value::String(s) if s.chars().count() > MAX => {
+            format!("{}…", Value::from(s.chars().take(MAX).collect::<String>()).to_string().trim_end_matches('"'))
+        }
+        Value::Array(_) => "[…]".into(),
+        Value::Object(_) => "{…}".into(),
+        _ => v.to_string(),
+    }
+}
+
+/// One failing keyword at one path, over every document checked.
+#[derive(Debug, Clone, Serialize)]
+pub struct Problem {
+    pub path: String,
+    pub keyword: &'static str,
+    /// The message of the first failure.
+    pub message: String,
+    /// Documents failing it.
+    pub docs: u64,
+    /// Index (in input order) of the first one.
+    pub first: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Conformance {
+    pub docs: u64,
+    pub failed: u64,
+    /// Most documents first, at most [`MAX_LISTED`].
+    pub problems: Vec<Problem>,
+    /// Problems beyond the listed ones.
+    pub unlisted: usize,
+}
+
+/// Documents checked one at a time, their failures gathered by path and keyword.
+#[derive(Debug)]
+pub struct Checker<'s> {
+    validator: Validator<'s>,
+    docs: u64,
+    failed: u64,
+    found: BTreeMap<(JPath, &'static str), (String, u64, u64)>,
+}
+
+impl<'s> Checker<'s> {
+    pub fn new(schema: &'s Value) -> Result<Self, String> {
+        Ok(Checker { validator: Validator::new(schema)?, docs: 0, failed: 0, found: BTreeMap::new() })
+    }
+
+    pub fn observe(&mut self, doc: &Value) {
+        let errs = self.validator.validate(doc);
+        if !errs.is_empty() {
+            self.failed += 1;
+            // the items of a list share a path: count each document once
+            let mut seen = BTreeSet::new();
+            for e in errs {
+                if seen.insert((e.path.clone(), e.keyword)) {
+                    self.found.entry((e.path, e.keyword)).or_insert((e.message, 0, self.docs)).1 += 1;
+                }
+            }
+        }
+        self.docs += 1;
+    }
+
+    pub fn finish(self) -> Conformance {
+        let mut problems: Vec<Problem> = self.found
+            .into_iter()
+            .map(|((path, keyword), (message, docs, first))| Problem { path: path.to_string(), keyword, message, docs, first })
+            .collect();
+        problems.sort_by(|a, b| b.docs.cmp(&a.docs).then_with(|| a.path.cmp(&b.path)));
+        let unlisted = problems.len().saturating_sub(MAX_LISTED);
+        problems.truncate(MAX_LISTED);
+        Conformance { docs: self.docs, failed: self.failed, problems, unlisted }
+    }
+}
+
+pub fn render(c: &Conformance) -> String {
+    let mut s = String::new();
+    let _ = writeln!(s, "documents: {} checked, {} fail", c.docs, c.failed);
+    for p in &c.problems {
+        let _ = writeln!(s, "\n{}: {} ({} doc(s), first: document {})", p.path, p.keyword, p.docs, p.first + 1);
+        let _ = writeln!(s, "  {}", p.message);
+    }
+    if c.unlisted > 0 {
+        let _ = writeln!(s, "\n… and {} more problem(s)", c.unlisted);
+    }
+    s
+}
@@ -0,0 +1,341 @@
+//! Inference quality against a reference schema (`json-osi score --truth`).
+//!
+//! Both schemas are flattened to one slot per path (`$.a`, `$.xs[*]`, `$[0]`),
+//! following local `$ref`s and merging `oneOf` / `anyOf` / `allOf` arms into
+//! the path they sit at. Scores:
+//! - paths: precision / recall of the inferred paths against the reference
+//! - kinds and required-ness: agreement on the paths both have
+//! - tightness: per constraint (numeric range, item count, string enum), whether
+//!   the inferred one is equal to, tighter than, looser than, or at odds with
+//!   the reference (absent means unconstrained)
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::jpath::{JPath, Seg};
+
+/// What a schema says about one path.
+#[derive(Debug, Clone, Default)]
+struct Slot {
+    kinds: BTreeSet<String>,
+    /// Only for object fields.
+    required: Option<bool>,
+    /// Allowed values (JSON text), if enumerated.
+    enum_: Option<BTreeSet<String>>,
+    min: Option<f64>,
+    max: Option<f64>,
+    min_items: Option<f64>,
+    max_items: Option<f64>,
+    /// Whether a numeric / array arm has been merged in yet (bounds start from it).
+    numeric: bool,
+    array: bool,
+}
+
+type Slots = BTreeMap<JPath, Slot>;
+
+fn kind_of(v: &Value) -> &'static str {
+    match v {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Widen a bound to cover another arm's (`None` is unbounded); the first arm sets it.
+fn widen(into: &mut Option<f64>, seen: bool, b: Option<f64>, pick: fn(f64, f64) -> f64) {
+    *into = match (seen, *into, b) {
+        (false, _, b) => b,
+        (true, Some(a), Some(b)) => Some(pick(a, b)),
+        _ => None,
+    };
+}
+
+struct Flatten<'a> {
+    root: &'a Value,
+    out: Slots,
+    /// `$ref`s being expanded, to stop on recursive definitions.
+    refs: Vec<String>,
+}
+
+impl Flatten<'_> {
+    fn walk(&mut self, s: &Value, at: &mut JPath, required: Option<bool>) {
+        let Value::Object(m) = s else { return };
+        if let Some(r) = m.get("$ref").and_then(Value::as_str) {
+            if self.refs.iter().any(|x| x == r) {
+                return;
+            }
+            if let Some(target) = r.strip_prefix('#').and_then(|p| self.root.pointer(p)) {
+                self.refs.push(r.to_string());
+                self.walk(target, at, required);
+                self.refs.pop();
+            }
+        }
+        for key in ["oneOf", "anyOf", "allOf"] {
+            for arm in m.get(key).and_then(Value::as_array).into_iter().flatten() {
+                self.walk(arm, at, required);
+            }
+        }
+
+        let slot = self.out.entry(at.clone()).or_default();
+        if let Some(r) = required {
+            slot.required = Some(slot.required.unwrap_or(false) || r);
+        }
+        let mut kinds: Vec<String> = match m.get("type") {
+            Some(Value::String(t)) => vec![t.clone()],
+            Some(Value::Array(ts)) => ts.iter().filter_map(Value::as_str).map(str::to_string).collect(),
+            _ => Vec::new(),
+        };
+        let values: Option<Vec<&Value>> = match (m.get("enum"), m.get("const")) {
+            (Some(Value::Array(xs)), _) => Some(xs.iter().collect()),
+            (_, Some(c)) => Some(vec![c]),
+            _ => None,
+        };
+        if let Some(xs) = &values {
+            if kinds.is_empty() {
+                kinds = xs.iter().map(|x| kind_of(x).to_string()).collect();
+            }
+            // strings only: that's what the inferred schema enumerates
+            let strs: BTreeSet<String> = xs.iter().filter(|x| x.is_string()).map(|x| x.to_string()).collect();
+            if !strs.is_empty() {
+                slot.enum_.get_or_insert_with(BTreeSet::new).extend(strs);
+            }
+        }
+        // bounds only count from arms of their own kind (a `null` arm doesn't unbound a range)
+        let num = |k: &str| m.get(k).and_then(Value::as_f64);
+        if kinds.iter().any(|k| k == "integer" || k == "number") {
+            widen(&mut slot.min, slot.numeric, num("minimum").or(num("exclusiveMinimum")), f64::min);
+            widen(&mut slot.max, slot.numeric, num("maximum").or(num("exclusiveMaximum")), f64::max);
+            slot.numeric = true;
+        }
+        if kinds.iter().any(|k| k == "array") {
+            widen(&mut slot.min_items, slot.array, num("minItems"), f64::min);
+            widen(&mut slot.max_items, slot.array, num("maxItems"), f64::max);
+            slot.array = true;
+        }
+        slot.kinds.extend(kinds);
+
+        if let Some(Value::Object(props)) = m.get("properties") {
+            let req: BTreeSet<&str> = m
+                .get("required")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .collect();
+            for (k, v) in props {
+                at.0.push(Seg::Field(k.clone()));
+                self.walk(v, at, Some(req.contains(k.as_str())));
+                at.0.pop();
+            }
+        }
+        // `prefixItems` (2020-12) or `items: [...]` (draft 4–7) for tuples
+        let tuple = m.get("prefixItems").or(m.get("items").filter(|i| i.is_array()));
+        for (i, e) in tuple.and_then(Value::as_array).into_iter().flatten().enumerate() {
+            at.0.push(Seg::Index(i));
+            self.walk(e, at, None);
+            at.0.pop();
+        }
+        if let Some(item) = m.get("items").filter(|i| i.is_object()) {
+            at.0.push(Seg::Items);
+            self.walk(item, at, None);
+            at.0.pop();
+        }
+    }
+}
+
+fn flatten(schema: &Value) -> Slots {
+    let mut f = Flatten { root: schema, out: Slots::new(), refs: Vec::new() };
+    f.walk(schema, &mut JPath::root(), None);
+    f.out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fit {
+    Equal,
+    Tighter,
+    Looser,
+    /// Neither contains the other.
+    Conflicting,
+}
+
+/// Inferred bounds against reference bounds (`None` is unbounded).
+fn fit_range(inf: (Option<f64>, Option<f64>), truth: (Option<f64>, Option<f64>)) -> Fit {
+    // does `a` admit everything `b` does?
+    let covers = |a: (Option<f64>, Option<f64>), b: (Option<f64>, Option<f64>)| {
+        let lo = match (a.0, b.0) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(x), Some(y)) => x <= y,
+        };
+        let hi = match (a.1, b.1) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(x), Some(y)) => x >= y,
+        };
+        lo && hi
+    };
+    match (covers(truth, inf), covers(inf, truth)) {
+        (true, true) => Fit::Equal,
+        (true, false) => Fit::Tighter,
+        (false, true) => Fit::Looser,
+        (false, false) => Fit::Conflicting,
+    }
+}
+
+fn fit_enum(inf: Option<&BTreeSet<String>>, truth: Option<&BTreeSet<String>>) -> Fit {
+    match (inf, truth) {
+        (None, None) => Fit::Equal,
+        (Some(_), None) => Fit::Tighter,
+        (None, Some(_)) => Fit::Looser,
+        (Some(a), Some(b)) if a == b => Fit::Equal,
+        (Some(a), Some(b)) if a.is_subset(b) => Fit::Tighter,
+        (Some(a), Some(b)) if a.is_superset(b) => Fit::Looser,
+        _ => Fit::Conflicting,
+    }
+}
+
+/// How inferred constraints of one kind compare to the reference, on shared paths.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Tightness {
+    pub equal: usize,
+    pub tighter: usize,
+    pub looser: usize,
+    pub conflicting: usize,
+}
+
+impl Tightness {
+    fn add(&mut self, f: Fit) {
+        match f {
+            Fit::Equal => self.equal += 1,
+            Fit::Tighter => self.tighter += 1,
+            Fit::Looser => self.looser += 1,
+            Fit::Conflicting => self.conflicting += 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Mismatch {
+    pub path: String,
+    pub inferred: String,
+    pub truth: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Score {
+    pub inferred_paths: usize,
+    pub truth_paths: usize,
+    pub shared_paths: usize,
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+    /// Shared paths whose kinds (including `null`) are the same.
+    pub kinds_agree: usize,
+    /// Shared object fields, and those whose required-ness agrees.
+    pub fields: usize,
+    pub required_agree: usize,
+    pub ranges: Tightness,
+    pub item_counts: Tightness,
+    pub enums: Tightness,
+    /// Reference paths never inferred.
+    pub missing: Vec<String>,
+    /// Inferred paths the reference doesn't have.
+    pub extra: Vec<String>,
+    pub kind_mismatches: Vec<Mismatch>,
+    pub required_mismatches: Vec<Mismatch>,
+}
+
+fn ratio(a: usize, b: usize) -> f64 {
+    if b == 0 { 1.0 } else { a as f64 / b as f64 }
+}
+
+pub fn score(inferred: &Value, truth: &Value) -> Score {
+    let inf = flatten(inferred);
+    let tru = flatten(truth);
+    let mut s = Score { inferred_paths: inf.len(), truth_paths: tru.len(), ..Score::default() };
+    let list = |k: &BTreeSet<String>| k.iter().cloned().collect::<Vec<_>>().join("|");
+    for (path, t) in &tru {
+        let Some(i) = inf.get(path) else {
+            s.missing.push(path.to_string());
+            continue;
+        };
+        s.shared_paths += 1;
+        if i.kinds == t.kinds {
+            s.kinds_agree += 1;
+        } else {
+            s.kind_mismatches.push(Mismatch { path: path.to_string(), inferred: list(&i.kinds), truth: list(&t.kinds) });
+        }
+        if let (Some(ri), Some(rt)) = (i.required, t.required) {
+            s.fields += 1;
+            if ri == rt {
+                s.required_agree += 1;
+            } else {
+                let word = |r: bool| if r { "required" } else { "optional" }.to_string();
+                s.required_mismatches.push(Mismatch { path: path.to_string(), inferred: word(ri), truth: word(rt) });
+            }
+        }
+        let has = |x: &Slot, k: &str| x.kinds.contains(k);
+        if (has(t, "integer") || has(t, "number")) && (has(i, "integer") || has(i, "number")) {
+            s.ranges.add(fit_range((i.min, i.max), (t.min, t.max)));
+        }
+        if has(t, "array") && has(i, "array") {
+            s.item_counts.add(fit_range((i.min_items, i.max_items), (t.min_items, t.max_items)));
+        }
+        if has(t, "string") && has(i, "string") {
+            s.enums.add(fit_enum(i.enum_.as_ref(), t.enum_.as_ref()));
+        }
+    }
+    s.extra = inf.keys().filter(|p| !tru.contains_key(*p)).map(|p| p.to_string()).collect();
+    s.precision = ratio(s.shared_paths, s.inferred_paths);
+    s.recall = ratio(s.shared_paths, s.truth_paths);
+    s.f1 = if s.precision + s.recall > 0.0 { 2.0 * s.precision * s.recall / (s.precision + s.recall) } else { 0.0 };
+    s
+}
+
+/// Paths listed per section before eliding the rest.
+const MAX_LISTED: usize = 20;
+
+impl Score {
+    pub fn render(&self) -> String {
+        let mut s = String::new();
+        let _ = writeln!(
+            s,
+            "paths: {} inferred, {} reference, {} shared — precision {:.3}, recall {:.3}, F1 {:.3}",
+            self.inferred_paths, self.truth_paths, self.shared_paths, self.precision, self.recall, self.f1
+        );
+        let _ = writeln!(s, "kinds agree: {} of {} shared paths", self.kinds_agree, self.shared_paths);
+        let _ = writeln!(s, "required agrees: {} of {} shared fields", self.required_agree, self.fields);
+        for (name, t) in [("numeric ranges", &self.ranges), ("item counts", &self.item_counts), ("string enums", &self.enums)] {
+            let _ = writeln!(
+                s,
+                "{name}: {} equal, {} tighter, {} looser, {} conflicting",
+                t.equal, t.tighter, t.looser, t.conflicting
+            );
+        }
+        let mut section = |title: &str, lines: Vec<String>| {
+            if lines.is_empty() {
+                return;
+            }
+            let _ = writeln!(s, "\n{title} ({}):", lines.len());
+            for l in lines.iter().take(MAX_LISTED) {
+                let _ = writeln!(s, "  {l}");
+            }
+            if lines.len() > MAX_LISTED {
+                let _ = writeln!(s, "  … and {} more", lines.len() - MAX_LISTED);
+            }
+        };
+        section("missing (in reference only)", self.missing.clone());
+        section("extra (inferred only)", self.extra.clone());
+        let mism = |xs: &[Mismatch]| xs.iter().map(|m| format!("{}: inferred {}, reference {}", m.path, m.inferred, m.truth)).collect();
+        section("kind mismatches", mism(&self.kind_mismatches));
+        section("required mismatches", mism(&self.required_mismatches));
+        s
+    }
+}
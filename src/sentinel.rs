@@ -0,0 +1,376 @@
+//! Sentinel values: markers that stand in for "missing" inside otherwise
+//! ordinary data (`--sentinels`).
+//!
+//! Two shapes are detected:
+//! - numeric: a sentinel-shaped integer (`-1`, `999999`, `i32::MAX`, …) sitting
+//!   apart from the rest of its slot (see [`crate::distribution`])
+//! - string: a missing-value word (`"N/A"`, `"none"`, `"-"`, …) next to real data,
+//!   possibly in a slot that is otherwise numeric
+//!
+//! Detection always lands in the report. The policy decides the Rust models:
+//! `keep` leaves types alone, `null` reads markers as `None`, `enum` gives each
+//! marker its own variant next to `Value(…)`. JSON Schema is unaffected, since
+//! the markers are valid JSON of the observed kinds.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::distribution::{paths_where, NumericSummary, MAX_POSITIONS, MAX_SLOTS};
+use crate::inference::{FieldC, U};
+use crate::ir::Ty;
+use crate::jpath::{JPath, Seg};
+
+/// Strings (compared trimmed, case-insensitively) that commonly mean "no value".
+const MISSING_WORDS: &[&str] = &[
+    "", "-", "--", "?", "n/a", "na", "#n/a", "none", "null", "nil", "nan",
+    "undefined", "unknown", "missing", "not available", "tbd",
+];
+
+fn missing_word(s: &str) -> bool {
+    let t = s.trim().to_lowercase();
+    MISSING_WORDS.contains(&t.as_str())
+}
+
+/// A marker value, as it appears in the JSON.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Mark {
+    Int(i64),
+    Str(String),
+}
+
+impl std::fmt::Display for Mark {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mark::Int(n) => write!(f, "{n}"),
+            Mark::Str(s) => write!(f, "{}", Value::from(s.as_str())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SentinelPolicy {
+    /// Report only; markers stay part of the value's type.
+    #[default]
+    Keep,
+    /// Markers (and null) deserialize as `None`.
+    Null,
+    /// Each marker is an enum variant next to `Value(…)`.
+    Enum,
+}
+
+impl SentinelPolicy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SentinelPolicy::Keep => "keep",
+            SentinelPolicy::Null => "null",
+            SentinelPolicy::Enum => "enum",
+        }
+    }
+}
+
+/// One marker found at one path.
+#[derive(Debug, Clone)]
+pub struct Sentinel {
+    pub path: JPath,
+    pub mark: Mark,
+    pub count: u64,
+    /// Non-null values at the path (numbers and strings).
+    pub total: u64,
+    /// Every string at the path is a marker, so the string arm of a mixed slot
+    /// exists only because of them.
+    pub only_strings: bool,
+}
+
+// --------------------------------- tracking --------------------------------- //
+
+#[derive(Debug, Clone, Default)]
+struct StrSlot {
+    strings: u64,
+    /// Exact counts of missing-value words.
+    marks: BTreeMap<String, u64>,
+}
+
+/// Missing-value words per string slot; numbers come from [`crate::distribution::NumericStats`].
+#[derive(Debug, Clone, Default)]
+pub struct MarkerStats {
+    slots: BTreeMap<JPath, StrSlot>,
+}
+
+impl MarkerStats {
+    pub fn observe(&mut self, v: &Value) {
+        self.walk(v, &mut JPath::root());
+    }
+
+    fn walk(&mut self, v: &Value, at: &mut JPath) {
+        match v {
+            Value::String(s) => {
+                if !self.slots.contains_key(at) {
+                    if self.slots.len() >= MAX_SLOTS {
+                        return;
+                    }
+                    self.slots.insert(at.clone(), StrSlot::default());
+                }
+                let slot = self.slots.get_mut(at).expect("inserted above");
+                slot.strings += 1;
+                if missing_word(s) {
+                    *slot.marks.entry(s.clone()).or_default() += 1;
+                }
+            }
+            Value::Array(xs) => {
+                for (i, x) in xs.iter().enumerate() {
+                    at.0.push(Seg::Items);
+                    self.walk(x, at);
+                    at.0.pop();
+                    if i < MAX_POSITIONS {
+                        at.0.push(Seg::Index(i));
+                        self.walk(x, at);
+                        at.0.pop();
+                    }
+                }
+            }
+            Value::Object(m) => {
+                for (k, x) in m {
+                    at.0.push(Seg::Field(k.clone()));
+                    self.walk(x, at);
+                    at.0.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn join(mut a: Self, b: Self) -> Self {
+        for (p, sb) in b.slots {
+            if !a.slots.contains_key(&p) && a.slots.len() >= MAX_SLOTS {
+                continue;
+            }
+            let sa = a.slots.entry(p).or_default();
+            sa.strings += sb.strings;
+            for (v, c) in sb.marks {
+                *sa.marks.entry(v).or_default() += c;
+            }
+        }
+        a
+    }
+}
+
+/// Sentinels at the paths of `ty`, in path order: the numeric candidates from
+/// `numbers` plus missing-value words that sit next to real data.
+pub fn detect(numbers: &[NumericSummary], markers: MarkerStats, ty: &Ty) -> Vec<Sentinel> {
+    let mut counts: BTreeMap<JPath, u64> = BTreeMap::new();
+    let mut out = Vec::new();
+    for n in numbers {
+        let path = n.path.parse::<JPath>().expect("summary paths come from JPath");
+        counts.insert(path.clone(), n.count);
+        for &(v, c) in &n.sentinels {
+            out.push(Sentinel { path: path.clone(), mark: Mark::Int(v), count: c, total: n.count, only_strings: false });
+        }
+    }
+    let mut slots = markers.slots;
+    for path in paths_where(ty, |t| matches!(t, Ty::String { .. })) {
+        let Some(slot) = slots.remove(&path) else { continue };
+        let marked: u64 = slot.marks.values().sum();
+        let total = slot.strings + counts.get(&path).copied().unwrap_or(0);
+        // a slot of nothing but markers has no data to tell them apart from
+        if marked == 0 || marked == total {
+            continue;
+        }
+        for (s, c) in slot.marks {
+            out.push(Sentinel { path: path.clone(), mark: Mark::Str(s), count: c, total, only_strings: marked == slot.strings });
+        }
+    }
+    out.sort_by(|a, b| (&a.path, &a.mark).cmp(&(&b.path, &b.mark)));
+    out
+}
+
+// ------------------------------- empty strings ------------------------------- //
+
+/// Every slot of `u` that held `""`, as a marker, and whether `""` looks like
+/// this data's way of writing null there: an object field where it sits next to
+/// real values while a sibling field holds actual nulls. `counted_as_null`: the
+/// evidence already left `""` out of the non-null counts (`--empty-strings-as-null`).
+pub fn empty_strings(u: &U, counted_as_null: bool) -> Vec<(Sentinel, bool)> {
+    fn walk(u: &U, total: u64, likely: bool, at: &mut JPath, counted_as_null: bool, out: &mut Vec<(Sentinel, bool)>) {
+        if let Some(s) = &u.str_ {
+            if s.empties > 0 {
+                let only_strings = s.lits.len() == 1 && s.lits.contains("");
+                let total = total.max(s.empties);
+                let sentinel = Sentinel { path: at.clone(), mark: Mark::Str(String::new()), count: s.empties, total, only_strings };
+                out.push((sentinel, likely && total > s.empties));
+            }
+            if let Some(doc) = &s.json {
+                walk(doc, 0, false, at, counted_as_null, out);
+            }
+        }
+        if let Some(arr) = &u.arr {
+            at.0.push(Seg::Items);
+            walk(&arr.item, 0, false, at, counted_as_null, out);
+            at.0.pop();
+            for (i, col) in arr.cols.iter().enumerate().take(MAX_POSITIONS) {
+                at.0.push(Seg::Index(i));
+                walk(col, 0, false, at, counted_as_null, out);
+                at.0.pop();
+            }
+        }
+        if let Some(obj) = &u.obj {
+            let has_nulls = |f: &FieldC| {
+                let gaps = f.present_in - f.non_null_in;
+                (if counted_as_null { gaps.saturating_sub(empties(f)) } else { gaps }) > 0
+            };
+            let with_nulls = obj.fields.values().filter(|f| has_nulls(f)).count();
+            for (k, f) in &obj.fields {
+                let sibling_nulls = with_nulls > usize::from(has_nulls(f));
+                let values = f.non_null_in + if counted_as_null { empties(f) } else { 0 };
+                at.0.push(Seg::Field(k.clone()));
+                walk(&f.ty, values, sibling_nulls, at, counted_as_null, out);
+                at.0.pop();
+            }
+        }
+    }
+    fn empties(f: &FieldC) -> u64 {
+        f.ty.str_.as_ref().map_or(0, |s| s.empties)
+    }
+    let mut out = Vec::new();
+    walk(u, 0, false, &mut JPath::root(), counted_as_null, &mut out);
+    out
+}
+
+// --------------------------------- modeling --------------------------------- //
+
+/// Where and how sentinels are modeled in the Rust types (recorded in artifact
+/// headers, since the markers aren't part of the evidence).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SentinelModel {
+    pub policy: SentinelPolicy,
+    /// Path → markers at it.
+    pub sites: BTreeMap<String, Site>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Site {
+    pub marks: Vec<Mark>,
+    /// Drop the string arm at this path: it only ever held markers.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub only_strings: bool,
+}
+
+impl SentinelModel {
+    /// Model every detected sentinel under `policy` (`keep` models nothing).
+    pub fn new(policy: SentinelPolicy, found: &[Sentinel]) -> Self {
+        let mut sites: BTreeMap<String, Site> = BTreeMap::new();
+        if policy != SentinelPolicy::Keep {
+            for s in found {
+                let site = sites.entry(s.path.to_string()).or_default();
+                site.marks.push(s.mark.clone());
+                site.only_strings |= s.only_strings;
+            }
+        }
+        SentinelModel { policy, sites }
+    }
+
+    /// Also read `""` as `None` at each of `found` (`--empty-strings-as-null`).
+    /// Only the `null` policy can: under `keep` it takes over for these sites alone.
+    pub fn with_empty_strings(mut self, found: &[Sentinel]) -> Self {
+        debug_assert!(self.policy != SentinelPolicy::Enum, "rejected by the CLI");
+        if found.is_empty() {
+            return self;
+        }
+        self.policy = SentinelPolicy::Null;
+        for s in found {
+            let site = self.sites.entry(s.path.to_string()).or_default();
+            if !site.marks.contains(&s.mark) {
+                site.marks.push(s.mark.clone());
+            }
+            site.only_strings |= s.only_strings;
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sites.is_empty()
+    }
+}
+
+/// `ty` with a [`Ty::Sentinel`] at every modeled path.
+pub fn model(ty: &Ty, m: &SentinelModel) -> Ty {
+    if m.is_empty() {
+        return ty.clone();
+    }
+    rewrite(ty, &mut JPath::root(), m)
+}
+
+fn rewrite(t: &Ty, at: &mut JPath, m: &SentinelModel) -> Ty {
+    let t = descend(t, at, m);
+    match m.sites.get(&at.to_string()) {
+        Some(site) => wrap(t, site, m.policy),
+        None => t,
+    }
+}
+
+/// Rewrite below `t`; nullables and union arms share `t`'s path, so they are
+/// descended through without being wrapped themselves.
+fn descend(t: &Ty, at: &mut JPath, m: &SentinelModel) -> Ty {
+    fn child(seg: Seg, t: &Ty, at: &mut JPath, m: &SentinelModel) -> Ty {
+        at.0.push(seg);
+        let out = rewrite(t, at, m);
+        at.0.pop();
+        out
+    }
+    match t {
+        Ty::Nullable(inner) => Ty::Nullable(Box::new(descend(inner, at, m))),
+        Ty::OneOf(arms) => Ty::OneOf(arms.iter().map(|a| descend(a, at, m)).collect()),
+        Ty::Tagged { tag, variants, stats } => Ty::Tagged {
+            tag: tag.clone(),
+            variants: variants.iter().map(|(value, v)| (value.clone(), descend(v, at, m))).collect(),
+            stats: *stats,
+        },
+        Ty::ArrayList { item, min_items, max_items, stats } => Ty::ArrayList {
+            item: Box::new(child(Seg::Items, item, at, m)),
+            min_items: *min_items,
+            max_items: *max_items,
+            stats: *stats,
+        },
+        Ty::Map { key, value, stats } => {
+            Ty::Map { key: key.clone(), value: Box::new(child(Seg::Items, value, at, m)), stats: *stats }
+        }
+        Ty::ArrayTuple { elems, min_items, max_items, stats } => Ty::ArrayTuple {
+            elems: elems.iter().enumerate().map(|(i, e)| child(Seg::Index(i), e, at, m)).collect(),
+            min_items: *min_items,
+            max_items: *max_items,
+            stats: *stats,
+        },
+        Ty::Object { fields, stats } => Ty::Object {
+            fields: fields
+                .iter()
+                .map(|f| crate::ir::Field {
+                    name: f.name.clone(),
+                    ty: child(Seg::Field(f.name.clone()), &f.ty, at, m),
+                    required: f.required,
+                    present: f.present,
+                    support: f.support,
+                })
+                .collect(),
+            stats: *stats,
+        },
+        _ => t.clone(),
+    }
+}
+
+fn wrap(t: Ty, site: &Site, policy: SentinelPolicy) -> Ty {
+    let (nullable, mut core) = match t {
+        Ty::Nullable(inner) => (true, *inner),
+        t => (false, t),
+    };
+    if site.only_strings && let Ty::OneOf(arms) = core {
+        let mut arms: Vec<Ty> = arms.into_iter().filter(|a| !matches!(a, Ty::String { .. })).collect();
+        core = if arms.len() == 1 { arms.remove(0) } else { Ty::OneOf(arms) };
+    }
+    let as_null = policy == SentinelPolicy::Null;
+    let s = Ty::Sentinel { inner: Box::new(core), marks: site.marks.clone(), as_null };
+    // `as_null` already reads null as `None`
+    if nullable && !as_null { Ty::Nullable(Box::new(s)) } else { s }
+}
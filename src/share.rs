@@ -0,0 +1,199 @@
+//! Types shared between paths (`gen --share-types`).
+//!
+//! The same record often turns up at many paths: a billing and a shipping
+//! address, the author of a post and of each of its comments. Each occurrence
+//! would be a type of its own. [`share`] hash-conses the normalized IR: records,
+//! tuples and tagged unions are keyed by structure (ignoring enum frequencies,
+//! evidence counts and examples), and each that occurs at two or more paths becomes one
+//! named definition. [`share_schema`] moves those into `$defs`, every occurrence
+//! a `$ref`; the Rust emitter emits one struct per definition under the same name
+//! (see [`crate::codegen::Codegen::with_shared`]).
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::Value;
+
+use crate::codegen::to_type_name;
+use crate::jpath::{JPath, Seg};
+use crate::norm_ir::NTy;
+
+/// The shared definitions of a type, outermost first.
+#[derive(Debug, Clone, Default)]
+pub struct Shared {
+    /// Definition name and the paths holding it, in document order.
+    pub defs: Vec<(String, Vec<JPath>)>,
+}
+
+impl Shared {
+    /// Path → definition name, for every occurrence.
+    pub fn names(&self) -> BTreeMap<String, String> {
+        self.defs
+            .iter()
+            .flat_map(|(name, paths)| paths.iter().map(move |p| (p.to_string(), name.clone())))
+            .collect()
+    }
+
+    /// Occurrences of all definitions.
+    pub fn occurrences(&self) -> usize {
+        self.defs.iter().map(|(_, paths)| paths.len()).sum()
+    }
+}
+
+/// The compound types of `n` found at two or more paths. A type only found
+/// inside the occurrences of a larger shared one is shared through that one.
+/// Names don't clash with each other or with `root`.
+pub fn share(n: &NTy, root: &str) -> Shared {
+    let mut found: BTreeMap<String, Vec<JPath>> = BTreeMap::new();
+    occurrences(n, &mut JPath::root(), &mut found);
+
+    // larger types first: they hold the smaller ones
+    let mut groups: Vec<(String, Vec<JPath>)> = found.into_iter().filter(|(_, paths)| paths.len() > 1).collect();
+    groups.sort_by(|(a, pa), (b, pb)| b.len().cmp(&a.len()).then_with(|| pa[0].to_string().cmp(&pb[0].to_string())));
+
+    let mut used: BTreeSet<String> = [root.to_string()].into();
+    let mut covered: Vec<JPath> = Vec::new();
+    let mut out = Shared::default();
+    for (_, paths) in groups {
+        let inside = |p: &JPath| covered.iter().any(|c| p.segs().len() > c.segs().len() && p.segs().starts_with(c.segs()));
+        let live: Vec<&JPath> = paths.iter().filter(|p| !inside(p)).collect();
+        if live.len() < 2 {
+            continue;
+        }
+        let first = live[0].clone();
+        covered.extend(paths.iter().filter(|p| **p != first).cloned());
+        let base = def_name(&first);
+        let name = (1..).map(|i| if i == 1 { base.clone() } else { format!("{base}{i}") }).find(|n| !used.contains(n)).unwrap();
+        used.insert(name.clone());
+        out.defs.push((name, paths));
+    }
+    out
+}
+
+/// Every record, tuple and tagged union below the root, by structural key.
+/// Union arms and tagged variants aren't looked into: their paths are ambiguous.
+fn occurrences(n: &NTy, at: &mut JPath, out: &mut BTreeMap<String, Vec<JPath>>) {
+    let mut below = |seg: Seg, n: &NTy, out: &mut BTreeMap<String, Vec<JPath>>| {
+        at.0.push(seg);
+        occurrences(n, at, out);
+        at.0.pop();
+    };
+    let compound = match n {
+        NTy::Object { fields, .. } => {
+            for f in fields {
+                below(Seg::Field(f.name.clone()), &f.ty, out);
+            }
+            !fields.is_empty()
+        }
+        NTy::ArrayTuple { elems, .. } => {
+            for (i, e) in elems.iter().enumerate() {
+                below(Seg::Index(i), e, out);
+            }
+            true
+        }
+        NTy::Tagged { .. } => true,
+        NTy::ArrayList { item: inner, .. } | NTy::Map { value: inner, .. } => {
+            below(Seg::Items, inner, out);
+            false
+        }
+        NTy::Nullable(inner) | NTy::JsonString(inner) => {
+            occurrences(inner, at, out);
+            false
+        }
+        _ => false,
+    };
+    if compound && !at.segs().is_empty() {
+        out.entry(structural_key(n)).or_default().push(at.clone());
+    }
+}
+
+/// `n` serialized without enum frequencies, evidence counts and examples.
+fn structural_key(n: &NTy) -> String {
+    fn uncounted(v: &mut Value) {
+        match v {
+            Value::Array(xs) => xs.iter_mut().for_each(uncounted),
+            Value::Object(o) => {
+                o.remove("enum_counts");
+                o.remove("support");
+                o.remove("stats");
+                o.remove("examples");
+                o.values_mut().for_each(uncounted);
+            }
+            _ => {}
+        }
+    }
+    let mut v = serde_json::to_value(n).expect("IR serializes");
+    uncounted(&mut v);
+    v.to_string()
+}
+
+/// A type name for the slot at `path`: its field, `Item` for list items, the
+/// position for tuple elements (`$.orders[*]` → `OrdersItem`).
+fn def_name(path: &JPath) -> String {
+    let mut suffix = String::new();
+    for seg in path.segs().iter().rev() {
+        match seg {
+            Seg::Field(k) => return to_type_name(&format!("{k}_{suffix}")),
+            Seg::Items => suffix = format!("item_{suffix}"),
+            Seg::Index(i) => suffix = format!("{i}_{suffix}"),
+        }
+    }
+    to_type_name(&suffix)
+}
+
+/// Move each shared type into `$defs`, its occurrences becoming `$ref`s. An
+/// occurrence annotated differently from the first (a description, a title)
+/// stays inline; differing enum frequencies, evidence counts or examples are
+/// dropped from the definition. Run it after the passes that locate nodes by path.
+pub fn share_schema(schema: &mut Value, shared: &Shared) {
+    let mut defs = serde_json::Map::new();
+    // innermost first, so the occurrences of outer types hold the same `$ref`s
+    for (name, paths) in shared.defs.iter().rev() {
+        let nodes: Vec<Value> = paths
+            .iter()
+            .filter_map(|p| crate::norm_ir::schema_at(schema, p.segs()).map(|n| crate::norm_ir::type_node(n).clone()))
+            .collect();
+        if nodes.len() != paths.len() {
+            continue;
+        }
+        let bare: Vec<Value> = nodes.iter().map(|n| uncounted(n.clone())).collect();
+        let same: Vec<usize> = (0..nodes.len()).filter(|&i| bare[i] == bare[0]).collect();
+        if same.len() < 2 {
+            continue;
+        }
+        let def = if same.iter().all(|&i| nodes[i] == nodes[0]) { nodes[0].clone() } else { bare[0].clone() };
+        let reference = serde_json::json!({ "$ref": format!("#/$defs/{name}") });
+        for &i in &same {
+            if let Some(node) = crate::norm_ir::schema_at(schema, paths[i].segs()) {
+                *crate::norm_ir::type_node(node) = reference.clone();
+            }
+        }
+        defs.insert(name.clone(), def);
+    }
+    if !defs.is_empty() && let Some(root) = schema.as_object_mut() {
+        root.insert("$defs".into(), Value::Object(defs));
+    }
+}
+
+/// A subschema without `x-frequency`, `x-evidence`, `x-stats` and `examples` (property names are kept).
+fn uncounted(mut v: Value) -> Value {
+    fn strip(v: &mut Value) {
+        match v {
+            Value::Array(xs) => xs.iter_mut().for_each(strip),
+            Value::Object(o) => {
+                o.remove("x-frequency");
+                o.remove("x-evidence");
+                o.remove("x-stats");
+                o.remove("examples");
+                for (k, child) in o.iter_mut() {
+                    match (k.as_str(), child) {
+                        ("properties", Value::Object(props)) => props.values_mut().for_each(strip),
+                        (_, child) => strip(child),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    strip(&mut v);
+    v
+}
@@ -0,0 +1,233 @@
+//! Record size estimates (`--size-estimate`): typical and worst-case bytes per
+//! document, serialized (JSON) and deserialized (Rust models, approximate).
+//!
+//! Each slot (`[*]` pools list items and tuple positions) keeps how often it
+//! appears, the serialized length of its scalars, and its string and array
+//! lengths. Estimates are built bottom-up from those:
+//! - typical: mean lengths, fields weighted by how often they are present
+//! - worst case: longest observed scalar and string, longest array, every
+//!   field present, so it bounds what the observed types admit rather than
+//!   any one document
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::jpath::{JPath, Seg};
+
+/// Stop tracking new slots past this (the estimate then says it's partial).
+pub const MAX_SLOTS: usize = 4096;
+
+/// Inline size of a `String` / `Vec` in the Rust models.
+const HEAP_HANDLE: f64 = 24.0;
+/// Inline size of a scalar (`i64`, `f64`, `bool`, enum tag), rounded to a word.
+const WORD: f64 = 8.0;
+
+#[derive(Debug, Clone, Default)]
+struct Slot {
+    /// Values seen here (for a field: objects it was present in).
+    values: u64,
+    /// Serialized bytes of null / bool / number / string values, and the most in one.
+    scalar_bytes: u64,
+    scalar_max: u64,
+    strings: u64,
+    /// UTF-8 bytes of string contents, and the longest.
+    str_bytes: u64,
+    str_max: u64,
+    arrays: u64,
+    items: u64,
+    items_max: u64,
+    objects: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SizeStats {
+    docs: u64,
+    slots: BTreeMap<JPath, Slot>,
+    truncated: bool,
+}
+
+impl SizeStats {
+    pub fn observe(&mut self, v: &Value) {
+        self.docs += 1;
+        self.walk(v, &mut JPath::root());
+    }
+
+    fn walk(&mut self, v: &Value, at: &mut JPath) {
+        if !self.slots.contains_key(at) {
+            if self.slots.len() >= MAX_SLOTS {
+                self.truncated = true;
+                return;
+            }
+            self.slots.insert(at.clone(), Slot::default());
+        }
+        let slot = self.slots.get_mut(at).expect("inserted above");
+        slot.values += 1;
+        let scalar = |slot: &mut Slot, n: u64| {
+            slot.scalar_bytes += n;
+            slot.scalar_max = slot.scalar_max.max(n);
+        };
+        match v {
+            Value::Null => scalar(slot, 4),
+            Value::Bool(b) => scalar(slot, if *b { 4 } else { 5 }),
+            Value::Number(n) => scalar(slot, n.to_string().len() as u64),
+            Value::String(s) => {
+                // quotes; escapes are rare enough to ignore
+                scalar(slot, s.len() as u64 + 2);
+                slot.strings += 1;
+                slot.str_bytes += s.len() as u64;
+                slot.str_max = slot.str_max.max(s.len() as u64);
+            }
+            Value::Array(xs) => {
+                slot.arrays += 1;
+                slot.items += xs.len() as u64;
+                slot.items_max = slot.items_max.max(xs.len() as u64);
+                at.0.push(Seg::Items);
+                for x in xs {
+                    self.walk(x, at);
+                }
+                at.0.pop();
+            }
+            Value::Object(m) => {
+                slot.objects += 1;
+                for (k, x) in m {
+                    at.0.push(Seg::Field(k.clone()));
+                    self.walk(x, at);
+                    at.0.pop();
+                }
+            }
+        }
+    }
+
+    pub fn join(mut a: Self, b: Self) -> Self {
+        a.docs += b.docs;
+        a.truncated |= b.truncated;
+        for (p, sb) in b.slots {
+            if !a.slots.contains_key(&p) && a.slots.len() >= MAX_SLOTS {
+                a.truncated = true;
+                continue;
+            }
+            let sa = a.slots.entry(p).or_default();
+            sa.values += sb.values;
+            sa.scalar_bytes += sb.scalar_bytes;
+            sa.scalar_max = sa.scalar_max.max(sb.scalar_max);
+            sa.strings += sb.strings;
+            sa.str_bytes += sb.str_bytes;
+            sa.str_max = sa.str_max.max(sb.str_max);
+            sa.arrays += sb.arrays;
+            sa.items += sb.items;
+            sa.items_max = sa.items_max.max(sb.items_max);
+            sa.objects += sb.objects;
+        }
+        a
+    }
+
+    /// Direct children of `at`: field names (with their paths) and the item path.
+    fn children(&self, at: &JPath) -> (Vec<(String, JPath)>, Option<JPath>) {
+        let mut fields = Vec::new();
+        let mut items = None;
+        for p in self.slots.range(at.clone()..).map(|(p, _)| p) {
+            if !p.0.starts_with(&at.0) {
+                break;
+            }
+            if p.0.len() != at.0.len() + 1 {
+                continue;
+            }
+            match p.0.last() {
+                Some(Seg::Field(k)) => fields.push((k.clone(), p.clone())),
+                Some(Seg::Items) => items = Some(p.clone()),
+                _ => {}
+            }
+        }
+        (fields, items)
+    }
+
+    /// Estimates for one value at `at`: each kind seen there, weighted by how often.
+    fn estimate(&self, at: &JPath) -> Sizes {
+        let Some(slot) = self.slots.get(at) else { return Sizes::default() };
+        let n = slot.values.max(1) as f64;
+        let (fields, items) = self.children(at);
+
+        // null / bool / number: inline words; strings: a handle plus their bytes
+        let others = (slot.values - slot.strings - slot.arrays - slot.objects) as f64;
+        let mut s = Sizes {
+            json: slot.scalar_bytes as f64 / n,
+            json_max: slot.scalar_max as f64,
+            mem: (others * WORD + slot.strings as f64 * HEAP_HANDLE + slot.str_bytes as f64) / n,
+            mem_max: if slot.strings > 0 { HEAP_HANDLE + slot.str_max as f64 } else if others > 0.0 { WORD } else { 0.0 },
+        };
+
+        if slot.arrays > 0 {
+            let item = items.map(|p| self.estimate(&p)).unwrap_or_default();
+            let mean_len = slot.items as f64 / slot.arrays as f64;
+            let share = slot.arrays as f64 / n;
+            s.json += share * (2.0 + mean_len * (item.json + 1.0));
+            s.json_max = s.json_max.max(2.0 + slot.items_max as f64 * (item.json_max + 1.0));
+            s.mem += share * (HEAP_HANDLE + mean_len * item.mem);
+            s.mem_max = s.mem_max.max(HEAP_HANDLE + slot.items_max as f64 * item.mem_max);
+        }
+        if slot.objects > 0 {
+            let (mut json, mut json_max, mut mem, mut mem_max) = (2.0, 2.0, 0.0, 0.0);
+            for (k, p) in &fields {
+                let child = self.estimate(p);
+                let present = self.slots[p].values as f64 / slot.objects as f64;
+                // `"key":value,`
+                let key = k.len() as f64 + 4.0;
+                json += present * (key + child.json);
+                json_max += key + child.json_max;
+                // an absent field still takes its inline `Option<…>`
+                mem += present * child.mem + (1.0 - present) * WORD;
+                mem_max += child.mem_max;
+            }
+            let share = slot.objects as f64 / n;
+            s.json += share * json;
+            s.json_max = s.json_max.max(json_max);
+            s.mem += share * mem;
+            s.mem_max = s.mem_max.max(mem_max);
+        }
+        s
+    }
+
+    pub fn summarize(&self) -> SizeSummary {
+        let root = self.estimate(&JPath::root());
+        let mut fields: Vec<FieldSize> = self
+            .children(&JPath::root())
+            .0
+            .into_iter()
+            .map(|(_, p)| {
+                let e = self.estimate(&p);
+                let present = self.slots[&p].values as f64 / self.docs.max(1) as f64;
+                FieldSize { path: p.to_string(), json: present * e.json, json_max: e.json_max }
+            })
+            .collect();
+        fields.sort_by(|a, b| b.json.total_cmp(&a.json).then_with(|| a.path.cmp(&b.path)));
+        SizeSummary { docs: self.docs, record: root, fields, partial: self.truncated }
+    }
+}
+
+/// Bytes for one value, typical (mean) and worst case.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sizes {
+    pub json: f64,
+    pub json_max: f64,
+    pub mem: f64,
+    pub mem_max: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldSize {
+    pub path: String,
+    /// Typical serialized bytes this field adds to a record (weighted by presence).
+    pub json: f64,
+    pub json_max: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SizeSummary {
+    pub docs: u64,
+    pub record: Sizes,
+    /// Root object fields, largest typical contribution first.
+    pub fields: Vec<FieldSize>,
+    /// Some slots weren't tracked ([`MAX_SLOTS`]), so sizes are underestimated.
+    pub partial: bool,
+}
@@ -0,0 +1,239 @@
+//! Payload format history over time (`--timestamp PATH`).
+//!
+//! Documents are bucketed by the timestamp at `PATH` (hour, day, week or
+//! month), and each bucket counts the documents every path appeared in. The
+//! first and last bucket a path shows up in turn the static inference into a
+//! timeline: when a field was introduced, and when it stopped being sent.
+//!
+//! Timestamps may be RFC 3339 strings, `YYYY-MM-DD[ HH:MM:SS]` strings, or
+//! Unix epochs (seconds, or milliseconds past 1e11) as numbers or strings.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Timelike, Utc};
+use serde_json::Value;
+
+use crate::jpath::{JPath, Seg};
+
+/// Distinct paths tracked per bucket (dynamic-key objects would grow without bound).
+pub const MAX_PATHS: usize = 4096;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Window {
+    Hour,
+    #[default]
+    Day,
+    /// ISO weeks, starting Monday.
+    Week,
+    Month,
+}
+
+impl Window {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Window::Hour => "hour",
+            Window::Day => "day",
+            Window::Week => "week",
+            Window::Month => "month",
+        }
+    }
+
+    /// Start of the window holding `t`.
+    fn start(self, t: DateTime<Utc>) -> DateTime<Utc> {
+        let d = t.date_naive();
+        let day = |d: NaiveDate| d.and_hms_opt(0, 0, 0).expect("midnight").and_utc();
+        match self {
+            Window::Hour => d.and_hms_opt(t.hour(), 0, 0).expect("whole hour").and_utc(),
+            Window::Day => day(d),
+            Window::Week => day(d - chrono::Days::new(d.weekday().num_days_from_monday() as u64)),
+            Window::Month => day(d.with_day(1).expect("first of month")),
+        }
+    }
+
+    fn label(self, start: DateTime<Utc>) -> String {
+        let fmt = match self {
+            Window::Hour => "%Y-%m-%dT%H",
+            Window::Day => "%Y-%m-%d",
+            Window::Week => "%G-W%V",
+            Window::Month => "%Y-%m",
+        };
+        start.format(fmt).to_string()
+    }
+}
+
+fn epoch(x: f64) -> Option<DateTime<Utc>> {
+    if !x.is_finite() {
+        return None;
+    }
+    let secs = if x.abs() >= 1e11 { x / 1000.0 } else { x };
+    DateTime::from_timestamp(secs.floor() as i64, 0)
+}
+
+pub fn parse_time(v: &Value) -> Option<DateTime<Utc>> {
+    match v {
+        Value::Number(n) => n.as_f64().and_then(epoch),
+        Value::String(s) => {
+            let s = s.trim();
+            DateTime::parse_from_rfc3339(s)
+                .map(|t| t.with_timezone(&Utc))
+                .ok()
+                .or_else(|| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").ok().map(|t| t.and_utc()))
+                .or_else(|| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok().and_then(|d| d.and_hms_opt(0, 0, 0)).map(|t| t.and_utc()))
+                .or_else(|| s.parse::<f64>().ok().and_then(epoch))
+        }
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Bucket {
+    docs: u64,
+    /// Path → documents it appeared in.
+    paths: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    at: JPath,
+    window: Window,
+    /// Window start (Unix seconds) → bucket.
+    buckets: BTreeMap<i64, Bucket>,
+    /// Documents without a readable timestamp.
+    untimed: u64,
+}
+
+/// Paths a document has: object fields, and `[*]` for list items.
+fn doc_paths(v: &Value, at: &mut JPath, out: &mut BTreeSet<String>) {
+    match v {
+        Value::Array(xs) => {
+            at.0.push(Seg::Items);
+            for x in xs {
+                out.insert(at.to_string());
+                doc_paths(x, at, out);
+            }
+            at.0.pop();
+        }
+        Value::Object(m) => {
+            for (k, x) in m {
+                at.0.push(Seg::Field(k.clone()));
+                out.insert(at.to_string());
+                doc_paths(x, at, out);
+                at.0.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+impl Timeline {
+    pub fn new(at: JPath, window: Window) -> Self {
+        Timeline { at, window, buckets: BTreeMap::new(), untimed: 0 }
+    }
+
+    pub fn observe(&mut self, doc: &Value) {
+        let Some(t) = self.at.resolve(doc).and_then(parse_time) else {
+            self.untimed += 1;
+            return;
+        };
+        let bucket = self.buckets.entry(self.window.start(t).timestamp()).or_default();
+        bucket.docs += 1;
+        let mut paths = BTreeSet::new();
+        doc_paths(doc, &mut JPath::root(), &mut paths);
+        for p in paths {
+            if let Some(c) = bucket.paths.get_mut(&p) {
+                *c += 1;
+            } else if bucket.paths.len() < MAX_PATHS {
+                bucket.paths.insert(p, 1);
+            }
+        }
+    }
+
+    pub fn join(mut a: Self, b: Self) -> Self {
+        a.untimed += b.untimed;
+        for (k, bb) in b.buckets {
+            let ba = a.buckets.entry(k).or_default();
+            ba.docs += bb.docs;
+            for (p, c) in bb.paths {
+                if let Some(x) = ba.paths.get_mut(&p) {
+                    *x += c;
+                } else if ba.paths.len() < MAX_PATHS {
+                    ba.paths.insert(p, c);
+                }
+            }
+        }
+        a
+    }
+
+    pub fn summarize(self) -> TimelineSummary {
+        let window = self.window;
+        // path → (first bucket index, last bucket index, docs)
+        let mut spans: BTreeMap<String, (usize, usize, u64)> = BTreeMap::new();
+        for (i, b) in self.buckets.values().enumerate() {
+            for (p, c) in &b.paths {
+                let s = spans.entry(p.clone()).or_insert((i, i, 0));
+                s.1 = i;
+                s.2 += c;
+            }
+        }
+        let mut buckets: Vec<BucketSummary> = self.buckets
+            .iter()
+            .map(|(start, b)| BucketSummary {
+                label: window.label(DateTime::from_timestamp(*start, 0).expect("bucket start")),
+                docs: b.docs,
+                paths: b.paths.len(),
+                appeared: Vec::new(),
+                gone: Vec::new(),
+            })
+            .collect();
+        let last = buckets.len().saturating_sub(1);
+        let mut lifespans = Vec::new();
+        for (p, (first, end, docs)) in spans {
+            if first > 0 {
+                buckets[first].appeared.push(p.clone());
+            }
+            if end < last {
+                buckets[end + 1].gone.push(p.clone());
+            }
+            if first > 0 || end < last {
+                lifespans.push(Lifespan {
+                    path: p,
+                    first: buckets[first].label.clone(),
+                    last: buckets[end].label.clone(),
+                    docs,
+                });
+            }
+        }
+        TimelineSummary { path: self.at.to_string(), window, untimed: self.untimed, buckets, lifespans }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BucketSummary {
+    pub label: String,
+    pub docs: u64,
+    /// Distinct paths seen in this window.
+    pub paths: usize,
+    /// Paths first seen in this window (empty for the first window, which is the baseline).
+    pub appeared: Vec<String>,
+    /// Paths last seen in the previous window.
+    pub gone: Vec<String>,
+}
+
+/// A path that doesn't span the whole timeline.
+#[derive(Debug, Clone)]
+pub struct Lifespan {
+    pub path: String,
+    pub first: String,
+    pub last: String,
+    pub docs: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct TimelineSummary {
+    /// The `--timestamp` path.
+    pub path: String,
+    pub window: Window,
+    pub untimed: u64,
+    pub buckets: Vec<BucketSummary>,
+    pub lifespans: Vec<Lifespan>,
+}
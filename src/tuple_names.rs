@@ -0,0 +1,54 @@
+//! Names for tuple positions (`gen --tuple-names FILE`).
+//!
+//! Positional arrays come out as tuple structs (`.0`, `.1`, …). A hints file
+//! names the positions whose meaning is known, by JSON path:
+//!
+//! ```json
+//! { "$.points[*][0]": "latitude", "$.points[*][1]": "longitude" }
+//! ```
+//!
+//! or, in a `.toml` file, the same table (`"$.points[*][0]" = "latitude"`).
+//!
+//! A tuple with a named position becomes a Rust struct with named fields (the
+//! others `_2`, `_3`, …), still read from an array; the schema carries each
+//! name as the position's `title`, and `--schema-tuples objects` keys the
+//! position by it.
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::jpath::{JPath, Seg};
+
+/// Read a hints file (TOML if it ends in `.toml`, JSON otherwise), keyed by
+/// normalized path; every path ends at a position.
+pub fn load(path: &std::path::Path) -> Result<BTreeMap<String, String>, String> {
+    let src = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let raw: BTreeMap<String, String> = if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("toml")) {
+        toml::from_str(&src).map_err(|e| e.to_string())?
+    } else {
+        serde_json::from_str(&src).map_err(|e| e.to_string())?
+    };
+    let mut out = BTreeMap::new();
+    for (at, name) in raw {
+        let at = at.parse::<JPath>()?;
+        if !matches!(at.segs().last(), Some(Seg::Index(_))) {
+            return Err(format!("{at}: not a tuple position (paths end with `[0]`, `[1]`, …)"));
+        }
+        if name.trim().is_empty() {
+            return Err(format!("{at}: empty name"));
+        }
+        out.insert(at.to_string(), name);
+    }
+    Ok(out)
+}
+
+/// Set `title` on the schema of every named position.
+pub fn annotate(schema: &mut Value, names: &BTreeMap<String, String>) {
+    for (path, name) in names {
+        let Ok(path) = path.parse::<JPath>() else { continue };
+        if let Some(node) = crate::norm_ir::schema_at(schema, path.segs()) {
+            node["title"] = Value::from(name.as_str());
+        }
+    }
+}
@@ -0,0 +1,381 @@
+//! Cross-endpoint ("soft") unification.
+//!
+//! Given several inferred IRs (one per endpoint), find compound types whose
+//! *shape* (kinds, field names, arities) matches in two or more of them, widen
+//! their constraints (bounds, requiredness, …) to cover every occurrence, emit
+//! each once into a `common` module, and have each endpoint module reference
+//! them instead of carrying its own copy.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::codegen::{is_compound, structural_key, to_field_name, to_type_name, Codegen, NameCollision, NamingPolicy};
+use crate::ir::{Field, Ty};
+use crate::metrics::CodeMetrics;
+
+/// One inferred IR and the module name it will be emitted under.
+#[derive(Debug, Clone)]
+pub struct Endpoint {
+    pub name: String,
+    pub ty: Ty,
+}
+
+/// Generated sources: the shared module plus one module per endpoint.
+#[derive(Debug, Clone)]
+pub struct Unified {
+    pub common: String,
+    /// `(module_name, source)` in endpoint order.
+    pub modules: Vec<(String, String)>,
+    /// Number of distinct component types hoisted into `common`.
+    pub shared: usize,
+    /// Name clashes recorded under [`crate::codegen::CollisionPolicy::Error`], across all modules.
+    pub collisions: Vec<NameCollision>,
+    /// `common` first, then every endpoint module.
+    pub metrics: Vec<CodeMetrics>,
+}
+
+impl Unified {
+    /// `mod.rs` wiring `common` and every endpoint module together.
+    pub fn mod_rs(&self) -> String {
+        let mut s = String::from("// AUTOGENERATED: module index for unified endpoint models\n");
+        s.push_str("pub mod common;\n");
+        for (m, _) in &self.modules {
+            s.push_str(&format!("pub mod {m};\n"));
+        }
+        s
+    }
+}
+
+// ---------- shapes ----------
+
+/// Drop every constraint that doesn't change the generated type's structure.
+fn strip(t: &Ty) -> Ty {
+    match t {
+        // the string form changes the Rust type under `--codegen-profile small`
+        // enums vs plain integers generate different Rust, as for strings
+        Ty::Integer { from_string, enum_, .. } => Ty::Integer {
+            min: None,
+            max: None,
+            from_string: *from_string,
+            multiple_of: None,
+            enum_: if enum_.is_empty() { Vec::new() } else { vec![0] },
+            stats: None,
+        },
+        Ty::Number { from_string, .. } => {
+            Ty::Number { min: None, max: None, from_string: *from_string, multiple_of: None, stats: None }
+        }
+        Ty::StringOrNumber { .. } => Ty::StringOrNumber { min: None, max: None, stats: None },
+        Ty::String { enum_, .. } => Ty::String {
+            // enums vs plain strings generate different Rust, so keep the distinction
+            enum_: if enum_.is_empty() { Vec::new() } else { vec![String::new()] },
+            enum_counts: Vec::new(),
+            pattern: None,
+            format_uri: false,
+            format: None,
+            min_length: None,
+            max_length: None,
+            stats: None,
+        },
+        Ty::ArrayList { item, .. } => {
+            Ty::ArrayList { item: Box::new(strip(item)), min_items: None, max_items: None, stats: None }
+        }
+        Ty::ArrayTuple { elems, max_items, .. } => Ty::ArrayTuple {
+            elems: elems.iter().map(strip).collect(),
+            min_items: 0,
+            max_items: *max_items,
+            stats: None,
+        },
+        Ty::Object { fields, .. } => Ty::Object {
+            fields: fields.iter().map(|f| Field { name: f.name.clone(), ty: strip(&f.ty), required: false, present: false, support: None }).collect(),
+            stats: None,
+        },
+        Ty::Map { value, .. } => Ty::Map {
+            key: Box::new(Ty::String {
+                enum_: Vec::new(),
+                enum_counts: Vec::new(),
+                pattern: None,
+                format_uri: false,
+                format: None,
+                min_length: None,
+                max_length: None,
+                stats: None,
+            }),
+            value: Box::new(strip(value)),
+            stats: None,
+        },
+        Ty::OneOf(arms) => Ty::OneOf(arms.iter().map(strip).collect()),
+        Ty::Tagged { tag, variants, .. } => Ty::Tagged {
+            tag: tag.clone(),
+            variants: variants.iter().map(|(value, v)| (value.clone(), strip(v))).collect(),
+            stats: None,
+        },
+        Ty::Nullable(inner) => Ty::Nullable(Box::new(strip(inner))),
+        Ty::JsonString(inner) => Ty::JsonString(Box::new(strip(inner))),
+        Ty::Sentinel { inner, marks, as_null } => Ty::Sentinel { inner: Box::new(strip(inner)), marks: marks.clone(), as_null: *as_null },
+        Ty::Never | Ty::Null | Ty::Bool | Ty::Any | Ty::Raw => t.clone(),
+    }
+}
+
+fn shape_key(t: &Ty) -> String {
+    structural_key(&strip(t))
+}
+
+/// Least constraint set covering both `a` and `b` (which share a shape).
+fn widen(a: &Ty, b: &Ty) -> Ty {
+    fn lo<T: PartialOrd + Copy>(x: Option<T>, y: Option<T>) -> Option<T> {
+        match (x, y) { (Some(x), Some(y)) => Some(if y < x { y } else { x }), _ => None }
+    }
+    fn hi<T: PartialOrd + Copy>(x: Option<T>, y: Option<T>) -> Option<T> {
+        match (x, y) { (Some(x), Some(y)) => Some(if y > x { y } else { x }), _ => None }
+    }
+    match (a, b) {
+        (
+            Ty::Integer { min: a0, max: a1, from_string, multiple_of: ma, enum_: ea, .. },
+            Ty::Integer { min: b0, max: b1, multiple_of: mb, enum_: eb, .. },
+        ) => {
+            // both sides' values are multiples of their common divisor
+            let multiple_of = match (*ma, *mb) {
+                (Some(mut x), Some(mut y)) => {
+                    while y != 0 {
+                        (x, y) = (y, x % y);
+                    }
+                    Some(x).filter(|g| *g > 1)
+                }
+                _ => None,
+            };
+            let enum_ = ea.iter().chain(eb).copied().collect::<BTreeSet<_>>().into_iter().collect();
+            Ty::Integer { min: lo(*a0, *b0), max: hi(*a1, *b1), from_string: *from_string, multiple_of, enum_, stats: None }
+        }
+        (
+            Ty::Number { min: a0, max: a1, from_string, multiple_of: ma, .. },
+            Ty::Number { min: b0, max: b1, multiple_of: mb, .. },
+        ) => {
+            let multiple_of = if ma == mb { *ma } else { None };
+            Ty::Number { min: lo(*a0, *b0), max: hi(*a1, *b1), from_string: *from_string, multiple_of, stats: None }
+        }
+        (Ty::StringOrNumber { min: a0, max: a1, .. }, Ty::StringOrNumber { min: b0, max: b1, .. }) => {
+            Ty::StringOrNumber { min: lo(*a0, *b0), max: hi(*a1, *b1), stats: None }
+        }
+        (
+            Ty::String { enum_: ea, enum_counts: ca, pattern: pa, format_uri: ua, format: fa, min_length: a0, max_length: a1, .. },
+            Ty::String { enum_: eb, enum_counts: cb, pattern: pb, format_uri: ub, format: fb, min_length: b0, max_length: b1, .. },
+        ) => {
+            // counts add up where both sides have them; else values in order
+            let (enum_, enum_counts) = if ca.len() == ea.len() && cb.len() == eb.len() {
+                let mut counts: BTreeMap<&String, u64> = BTreeMap::new();
+                for (v, n) in ea.iter().zip(ca).chain(eb.iter().zip(cb)) {
+                    *counts.entry(v).or_default() += n;
+                }
+                let mut both: Vec<(&String, u64)> = counts.into_iter().collect();
+                both.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+                both.into_iter().map(|(v, n)| (v.clone(), n)).unzip()
+            } else {
+                (ea.iter().chain(eb).cloned().collect::<BTreeSet<_>>().into_iter().collect(), Vec::new())
+            };
+            Ty::String {
+                enum_,
+                enum_counts,
+                pattern: if pa == pb { pa.clone() } else { None },
+                format_uri: *ua && *ub,
+                format: if fa == fb { *fa } else { None },
+                min_length: lo(*a0, *b0),
+                max_length: hi(*a1, *b1),
+                stats: None,
+            }
+        }
+        (
+            Ty::ArrayList { item: ia, min_items: a0, max_items: a1, .. },
+            Ty::ArrayList { item: ib, min_items: b0, max_items: b1, .. },
+        ) => Ty::ArrayList { item: Box::new(widen(ia, ib)), min_items: lo(*a0, *b0), max_items: hi(*a1, *b1), stats: None },
+        (
+            Ty::ArrayTuple { elems: ea, min_items: a0, max_items, .. },
+            Ty::ArrayTuple { elems: eb, min_items: b0, .. },
+        ) => Ty::ArrayTuple {
+            elems: ea.iter().zip(eb).map(|(x, y)| widen(x, y)).collect(),
+            min_items: (*a0).min(*b0),
+            max_items: *max_items,
+            stats: None,
+        },
+        (Ty::Object { fields: fa, .. }, Ty::Object { fields: fb, .. }) => Ty::Object {
+            fields: fa.iter().zip(fb).map(|(x, y)| Field {
+                name: x.name.clone(),
+                ty: widen(&x.ty, &y.ty),
+                required: x.required && y.required,
+                present: x.present && y.present,
+                support: None,
+            }).collect(),
+            stats: None,
+        },
+        (Ty::Map { key: ka, value: va, .. }, Ty::Map { key: kb, value: vb, .. }) => {
+            Ty::Map { key: Box::new(widen(ka, kb)), value: Box::new(widen(va, vb)), stats: None }
+        }
+        (Ty::Tagged { tag, variants: va, .. }, Ty::Tagged { variants: vb, .. }) => Ty::Tagged {
+            tag: tag.clone(),
+            variants: va.iter().zip(vb).map(|((value, x), (_, y))| (value.clone(), widen(x, y))).collect(),
+            stats: None,
+        },
+        (Ty::OneOf(xa), Ty::OneOf(xb)) => Ty::OneOf(xa.iter().zip(xb).map(|(x, y)| widen(x, y)).collect()),
+        (Ty::Nullable(x), Ty::Nullable(y)) => Ty::Nullable(Box::new(widen(x, y))),
+        (Ty::JsonString(x), Ty::JsonString(y)) => Ty::JsonString(Box::new(widen(x, y))),
+        _ => a.clone(),
+    }
+}
+
+// ---------- collection ----------
+
+/// Every occurrence of one compound shape across all endpoints.
+struct Candidate<'a> {
+    hint: String,
+    /// Where the first occurrence lives (endpoint name, then fields/indices).
+    path: Vec<String>,
+    endpoints: BTreeSet<usize>,
+    occurrences: Vec<&'a Ty>,
+}
+
+fn collect<'a>(t: &'a Ty, hint: &str, path: &mut Vec<String>, ep: usize, out: &mut BTreeMap<String, Candidate<'a>>) {
+    if is_compound(t) {
+        let c = out.entry(shape_key(t)).or_insert_with(|| Candidate {
+            hint: hint.to_string(),
+            path: path.clone(),
+            endpoints: BTreeSet::new(),
+            occurrences: Vec::new(),
+        });
+        c.endpoints.insert(ep);
+        c.occurrences.push(t);
+    }
+    let mut descend = |seg: String, child: &'a Ty, hint: &str, out: &mut BTreeMap<String, Candidate<'a>>| {
+        path.push(seg);
+        collect(child, hint, path, ep, out);
+        path.pop();
+    };
+    match t {
+        Ty::Nullable(inner) | Ty::JsonString(inner) => collect(inner, hint, path, ep, out),
+        Ty::ArrayList { item, .. } => descend("Item".into(), item, &format!("{hint}Item"), out),
+        Ty::Map { value, .. } => descend("Value".into(), value, &format!("{hint}Value"), out),
+        Ty::ArrayTuple { elems, .. } => {
+            for (i, e) in elems.iter().enumerate() {
+                descend(i.to_string(), e, &format!("{hint}{i}"), out);
+            }
+        }
+        // Field types are named after the field alone: shared types shouldn't
+        // carry the first endpoint's parent path in their name.
+        Ty::Object { fields, .. } => {
+            for f in fields {
+                descend(f.name.clone(), &f.ty, &to_type_name(&f.name), out);
+            }
+        }
+        Ty::OneOf(arms) => {
+            for (i, a) in arms.iter().enumerate() {
+                descend(format!("Alt{i}"), a, &format!("{hint}Alt{i}"), out);
+            }
+        }
+        Ty::Tagged { variants, .. } => {
+            for (value, v) in variants {
+                let var = to_type_name(value);
+                descend(var.clone(), v, &format!("{hint}{var}"), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Replace every subtree whose shape has a canonical (widened) form with it, bottom-up.
+fn canonicalize(t: &Ty, canon: &BTreeMap<String, Ty>) -> Ty {
+    let rebuilt = match t {
+        Ty::Nullable(inner) => Ty::Nullable(Box::new(canonicalize(inner, canon))),
+        Ty::JsonString(inner) => Ty::JsonString(Box::new(canonicalize(inner, canon))),
+        Ty::ArrayList { item, min_items, max_items, stats } => Ty::ArrayList {
+            item: Box::new(canonicalize(item, canon)),
+            min_items: *min_items,
+            max_items: *max_items,
+            stats: *stats,
+        },
+        Ty::Map { key, value, stats } => {
+            Ty::Map { key: key.clone(), value: Box::new(canonicalize(value, canon)), stats: *stats }
+        }
+        Ty::ArrayTuple { elems, min_items, max_items, stats } => Ty::ArrayTuple {
+            elems: elems.iter().map(|e| canonicalize(e, canon)).collect(),
+            min_items: *min_items,
+            max_items: *max_items,
+            stats: *stats,
+        },
+        Ty::Object { fields, stats } => Ty::Object {
+            fields: fields.iter().map(|f| Field {
+                name: f.name.clone(),
+                ty: canonicalize(&f.ty, canon),
+                required: f.required,
+                present: f.present,
+                support: f.support,
+            }).collect(),
+            stats: *stats,
+        },
+        Ty::OneOf(arms) => Ty::OneOf(arms.iter().map(|a| canonicalize(a, canon)).collect()),
+        Ty::Tagged { tag, variants, stats } => Ty::Tagged {
+            tag: tag.clone(),
+            variants: variants.iter().map(|(value, v)| (value.clone(), canonicalize(v, canon))).collect(),
+            stats: *stats,
+        },
+        _ => t.clone(),
+    };
+    if is_compound(&rebuilt) && let Some(c) = canon.get(&shape_key(&rebuilt)) {
+        return c.clone();
+    }
+    rebuilt
+}
+
+pub fn unify(endpoints: &[Endpoint], root_name: &str, naming: &NamingPolicy) -> Unified {
+    let mut candidates = BTreeMap::new();
+    for (i, ep) in endpoints.iter().enumerate() {
+        collect(&ep.ty, &to_type_name(&ep.name), &mut vec![ep.name.clone()], i, &mut candidates);
+    }
+    let mut shared: Vec<(&String, &Candidate)> = candidates
+        .iter()
+        .filter(|(_, c)| c.endpoints.len() >= 2)
+        .collect();
+
+    // Smallest shapes first: a parent's canonical form must already contain
+    // its children's canonical forms, or the two would drift apart.
+    shared.sort_by(|(ka, _), (kb, _)| ka.len().cmp(&kb.len()).then_with(|| ka.cmp(kb)));
+    let mut canon = BTreeMap::<String, Ty>::new();
+    for (key, c) in &shared {
+        let merged = c.occurrences
+            .iter()
+            .map(|t| canonicalize(t, &canon))
+            .reduce(|a, b| widen(&a, &b))
+            .expect("candidate has at least one occurrence");
+        canon.insert((*key).clone(), merged);
+    }
+
+    // Largest first, so nested shared types are emitted as part of their
+    // parent and then found in the codegen's dedupe table.
+    let mut common = Codegen::new().with_dedupe(true).with_naming(naming.clone());
+    common.emit_prelude();
+    for (key, c) in shared.iter().rev() {
+        common.emit_item_at(&canon[*key], &c.hint, c.path.clone());
+    }
+    let mut collisions = common.collisions().to_vec();
+    let mut metrics = vec![common.metrics("common")];
+    let reuse: BTreeMap<String, String> = common
+        .emitted()
+        .iter()
+        .map(|(k, name)| (k.clone(), format!("super::common::{name}")))
+        .collect();
+
+    let modules = endpoints
+        .iter()
+        .map(|ep| {
+            let mut cg = Codegen::new().with_reuse(reuse.clone()).with_naming(naming.clone());
+            let resolved = cg.emit(&canonicalize(&ep.ty, &canon), root_name);
+            collisions.extend_from_slice(cg.collisions());
+            let module = to_field_name(&ep.name);
+            metrics.push(cg.metrics(&module));
+            let mut src = cg.into_string();
+            // The whole endpoint may itself be a shared type.
+            if resolved != root_name {
+                src.push_str(&format!("pub type {root_name} = {resolved};\n"));
+            }
+            (module, src)
+        })
+        .collect();
+
+    Unified { common: common.into_string(), modules, shared: shared.len(), collisions, metrics }
+}
@@ -0,0 +1,131 @@
+//! Unions and unexpected nulls in the normalized IR, for the report.
+//!
+//! A `oneOf` usually means the corpus mixes shapes on purpose, or that a jq
+//! filter lets through values it shouldn't; a null among list items or in a
+//! key every object has is often the same. Either way it's a decision for the
+//! user (an `--overrides` entry, a better `--jq-expr`), so every such path is
+//! listed with how much evidence each side has.
+
+use crate::jpath::{JPath, Seg};
+use crate::norm_ir::{NTy, Support};
+
+/// A path whose values normalized to a union.
+#[derive(Debug, Clone)]
+pub struct Union {
+    pub path: JPath,
+    /// Each arm's kind, and how many values it was inferred from (unknown for
+    /// `boolean` below lists and maps, and in older evidence).
+    pub arms: Vec<(&'static str, Option<u64>)>,
+    /// Null is accepted too.
+    pub nullable: bool,
+}
+
+/// A path where null is accepted though some value was expected: list and map
+/// items, tuple positions, the root, and fields whose key every object has.
+/// Optional fields that are sometimes null aren't listed.
+#[derive(Debug, Clone)]
+pub struct Nulls {
+    pub path: JPath,
+    /// Null values, and out of how many, where the evidence tells.
+    pub nulls: Option<(u64, u64)>,
+    /// What the non-null values are.
+    pub kind: &'static str,
+}
+
+/// The unions and unexpected nulls of `n`, parents first. Run it before
+/// [`crate::norm_ir::strip_support`]: the counts come from the IR's annotations.
+pub fn diagnose(n: &NTy) -> (Vec<Union>, Vec<Nulls>) {
+    let mut unions = Vec::new();
+    let mut nulls = Vec::new();
+    walk(n, &mut JPath::root(), None, true, &mut unions, &mut nulls);
+    (unions, nulls)
+}
+
+/// `support`: of the value at `at`, when it is a field or tuple position;
+/// `expected`: whether a null there is unexpected (see [`Nulls`]).
+fn walk(n: &NTy, at: &mut JPath, support: Option<Support>, expected: bool, unions: &mut Vec<Union>, nulls: &mut Vec<Nulls>) {
+    match n {
+        NTy::Nullable(inner) => {
+            if expected {
+                nulls.push(Nulls {
+                    path: at.clone(),
+                    nulls: support.map(|s| (s.present - s.non_null, s.present)),
+                    kind: kind(inner),
+                });
+            }
+            match &**inner {
+                NTy::OneOf(arms) => {
+                    unions.push(union(at, arms, support, true));
+                    arms.iter().for_each(|a| walk(a, at, None, false, unions, nulls));
+                }
+                inner => walk(inner, at, None, false, unions, nulls),
+            }
+            return;
+        }
+        NTy::OneOf(arms) => unions.push(union(at, arms, support, false)),
+        _ => {}
+    }
+    let mut below = |seg: Seg, n: &NTy, support: Option<Support>, expected: bool, unions: &mut Vec<Union>, nulls: &mut Vec<Nulls>| {
+        at.0.push(seg);
+        walk(n, at, support, expected, unions, nulls);
+        at.0.pop();
+    };
+    match n {
+        NTy::ArrayTuple { elems, support, .. } => {
+            for (i, e) in elems.iter().enumerate() {
+                below(Seg::Index(i), e, support.get(i).copied(), true, unions, nulls);
+            }
+        }
+        NTy::Object { fields, .. } => {
+            for f in fields {
+                below(Seg::Field(f.name.clone()), &f.ty, f.support, f.present, unions, nulls);
+            }
+        }
+        NTy::ArrayList { item: inner, .. } | NTy::Map { value: inner, .. } => below(Seg::Items, inner, None, true, unions, nulls),
+        NTy::Tagged { variants, .. } => variants.iter().for_each(|(_, v)| walk(v, at, None, false, unions, nulls)),
+        NTy::JsonString(inner) => walk(inner, at, None, false, unions, nulls),
+        NTy::OneOf(arms) => arms.iter().for_each(|a| walk(a, at, None, false, unions, nulls)),
+        NTy::Nullable(_)
+        | NTy::Null
+        | NTy::Bool
+        | NTy::Integer { .. }
+        | NTy::Number { .. }
+        | NTy::StringOrNumber { .. }
+        | NTy::String { .. }
+        | NTy::Any
+        | NTy::Raw => {}
+    }
+}
+
+fn union(at: &JPath, arms: &[NTy], support: Option<Support>, nullable: bool) -> Union {
+    let mut arms: Vec<(&'static str, Option<u64>)> = arms.iter().map(|a| (kind(a), a.stats().map(|s| s.samples))).collect();
+    // a field's or tuple position's non-null values are the arms' together
+    let unknown: Vec<usize> = (0..arms.len()).filter(|&i| arms[i].1.is_none()).collect();
+    if let (Some(support), [i]) = (support, &unknown[..]) {
+        let known: u64 = arms.iter().filter_map(|a| a.1).sum();
+        arms[*i].1 = support.non_null.checked_sub(known);
+    }
+    Union { path: at.clone(), arms, nullable }
+}
+
+/// What a node holds, in a word or two.
+fn kind(n: &NTy) -> &'static str {
+    match n {
+        NTy::Null => "null",
+        NTy::Bool => "boolean",
+        NTy::Integer { .. } => "integer",
+        NTy::Number { .. } => "number",
+        NTy::StringOrNumber { .. } => "number or numeric string",
+        NTy::String { .. } => "string",
+        NTy::ArrayList { .. } => "array",
+        NTy::ArrayTuple { .. } => "tuple",
+        NTy::Object { .. } => "object",
+        NTy::Map { .. } => "map",
+        NTy::Tagged { .. } => "tagged object",
+        NTy::JsonString(_) => "JSON in a string",
+        NTy::Nullable(inner) => kind(inner),
+        NTy::OneOf(_) => "union",
+        NTy::Any => "any JSON",
+        NTy::Raw => "raw JSON",
+    }
+}
@@ -0,0 +1,349 @@
+//! Re-validating sample documents against the lowered IR (`gen --revalidate`).
+//!
+//! Every input document contributed to the evidence, so every one of them must
+//! pass the types generated from it. A failure means normalization dropped
+//! something or a constraint came out tighter than the data; each one is
+//! reported as the smallest relaxation that would let the document through.
+//!
+//! The checks mirror what the strict Rust models enforce (see
+//! [`crate::codegen::Codegen::generated_config`]).
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use regex::Regex;
+use serde_json::Value;
+
+use crate::ir::{IntWidth, Mark, Ty};
+use crate::jpath::{JPath, Seg};
+
+/// Documents kept for re-validation (first ones in input order).
+pub const MAX_SAMPLES: usize = 10_000;
+
+/// The minimal policy relaxation that would accept a failing value.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Relax {
+    DropMinimum,
+    DropMaximum,
+    DropEnum,
+    DropPattern,
+    DropUriFormat,
+    /// A well-known string format (`uuid`, `email`, …).
+    DropFormat(&'static str),
+    /// The string doesn't hold a JSON document (`--json-strings`).
+    DropJsonString,
+    DropMinItems,
+    DropMaxItems,
+    /// Tuple: fewer elements than `minItems`.
+    LowerTupleMinItems(usize),
+    /// Tuple: more elements than it has slots.
+    RaiseTupleMaxItems(usize),
+    AllowField(String),
+    OptionalField(String),
+    /// Tagged union: a tag value (or a missing tag, `None`) without a variant.
+    AllowVariant(Option<String>),
+    /// A JSON kind the type has no arm for.
+    AllowKind(&'static str),
+}
+
+impl fmt::Display for Relax {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Relax::DropMinimum => f.write_str("drop minimum"),
+            Relax::DropMaximum => f.write_str("drop maximum"),
+            Relax::DropEnum => f.write_str("drop enum (plain string)"),
+            Relax::DropPattern => f.write_str("drop pattern"),
+            Relax::DropUriFormat => f.write_str("drop format uri"),
+            Relax::DropFormat(name) => write!(f, "drop format {name}"),
+            Relax::DropJsonString => f.write_str("keep as a plain string (not JSON)"),
+            Relax::DropMinItems => f.write_str("drop minItems"),
+            Relax::DropMaxItems => f.write_str("drop maxItems"),
+            Relax::LowerTupleMinItems(n) => write!(f, "lower tuple minItems to {n}"),
+            Relax::RaiseTupleMaxItems(n) => write!(f, "raise tuple maxItems to {n}"),
+            Relax::AllowField(k) => write!(f, "allow field `{k}`"),
+            Relax::OptionalField(k) => write!(f, "make field `{k}` optional"),
+            Relax::AllowVariant(Some(v)) => write!(f, "add tagged-union variant {v:?}"),
+            Relax::AllowVariant(None) => f.write_str("drop the tagged union (tag missing)"),
+            Relax::AllowKind("null") => f.write_str("make nullable"),
+            Relax::AllowKind(kind) => write!(f, "allow {kind}"),
+        }
+    }
+}
+
+/// One relaxation at one path, with how many sample documents needed it.
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    pub path: JPath,
+    pub relax: Relax,
+    pub docs: u64,
+    /// Index (in sample order) of the first document that needed it.
+    pub first: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Revalidation {
+    pub checked: usize,
+    pub failed: usize,
+    pub suggestions: Vec<Suggestion>,
+    /// The same samples against the written schema (when `gen` writes one).
+    pub schema: Option<crate::schema_check::Conformance>,
+}
+
+/// Validate every sample against `root`; suggestions are ordered by path.
+pub fn revalidate(root: &Ty, samples: &[Value]) -> Revalidation {
+    let mut v = Validator::default();
+    let mut out = Revalidation { checked: samples.len(), ..Revalidation::default() };
+    let mut found: BTreeMap<(JPath, Relax), (u64, usize)> = BTreeMap::new();
+    for (i, doc) in samples.iter().enumerate() {
+        let mut errs = Vec::new();
+        v.check(doc, root, &JPath::root(), &mut errs);
+        if errs.is_empty() {
+            continue;
+        }
+        out.failed += 1;
+        errs.sort();
+        errs.dedup();
+        for e in errs {
+            found.entry(e).or_insert((0, i)).0 += 1;
+        }
+    }
+    out.suggestions = found
+        .into_iter()
+        .map(|((path, relax), (docs, first))| Suggestion { path, relax, docs, first })
+        .collect();
+    out
+}
+
+#[derive(Default)]
+struct Validator {
+    patterns: HashMap<String, Option<Regex>>,
+}
+
+fn kind_of(v: &Value) -> &'static str {
+    match v {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.as_i64().is_some() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Whether `t` is meant for values of `v`'s kind at all (constraints aside).
+fn same_kind(t: &Ty, v: &Value) -> bool {
+    match (t, v) {
+        (Ty::Nullable(inner), _) => v.is_null() || same_kind(inner, v),
+        (Ty::Sentinel { inner, marks, as_null }, _) => {
+            (*as_null && v.is_null()) || is_mark(marks, v) || same_kind(inner, v)
+        }
+        (Ty::OneOf(arms), _) => arms.iter().any(|a| same_kind(a, v)),
+        (Ty::Null, Value::Null) | (Ty::Bool, Value::Bool(_)) | (Ty::String { .. }, Value::String(_)) => true,
+        (Ty::JsonString(_), Value::String(_)) | (Ty::Any | Ty::Raw, _) => true,
+        (Ty::Integer { from_string: true, .. } | Ty::Number { from_string: true, .. }, Value::String(_)) => true,
+        (Ty::Integer { .. }, Value::Number(n)) => n.as_i64().is_some() || n.as_u64().is_some(),
+        (Ty::Number { .. }, Value::Number(_)) => true,
+        (Ty::StringOrNumber { .. }, Value::Number(_) | Value::String(_)) => true,
+        (Ty::ArrayList { .. } | Ty::ArrayTuple { .. }, Value::Array(_)) => true,
+        (Ty::Object { .. } | Ty::Map { .. } | Ty::Tagged { .. }, Value::Object(_)) => true,
+        _ => false,
+    }
+}
+
+/// Only JSON number characters, starting like one (the generated `NumOrString` check).
+fn spells_number(s: &str) -> bool {
+    s.starts_with(|c: char| c == '-' || c.is_ascii_digit())
+        && s.bytes().all(|b| b.is_ascii_digit() || b"+-.eE".contains(&b))
+}
+
+/// Exactly JSON number syntax (the generated `NumberLike` check).
+fn spells_json_number(s: &str) -> bool {
+    s.trim() == s && serde_json::from_str::<serde_json::Number>(s).is_ok()
+}
+
+fn is_mark(marks: &[Mark], v: &Value) -> bool {
+    marks.iter().any(|m| match m {
+        Mark::Int(n) => v.as_i64() == Some(*n),
+        Mark::Str(s) => v.as_str() == Some(s.as_str()),
+    })
+}
+
+fn fail(errs: &mut Vec<(JPath, Relax)>, at: &JPath, r: Relax) {
+    errs.push((at.clone(), r));
+}
+
+// same tolerance as the generated `__ge_f64` / `__le_f64`
+fn tol(b: f64) -> f64 {
+    let t = f64::max(1e-12, 1e-12 * b.abs());
+    if t.is_finite() { t } else { 0.0 }
+}
+
+impl Validator {
+    fn check(&mut self, v: &Value, t: &Ty, at: &JPath, errs: &mut Vec<(JPath, Relax)>) {
+        match (t, v) {
+            (Ty::Nullable(_), Value::Null) => {}
+            (Ty::Nullable(inner), _) => self.check(v, inner, at, errs),
+            (Ty::Sentinel { as_null: true, .. }, Value::Null) => {}
+            (Ty::Sentinel { marks, .. }, _) if is_mark(marks, v) => {}
+            (Ty::Sentinel { inner, .. }, _) => self.check(v, inner, at, errs),
+            (Ty::OneOf(arms), _) => {
+                let mut best: Option<Vec<(JPath, Relax)>> = None;
+                for a in arms.iter().filter(|a| same_kind(a, v)) {
+                    let mut e = Vec::new();
+                    self.check(v, a, at, &mut e);
+                    if e.is_empty() {
+                        return;
+                    }
+                    if best.as_ref().is_none_or(|b| e.len() < b.len()) {
+                        best = Some(e);
+                    }
+                }
+                match best {
+                    Some(e) => errs.extend(e),
+                    None => fail(errs, at, Relax::AllowKind(kind_of(v))),
+                }
+            }
+            (Ty::Never, _) => fail(errs, at, Relax::AllowKind(kind_of(v))),
+            _ if !same_kind(t, v) => fail(errs, at, Relax::AllowKind(kind_of(v))),
+
+            (Ty::Null, _) | (Ty::Bool, _) | (Ty::Any | Ty::Raw, _) => {}
+            // numeric strings parse like the generated `NumOrString`
+            (Ty::Integer { min, max, enum_, .. }, _) => {
+                let exact = v.as_i64().map(i128::from).or_else(|| v.as_u64().map(i128::from));
+                let Some(x) = exact.or_else(|| v.as_str().filter(|s| spells_number(s))?.parse().ok()) else {
+                    return fail(errs, at, Relax::AllowKind("string"));
+                };
+                // the generated enum reads its values only
+                if !enum_.is_empty() {
+                    if !enum_.iter().any(|&e| i128::from(e) == x) {
+                        fail(errs, at, Relax::DropEnum);
+                    }
+                    return;
+                }
+                // the generated Rust integer is as narrow as the bounds allow
+                if !IntWidth::of(*min, *max).contains(x) {
+                    let below = min.is_some_and(|m| x < m);
+                    return fail(errs, at, if below { Relax::DropMinimum } else { Relax::DropMaximum });
+                }
+                if crate::inference::CHECK_INT_BOUNDS {
+                    if min.is_some_and(|m| x < m) {
+                        fail(errs, at, Relax::DropMinimum);
+                    }
+                    if max.is_some_and(|m| x > m) {
+                        fail(errs, at, Relax::DropMaximum);
+                    }
+                }
+            }
+            (Ty::Number { min, max, .. }, _) => {
+                let x = match v.as_str() {
+                    Some(s) => match s.parse::<f64>() {
+                        Ok(x) if x.is_finite() && spells_number(s) => x,
+                        _ => return fail(errs, at, Relax::AllowKind("string")),
+                    },
+                    None => v.as_f64().unwrap_or(f64::NAN),
+                };
+                if crate::inference::CHECK_NUM_BOUNDS {
+                    if min.is_some_and(|m| x + tol(m) < m) {
+                        fail(errs, at, Relax::DropMinimum);
+                    }
+                    if max.is_some_and(|m| x > m + tol(m)) {
+                        fail(errs, at, Relax::DropMaximum);
+                    }
+                }
+            }
+            // bounds are the schema's; the generated `NumberLike` keeps any number
+            (Ty::StringOrNumber { .. }, Value::String(s)) if !spells_json_number(s) => {
+                fail(errs, at, Relax::AllowKind("string"));
+            }
+            (Ty::StringOrNumber { .. }, _) => {}
+            (Ty::String { enum_, pattern, format_uri, format, .. }, Value::String(s)) => {
+                // same precedence as `Codegen::emit_string_kind`
+                if !enum_.is_empty() && enum_.len() <= 32 {
+                    if !enum_.contains(s) {
+                        fail(errs, at, Relax::DropEnum);
+                    }
+                } else if let Some(pat) = pattern {
+                    let rx = self.patterns
+                        .entry(pat.clone())
+                        .or_insert_with(|| Regex::new(pat).ok());
+                    if rx.as_ref().is_some_and(|rx| !rx.is_match(s)) {
+                        fail(errs, at, Relax::DropPattern);
+                    }
+                } else if *format_uri
+                    && !["http://", "https://", "mailto:", "tel:"].iter().any(|p| s.starts_with(p))
+                {
+                    fail(errs, at, Relax::DropUriFormat);
+                } else if let Some(f) = format.filter(|f| !f.matches(s)) {
+                    fail(errs, at, Relax::DropFormat(f.as_str()));
+                }
+            }
+            // the document's paths are the string's own (see `NTy::JsonString`)
+            (Ty::JsonString(inner), Value::String(s)) => match serde_json::from_str::<Value>(s) {
+                Ok(doc) => self.check(&doc, inner, at, errs),
+                Err(_) => fail(errs, at, Relax::DropJsonString),
+            },
+            (Ty::ArrayList { item, min_items, max_items, .. }, Value::Array(xs)) => {
+                if min_items.is_some_and(|m| xs.len() < m as usize) {
+                    fail(errs, at, Relax::DropMinItems);
+                }
+                if max_items.is_some_and(|m| xs.len() > m as usize) {
+                    fail(errs, at, Relax::DropMaxItems);
+                }
+                let items = at.child(Seg::Items);
+                for x in xs {
+                    self.check(x, item, &items, errs);
+                }
+            }
+            (Ty::ArrayTuple { elems, min_items, .. }, Value::Array(xs)) => {
+                if xs.len() < *min_items as usize {
+                    fail(errs, at, Relax::LowerTupleMinItems(xs.len()));
+                }
+                if xs.len() > elems.len() {
+                    fail(errs, at, Relax::RaiseTupleMaxItems(xs.len()));
+                }
+                for (i, (x, e)) in xs.iter().zip(elems).enumerate() {
+                    // optional slots take null as "absent"
+                    if x.is_null() && i >= *min_items as usize {
+                        continue;
+                    }
+                    self.check(x, e, &at.child(Seg::Index(i)), errs);
+                }
+            }
+            (Ty::Object { fields, .. }, Value::Object(m)) => {
+                for k in m.keys().filter(|k| !fields.iter().any(|f| &f.name == *k)) {
+                    fail(errs, at, Relax::AllowField(k.clone()));
+                }
+                for f in fields {
+                    match m.get(&f.name) {
+                        None if f.present => fail(errs, at, Relax::OptionalField(f.name.clone())),
+                        Some(Value::Null) if !f.required => {}
+                        None => {}
+                        Some(x) => self.check(x, &f.ty, &at.child(Seg::Field(f.name.clone())), errs),
+                    }
+                }
+            }
+            // the variant's fields are the object's, minus the tag
+            (Ty::Tagged { tag, variants, .. }, Value::Object(m)) => {
+                let value = m.get(tag).and_then(Value::as_str);
+                match variants.iter().find(|(v, _)| Some(v.as_str()) == value) {
+                    Some((_, ty)) => {
+                        let mut rest = m.clone();
+                        rest.remove(tag);
+                        self.check(&Value::Object(rest), ty, at, errs);
+                    }
+                    None => fail(errs, at, Relax::AllowVariant(value.map(str::to_string))),
+                }
+            }
+            // keys are checked as strings at the map's own path
+            (Ty::Map { key, value, .. }, Value::Object(m)) => {
+                let values = at.child(Seg::Items);
+                for (k, x) in m {
+                    self.check(&Value::String(k.clone()), key, at, errs);
+                    self.check(x, value, &values, errs);
+                }
+            }
+            _ => unreachable!("same_kind"),
+        }
+    }
+}
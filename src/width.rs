@@ -0,0 +1,102 @@
+//! Key cardinality of object slots, for the report.
+//!
+//! A slot whose keys are data (ids, dates, user names) that slipped past map
+//! detection becomes a struct with a field per key: thousands of them, and a
+//! schema nobody can read. Past `max_object_fields` distinct keys (see
+//! [`crate::inference::caps`]) such a slot is typed as a map whatever its keys
+//! look like; the report lists the widest slots, and how each was typed, so the
+//! near misses are visible too.
+
+use crate::inference::U;
+use crate::jpath::{JPath, Seg};
+use crate::overrides::Overrides;
+
+/// Slots with fewer distinct keys are not listed.
+pub const MIN_LISTED_KEYS: usize = 32;
+/// Widest slots listed.
+pub const MAX_LISTED: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lowered {
+    Record,
+    Map,
+    /// Past `max_object_fields` keys: a map whatever they look like.
+    ForcedMap,
+}
+
+impl Lowered {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Lowered::Record => "record",
+            Lowered::Map => "map",
+            Lowered::ForcedMap => "map (max_object_fields)",
+        }
+    }
+}
+
+/// One object slot and its keys.
+#[derive(Debug, Clone)]
+pub struct Width {
+    pub path: JPath,
+    /// Distinct keys over every object.
+    pub keys: usize,
+    pub objects: u64,
+    /// Keys per object, on average.
+    pub mean: f64,
+    pub lowered: Lowered,
+}
+
+/// The object slots of `u` with at least [`MIN_LISTED_KEYS`] distinct keys,
+/// widest first, at most [`MAX_LISTED`].
+pub fn survey(u: &U, ov: &Overrides) -> Vec<Width> {
+    let mut out = Vec::new();
+    walk(u, &JPath::root(), ov, &mut out);
+    out.sort_by(|a, b| b.keys.cmp(&a.keys).then_with(|| a.path.to_string().cmp(&b.path.to_string())));
+    out.truncate(MAX_LISTED);
+    out
+}
+
+fn walk(u: &U, at: &JPath, ov: &Overrides, out: &mut Vec<Width>) {
+    if ov.passthrough(at) {
+        return;
+    }
+    if let Some(arr) = &u.arr {
+        let tuple = ov.tuple_at(at).unwrap_or_else(|| crate::inference::decide_tuple(arr));
+        if tuple && !arr.cols.is_empty() {
+            for (i, c) in arr.cols.iter().enumerate() {
+                walk(c, &at.child(Seg::Index(i)), ov, out);
+            }
+        } else {
+            walk(&arr.item, &at.child(Seg::Items), ov, out);
+        }
+    }
+    let Some(obj) = &u.obj else { return };
+    let keys = obj.wide.as_ref().map_or(obj.fields.len(), |w| w.keys as usize);
+    let map = crate::inference::decide_map(obj);
+    if keys >= MIN_LISTED_KEYS {
+        let present: u64 = match &obj.wide {
+            Some(w) => w.present,
+            None => obj.fields.values().map(|f| f.present_in).sum(),
+        };
+        let lowered = match &map {
+            None => Lowered::Record,
+            Some(_) if obj.wide.is_some() || keys > crate::inference::caps::max_object_fields() => Lowered::ForcedMap,
+            Some(_) => Lowered::Map,
+        };
+        out.push(Width {
+            path: at.clone(),
+            keys,
+            objects: obj.seen_objects,
+            mean: present as f64 / obj.seen_objects.max(1) as f64,
+            lowered,
+        });
+    }
+    match map {
+        Some(value) => walk(&value, &at.child(Seg::Items), ov, out),
+        None => {
+            for (name, f) in &obj.fields {
+                walk(&f.ty, &at.child(Seg::Field(name.clone())), ov, out);
+            }
+        }
+    }
+}
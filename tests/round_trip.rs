@@ -0,0 +1,97 @@
+//! Round trips through the binary: `gen-corpus` writes documents and the
+//! schema they were drawn from; `gen` infers a schema from the documents; both
+//! schemas must accept every document, and the inferred one must agree with the
+//! reference wherever they share a path.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const BIN: &str = env!("CARGO_BIN_EXE_json-osi");
+
+/// Corpus shapes, as `gen-corpus` flags.
+const SHAPES: &[&[&str]] = &[
+    &[],
+    &["--arity-drift", "3", "--null-pad", "0.4"],
+    &["--enum-cardinality", "1", "--depth", "0"],
+    &["--depth", "4", "--blob-len", "64", "--tuple-arity", "2"],
+];
+
+const SEEDS: &[u64] = &[0, 1, 7, 42];
+
+struct Scratch(PathBuf);
+
+impl Scratch {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("json-osi-round-trip-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Scratch(dir)
+    }
+
+    fn path(&self, file: &str) -> PathBuf {
+        self.0.join(file)
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn run(args: &[&str]) -> Output {
+    Command::new(BIN).args(args).output().unwrap_or_else(|e| panic!("{BIN}: {e}"))
+}
+
+fn ok(what: &str, args: &[&str]) -> Output {
+    let out = run(args);
+    assert!(
+        out.status.success(),
+        "{what}: json-osi {}\nexit {:?}\n{}",
+        args.join(" "),
+        out.status.code(),
+        String::from_utf8_lossy(&out.stderr),
+    );
+    out
+}
+
+fn s(p: &Path) -> &str {
+    p.to_str().unwrap()
+}
+
+#[test]
+fn inferred_and_reference_schemas_accept_the_corpus() {
+    for (i, shape) in SHAPES.iter().enumerate() {
+        for seed in SEEDS {
+            let dir = Scratch::new(&format!("{i}-{seed}"));
+            let (corpus, truth, inferred) = (dir.path("corpus.ndjson"), dir.path("truth.json"), dir.path("inferred.json"));
+            let what = format!("seed {seed}, {shape:?}");
+            let seed = seed.to_string();
+            let mut args = vec!["gen-corpus", "--out", s(&corpus), "--truth", s(&truth), "--seed", &seed, "--docs", "120"];
+            args.extend_from_slice(shape);
+            ok(&what, &args);
+            ok(&what, &["validate", "-i", s(&corpus), "--ndjson", "--schema", s(&truth), "--mode", "documents"]);
+            ok(&what, &["gen", "-i", s(&corpus), "--ndjson", "--schema", s(&inferred)]);
+            ok(&what, &["validate", "-i", s(&corpus), "--ndjson", "--schema", s(&inferred), "--mode", "documents"]);
+
+            let out = ok(&what, &["score", "-i", s(&corpus), "--ndjson", "--truth", s(&truth), "--json"]);
+            let score: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+            assert_eq!(score["kinds_agree"], score["shared_paths"], "{what}: {score:#}");
+            for check in ["ranges", "enums", "item_counts"] {
+                assert_eq!(score[check]["conflicting"], 0, "{what}: {check}: {score:#}");
+            }
+            assert!(score["precision"].as_f64().unwrap() > 0.9, "{what}: {score:#}");
+        }
+    }
+}
+
+#[test]
+fn validate_rejects_a_document_outside_the_schema() {
+    let dir = Scratch::new("reject");
+    let (corpus, truth) = (dir.path("corpus.ndjson"), dir.path("truth.json"));
+    ok("gen-corpus", &["gen-corpus", "--out", s(&corpus), "--truth", s(&truth), "--seed", "5", "--docs", "20"]);
+    let mut docs = std::fs::read_to_string(&corpus).unwrap();
+    docs.push_str("[\"not\", \"a\", \"record\"]\n");
+    std::fs::write(&corpus, docs).unwrap();
+    let out = run(&["validate", "-i", s(&corpus), "--ndjson", "--schema", s(&truth), "--mode", "documents"]);
+    assert_eq!(out.status.code(), Some(1), "{}", String::from_utf8_lossy(&out.stderr));
+}